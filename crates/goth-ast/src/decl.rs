@@ -1,19 +1,20 @@
 //! Top-level declarations in Goth
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::expr::Expr;
 use crate::types::{Type, Constraint, TypeParam};
 use crate::effect::Effects;
 
 /// A complete Goth program/module
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Module {
     pub name: Option<Box<str>>,
     pub decls: Vec<Decl>,
 }
 
 /// Top-level declaration
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Decl {
     /// Function definition
     Fn(FnDecl),
@@ -42,7 +43,7 @@ pub enum Decl {
 }
 
 /// Use declaration - imports another file's declarations
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct UseDecl {
     pub path: Box<str>,
 }
@@ -54,10 +55,13 @@ pub struct UseDecl {
 /// │  ⊢ Preconditions
 /// │  ⊨ Postconditions
 /// ╰─ Implementation
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct FnDecl {
     pub name: Box<str>,
     pub type_params: Vec<TypeParam>,
+    /// Parameter names in order (empty if parameters are positional only)
+    #[serde(default)]
+    pub param_names: Vec<Box<str>>,
     pub signature: Type,
     pub effects: Effects,
     pub constraints: Vec<Constraint>,
@@ -68,7 +72,7 @@ pub struct FnDecl {
 
 /// Type alias declaration
 /// Name ≡ Type
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TypeDecl {
     pub name: Box<str>,
     pub params: Vec<TypeParam>,
@@ -81,7 +85,7 @@ pub struct TypeDecl {
 /// Example:
 ///   enum Option τ where Some τ | None
 ///   enum Either α β where Left α | Right β
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct EnumDecl {
     pub name: Box<str>,
     pub params: Vec<TypeParam>,
@@ -89,7 +93,7 @@ pub struct EnumDecl {
 }
 
 /// A single variant in an enum
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct EnumVariant {
     pub name: Box<str>,
     pub payload: Option<Type>,
@@ -97,7 +101,7 @@ pub struct EnumVariant {
 
 /// Typeclass declaration
 /// class ClassName τ where ...
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ClassDecl {
     pub name: Box<str>,
     pub param: TypeParam,
@@ -106,7 +110,7 @@ pub struct ClassDecl {
 }
 
 /// Method signature in a class
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct MethodSig {
     pub name: Box<str>,
     pub signature: Type,
@@ -115,7 +119,7 @@ pub struct MethodSig {
 
 /// Typeclass implementation
 /// impl ClassName Type where ...
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct ImplDecl {
     pub class_name: Box<str>,
     pub target: Type,
@@ -124,14 +128,14 @@ pub struct ImplDecl {
 }
 
 /// Method implementation
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct MethodImpl {
     pub name: Box<str>,
     pub body: Expr,
 }
 
 /// Top-level let binding
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct LetDecl {
     pub name: Box<str>,
     pub type_: Option<Type>,
@@ -139,7 +143,7 @@ pub struct LetDecl {
 }
 
 /// Operator declaration
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct OpDecl {
     pub name: Box<str>,
     pub glyph: Box<str>,
@@ -168,6 +172,7 @@ impl FnDecl {
         FnDecl {
             name: name.into(),
             type_params: vec![],
+            param_names: vec![],
             signature: sig,
             effects: Effects::pure(),
             constraints: vec![],
@@ -177,6 +182,12 @@ impl FnDecl {
         }
     }
 
+    /// Name the parameters, enabling named-argument application
+    pub fn with_param_names(mut self, names: impl IntoIterator<Item = impl Into<Box<str>>>) -> Self {
+        self.param_names = names.into_iter().map(Into::into).collect();
+        self
+    }
+
     /// Add effects annotation
     pub fn with_effects(mut self, effects: Effects) -> Self {
         self.effects = effects;
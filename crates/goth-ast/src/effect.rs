@@ -3,11 +3,12 @@
 //! Effects track capabilities like IO, mutation, and randomness.
 //! Pure (□) is the default; effects are explicitly annotated.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 
 /// An effect
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
 pub enum Effect {
     /// Pure (no effects) - □
     Pure,
@@ -35,7 +36,7 @@ pub enum Effect {
 }
 
 /// A set of effects (effect row)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Effects(pub BTreeSet<Effect>);
 
 impl Effects {
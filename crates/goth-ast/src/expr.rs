@@ -2,6 +2,7 @@
 //!
 //! The core of the AST. Uses de Bruijn indices for variable binding.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::literal::Literal;
 use crate::op::{BinOp, UnaryOp};
@@ -9,7 +10,11 @@ use crate::types::Type;
 use crate::pattern::Pattern;
 
 /// Expression (the core AST node)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// `Serialize`/`Deserialize` are implemented in `ser` on top of the derived
+/// (remote) impls, so `.gast` loading can be made lenient.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(remote = "Self")]
 pub enum Expr {
     // ============ Atoms ============
     
@@ -31,6 +36,10 @@ pub enum Expr {
     /// Function application: f x
     App(Box<Expr>, Box<Expr>),
 
+    /// Named-argument application: f ⟨width: 3, height: 4⟩
+    /// Rewritten to positional `App`s using the callee's parameter names
+    AppNamed(Box<Expr>, Vec<(Box<str>, Expr)>),
+
     /// Lambda abstraction: λ→ body
     /// The parameter is implicit (accessed as ₀ in body)
     Lam(Box<Expr>),
@@ -80,13 +89,13 @@ pub enum Expr {
 
     // ============ Data Construction ============
     
-    /// Tuple: ⟨e₀, e₁, ...⟩
+    /// Tuple: ⟨e₀, e₁, ...⟩, elements evaluated left to right
     Tuple(Vec<Expr>),
 
     /// Labeled tuple (record): ⟨x: e₀, y: e₁, ...⟩
     Record(Vec<(Box<str>, Expr)>),
 
-    /// Array: [e₀, e₁, ...]
+    /// Array: [e₀, e₁, ...], elements evaluated left to right
     Array(Vec<Expr>),
 
     /// Array fill: [shape]⊢value
@@ -155,17 +164,23 @@ pub enum Expr {
 
     /// Unquote (splice in macro): ‹expr›
     Unquote(Box<Expr>),
+
+    /// Node of a kind this version does not know, kept as raw JSON
+    /// (only produced by `ser::from_json_lenient`)
+    #[serde(skip_deserializing)]
+    #[schemars(skip)]
+    Unknown(serde_json::Value),
 }
 
 /// Field access (by name or index)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum FieldAccess {
     Named(Box<str>),
     Index(u32),
 }
 
 /// Match arm: pattern → body
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct MatchArm {
     pub pattern: Pattern,
     pub guard: Option<Expr>,
@@ -173,7 +188,7 @@ pub struct MatchArm {
 }
 
 /// Cast kind
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum CastKind {
     /// Compile-time cast (zero-cost view)
     Static,
@@ -184,7 +199,7 @@ pub enum CastKind {
 }
 
 /// Operation in do-notation
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum DoOp {
     /// Map: ↦ f
     Map(Expr),
@@ -250,6 +265,11 @@ impl Expr {
         Expr::App(Box::new(func), Box::new(arg))
     }
 
+    /// Named-argument application
+    pub fn app_named(func: Expr, args: impl IntoIterator<Item = (impl Into<Box<str>>, Expr)>) -> Self {
+        Expr::AppNamed(Box::new(func), args.into_iter().map(|(n, e)| (n.into(), e)).collect())
+    }
+
     /// Multi-argument application
     pub fn app_n(func: Expr, args: impl IntoIterator<Item = Expr>) -> Self {
         args.into_iter().fold(func, |f, a| Expr::app(f, a))
@@ -391,6 +411,46 @@ impl Expr {
     }
 }
 
+impl Expr {
+    /// Name of this expression's variant, e.g. "Match", for concise diagnostics
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Expr::Idx { .. } => "Idx",
+            Expr::Name { .. } => "Name",
+            Expr::Lit { .. } => "Lit",
+            Expr::Prim { .. } => "Prim",
+            Expr::App { .. } => "App",
+            Expr::AppNamed { .. } => "AppNamed",
+            Expr::Lam { .. } => "Lam",
+            Expr::LamN { .. } => "LamN",
+            Expr::Let { .. } => "Let",
+            Expr::LetRec { .. } => "LetRec",
+            Expr::Match { .. } => "Match",
+            Expr::If { .. } => "If",
+            Expr::BinOp { .. } => "BinOp",
+            Expr::UnaryOp { .. } => "UnaryOp",
+            Expr::Norm { .. } => "Norm",
+            Expr::Tuple { .. } => "Tuple",
+            Expr::Record { .. } => "Record",
+            Expr::Array { .. } => "Array",
+            Expr::ArrayFill { .. } => "ArrayFill",
+            Expr::Variant { .. } => "Variant",
+            Expr::Field { .. } => "Field",
+            Expr::Index { .. } => "Index",
+            Expr::Slice { .. } => "Slice",
+            Expr::Annot { .. } => "Annot",
+            Expr::Cast { .. } => "Cast",
+            Expr::Update { .. } => "Update",
+            Expr::Do { .. } => "Do",
+            Expr::Disabled { .. } => "Disabled",
+            Expr::Hole => "Hole",
+            Expr::Quote { .. } => "Quote",
+            Expr::Unquote { .. } => "Unquote",
+            Expr::Unknown { .. } => "Unknown",
+        }
+    }
+}
+
 impl MatchArm {
     pub fn new(pattern: Pattern, body: Expr) -> Self {
         MatchArm { pattern, guard: None, body }
@@ -505,6 +565,69 @@ impl Expr {
             _ => self.clone(), // TODO: complete
         }
     }
+
+    /// Structural equality up to the names of bound variables.
+    /// Variables are de Bruijn indices, so only the name hints in patterns can differ.
+    pub fn alpha_eq(&self, other: &Expr) -> bool {
+        fn all(a: &[Expr], b: &[Expr]) -> bool { a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.alpha_eq(y)) }
+        fn fields(a: &[(Box<str>, Expr)], b: &[(Box<str>, Expr)]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|((n, x), (m, y))| n == m && x.alpha_eq(y))
+        }
+        fn opt(a: &Option<Box<Expr>>, b: &Option<Box<Expr>>) -> bool {
+            match (a, b) {
+                (Some(x), Some(y)) => x.alpha_eq(y),
+                (x, y) => x.is_none() && y.is_none(),
+            }
+        }
+        match (self, other) {
+            (Expr::App(f, x), Expr::App(g, y)) => f.alpha_eq(g) && x.alpha_eq(y),
+            (Expr::AppNamed(f, xs), Expr::AppNamed(g, ys)) => f.alpha_eq(g) && fields(xs, ys),
+            (Expr::Lam(a), Expr::Lam(b)) | (Expr::Norm(a), Expr::Norm(b)) | (Expr::Disabled(a), Expr::Disabled(b))
+            | (Expr::Quote(a), Expr::Quote(b)) | (Expr::Unquote(a), Expr::Unquote(b)) => a.alpha_eq(b),
+            (Expr::LamN(n, a), Expr::LamN(m, b)) => n == m && a.alpha_eq(b),
+            (Expr::Let { pattern: p, type_: s, value: v, body: e }, Expr::Let { pattern: q, type_: t, value: w, body: f }) => {
+                p.alpha_eq(q) && s == t && v.alpha_eq(w) && e.alpha_eq(f)
+            }
+            (Expr::LetRec { bindings: a, body: e }, Expr::LetRec { bindings: b, body: f }) => {
+                a.len() == b.len() && a.iter().zip(b).all(|((p, x), (q, y))| p.alpha_eq(q) && x.alpha_eq(y)) && e.alpha_eq(f)
+            }
+            (Expr::Match { scrutinee: s, arms: a }, Expr::Match { scrutinee: t, arms: b }) => {
+                s.alpha_eq(t) && a.len() == b.len() && a.iter().zip(b).all(|(x, y)| {
+                    x.pattern.alpha_eq(&y.pattern) && x.body.alpha_eq(&y.body) && match (&x.guard, &y.guard) {
+                        (Some(g), Some(h)) => g.alpha_eq(h),
+                        (g, h) => g.is_none() && h.is_none(),
+                    }
+                })
+            }
+            (Expr::If { cond: c1, then_: t1, else_: e1 }, Expr::If { cond: c2, then_: t2, else_: e2 }) => {
+                c1.alpha_eq(c2) && t1.alpha_eq(t2) && e1.alpha_eq(e2)
+            }
+            (Expr::BinOp(op1, l1, r1), Expr::BinOp(op2, l2, r2)) => op1 == op2 && l1.alpha_eq(l2) && r1.alpha_eq(r2),
+            (Expr::UnaryOp(op1, a), Expr::UnaryOp(op2, b)) => op1 == op2 && a.alpha_eq(b),
+            (Expr::Tuple(a), Expr::Tuple(b)) | (Expr::Array(a), Expr::Array(b)) => all(a, b),
+            (Expr::Record(a), Expr::Record(b)) => fields(a, b),
+            (Expr::ArrayFill { shape: s, value: a }, Expr::ArrayFill { shape: t, value: b }) => all(s, t) && a.alpha_eq(b),
+            (Expr::Variant { constructor: c, payload: a }, Expr::Variant { constructor: d, payload: b }) => c == d && opt(a, b),
+            (Expr::Field(a, x), Expr::Field(b, y)) => x == y && a.alpha_eq(b),
+            (Expr::Index(a, is), Expr::Index(b, js)) => a.alpha_eq(b) && all(is, js),
+            (Expr::Slice { array: a, start: s1, end: e1 }, Expr::Slice { array: b, start: s2, end: e2 }) => {
+                a.alpha_eq(b) && opt(s1, s2) && opt(e1, e2)
+            }
+            (Expr::Annot(a, s), Expr::Annot(b, t)) => s == t && a.alpha_eq(b),
+            (Expr::Cast { expr: a, target: s, kind: k }, Expr::Cast { expr: b, target: t, kind: l }) => s == t && k == l && a.alpha_eq(b),
+            (Expr::Update { base: a, fields: x }, Expr::Update { base: b, fields: y }) => a.alpha_eq(b) && fields(x, y),
+            (Expr::Do { init: a, ops: x }, Expr::Do { init: b, ops: y }) => {
+                a.alpha_eq(b) && x.len() == y.len() && x.iter().zip(y).all(|pair| match pair {
+                    (DoOp::Map(e), DoOp::Map(f)) | (DoOp::Filter(e), DoOp::Filter(f)) | (DoOp::Bind(e), DoOp::Bind(f)) => e.alpha_eq(f),
+                    (DoOp::Op(o, e), DoOp::Op(p, f)) => o == p && e.alpha_eq(f),
+                    (DoOp::Let(p, e), DoOp::Let(q, f)) => p.alpha_eq(q) && e.alpha_eq(f),
+                    _ => false,
+                })
+            }
+            // Leaves, and nodes of different kinds
+            _ => self == other,
+        }
+    }
 }
 
 // ============ Display ============
@@ -532,6 +655,14 @@ impl std::fmt::Display for Expr {
             },
             Expr::Prim(name) => write!(f, "⊥{}", name),
             Expr::App(func, arg) => write!(f, "({} {})", func, arg),
+            Expr::AppNamed(func, args) => {
+                write!(f, "({} ⟨", func)?;
+                for (i, (name, e)) in args.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}: {}", name, e)?;
+                }
+                write!(f, "⟩)")
+            }
             Expr::Lam(body) => write!(f, "λ→ {}", body),
             Expr::LamN(n, body) => write!(f, "λ{}→ {}", n, body),
             Expr::Let { pattern, type_, value, body } => {
@@ -571,6 +702,7 @@ impl std::fmt::Display for Expr {
             }
             Expr::Hole => write!(f, "_"),
             Expr::Disabled(e) => write!(f, "#- {} -#", e),
+            Expr::Unknown(v) => write!(f, "<unknown {}>", v),
             _ => write!(f, "..."), // TODO: complete
         }
     }
@@ -2,10 +2,11 @@
 //!
 //! Track value ranges at the type level: F64⊢[0..1]
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// A bound (endpoint) of an interval
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Bound {
     /// Negative infinity
     NegInf,
@@ -21,14 +22,14 @@ pub enum Bound {
 }
 
 /// Whether a bound is inclusive or exclusive
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum BoundKind {
     Inclusive, // [ or ]
     Exclusive, // ( or )
 }
 
 /// An interval [a..b], (a..b), [a..b), (a..b]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Interval {
     pub lo: Bound,
     pub lo_kind: BoundKind,
@@ -37,7 +38,7 @@ pub struct Interval {
 }
 
 /// Interval set (union of intervals)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct IntervalSet(pub Vec<Interval>);
 
 impl Interval {
@@ -137,6 +138,23 @@ impl Interval {
         
         above_lo && below_hi
     }
+
+    /// Check if `x` lies in this interval (symbolic bounds are assumed to admit it)
+    pub fn contains(&self, x: f64) -> bool {
+        let above_lo = match &self.lo {
+            Bound::NegInf => true,
+            Bound::PosInf => false,
+            Bound::Const(lo) => x > *lo || (x == *lo && self.lo_kind == BoundKind::Inclusive),
+            Bound::Var(_) => true,
+        };
+        let below_hi = match &self.hi {
+            Bound::NegInf => false,
+            Bound::PosInf => true,
+            Bound::Const(hi) => x < *hi || (x == *hi && self.hi_kind == BoundKind::Inclusive),
+            Bound::Var(_) => true,
+        };
+        above_lo && below_hi
+    }
 }
 
 impl Bound {
@@ -145,6 +163,12 @@ impl Bound {
     }
 }
 
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> Self {
+        IntervalSet::single(interval)
+    }
+}
+
 impl IntervalSet {
     pub fn single(interval: Interval) -> Self {
         IntervalSet(vec![interval])
@@ -164,6 +188,11 @@ impl IntervalSet {
     pub fn is_undefined(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Check if `x` lies in any interval of the set
+    pub fn contains(&self, x: f64) -> bool {
+        self.0.iter().any(|i| i.contains(x))
+    }
 }
 
 impl std::fmt::Display for Bound {
@@ -134,6 +134,13 @@ mod tests {
         assert!(text.contains("F64"));
     }
 
+    #[test]
+    fn test_dynamic_dim_display() {
+        let shape = Shape(vec![Dim::Const(3), Dim::Dynamic]);
+        assert_eq!(shape.to_string(), "[3 ?]");
+        assert_eq!(Dim::Dynamic.to_string(), "?");
+    }
+
     #[test]
     fn test_vector_map() {
         // xs ↦ (λ→ ₀ × 2)
@@ -225,6 +232,11 @@ mod tests {
         
         assert!(unit.may_contain_zero());
         assert!(!positive.may_contain_zero());
+
+        assert!(unit.contains(0.0) && unit.contains(1.0) && !unit.contains(1.5));
+        assert!(!positive.contains(0.0) && positive.contains(1e300));
+        assert!(IntervalSet::single(Interval::non_negative()).contains(0.0));
+        assert!(!IntervalSet::single(Interval::non_negative()).contains(-1.0));
     }
 
     #[test]
@@ -289,4 +301,12 @@ mod tests {
         assert!(text.contains("#-"));
         assert!(text.contains("-#"));
     }
+
+    #[test]
+    fn test_expr_variant_name() {
+        assert_eq!(Expr::int(1).variant_name(), "Lit");
+        assert_eq!(Expr::add(Expr::idx(0), Expr::int(1)).variant_name(), "BinOp");
+        assert_eq!(Expr::match_(Expr::idx(0), vec![]).variant_name(), "Match");
+        assert_eq!(Expr::Hole.variant_name(), "Hole");
+    }
 }
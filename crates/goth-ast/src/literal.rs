@@ -1,9 +1,10 @@
 //! Literal values in Goth
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Literal values
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Literal {
     /// Integer literal (arbitrary precision in AST)
     Int(i128),
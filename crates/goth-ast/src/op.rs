@@ -2,10 +2,11 @@
 //!
 //! Both built-in operators and user-defined operator metadata.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Binary operators
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum BinOp {
     // Arithmetic
     Add,      // +
@@ -43,7 +44,7 @@ pub enum BinOp {
 }
 
 /// Unary operators
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum UnaryOp {
     Neg,      // - (numeric negation)
     Not,      // ¬ (logical not)
@@ -73,7 +74,7 @@ pub enum UnaryOp {
 }
 
 /// Operator associativity
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum Assoc {
     Left,
     Right,
@@ -81,7 +82,7 @@ pub enum Assoc {
 }
 
 /// Operator metadata for user-defined operators
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct OpMeta {
     pub name: Box<str>,
     pub glyph: Box<str>,      // Unicode representation
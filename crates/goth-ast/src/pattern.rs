@@ -1,11 +1,12 @@
 //! Pattern matching in Goth
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::literal::Literal;
 use crate::types::Type;
 
 /// A pattern for matching
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Pattern {
     /// Wildcard pattern: _
     Wildcard,
@@ -113,6 +114,26 @@ impl Pattern {
         }
     }
 
+    /// Structural equality ignoring the names of bound variables
+    pub fn alpha_eq(&self, other: &Pattern) -> bool {
+        let all = |a: &[Pattern], b: &[Pattern]| a.len() == b.len() && a.iter().zip(b).all(|(p, q)| p.alpha_eq(q));
+        match (self, other) {
+            (Pattern::Var(_), Pattern::Var(_)) => true,
+            (Pattern::Array(a), Pattern::Array(b)) | (Pattern::Tuple(a), Pattern::Tuple(b)) => all(a, b),
+            (Pattern::ArraySplit { head: h1, tail: t1 }, Pattern::ArraySplit { head: h2, tail: t2 }) => all(h1, h2) && t1.alpha_eq(t2),
+            (Pattern::Variant { constructor: c1, payload: p1 }, Pattern::Variant { constructor: c2, payload: p2 }) => {
+                c1 == c2 && match (p1, p2) {
+                    (Some(p), Some(q)) => p.alpha_eq(q),
+                    (p, q) => p.is_none() && q.is_none(),
+                }
+            }
+            (Pattern::Typed(p, t), Pattern::Typed(q, u)) => t == u && p.alpha_eq(q),
+            (Pattern::Or(p1, p2), Pattern::Or(q1, q2)) => p1.alpha_eq(q1) && p2.alpha_eq(q2),
+            (Pattern::Guard(p, e), Pattern::Guard(q, f)) => p.alpha_eq(q) && e.alpha_eq(f),
+            _ => self == other,
+        }
+    }
+
     /// Check if this pattern is irrefutable (always matches)
     pub fn is_irrefutable(&self) -> bool {
         match self {
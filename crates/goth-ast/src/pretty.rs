@@ -15,6 +15,8 @@ pub struct PrettyConfig {
     pub indent: String,
     /// Maximum line width before wrapping
     pub max_width: usize,
+    /// Render the pure effect (□) on function signatures instead of omitting it
+    pub show_pure: bool,
 }
 
 impl Default for PrettyConfig {
@@ -23,6 +25,7 @@ impl Default for PrettyConfig {
             unicode: true,
             indent: "  ".to_string(),
             max_width: 100,
+            show_pure: false,
         }
     }
 }
@@ -128,6 +131,11 @@ impl Pretty {
         self.write(&f.name);
         self.write(" : ");
         self.print_type(&f.signature);
+        if !f.effects.is_pure() || self.config.show_pure {
+            self.write(" ");
+            let effects = self.symbols(&f.effects.to_string());
+            self.write(&effects);
+        }
         self.newline();
 
         // Constraints
@@ -268,14 +276,18 @@ impl Pretty {
             
             Type::Var(v) => self.write(v),
             
-            Type::Effectful(ty, _effects) => {
+            Type::Effectful(ty, effects) => {
                 self.print_type(ty);
-                self.write(if self.config.unicode { "⊢ε" } else { " !! " });
+                self.write(if self.config.unicode { "⊢" } else { " |- " });
+                let effects = self.symbols(&effects.to_string());
+                self.write(&effects);
             }
             
-            Type::Interval(ty, _interval) => {
+            Type::Interval(ty, interval) => {
                 self.print_type(ty);
-                self.write(if self.config.unicode { "⊢[..]" } else { " @[..] " });
+                self.write(if self.config.unicode { "⊢" } else { " |- " });
+                let interval = self.symbols(&interval.to_string());
+                self.write(&interval);
             }
             
             Type::Refinement { name, base, predicate: _ } => {
@@ -334,6 +346,10 @@ impl Pretty {
         
         match expr {
             Expr::Lit(lit) => match lit {
+                // A negative number is written tightly, but an argument
+                // position would read its sign as subtraction
+                Literal::Int(n) if *n < 0 && prec > 10 => self.write(&format!("({})", n)),
+                Literal::Float(f) if f.is_sign_negative() && prec > 10 => self.write(&format!("({})", f)),
                 Literal::Int(n) => self.write(&n.to_string()),
                 Literal::Float(f) => self.write(&f.to_string()),
                 Literal::True => self.write("true"),
@@ -392,6 +408,24 @@ impl Pretty {
                 
                 if needs_parens { self.write(")"); }
             }
+
+            Expr::AppNamed(f, args) => {
+                let app_prec = 10;
+                let needs_parens = prec > app_prec;
+                if needs_parens { self.write("("); }
+
+                self.print_expr_prec(f, app_prec);
+                self.write(if self.config.unicode { " ⟨" } else { " (" });
+                for (i, (name, arg)) in args.iter().enumerate() {
+                    if i > 0 { self.write(", "); }
+                    self.write(name);
+                    self.write(": ");
+                    self.print_expr_prec(arg, 0);
+                }
+                self.write(if self.config.unicode { "⟩" } else { ")" });
+
+                if needs_parens { self.write(")"); }
+            }
             
             Expr::Let { pattern, type_, value, body } => {
                 let needs_parens = prec > 0;
@@ -441,8 +475,11 @@ impl Pretty {
             }
             
             Expr::UnaryOp(op, operand) => {
+                // The operand binds tighter than application, so compounds
+                // and nested negations are parenthesized: -(a + b), -(-5).
+                // An application argument must be an atom: f (-x)
                 let un_prec = 11;
-                let needs_parens = prec > un_prec;
+                let needs_parens = prec >= un_prec;
                 if needs_parens { self.write("("); }
                 
                 let op_str = unop_str(op, self.config.unicode);
@@ -602,6 +639,19 @@ impl Pretty {
         }
     }
 
+    /// Replace the Unicode symbols used by the effect and interval `Display` impls
+    /// with ASCII fallbacks when configured
+    fn symbols(&self, text: &str) -> String {
+        if self.config.unicode { return text.to_string(); }
+        text.replace('□', "pure")
+            .replace('◇', "!")
+            .replace('⟨', "<")
+            .replace('⟩', ">")
+            .replace('∞', "inf")
+            .replace('∪', "U")
+            .replace('⊥', "_|_")
+    }
+
     /// Get the output string
     pub fn finish(self) -> String {
         self.output
@@ -980,4 +1030,51 @@ mod tests {
         let ascii_output = p.finish();
         assert!(ascii_output.contains("*"));
     }
+
+    #[test]
+    fn test_print_fn_effects_and_interval() {
+        use crate::effect::{Effect, Effects};
+        use crate::interval::{Bound, Interval, IntervalSet};
+
+        let unit = IntervalSet::single(Interval::closed(Bound::Const(0.0), Bound::PosInf));
+        let sig = Type::func(Type::Prim(PrimType::F64), Type::Interval(Box::new(Type::Prim(PrimType::F64)), unit));
+        let mut f = FnDecl::simple("sample", sig, Expr::Idx(0));
+        f.effects = Effects::single(Effect::Io);
+
+        assert_eq!(print_fn(&f).lines().next(), Some("╭─ sample : F64 → F64⊢[0..∞] ◇io"));
+
+        let mut p = Pretty::new(PrettyConfig::ascii());
+        p.print_fn(&f);
+        assert_eq!(p.finish().lines().next(), Some("/- sample : F64 -> F64 |- [0..inf] !io"));
+
+        // Pure functions omit □ unless configured to show it
+        f.effects = Effects::pure();
+        assert_eq!(print_fn(&f).lines().next(), Some("╭─ sample : F64 → F64⊢[0..∞]"));
+        let mut p = Pretty::new(PrettyConfig { show_pure: true, ..Default::default() });
+        p.print_fn(&f);
+        assert_eq!(p.finish().lines().next(), Some("╭─ sample : F64 → F64⊢[0..∞] □"));
+    }
+
+    #[test]
+    fn test_print_negated_literals() {
+        use crate::op::UnaryOp;
+        assert_eq!(print_expr(&Expr::int(-5)), "-5");
+        assert_eq!(print_expr(&Expr::UnaryOp(UnaryOp::Neg, Box::new(Expr::int(5)))), "-5");
+        assert_eq!(print_expr(&Expr::binop(BinOp::Sub, Expr::int(1), Expr::int(-5))), "1 - -5");
+        // Arguments are atoms, so a sign there is parenthesized
+        assert_eq!(print_expr(&Expr::app(Expr::name("f"), Expr::int(-5))), "f (-5)");
+        assert_eq!(print_expr(&Expr::app(Expr::name("f"), Expr::float(-0.5))), "f (-0.5)");
+        assert_eq!(print_expr(&Expr::UnaryOp(UnaryOp::Neg, Box::new(Expr::int(-5)))), "-(-5)");
+    }
+
+    #[test]
+    fn test_print_negated_subexpressions() {
+        use crate::op::UnaryOp;
+        let neg = |e: Expr| Expr::UnaryOp(UnaryOp::Neg, Box::new(e));
+        assert_eq!(print_expr(&neg(Expr::binop(BinOp::Add, Expr::name("a"), Expr::name("b")))), "-(a + b)");
+        assert_eq!(print_expr(&neg(Expr::app(Expr::name("f"), Expr::name("x")))), "-(f x)");
+        assert_eq!(print_expr(&neg(neg(Expr::name("x")))), "-(-x)");
+        assert_eq!(print_expr(&Expr::app(Expr::name("f"), neg(Expr::name("x")))), "f (-x)");
+        assert_eq!(print_expr(&Expr::binop(BinOp::Mul, neg(Expr::name("a")), Expr::name("b"))), "-a × b");
+    }
 }
@@ -7,6 +7,9 @@
 
 use crate::decl::Module;
 use crate::expr::Expr;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::Cell;
 use thiserror::Error;
 
 /// Serialization error
@@ -51,6 +54,85 @@ pub fn from_json_bytes(bytes: &[u8]) -> Result<Module> {
     Ok(serde_json::from_slice(bytes)?)
 }
 
+/// Deserialize module from JSON, loading expressions of unknown kinds (e.g.
+/// written by a newer version) as [`Expr::Unknown`] instead of failing
+pub fn from_json_lenient(json: &str) -> Result<Module> {
+    // Only pay for the lenient path when the strict one fails
+    from_json(json).or_else(|_| {
+        let was = LENIENT.with(|l| l.replace(true));
+        let module = serde_json::from_str(json);
+        LENIENT.with(|l| l.set(was));
+        Ok(module?)
+    })
+}
+
+// ============ Lenient Expr (de)serialization ============
+
+thread_local! {
+    static LENIENT: Cell<bool> = const { Cell::new(false) };
+}
+
+impl Serialize for Expr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            // Written back as read, so a lenient load/save round-trips
+            Expr::Unknown(raw) => raw.serialize(serializer),
+            _ => Expr::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Expr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        if !LENIENT.with(Cell::get) {
+            return Expr::deserialize(deserializer);
+        }
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        // Externally tagged: a unit variant is a string, any other a single-key object
+        let tag = match &raw {
+            serde_json::Value::String(tag) => Some(tag.as_str()),
+            serde_json::Value::Object(fields) if fields.len() == 1 => fields.keys().next().map(String::as_str),
+            _ => None,
+        };
+        if tag.is_some_and(|tag| !expr_variants().contains(&tag)) {
+            return Ok(Expr::Unknown(raw));
+        }
+        Expr::deserialize(&raw).map_err(D::Error::custom)
+    }
+}
+
+/// The variant names the derived `Expr` deserializer accepts, captured by
+/// handing it a deserializer that records them and fails
+fn expr_variants() -> &'static [&'static str] {
+    use serde::de::Visitor;
+
+    #[derive(Debug)]
+    struct Variants(&'static [&'static str]);
+    impl std::fmt::Display for Variants {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "variants {:?}", self.0) }
+    }
+    impl std::error::Error for Variants {}
+    impl serde::de::Error for Variants {
+        fn custom<T: std::fmt::Display>(_: T) -> Self { Variants(&[]) }
+    }
+
+    struct Probe;
+    impl<'de> Deserializer<'de> for Probe {
+        type Error = Variants;
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> std::result::Result<V::Value, Variants> { Err(Variants(&[])) }
+        fn deserialize_enum<V: Visitor<'de>>(self, _: &'static str, variants: &'static [&'static str], _: V) -> std::result::Result<V::Value, Variants> {
+            Err(Variants(variants))
+        }
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+            unit_struct newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+        }
+    }
+
+    static VARIANTS: std::sync::OnceLock<&'static [&'static str]> = std::sync::OnceLock::new();
+    VARIANTS.get_or_init(|| match Expr::deserialize(Probe) { Err(Variants(variants)) => variants, Ok(_) => &[] })
+}
+
 // ============ Binary (.gbin) ============
 
 /// Serialize module to binary
@@ -134,6 +216,23 @@ pub fn estimate_json_size(module: &Module) -> usize {
     serde_json::to_string(module).map(|s| s.len()).unwrap_or(0)
 }
 
+// ============ Schema ============
+
+/// Version of the `.gast` format described by [`schema`], bumped whenever
+/// the serialized shape of the AST changes incompatibly
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema for `.gast` modules, generated from the AST types
+///
+/// The version is recorded under `"version"` so tools can detect a format
+/// they were not written against.
+pub fn schema() -> serde_json::Value {
+    let mut root = schemars::schema_for!(Module);
+    root.schema.metadata().title = Some("Goth AST module (.gast)".into());
+    root.schema.extensions.insert("version".into(), SCHEMA_VERSION.into());
+    serde_json::to_value(root).expect("schema serializes to JSON")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +456,7 @@ mod tests {
                     postconditions: vec![],
                     constraints: vec![],
                     type_params: vec![],
+                    param_names: vec![],
                 }),
             ],
         };
@@ -366,6 +466,99 @@ mod tests {
         assert_eq!(module, parsed);
     }
     
+    // ============ Schema Tests ============
+    
+    fn assert_valid(instance: &serde_json::Value) {
+        let schema = schema();
+        let compiled = jsonschema::JSONSchema::compile(&schema).unwrap();
+        let errors: Vec<String> = match compiled.validate(instance) {
+            Ok(()) => vec![],
+            Err(errors) => errors.map(|e| format!("{} at {}", e, e.instance_path)).collect(),
+        };
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+    
+    #[test]
+    fn test_schema_version() {
+        assert_eq!(schema()["version"], serde_json::json!(SCHEMA_VERSION));
+    }
+    
+    #[test]
+    fn test_schema_validates_module() {
+        let module = Module {
+            name: Some("sample".into()),
+            decls: vec![
+                Decl::Let(LetDecl {
+                    name: "xs".into(),
+                    type_: None,
+                    value: Expr::Array(vec![Expr::Lit(Literal::Int(1)), Expr::Lit(Literal::Float(2.5))]),
+                }),
+                Decl::Fn(FnDecl {
+                    name: "inc".into(),
+                    signature: Type::func(Type::Prim(PrimType::I64), Type::Prim(PrimType::I64)),
+                    effects: Effects::pure(),
+                    body: Expr::Match {
+                        scrutinee: Box::new(Expr::Idx(0)),
+                        arms: vec![
+                            crate::expr::MatchArm::new(Pattern::Lit(Literal::Int(0)), Expr::Lit(Literal::Int(1))),
+                            crate::expr::MatchArm::new(Pattern::Wildcard, Expr::BinOp(BinOp::Add, Box::new(Expr::Idx(0)), Box::new(Expr::Lit(Literal::Int(1))))),
+                        ],
+                    },
+                    preconditions: vec![],
+                    postconditions: vec![],
+                    constraints: vec![],
+                    type_params: vec![],
+                    param_names: vec!["n".into()],
+                }),
+            ],
+        };
+        let instance: serde_json::Value = serde_json::from_str(&to_json(&module).unwrap()).unwrap();
+        assert_valid(&instance);
+    }
+    
+    #[test]
+    fn test_schema_rejects_unknown_expr() {
+        let schema = schema();
+        let compiled = jsonschema::JSONSchema::compile(&schema).unwrap();
+        let instance = serde_json::json!({ "name": null, "decls": [{ "Let": { "name": "x", "type_": null, "value": { "Bogus": 1 } } }] });
+        assert!(!compiled.is_valid(&instance));
+    }
+    
+    // ============ Lenient Loading Tests ============
+    
+    fn module_with_future_node() -> String {
+        let module = Module {
+            name: None,
+            decls: vec![Decl::Let(LetDecl {
+                name: "x".into(),
+                type_: None,
+                value: Expr::BinOp(BinOp::Add, Box::new(Expr::Lit(Literal::Int(1))), Box::new(Expr::Hole)),
+            })],
+        };
+        to_json(&module).unwrap().replace("\"Hole\"", r#"{ "FutureNode": { "x": 1 } }"#)
+    }
+    
+    #[test]
+    fn test_lenient_unknown_node() {
+        let json = module_with_future_node();
+        assert!(from_json(&json).is_err());
+        let module = from_json_lenient(&json).unwrap();
+        let Decl::Let(LetDecl { value: Expr::BinOp(BinOp::Add, l, r), .. }) = &module.decls[0] else { panic!("unexpected {:?}", module.decls[0]) };
+        assert_eq!(**l, Expr::Lit(Literal::Int(1)));
+        assert_eq!(**r, Expr::Unknown(serde_json::json!({ "FutureNode": { "x": 1 } })));
+        // Saving writes the unknown node back unchanged
+        assert_eq!(from_json_lenient(&to_json(&module).unwrap()).unwrap(), module);
+    }
+    
+    #[test]
+    fn test_lenient_still_rejects_malformed() {
+        let json = module_with_future_node().replace("\"value\"", "\"valu\"");
+        assert!(from_json_lenient(&json).is_err());
+        // Only unknown expression kinds are tolerated, not unknown operators inside known ones
+        let json = module_with_future_node().replace("\"Add\"", "\"FutureOp\"");
+        assert!(from_json_lenient(&json).is_err());
+    }
+    
     // ============ Binary Tests ============
     
     #[test]
@@ -2,10 +2,11 @@
 //!
 //! Shapes can be concrete (known dimensions) or symbolic (type variables).
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// A single dimension in a shape
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum Dim {
     /// Concrete dimension (known at compile time)
     Const(u64),
@@ -15,10 +16,13 @@ pub enum Dim {
 
     /// Binary operation on dimensions (for dependent shapes)
     BinOp(Box<Dim>, DimOp, Box<Dim>),
+
+    /// Unknown until run time, such as the length of a filter's result
+    Dynamic,
 }
 
 /// Operations on dimensions
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum DimOp {
     Add,
     Sub,
@@ -27,7 +31,7 @@ pub enum DimOp {
 }
 
 /// A tensor shape (list of dimensions)
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct Shape(pub Vec<Dim>);
 
 impl Shape {
@@ -113,6 +117,7 @@ impl std::fmt::Display for Dim {
         match self {
             Dim::Const(n) => write!(f, "{}", n),
             Dim::Var(name) => write!(f, "{}", name),
+            Dim::Dynamic => write!(f, "?"),
             Dim::BinOp(l, op, r) => {
                 let op_str = match op {
                     DimOp::Add => "+",
@@ -9,6 +9,7 @@
 //! - Refinement types (predicates)
 //! - Universal and existential quantification
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use crate::shape::Shape;
 use crate::effect::Effects;
@@ -16,7 +17,7 @@ use crate::interval::IntervalSet;
 use crate::expr::Expr;
 
 /// Primitive types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum PrimType {
     // Floating point
     F64,
@@ -46,7 +47,7 @@ pub enum PrimType {
 }
 
 /// Type representation
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Type {
     /// Primitive type
     Prim(PrimType),
@@ -99,7 +100,7 @@ pub enum Type {
 }
 
 /// A field in a tuple type
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TupleField {
     /// Optional label
     pub label: Option<Box<str>>,
@@ -108,7 +109,7 @@ pub struct TupleField {
 }
 
 /// An arm in a variant type
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct VariantArm {
     /// Constructor name
     pub name: Box<str>,
@@ -117,14 +118,14 @@ pub struct VariantArm {
 }
 
 /// Type parameter (for quantification)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct TypeParam {
     pub name: Box<str>,
     pub kind: TypeParamKind,
 }
 
 /// Kind of type parameter
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum TypeParamKind {
     /// Type variable
     Type,
@@ -135,7 +136,7 @@ pub enum TypeParamKind {
 }
 
 /// Type constraint (for where clauses)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Constraint {
     /// Type implements typeclass: T: Class
     HasClass(Type, Box<str>),
@@ -150,7 +151,7 @@ pub enum Constraint {
     Predicate(Expr),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ShapeCmpOp {
     Eq,
     Neq,
@@ -174,6 +175,39 @@ impl Type {
     pub fn char() -> Self { Type::Prim(PrimType::Char) }
     pub fn nat() -> Self { Type::Prim(PrimType::Nat) }
 
+    /// Scalar `F64`
+    ///
+    /// ```
+    /// use goth_ast::prelude::*;
+    /// assert_eq!(Type::scalar_f64(), Type::Prim(PrimType::F64));
+    /// ```
+    pub fn scalar_f64() -> Self { Type::f64() }
+
+    /// Scalar `I64`
+    ///
+    /// ```
+    /// use goth_ast::prelude::*;
+    /// assert_eq!(Type::scalar_i64(), Type::Prim(PrimType::I64));
+    /// ```
+    pub fn scalar_i64() -> Self { Type::i64() }
+
+    /// `I64` tensor with constant dimensions
+    ///
+    /// ```
+    /// use goth_ast::prelude::*;
+    /// let ty = Type::vec_i64(&[2, 3]);
+    /// assert_eq!(ty, Type::tensor(Shape(vec![Dim::constant(2), Dim::constant(3)]), Type::i64()));
+    /// ```
+    pub fn vec_i64(dims: &[u64]) -> Self { Type::tensor(dims.into(), Type::i64()) }
+
+    /// `F64` tensor with constant dimensions
+    ///
+    /// ```
+    /// use goth_ast::prelude::*;
+    /// assert_eq!(Type::vec_f64(&[4]), Type::vector(Dim::constant(4), Type::f64()));
+    /// ```
+    pub fn vec_f64(dims: &[u64]) -> Self { Type::tensor(dims.into(), Type::f64()) }
+
     // Tensor
     pub fn tensor(shape: Shape, elem: Type) -> Self {
         Type::Tensor(shape, Box::new(elem))
@@ -232,9 +266,15 @@ impl Type {
         }
     }
 
-    // With interval
-    pub fn with_interval(self, interval: IntervalSet) -> Self {
-        Type::Interval(Box::new(self), interval)
+    /// Refine with an interval (or union of intervals)
+    ///
+    /// ```
+    /// use goth_ast::prelude::*;
+    /// let unit = Type::scalar_f64().with_interval(Interval::unit());
+    /// assert_eq!(unit, Type::Interval(Box::new(Type::f64()), IntervalSet::single(Interval::unit())));
+    /// ```
+    pub fn with_interval(self, interval: impl Into<IntervalSet>) -> Self {
+        Type::Interval(Box::new(self), interval.into())
     }
 
     // Check if this is a function type
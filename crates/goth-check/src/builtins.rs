@@ -338,13 +338,27 @@ pub fn unaryop_type(op: UnaryOp, operand: &Type) -> TypeResult<Type> {
             }
         }
 
+        // Abs, Sign: T → T (where T is numeric), so integers stay integers
+        UnaryOp::Abs | UnaryOp::Sign => {
+            match operand {
+                Type::Prim(p) if p.is_numeric() => Ok(operand.clone()),
+                Type::Tensor(sh, el) => {
+                    let inner = unaryop_type(op, el)?;
+                    Ok(Type::Tensor(sh.clone(), Box::new(inner)))
+                }
+                _ => Err(TypeError::InvalidUnaryOp {
+                    op: format!("{:?}", op),
+                    operand: operand.clone(),
+                })
+            }
+        }
+
         // Sqrt, Floor, Ceil, Round, and math functions: T → F64 (where T is numeric)
         UnaryOp::Sqrt | UnaryOp::Floor | UnaryOp::Ceil | UnaryOp::Round
         | UnaryOp::Gamma | UnaryOp::Ln | UnaryOp::Log10 | UnaryOp::Log2 | UnaryOp::Exp
         | UnaryOp::Sin | UnaryOp::Cos | UnaryOp::Tan
         | UnaryOp::Asin | UnaryOp::Acos | UnaryOp::Atan
-        | UnaryOp::Sinh | UnaryOp::Cosh | UnaryOp::Tanh
-        | UnaryOp::Abs | UnaryOp::Sign => {
+        | UnaryOp::Sinh | UnaryOp::Cosh | UnaryOp::Tanh => {
             match operand {
                 Type::Prim(p) if p.is_numeric() => Ok(Type::Prim(PrimType::F64)),
                 Type::Tensor(sh, el) => {
@@ -368,6 +382,26 @@ pub fn primitive_type(name: &str) -> Option<Type> {
         "floor" | "ceil" | "round" | "abs" => {
             Some(Type::func(Type::Prim(PrimType::F64), Type::Prim(PrimType::F64)))
         }
+        "sign" | "signum" => {
+            // ∀α. α → α (elementwise over tensors, keeping Int or Float)
+            Some(Type::Forall(
+                vec![TypeParam { name: "α".into(), kind: TypeParamKind::Type }],
+                Box::new(Type::func(Type::Var("α".into()), Type::Var("α".into()))),
+            ))
+        }
+        "clamp" => {
+            // ∀α β. α → β → β → α (clamp a scalar or each tensor element between two bounds)
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                    TypeParam { name: "β".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func_n(
+                    [Type::Var("α".into()), Type::Var("β".into()), Type::Var("β".into())],
+                    Type::Var("α".into()),
+                )),
+            ))
+        }
         "atan2" | "pow" | "min" | "max" => {
             Some(Type::func_n(
                 [Type::Prim(PrimType::F64), Type::Prim(PrimType::F64)],
@@ -387,6 +421,19 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 )),
             ))
         }
+        "argmin" | "argmax" => {
+            // ∀n α. [n]α → I64 (index of the first extreme element)
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func(
+                    Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Var("α".into()))),
+                    Type::Prim(PrimType::I64),
+                )),
+            ))
+        }
         // Matrix operations with shape checking
         "dot" | "·" => {
             // ∀n. [n]F64 → [n]F64 → F64 (dot product)
@@ -460,6 +507,13 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 Type::Tensor(Shape(vec![Dim::Var("m".into())]), Box::new(Type::Prim(PrimType::I64))),
             ))
         }
+        "rangeStep" | "range_step" => {
+            // I64 → I64 → I64 → [m]I64 (generate start, start+step, ... before end)
+            Some(Type::func_n(
+                [Type::Prim(PrimType::I64), Type::Prim(PrimType::I64), Type::Prim(PrimType::I64)],
+                Type::Tensor(Shape(vec![Dim::Var("m".into())]), Box::new(Type::Prim(PrimType::I64))),
+            ))
+        }
         // Type conversions
         "toInt" => {
             // ∀α. α → I64 (convert any type to integer)
@@ -482,10 +536,14 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 Box::new(Type::func(Type::Var("α".into()), Type::Prim(PrimType::Bool))),
             ))
         }
-        "toChar" => {
+        "toChar" | "chr" => {
             // I64 → Char (convert integer to character)
             Some(Type::func(Type::Prim(PrimType::I64), Type::Prim(PrimType::Char)))
         }
+        "ord" => {
+            // Char → I64 (code point of a character)
+            Some(Type::func(Type::Prim(PrimType::Char), Type::Prim(PrimType::I64)))
+        }
         "parseInt" => {
             // String → I64 (parse string as integer)
             Some(Type::func(
@@ -544,9 +602,74 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 )),
             ))
         }
+        "fold" => {
+            // ∀n α β. [n]α → β → (β → α → β) → β (left fold from an initial accumulator)
+            let acc = || Type::Var("β".into());
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                    TypeParam { name: "β".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func_n(
+                    [
+                        Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Var("α".into()))),
+                        acc(),
+                        Type::func_n([acc(), Type::Var("α".into())], acc()),
+                    ],
+                    acc(),
+                )),
+            ))
+        }
+        "scan" => {
+            // ∀n α β. [n]α → β → (β → α → β) → [n]β (every accumulator of a left fold)
+            let acc = || Type::Var("β".into());
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                    TypeParam { name: "β".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func_n(
+                    [
+                        Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Var("α".into()))),
+                        acc(),
+                        Type::func_n([acc(), Type::Var("α".into())], acc()),
+                    ],
+                    Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(acc())),
+                )),
+            ))
+        }
+        "zipWith" | "zip_with" => {
+            // ∀n α β γ. [n]α → [n]β → (α → β → γ) → [n]γ
+            let vector = |elem: &str| Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Var(elem.into())));
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                    TypeParam { name: "β".into(), kind: TypeParamKind::Type },
+                    TypeParam { name: "γ".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func_n(
+                    [vector("α"), vector("β"), Type::func_n([Type::Var("α".into()), Type::Var("β".into())], Type::Var("γ".into()))],
+                    vector("γ"),
+                )),
+            ))
+        }
+        "memoize" => {
+            // ∀α β. (α → β) → α → β (the same function, caching results by argument)
+            let func = || Type::func(Type::Var("α".into()), Type::Var("β".into()));
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                    TypeParam { name: "β".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func(func(), func())),
+            ))
+        }
         // Array operations
-        "reverse" => {
-            // ∀n α. [n]α → [n]α (reverse array)
+        "reverse" | "sort" => {
+            // ∀n α. [n]α → [n]α (reverse or sort array)
             Some(Type::Forall(
                 vec![
                     TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
@@ -558,6 +681,20 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 )),
             ))
         }
+        "sortBy" | "sort_by" => {
+            // ∀n α. [n]α → (α → α → Bool) → [n]α (sort by a less-than function)
+            let vector = || Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Var("α".into())));
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func_n(
+                    [vector(), Type::func_n([Type::Var("α".into()), Type::Var("α".into())], Type::Prim(PrimType::Bool))],
+                    vector(),
+                )),
+            ))
+        }
         "take" => {
             // ∀n m α. I64 → [n]α → [m]α (take first k elements)
             Some(Type::Forall(
@@ -610,12 +747,44 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 )),
             ))
         }
+        "concat_axis" | "concatAxis" => {
+            // ∀a b c d e f α. [a b]α → [c d]α → I64 → [e f]α (join two matrices along rows or columns)
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "a".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "b".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "c".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "d".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "e".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "f".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "α".into(), kind: TypeParamKind::Type },
+                ],
+                Box::new(Type::func_n(
+                    [
+                        Type::Tensor(Shape(vec![Dim::Var("a".into()), Dim::Var("b".into())]), Box::new(Type::Var("α".into()))),
+                        Type::Tensor(Shape(vec![Dim::Var("c".into()), Dim::Var("d".into())]), Box::new(Type::Var("α".into()))),
+                        Type::Prim(PrimType::I64),
+                    ],
+                    Type::Tensor(Shape(vec![Dim::Var("e".into()), Dim::Var("f".into())]), Box::new(Type::Var("α".into()))),
+                )),
+            ))
+        }
         // I/O
         "print" => {
-            // ∀α. α → Unit (print value)
+            // ∀α. α → α (print value, returning it for inline use)
             Some(Type::Forall(
                 vec![TypeParam { name: "α".into(), kind: TypeParamKind::Type }],
-                Box::new(Type::func(Type::Var("α".into()), Type::unit())),
+                Box::new(Type::func(Type::Var("α".into()), Type::Var("α".into()))),
+            ))
+        }
+        "trace" => {
+            // ∀α. String → α → α (record a labelled value, returning it)
+            Some(Type::Forall(
+                vec![TypeParam { name: "α".into(), kind: TypeParamKind::Type }],
+                Box::new(Type::func_n(
+                    [Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Prim(PrimType::Char))), Type::Var("α".into())],
+                    Type::Var("α".into()),
+                )),
             ))
         }
         "readLine" => {
@@ -740,7 +909,7 @@ pub fn primitive_type(name: &str) -> Option<Type> {
             ))
         }
         // String concatenation
-        "strConcat" | "⧺" => {
+        "strConcat" | "concat_str" | "concatStr" | "⧺" => {
             // String → String → String (concatenate two strings)
             Some(Type::func_n(
                 [
@@ -750,8 +919,16 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 Type::Tensor(Shape(vec![Dim::Var("p".into())]), Box::new(Type::Prim(PrimType::Char))),
             ))
         }
+        // String length in characters
+        "strLen" | "str_len" => {
+            // String → I64
+            Some(Type::func(
+                Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Prim(PrimType::Char))),
+                Type::Prim(PrimType::I64),
+            ))
+        }
         // Replicate: create an array of n copies of a value
-        "replicate" => {
+        "replicate" | "fill" => {
             // ∀α. I64 → α → [n]α
             Some(Type::Forall(
                 vec![TypeParam { name: "α".into(), kind: TypeParamKind::Type }],
@@ -761,6 +938,20 @@ pub fn primitive_type(name: &str) -> Option<Type> {
                 )),
             ))
         }
+        "zeros" | "ones" => {
+            // ∀k n. [k]I64 → [n]I64 (an Int tensor of the given shape; its rank is the shape's
+            // length, which is only known at runtime, so it is typed as a vector)
+            Some(Type::Forall(
+                vec![
+                    TypeParam { name: "k".into(), kind: TypeParamKind::Shape },
+                    TypeParam { name: "n".into(), kind: TypeParamKind::Shape },
+                ],
+                Box::new(Type::func(
+                    Type::Tensor(Shape(vec![Dim::Var("k".into())]), Box::new(Type::Prim(PrimType::I64))),
+                    Type::Tensor(Shape(vec![Dim::Var("n".into())]), Box::new(Type::Prim(PrimType::I64))),
+                )),
+            ))
+        }
         // Shape query
         "shape" => {
             // ∀n α. [n]α → [1]I64 (get shape as array)
@@ -0,0 +1,157 @@
+//! Interval inference for Goth
+//!
+//! Computes a conservative range for an expression's value from literals,
+//! interval annotations (`F64⊢[0..∞]`) and reductions over tensors whose
+//! elements carry an interval. The rules for reductions over `n ≥ 0`
+//! elements drawn from `[lo..hi]`:
+//!
+//! - `sum`: non-negative when `lo ≥ 0`, non-positive when `hi ≤ 0`
+//! - `prod`: within `[0..1]` when `0 ≤ lo` and `hi ≤ 1`, at least 1 when
+//!   `lo ≥ 1`, non-negative when `lo ≥ 0`
+//! - `min` / `max`: within the elements' interval (they are never empty)
+//!
+//! Both bounds of a sum or product include the empty reduction (0 or 1).
+//! Anything else, including symbolic bounds, infers no interval.
+
+use goth_ast::expr::Expr;
+use goth_ast::interval::{Bound, Interval, IntervalSet};
+use goth_ast::literal::Literal;
+use goth_ast::op::UnaryOp;
+use goth_ast::types::Type;
+
+/// The interval `expr`'s value is known to lie in, if any
+pub fn infer_interval(expr: &Expr) -> Option<IntervalSet> {
+    match expr {
+        Expr::Lit(Literal::Int(n)) => Some(point(*n as f64)),
+        Expr::Lit(Literal::Float(f)) => Some(point(*f)),
+        Expr::Annot(inner, ty) => match ty {
+            Type::Interval(_, set) => Some(set.clone()),
+            _ => infer_interval(inner),
+        },
+        Expr::UnaryOp(UnaryOp::Sum, tensor) => sum_interval(&element_interval(tensor)?),
+        Expr::UnaryOp(UnaryOp::Prod, tensor) => product_interval(&element_interval(tensor)?),
+        Expr::App(f, tensor) => match f.as_ref() {
+            Expr::Name(name) => match name.as_ref() {
+                "sum" | "Σ" => sum_interval(&element_interval(tensor)?),
+                "prod" | "Π" => product_interval(&element_interval(tensor)?),
+                "min" | "max" => element_interval(tensor),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The interval every element of the tensor `expr` lies in, if known
+pub fn element_interval(expr: &Expr) -> Option<IntervalSet> {
+    match expr {
+        Expr::Annot(_, Type::Tensor(_, elem)) => match elem.as_ref() {
+            Type::Interval(_, set) => Some(set.clone()),
+            _ => None,
+        },
+        Expr::Array(elems) if !elems.is_empty() => {
+            let sets = elems.iter().map(infer_interval).collect::<Option<Vec<_>>>()?;
+            Some(IntervalSet(sets.into_iter().flat_map(|s| s.0).collect()))
+        }
+        _ => None,
+    }
+}
+
+/// Interval of the sum of any number of elements from `elems`
+pub fn sum_interval(elems: &IntervalSet) -> Option<IntervalSet> {
+    let (lo, hi) = hull(elems)?;
+    let interval = if lo >= 0.0 {
+        Interval::non_negative()
+    } else if hi <= 0.0 {
+        Interval::half_open_left(Bound::NegInf, Bound::Const(0.0))
+    } else {
+        Interval::all()
+    };
+    Some(IntervalSet::single(interval))
+}
+
+/// Interval of the product of any number of elements from `elems`
+pub fn product_interval(elems: &IntervalSet) -> Option<IntervalSet> {
+    let (lo, hi) = hull(elems)?;
+    let interval = if lo >= 0.0 && hi <= 1.0 {
+        Interval::unit()
+    } else if lo >= 1.0 {
+        Interval::half_open_right(Bound::Const(1.0), Bound::PosInf)
+    } else if lo >= 0.0 {
+        Interval::non_negative()
+    } else {
+        Interval::all()
+    };
+    Some(IntervalSet::single(interval))
+}
+
+fn point(x: f64) -> IntervalSet {
+    IntervalSet::single(Interval::closed(Bound::Const(x), Bound::Const(x)))
+}
+
+/// A bound as a number; symbolic bounds have none
+fn bound_value(bound: &Bound) -> Option<f64> {
+    match bound {
+        Bound::NegInf => Some(f64::NEG_INFINITY),
+        Bound::PosInf => Some(f64::INFINITY),
+        Bound::Const(x) => Some(*x),
+        Bound::Var(_) => None,
+    }
+}
+
+/// Smallest and largest bound over the whole set, or `None` if it is
+/// undefined or a bound is symbolic
+fn hull(set: &IntervalSet) -> Option<(f64, f64)> {
+    if set.is_undefined() {
+        return None;
+    }
+    set.0.iter().try_fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), i| {
+        Some((lo.min(bound_value(&i.lo)?), hi.max(bound_value(&i.hi)?)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goth_ast::shape::Shape;
+
+    fn tensor_of(interval: Interval) -> Expr {
+        let elem = Type::f64().with_interval(interval);
+        Expr::Annot(Box::new(Expr::name("xs")), Type::tensor(Shape::symbolic(&["n"]), elem))
+    }
+
+    #[test]
+    fn test_sum_of_non_negative_is_non_negative() {
+        let sum = Expr::sum(tensor_of(Interval::unit()));
+        let set = infer_interval(&sum).unwrap();
+        assert!(set.contains(0.0) && set.contains(1e9));
+        assert!(!set.contains(-1.0));
+        let call = Expr::app(Expr::name("sum"), tensor_of(Interval::non_negative()));
+        assert_eq!(infer_interval(&call), Some(IntervalSet::single(Interval::non_negative())));
+    }
+
+    #[test]
+    fn test_product_intervals() {
+        let prod = |i| infer_interval(&Expr::app(Expr::name("prod"), tensor_of(i))).unwrap();
+        assert_eq!(prod(Interval::unit()), IntervalSet::single(Interval::unit()));
+        assert!(!prod(Interval::closed(Bound::Const(2.0), Bound::Const(3.0))).contains(0.5));
+        assert!(prod(Interval::closed(Bound::Const(-1.0), Bound::Const(1.0))).contains(-5.0));
+    }
+
+    #[test]
+    fn test_min_max_stay_within_elements() {
+        let xs = Expr::array(vec![Expr::int(3), Expr::int(1), Expr::float(4.5)]);
+        let max = infer_interval(&Expr::app(Expr::name("max"), xs.clone())).unwrap();
+        assert!(max.contains(4.5) && !max.contains(2.0));
+        let min = infer_interval(&Expr::app(Expr::name("min"), xs)).unwrap();
+        assert!(min.contains(1.0) && !min.contains(-1.0));
+    }
+
+    #[test]
+    fn test_unknown_elements_infer_nothing() {
+        assert_eq!(infer_interval(&Expr::sum(Expr::name("xs"))), None);
+        let symbolic = Interval::closed(Bound::Const(0.0), Bound::Var("n".into()));
+        assert_eq!(infer_interval(&Expr::sum(tensor_of(symbolic))), None);
+    }
+}
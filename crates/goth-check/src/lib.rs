@@ -25,6 +25,7 @@ pub mod builtins;
 pub mod infer;
 pub mod check;
 pub mod shapes;
+pub mod intervals;
 
 pub use error::{TypeError, TypeResult};
 pub use context::Context;
@@ -50,21 +51,22 @@ impl TypeChecker {
                      "asin", "acos", "atan", "sinh", "cosh", "tanh",
                      "floor", "ceil", "round", "abs", "length", "len",
                      "dot", "·", "matmul", "transpose", "⍉", "norm",
-                     "iota", "ι", "⍳", "range",
+                     "iota", "ι", "⍳", "range", "rangeStep", "range_step", "fill", "replicate",
                      // Type conversions
-                     "toInt", "toFloat", "toBool", "toChar", "toString", "chars",
+                     "toInt", "toFloat", "toBool", "toChar", "toString", "chars", "ord", "chr",
                      "parseInt", "parseFloat",
                      // Aggregation
-                     "sum", "Σ", "prod", "Π",
+                     "sum", "Σ", "prod", "Π", "fold", "scan", "zipWith", "zip_with", "memoize",
                      // Array operations
-                     "reverse", "take", "drop", "concat", "⧺",
+                     "reverse", "sort", "sortBy", "sort_by", "take", "drop", "concat", "⧺", "argmin", "argmax",
                      // I/O
-                     "print", "readLine", "readFile", "writeFile",
+                     "print", "readLine", "readFile", "writeFile", "trace",
                      // TUI primitives
                      "write", "flush", "readKey", "rawModeEnter", "rawModeExit", "sleep",
                      // String operations (for wc-like utilities)
                      "lines", "words", "bytes",
-                     "strEq", "startsWith", "endsWith", "contains"] {
+                     "strEq", "startsWith", "endsWith", "contains",
+                     "strConcat", "concat_str", "concatStr", "strLen", "str_len"] {
             if let Some(ty) = builtins::primitive_type(prim) {
                 ctx.define_global(prim, ty);
             }
@@ -366,6 +368,7 @@ mod tests {
             postconditions: vec![],
             constraints: vec![],
             type_params: vec![],
+            param_names: vec![],
         };
         
         let module = Module {
@@ -419,6 +422,7 @@ mod tests {
             postconditions: vec![],
             constraints: vec![],
             type_params: vec![],
+            param_names: vec![],
         };
         
         let module = Module {
@@ -472,6 +476,7 @@ mod tests {
             postconditions: vec![],
             constraints: vec![],
             type_params: vec![],
+            param_names: vec![],
         };
         
         let module = Module {
@@ -514,6 +519,7 @@ mod tests {
             postconditions: vec![],
             constraints: vec![],
             type_params: vec![],
+            param_names: vec![],
         };
         
         let module = Module {
@@ -819,6 +825,7 @@ mod tests {
             postconditions: vec![],
             constraints: vec![],
             type_params: vec![],
+            param_names: vec![],
         };
 
         let module = Module {
@@ -861,6 +868,7 @@ mod tests {
             postconditions: vec![],
             constraints: vec![],
             type_params: vec![],
+            param_names: vec![],
         };
 
         let module = Module {
@@ -892,6 +900,30 @@ mod tests {
         assert_eq!(ty, Type::Prim(PrimType::F64));
     }
 
+    #[test]
+    fn test_fold_type() {
+        // fold : ∀n α β. [n]α → β → (β → α → β) → β
+        let mut checker = TypeChecker::new();
+        checker.ctx.define_global("step", Type::func_n([Type::Prim(PrimType::F64), Type::Prim(PrimType::I64)], Type::Prim(PrimType::F64)));
+        let fold = |arr: Expr| Expr::app(Expr::app(Expr::app(Expr::name("fold"), arr), Expr::Lit(Literal::Float(0.0))), Expr::name("step"));
+
+        let arr = Expr::Array(vec![Expr::Lit(Literal::Int(1)), Expr::Lit(Literal::Int(2)), Expr::Lit(Literal::Int(3))]);
+        assert!(checker.infer(&fold(arr)).is_ok());
+        // The first argument must be a tensor
+        assert!(checker.infer(&fold(Expr::Lit(Literal::Int(1)))).is_err());
+    }
+
+    #[test]
+    fn test_memoize_type() {
+        // memoize : ∀α β. (α → β) → α → β
+        let mut checker = TypeChecker::new();
+        checker.ctx.define_global("f", Type::func(Type::Prim(PrimType::I64), Type::Prim(PrimType::F64)));
+        let memoized = Expr::app(Expr::name("memoize"), Expr::name("f"));
+        assert!(checker.infer(&Expr::app(memoized, Expr::Lit(Literal::Int(3)))).is_ok());
+        // Only functions can be memoized
+        assert!(checker.infer(&Expr::app(Expr::name("memoize"), Expr::Lit(Literal::Int(1)))).is_err());
+    }
+
     #[test]
     fn test_tensor_concat_shapes() {
         // concat [2]F64 [3]F64 → [5]F64 (or [2+3]F64)
@@ -1149,4 +1181,101 @@ mod tests {
         let result = checker.infer(&expr);
         assert!(result.is_err(), "let x : [3]I64 = [1.0, 2.0, 3.0] should fail due to element type mismatch");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_argmax_returns_index() {
+        let mut checker = TypeChecker::new();
+        let xs = Expr::Array(vec![Expr::Lit(Literal::Int(1)), Expr::Lit(Literal::Int(3)), Expr::Lit(Literal::Int(2))]);
+        for name in ["argmin", "argmax"] {
+            let ty = checker.infer(&Expr::App(Box::new(Expr::Name(name.into())), Box::new(xs.clone()))).unwrap();
+            assert_eq!(ty, Type::Prim(PrimType::I64));
+        }
+        assert!(checker.infer(&Expr::App(Box::new(Expr::Name("argmax".into())), Box::new(Expr::Lit(Literal::Int(1))))).is_err());
+    }
+
+    #[test]
+    fn test_interval_annotation_types_as_its_base() {
+        use goth_ast::interval::{Bound, Interval};
+        // Interval bounds are checked at run time in contracts mode, so a looser inferred interval is accepted
+        let mut checker = TypeChecker::new();
+        let xs = Expr::Annot(Box::new(Expr::name("xs")), Type::tensor(goth_ast::shape::Shape::symbolic(&["n"]), Type::f64().with_interval(Interval::closed(Bound::Const(-1.0), Bound::Const(1.0)))));
+        checker.define("xs", Type::tensor(goth_ast::shape::Shape::symbolic(&["n"]), Type::f64()));
+        let total = Expr::Annot(Box::new(Expr::app(Expr::name("sum"), xs)), Type::f64().with_interval(Interval::non_negative()));
+        assert!(checker.infer(&total).is_ok());
+        assert!(checker.infer(&Expr::Annot(Box::new(Expr::float(1.5)), Type::f64().with_interval(Interval::unit()))).is_ok());
+        assert!(checker.infer(&Expr::Annot(Box::new(Expr::bool(true)), Type::f64().with_interval(Interval::unit()))).is_err());
+    }
+
+    #[test]
+    fn test_string_primitives() {
+        let mut checker = TypeChecker::new();
+        let s = |text: &str| Box::new(Expr::Lit(Literal::String(text.into())));
+        let len = Expr::App(Box::new(Expr::Name("str_len".into())), s("abc"));
+        assert_eq!(checker.infer(&len).unwrap(), Type::Prim(PrimType::I64));
+        let joined = Expr::App(Box::new(Expr::App(Box::new(Expr::Name("concat_str".into())), s("a"))), s("b"));
+        assert!(matches!(checker.infer(&joined).unwrap(), Type::Tensor(_, elem) if *elem == Type::Prim(PrimType::Char)));
+    }
+
+    #[test]
+    fn test_ord_and_chr() {
+        let mut checker = TypeChecker::new();
+        let ord = Expr::App(Box::new(Expr::Name("ord".into())), Box::new(Expr::Lit(Literal::Char('A'))));
+        assert_eq!(checker.infer(&ord).unwrap(), Type::Prim(PrimType::I64));
+        let chr = Expr::App(Box::new(Expr::Name("chr".into())), Box::new(Expr::Lit(Literal::Int(65))));
+        assert_eq!(checker.infer(&chr).unwrap(), Type::Prim(PrimType::Char));
+        assert!(checker.infer(&Expr::App(Box::new(Expr::Name("ord".into())), Box::new(Expr::Lit(Literal::Int(65))))).is_err());
+    }
+
+    #[test]
+    fn test_fill_returns_vector() {
+        let mut checker = TypeChecker::new();
+        let call = Expr::App(Box::new(Expr::App(Box::new(Expr::Name("fill".into())), Box::new(Expr::Lit(Literal::Int(3))))), Box::new(Expr::Lit(Literal::Float(0.5))));
+        match checker.infer(&call).unwrap() {
+            Type::Tensor(shape, _) => assert_eq!(shape.rank(), 1),
+            other => panic!("expected a tensor, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sign_keeps_its_argument_type() {
+        let mut checker = TypeChecker::new();
+        let ints = |ns: &[i128]| Expr::Array(ns.iter().map(|&n| Expr::Lit(Literal::Int(n))).collect());
+        assert!(checker.infer(&Expr::app(Expr::name("signum"), ints(&[-1, 2]))).is_ok());
+        assert!(checker.infer(&Expr::app(Expr::name("sign"), Expr::Lit(Literal::Float(-0.5)))).is_ok());
+    }
+
+    #[test]
+    fn test_clamp_scalars_and_tensors() {
+        let mut checker = TypeChecker::new();
+        let clamp = |x: Expr| Expr::app_n(Expr::name("clamp"), vec![x, Expr::Lit(Literal::Int(0)), Expr::Lit(Literal::Int(3))]);
+        assert!(checker.infer(&clamp(Expr::Lit(Literal::Int(5)))).is_ok());
+        assert!(checker.infer(&clamp(Expr::Array(vec![Expr::Lit(Literal::Int(-1)), Expr::Lit(Literal::Int(4))]))).is_ok());
+    }
+
+    #[test]
+    fn test_concat_axis_joins_matrices() {
+        let mut checker = TypeChecker::new();
+        let row = |ns: &[i128]| Expr::Array(ns.iter().map(|&n| Expr::Lit(Literal::Int(n))).collect());
+        let matrix = Expr::Array(vec![row(&[1, 2]), row(&[3, 4])]);
+        let joined = Expr::app_n(Expr::name("concat_axis"), vec![matrix.clone(), matrix.clone(), Expr::Lit(Literal::Int(1))]);
+        match checker.infer(&joined).unwrap() {
+            Type::Tensor(shape, _) => assert_eq!(shape.rank(), 2),
+            other => panic!("expected a tensor, got {:?}", other),
+        }
+        assert!(checker.infer(&Expr::app_n(Expr::name("concatAxis"), vec![matrix.clone(), Expr::Lit(Literal::Int(3)), Expr::Lit(Literal::Int(0))])).is_err());
+        assert!(checker.infer(&Expr::app_n(Expr::name("concatAxis"), vec![matrix.clone(), matrix, Expr::Lit(Literal::Float(0.0))])).is_err());
+    }
+
+    #[test]
+    fn test_zeros_and_ones_build_int_tensors() {
+        let mut checker = TypeChecker::new();
+        let shape = Expr::Array(vec![Expr::Lit(Literal::Int(2)), Expr::Lit(Literal::Int(3))]);
+        for name in ["zeros", "ones"] {
+            match checker.infer(&Expr::app(Expr::name(name), shape.clone())).unwrap() {
+                Type::Tensor(_, elem) => assert_eq!(*elem, Type::Prim(PrimType::I64)),
+                other => panic!("expected a tensor, got {:?}", other),
+            }
+            assert!(checker.infer(&Expr::app(Expr::name(name), Expr::Lit(Literal::Float(2.0)))).is_err());
+        }
+    }
+}
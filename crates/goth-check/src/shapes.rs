@@ -117,7 +117,7 @@ pub fn apply_subst(subst: &ShapeSubst, dim: &Dim) -> Dim {
         Dim::Var(v) => {
             subst.bindings.get(v.as_ref()).cloned().unwrap_or_else(|| dim.clone())
         }
-        Dim::Const(_) => dim.clone(),
+        Dim::Const(_) | Dim::Dynamic => dim.clone(),
         Dim::BinOp(l, op, r) => {
             let l_sub = apply_subst(subst, l);
             let r_sub = apply_subst(subst, r);
@@ -144,12 +144,17 @@ pub fn apply_subst_shape(subst: &ShapeSubst, shape: &Shape) -> Shape {
 /// - 3 + 4 → 7 (constant folding)
 pub fn simplify_dim(dim: &Dim) -> Dim {
     match dim {
-        Dim::Const(_) | Dim::Var(_) => dim.clone(),
+        Dim::Const(_) | Dim::Var(_) | Dim::Dynamic => dim.clone(),
 
         Dim::BinOp(l, op, r) => {
             let l = simplify_dim(l);
             let r = simplify_dim(r);
 
+            // Arithmetic on an unknown dimension is unknown
+            if l == Dim::Dynamic || r == Dim::Dynamic {
+                return Dim::Dynamic;
+            }
+
             // Constant folding
             if let (Dim::Const(n1), Dim::Const(n2)) = (&l, &r) {
                 return match op {
@@ -212,7 +217,7 @@ pub fn simplify_dim(dim: &Dim) -> Dim {
 pub fn dim_occurs_in(var: &str, dim: &Dim) -> bool {
     match dim {
         Dim::Var(v) => var == v.as_ref(),
-        Dim::Const(_) => false,
+        Dim::Const(_) | Dim::Dynamic => false,
         Dim::BinOp(l, _, r) => dim_occurs_in(var, l) || dim_occurs_in(var, r),
     }
 }
@@ -227,7 +232,7 @@ pub fn collect_dim_vars(dim: &Dim) -> HashSet<String> {
 fn collect_dim_vars_into(dim: &Dim, vars: &mut HashSet<String>) {
     match dim {
         Dim::Var(v) => { vars.insert(v.to_string()); }
-        Dim::Const(_) => {}
+        Dim::Const(_) | Dim::Dynamic => {}
         Dim::BinOp(l, _, r) => {
             collect_dim_vars_into(l, vars);
             collect_dim_vars_into(r, vars);
@@ -277,6 +282,11 @@ pub fn unify_dims(d1: &Dim, d2: &Dim, position: usize) -> TypeResult<ShapeSubst>
     let d2 = simplify_dim(d2);
 
     match (&d1, &d2) {
+        // A dimension only known at run time is accepted against anything
+        (Dim::Dynamic, _) | (_, Dim::Dynamic) => {
+            Ok(ShapeSubst::new())
+        }
+
         // Same constant: OK
         (Dim::Const(n1), Dim::Const(n2)) if n1 == n2 => {
             Ok(ShapeSubst::new())
@@ -674,6 +684,32 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_unify_shapes_symbolic_chain() {
+        // [n m] ~ [m 3]: n ↦ m, then m ↦ 3, so both resolve to 3
+        let sh1 = Shape(vec![Dim::Var("n".into()), Dim::Var("m".into())]);
+        let sh2 = Shape(vec![Dim::Var("m".into()), Dim::Const(3)]);
+        let s = unify_shapes(&sh1, &sh2).unwrap();
+        assert_eq!(apply_subst_shape(&s, &sh1), Shape(vec![Dim::Const(3), Dim::Const(3)]));
+        assert_eq!(apply_subst_shape(&s, &sh2), Shape(vec![Dim::Const(3), Dim::Const(3)]));
+    }
+
+    #[test]
+    fn test_unify_shapes_const_mismatch() {
+        // [n n] ~ [2 3]: n ↦ 2 is applied before the second dimension
+        let sh1 = Shape(vec![Dim::Var("n".into()), Dim::Var("n".into())]);
+        let sh2 = Shape(vec![Dim::Const(2), Dim::Const(3)]);
+        assert!(matches!(unify_shapes(&sh1, &sh2), Err(TypeError::DimMismatch { position: 1, .. })));
+    }
+
+    #[test]
+    fn test_unify_shapes_occurs_after_subst() {
+        // [n n] ~ [m m+1]: n ↦ m makes the second dimension m ~ m + 1
+        let sh1 = Shape(vec![Dim::Var("n".into()), Dim::Var("n".into())]);
+        let sh2 = Shape(vec![Dim::Var("m".into()), Dim::BinOp(Box::new(Dim::Var("m".into())), DimOp::Add, Box::new(Dim::Const(1)))]);
+        assert!(unify_shapes(&sh1, &sh2).is_err());
+    }
+
     #[test]
     fn test_occurs_check() {
         // n cannot unify with n + 1 (infinite dimension)
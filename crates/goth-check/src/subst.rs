@@ -132,7 +132,7 @@ pub fn apply_dim(subst: &Subst, dim: &Dim) -> Dim {
         Dim::Var(v) => {
             subst.shapes.get(v.as_ref()).cloned().unwrap_or_else(|| dim.clone())
         }
-        Dim::Const(_) => dim.clone(),
+        Dim::Const(_) | Dim::Dynamic => dim.clone(),
         Dim::BinOp(l, op, r) => {
             Dim::BinOp(
                 Box::new(apply_dim(subst, l)),
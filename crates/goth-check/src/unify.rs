@@ -46,6 +46,11 @@ pub fn unify(t1: &Type, t2: &Type) -> TypeResult<Subst> {
             unify_var(v, ty)
         }
 
+        // Interval refinements unify as their base type; annotations check the interval itself
+        (Type::Interval(base, _), ty) | (ty, Type::Interval(base, _)) => {
+            unify(base, ty)
+        }
+
         // Function types
         (Type::Fn(a1, r1), Type::Fn(a2, r2)) => {
             let s1 = unify(a1, a2)?;
@@ -830,8 +830,16 @@ fn run_from_json(path: &PathBuf, check: bool, render: bool, trace: bool, program
     println!("{}: parsed {} declaration(s)", "OK".green().bold(), module.decls.len());
 
     // JSON AST may contain both Names (for globals) and Idx (for lambdas)
-    // Resolve any remaining Names to indices
-    let module = resolve_module(module);
+    // Resolve any remaining Names to indices and rewrite named-argument calls
+    let module = match resolve_module_checked(module) {
+        Ok(module) => module,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("{}: {}", "Error".red().bold(), e);
+            }
+            return;
+        }
+    };
 
     // Type check if requested
     if check {
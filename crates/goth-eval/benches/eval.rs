@@ -0,0 +1,63 @@
+//! Interpreter benchmarks
+//!
+//! Run with `cargo bench -p goth-eval`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use goth_ast::prelude::*;
+use goth_eval::prelude::*;
+use goth_eval::{bytecode, prim};
+
+/// fib n = if n < 2 then n else fib (n - 1) + fib (n - 2)
+fn fib_evaluator() -> Evaluator {
+    let mut e = Evaluator::new();
+    let body = Expr::if_(
+        Expr::binop(BinOp::Lt, Expr::idx(0), Expr::int(2)),
+        Expr::idx(0),
+        Expr::add(
+            Expr::app(Expr::name("fib"), Expr::sub(Expr::idx(0), Expr::int(1))),
+            Expr::app(Expr::name("fib"), Expr::sub(Expr::idx(0), Expr::int(2))),
+        ),
+    );
+    let fib = e.eval(&Expr::lam(body)).unwrap();
+    e.define("fib", fib);
+    e
+}
+
+fn bench_scalar_binop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("binop");
+    let (a, b) = (Value::Int(2), Value::Int(3));
+    group.bench_function("general", |bench| bench.iter(|| prim::apply_binop(&BinOp::Add, black_box(a.clone()), black_box(b.clone()))));
+    group.bench_function("scalar_fast_path", |bench| bench.iter(|| prim::scalar_binop(&BinOp::Add, black_box(&a), black_box(&b))));
+    group.finish();
+}
+
+fn bench_fib(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fib");
+    group.sample_size(10);
+    let mut e = fib_evaluator();
+    let call = Expr::app(Expr::name("fib"), Expr::int(30));
+    group.bench_function("fib 30", |bench| bench.iter(|| e.eval(black_box(&call)).unwrap()));
+    let program = bytecode::compile(&call);
+    let env = Env::with_globals(e.globals());
+    group.bench_function("fib 30 compiled", |bench| bench.iter(|| e.run(black_box(&program), &env).unwrap()));
+    group.finish();
+}
+
+/// A 64-arm match over 0..64 with a catch-all, applied to its last arm
+fn bench_int_match(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match");
+    let mut e = Evaluator::new();
+    let mut arms: Vec<MatchArm> = (0..64).map(|n| MatchArm::new(Pattern::Lit(Literal::Int(n)), Expr::int(n * 10))).collect();
+    arms.push(MatchArm::new(Pattern::Wildcard, Expr::int(-1)));
+    let f = e.eval(&Expr::lam(Expr::match_(Expr::idx(0), arms))).unwrap();
+    e.define("f", f);
+    let call = Expr::app(Expr::name("f"), Expr::int(63));
+    group.bench_function("64 arms", |bench| bench.iter(|| e.eval(black_box(&call)).unwrap()));
+    let program = bytecode::compile(&call);
+    let env = Env::with_globals(e.globals());
+    group.bench_function("64 arms compiled", |bench| bench.iter(|| e.run(black_box(&program), &env).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_scalar_binop, bench_fib, bench_int_match);
+criterion_main!(benches);
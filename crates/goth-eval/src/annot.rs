@@ -0,0 +1,92 @@
+//! Runtime checks of type annotations
+//!
+//! Used by [`Evaluator::with_contracts`](crate::eval::Evaluator::with_contracts)
+//! to check annotated `let` bindings and typed patterns. Only what can be
+//! observed on a value is checked: the kind of scalars, tensor ranks and
+//! constant dimensions (shape variables must agree within one annotation),
+//! tuple arity, whether a value is callable and that numbers lie within an
+//! interval annotation (`F64⊢[0..∞]`). Type variables, variants and
+//! quantified types accept anything.
+
+use crate::error::{EvalError, EvalResult};
+use crate::value::Value;
+use goth_ast::interval::IntervalSet;
+use goth_ast::shape::Dim;
+use goth_ast::types::{PrimType, Type};
+use std::collections::HashMap;
+
+/// Check that `value` fits the annotation `ty`
+pub fn check_annotation(value: &Value, ty: &Type) -> EvalResult<()> {
+    check(value, ty, &mut HashMap::new())
+}
+
+fn check<'t>(value: &Value, ty: &'t Type, dims: &mut HashMap<&'t str, usize>) -> EvalResult<()> {
+    match ty {
+        Type::Prim(prim) => check_prim(value, *prim),
+        Type::Tensor(shape, elem) => {
+            let Value::Tensor(t) = value else { return Err(EvalError::type_error("Tensor", value)) };
+            if shape.0.len() != t.rank() {
+                return Err(EvalError::type_error_msg(format!("expected a tensor of shape {}, got shape {:?}", shape, t.shape)));
+            }
+            for (dim, &actual) in shape.0.iter().zip(&t.shape) {
+                let expected = match dim {
+                    Dim::Const(n) => Some(*n as usize),
+                    Dim::Var(name) => Some(*dims.entry(name).or_insert(actual)),
+                    Dim::BinOp(..) | Dim::Dynamic => None,
+                };
+                if expected.is_some_and(|n| n != actual) {
+                    return Err(EvalError::type_error_msg(format!("expected a tensor of shape {}, got shape {:?}", shape, t.shape)));
+                }
+            }
+            t.iter().try_for_each(|v| check(&v, elem, dims))
+        }
+        Type::Tuple(fields) if fields.is_empty() => match value { Value::Unit => Ok(()), _ => Err(EvalError::type_error("Unit", value)) },
+        Type::Tuple(fields) => match value {
+            Value::Tuple(vs) if vs.len() == fields.len() => vs.iter().zip(fields).try_for_each(|(v, f)| check(v, &f.ty, dims)),
+            Value::Tuple(vs) => Err(EvalError::type_error_msg(format!("expected a {}-tuple, got a {}-tuple", fields.len(), vs.len()))),
+            _ => Err(EvalError::type_error("Tuple", value)),
+        },
+        Type::Fn(..) => if value.is_callable() { Ok(()) } else { Err(EvalError::type_error("function", value)) },
+        Type::Interval(inner, set) => { check(value, inner, dims)?; check_interval(value, set) }
+        Type::Effectful(inner, _) => check(value, inner, dims),
+        Type::Refinement { base, .. } => check(value, base, dims),
+        Type::Uncertain(v, u) => match value {
+            Value::Uncertain { value, uncertainty } => { check(value, v, dims)?; check(uncertainty, u, dims) }
+            _ => Err(EvalError::type_error("Uncertain", value)),
+        },
+        Type::Variant(_) | Type::Forall(..) | Type::Exists(..) | Type::Var(_) | Type::App(..) | Type::Option(_) | Type::Hole => Ok(()),
+    }
+}
+
+/// Check that a number, or every number in a tensor, lies in `set`
+fn check_interval(value: &Value, set: &IntervalSet) -> EvalResult<()> {
+    let x = match value {
+        Value::Int(n) => *n as f64,
+        Value::Float(f) => f.0,
+        Value::Tensor(t) => return t.iter().try_for_each(|v| check_interval(&v, set)),
+        _ => return Ok(()),
+    };
+    if set.contains(x) { Ok(()) } else { Err(EvalError::type_error_msg(format!("{} is outside the interval {}", value, set))) }
+}
+
+fn check_prim(value: &Value, prim: PrimType) -> EvalResult<()> {
+    let ok = match prim {
+        PrimType::F64 | PrimType::F32 => matches!(value, Value::Float(_)),
+        PrimType::Bool => matches!(value, Value::Bool(_)),
+        PrimType::Char => matches!(value, Value::Char(_)),
+        PrimType::String => matches!(value, Value::Tensor(t) if t.to_string_value().is_some()),
+        // Every integer type, checked for kind but not range
+        _ => matches!(value, Value::Int(_)),
+    };
+    if ok { Ok(()) } else { Err(EvalError::TypeError { expected: prim_name(prim), got: value.type_name() }) }
+}
+
+fn prim_name(prim: PrimType) -> &'static str {
+    match prim {
+        PrimType::F64 | PrimType::F32 => "Float",
+        PrimType::Bool => "Bool",
+        PrimType::Char => "Char",
+        PrimType::String => "String",
+        _ => "Int",
+    }
+}
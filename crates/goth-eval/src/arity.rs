@@ -0,0 +1,112 @@
+//! Static arity checking
+//!
+//! A cheap pass over a resolved expression that flags applications of
+//! globals, primitives and lambda literals to more arguments than they can
+//! take. Only direct calls are checked: a callee whose result might itself
+//! be a function (higher-order code, locals, `fold`) is never reported.
+
+use crate::eval::{prim_arity, is_variadic, Evaluator};
+use crate::value::{PrimFn, Value};
+use goth_ast::expr::{Expr, DoOp};
+use thiserror::Error;
+
+/// A function applied to more arguments than it accepts
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{func} takes {expected} argument(s) but is applied to {got}")]
+pub struct ArityError {
+    pub func: String,
+    pub expected: usize,
+    pub got: usize,
+}
+
+/// Check `expr` (after name resolution) for over-applied calls, using the
+/// arities of the evaluator's globals
+pub fn check_arities(expr: &Expr, evaluator: &Evaluator) -> Result<(), Vec<ArityError>> {
+    let globals = evaluator.globals();
+    let globals = globals.borrow();
+    let mut errors = Vec::new();
+    walk(expr, &|name| globals.get(name).and_then(value_arity), &mut errors);
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Total number of arguments a value accepts, if it is known never to return a function
+fn value_arity(value: &Value) -> Option<usize> {
+    match value {
+        Value::Primitive(p) => prim_total_arity(p),
+        Value::Closure(c) => first_order_arity(&c.body).map(|n| c.arity as usize + n),
+        Value::Partial { func, args, .. } => value_arity(func).map(|n| n.saturating_sub(args.len())),
+        _ => None,
+    }
+}
+
+fn prim_total_arity(prim: &PrimFn) -> Option<usize> {
+    // fold returns its accumulator and index returns an element, either of which may be a function
+    if is_variadic(prim) || matches!(prim, PrimFn::Fold | PrimFn::Index) { None } else { Some(prim_arity(prim)) }
+}
+
+/// Number of lambdas wrapping `body`, if what lies beneath cannot evaluate to a function
+fn first_order_arity(body: &Expr) -> Option<usize> {
+    match body {
+        Expr::Lam(b) => first_order_arity(b).map(|n| n + 1),
+        Expr::LamN(k, b) => first_order_arity(b).map(|n| n + *k as usize),
+        _ if first_order(body) => Some(0),
+        _ => None,
+    }
+}
+
+/// Conservative: true only for expressions whose value is certainly not a function
+fn first_order(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) | Expr::BinOp(..) | Expr::UnaryOp(..) | Expr::Norm(_) | Expr::Array(_) | Expr::ArrayFill { .. } | Expr::Tuple(_) | Expr::Record(_) | Expr::Variant { .. } => true,
+        Expr::Let { body, .. } | Expr::LetRec { body, .. } | Expr::Annot(body, _) => first_order(body),
+        Expr::If { then_, else_, .. } => first_order(then_) && first_order(else_),
+        Expr::Match { arms, .. } => arms.iter().all(|arm| first_order(&arm.body)),
+        _ => false,
+    }
+}
+
+fn callee_name(head: &Expr) -> String {
+    match head {
+        Expr::Name(name) | Expr::Prim(name) => name.to_string(),
+        other => format!("{}", other),
+    }
+}
+
+fn walk(expr: &Expr, global_arity: &dyn Fn(&str) -> Option<usize>, errors: &mut Vec<ArityError>) {
+    let mut go = |e: &Expr| walk(e, global_arity, errors);
+    match expr {
+        Expr::App(..) => {
+            // Collect the whole application spine so `f a b c` is checked once
+            let mut args = Vec::new();
+            let mut head = expr;
+            while let Expr::App(f, x) = head { args.push(x.as_ref()); head = f; }
+            let expected = match head {
+                Expr::Name(name) | Expr::Prim(name) => global_arity(name),
+                Expr::Lam(_) | Expr::LamN(..) => first_order_arity(head),
+                _ => None,
+            };
+            if let Some(expected) = expected {
+                if args.len() > expected { errors.push(ArityError { func: callee_name(head), expected, got: args.len() }); }
+            }
+            walk(head, global_arity, errors);
+            for a in args.into_iter().rev() { walk(a, global_arity, errors); }
+        }
+        Expr::Idx(_) | Expr::Name(_) | Expr::Lit(_) | Expr::Prim(_) | Expr::Hole | Expr::Unknown(_) => {}
+        Expr::BinOp(_, l, r) => { go(l); go(r); }
+        Expr::Lam(body) | Expr::LamN(_, body) => go(body),
+        Expr::Let { value, body, .. } => { go(value); go(body); }
+        Expr::LetRec { bindings, body } => { for (_, e) in bindings { go(e); } go(body); }
+        Expr::Match { scrutinee, arms } => { go(scrutinee); for arm in arms { if let Some(guard) = &arm.guard { go(guard); } go(&arm.body); } }
+        Expr::If { cond, then_, else_ } => { go(cond); go(then_); go(else_); }
+        Expr::UnaryOp(_, e) | Expr::Norm(e) | Expr::Field(e, _) | Expr::Annot(e, _) | Expr::Cast { expr: e, .. } | Expr::Disabled(e) | Expr::Quote(e) | Expr::Unquote(e) => go(e),
+        Expr::Tuple(es) | Expr::Array(es) => { for e in es { go(e); } }
+        Expr::Record(fields) => { for (_, e) in fields { go(e); } }
+        Expr::AppNamed(f, args) => { go(f); for (_, e) in args { go(e); } }
+        Expr::ArrayFill { shape, value } => { for e in shape { go(e); } go(value); }
+        Expr::Variant { payload, .. } => { if let Some(p) = payload { go(p); } }
+        Expr::Index(base, indices) => { go(base); for e in indices { go(e); } }
+        Expr::Slice { array, start, end } => { go(array); for e in [start, end].into_iter().flatten() { go(e); } }
+        Expr::Update { base, fields } => { go(base); for (_, e) in fields { go(e); } }
+        Expr::Do { init, ops } => { go(init); for op in ops { match op { DoOp::Map(e) | DoOp::Filter(e) | DoOp::Bind(e) | DoOp::Op(_, e) | DoOp::Let(_, e) => go(e) } } }
+    }
+}
@@ -0,0 +1,179 @@
+//! Raw numeric buffers
+//!
+//! Tensors are exported as the packed bytes of their elements in row-major
+//! order, as NumPy lays out a C-contiguous array, so the buffer can be handed
+//! to `numpy.frombuffer` with a matching dtype and reshaped. Only the data is
+//! written: the shape, dtype and byte order travel separately.
+//!
+//! Int tensors export as `i64` and float tensors as `f64` unless another
+//! dtype is asked for. Ints convert to any dtype, failing if one doesn't fit;
+//! floats only export as float dtypes. Other tensors cannot be exported.
+//!
+//! [`write_npy`] and [`read_npy`] wrap the same buffers in NumPy's `.npy`
+//! format: a magic string, a version, and a header recording the dtype,
+//! `fortran_order` and shape, padded so the data starts on a 64-byte boundary.
+//! Written files are version 1.0, little-endian and in C order; reading also
+//! accepts big-endian data and the 4-byte header lengths of versions 2 and 3.
+
+use crate::error::{EvalError, EvalResult};
+use crate::value::{Tensor, TensorData, Value};
+use std::path::Path;
+
+/// Byte order of each element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// Element type of a raw buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl DType {
+    /// Bytes per element
+    pub fn size(self) -> usize {
+        match self { DType::I32 | DType::F32 => 4, DType::I64 | DType::F64 => 8 }
+    }
+}
+
+/// The elements of the tensor `value` as `i64`s or `f64`s in the given byte order
+pub fn tensor_to_bytes(value: &Value, endian: Endian) -> EvalResult<Vec<u8>> {
+    let tensor = numeric_tensor(value)?;
+    let dtype = if matches!(tensor.data, TensorData::Int(_)) { DType::I64 } else { DType::F64 };
+    tensor_to_bytes_as(value, dtype, endian)
+}
+
+/// The elements of the tensor `value` converted to `dtype`, in the given byte order
+pub fn tensor_to_bytes_as(value: &Value, dtype: DType, endian: Endian) -> EvalResult<Vec<u8>> {
+    let tensor = numeric_tensor(value)?;
+    let mut bytes = Vec::with_capacity(tensor.len() * dtype.size());
+    match (&tensor.data, dtype) {
+        (TensorData::Int(ns), DType::I32) => for &n in ns { let x: i32 = fit(n, dtype)?; put(&mut bytes, endian, x.to_le_bytes(), x.to_be_bytes()) },
+        (TensorData::Int(ns), DType::I64) => for &n in ns { let x: i64 = fit(n, dtype)?; put(&mut bytes, endian, x.to_le_bytes(), x.to_be_bytes()) },
+        (TensorData::Int(ns), DType::F32) => for &n in ns { put(&mut bytes, endian, (n as f32).to_le_bytes(), (n as f32).to_be_bytes()) },
+        (TensorData::Int(ns), DType::F64) => for &n in ns { put(&mut bytes, endian, (n as f64).to_le_bytes(), (n as f64).to_be_bytes()) },
+        (TensorData::Float(fs), DType::F32) => for f in fs { put(&mut bytes, endian, (f.0 as f32).to_le_bytes(), (f.0 as f32).to_be_bytes()) },
+        (TensorData::Float(fs), DType::F64) => for f in fs { put(&mut bytes, endian, f.0.to_le_bytes(), f.0.to_be_bytes()) },
+        (_, dtype) => return Err(EvalError::type_error_msg(format!("cannot export a float tensor as {:?}", dtype))),
+    }
+    Ok(bytes)
+}
+
+/// A tensor of `shape` read from `bytes` holding `dtype` elements in the given byte order.
+/// Integer dtypes give an Int tensor and float dtypes a Float tensor.
+pub fn tensor_from_bytes(bytes: &[u8], shape: &[usize], dtype: DType, endian: Endian) -> EvalResult<Value> {
+    let len: usize = shape.iter().product();
+    if bytes.len() != len * dtype.size() {
+        return Err(EvalError::shape_mismatch(format!("{} bytes of {:?} do not fill a tensor of shape {:?}", bytes.len(), dtype, shape)));
+    }
+    let chunks = bytes.chunks_exact(dtype.size());
+    let tensor = match dtype {
+        DType::I32 => Tensor::from_ints(chunks.map(|c| read(endian, c, i32::from_le_bytes, i32::from_be_bytes) as i128).collect()),
+        DType::I64 => Tensor::from_ints(chunks.map(|c| read(endian, c, i64::from_le_bytes, i64::from_be_bytes) as i128).collect()),
+        DType::F32 => Tensor::from_floats(chunks.map(|c| read(endian, c, f32::from_le_bytes, f32::from_be_bytes) as f64).collect()),
+        DType::F64 => Tensor::from_floats(chunks.map(|c| read(endian, c, f64::from_le_bytes, f64::from_be_bytes)).collect()),
+    };
+    Ok(Value::Tensor(Tensor { shape: shape.to_vec(), data: tensor.data }))
+}
+
+/// Leading bytes of every `.npy` file
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// The data of a `.npy` file begins at a multiple of this many bytes
+const NPY_ALIGN: usize = 64;
+
+/// The tensor `value` in `.npy` format, as `<i8` or `<f8`
+pub fn to_npy(value: &Value) -> EvalResult<Vec<u8>> {
+    let tensor = numeric_tensor(value)?;
+    let descr = if matches!(tensor.data, TensorData::Int(_)) { "<i8" } else { "<f8" };
+    let shape = match tensor.shape.as_slice() {
+        [n] => format!("({},)", n),
+        dims => format!("({})", dims.iter().map(usize::to_string).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    // Pad as NumPy does, always with at least one space before the newline
+    let padding = NPY_ALIGN - (NPY_MAGIC.len() + 4 + header.len() + 1) % NPY_ALIGN;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+    let header_len = u16::try_from(header.len()).map_err(|_| EvalError::Serialization(format!("npy header for shape {:?} is too long", tensor.shape)))?;
+    let mut bytes = NPY_MAGIC.to_vec();
+    bytes.extend([1, 0]);
+    bytes.extend(header_len.to_le_bytes());
+    bytes.extend(header.into_bytes());
+    bytes.extend(tensor_to_bytes(value, Endian::Little)?);
+    Ok(bytes)
+}
+
+/// The tensor stored in the `.npy` file contents `bytes`
+pub fn from_npy(bytes: &[u8]) -> EvalResult<Value> {
+    let malformed = |what: &str| EvalError::Serialization(format!("malformed npy file: {}", what));
+    let rest = bytes.strip_prefix(NPY_MAGIC).ok_or_else(|| malformed("missing magic string"))?;
+    let (header_len, rest) = match rest {
+        [1, _, a, b, rest @ ..] => (u16::from_le_bytes([*a, *b]) as usize, rest),
+        [2 | 3, _, a, b, c, d, rest @ ..] => (u32::from_le_bytes([*a, *b, *c, *d]) as usize, rest),
+        _ => return Err(malformed("unsupported version")),
+    };
+    if rest.len() < header_len { return Err(malformed("truncated header")); }
+    let (header, data) = rest.split_at(header_len);
+    let header = std::str::from_utf8(header).map_err(|_| malformed("header is not text"))?;
+    let descr = header_value(header, "descr").and_then(|v| v.strip_prefix('\'')).and_then(|v| v.split('\'').next()).ok_or_else(|| malformed("missing descr"))?;
+    let (endian, dtype) = parse_descr(descr).ok_or_else(|| EvalError::Serialization(format!("unsupported npy dtype {}", descr)))?;
+    if header_value(header, "fortran_order").is_none_or(|v| !v.starts_with("False")) {
+        return Err(EvalError::Serialization("only C-ordered npy files can be read".into()));
+    }
+    let dims = header_value(header, "shape").and_then(|v| v.strip_prefix('(')).and_then(|v| v.split(')').next()).ok_or_else(|| malformed("missing shape"))?;
+    let shape = dims.split(',').map(str::trim).filter(|d| !d.is_empty()).map(str::parse).collect::<Result<Vec<usize>, _>>().map_err(|_| malformed("bad shape"))?;
+    tensor_from_bytes(data, &shape, dtype, endian)
+}
+
+/// Write the tensor `value` to `path` as a `.npy` file
+pub fn write_npy(value: &Value, path: impl AsRef<Path>) -> EvalResult<()> {
+    let path = path.as_ref();
+    std::fs::write(path, to_npy(value)?).map_err(|e| EvalError::IoError(format!("Failed to write '{}': {}", path.display(), e)))
+}
+
+/// Read the tensor in the `.npy` file at `path`
+pub fn read_npy(path: impl AsRef<Path>) -> EvalResult<Value> {
+    let path = path.as_ref();
+    from_npy(&std::fs::read(path).map_err(|e| EvalError::IoError(format!("Failed to read '{}': {}", path.display(), e)))?)
+}
+
+/// The text following `'key':` in a header dictionary
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}':", key))? + key.len() + 3;
+    Some(header[start..].trim_start())
+}
+
+/// Byte order and element type of a NumPy type string such as `<i8`
+fn parse_descr(descr: &str) -> Option<(Endian, DType)> {
+    let endian = match descr.chars().next()? { '<' | '|' => Endian::Little, '>' => Endian::Big, _ => return None };
+    let dtype = match &descr[1..] { "i4" => DType::I32, "i8" => DType::I64, "f4" => DType::F32, "f8" => DType::F64, _ => return None };
+    Some((endian, dtype))
+}
+
+fn numeric_tensor(value: &Value) -> EvalResult<&Tensor> {
+    match value {
+        Value::Tensor(t) if matches!(t.data, TensorData::Int(_) | TensorData::Float(_)) => Ok(t),
+        Value::Tensor(_) => Err(EvalError::type_error_msg("only Int and Float tensors can be exported as raw bytes")),
+        _ => Err(EvalError::type_error("Tensor", value)),
+    }
+}
+
+fn fit<T: TryFrom<i128>>(n: i128, dtype: DType) -> EvalResult<T> {
+    T::try_from(n).map_err(|_| EvalError::type_error_msg(format!("{} does not fit in {:?}", n, dtype)))
+}
+
+fn put<const N: usize>(bytes: &mut Vec<u8>, endian: Endian, le: [u8; N], be: [u8; N]) {
+    bytes.extend_from_slice(match endian { Endian::Little => &le, Endian::Big => &be });
+}
+
+fn read<const N: usize, T>(endian: Endian, chunk: &[u8], le: fn([u8; N]) -> T, be: fn([u8; N]) -> T) -> T {
+    let array: [u8; N] = chunk.try_into().expect("chunk of the element size");
+    match endian { Endian::Little => le(array), Endian::Big => be(array) }
+}
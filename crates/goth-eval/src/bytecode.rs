@@ -0,0 +1,333 @@
+//! Bytecode compilation
+//!
+//! [`compile`] flattens an expression into a program for a small stack
+//! machine and [`run`] executes it. The tree-walking evaluator stays the
+//! reference: anything without a dedicated instruction compiles to
+//! [`Instr::Eval`], which hands the subexpression to the interpreter in the
+//! current environment, so compiled and interpreted results agree.
+//!
+//! Calls to global closures with exactly their arity run the callee's body
+//! compiled (bodies are cached on the evaluator, and tail calls reuse the
+//! current frame). Every other application goes through the interpreter.
+//!
+//! A match whose arms are integer literals covering a dense range (plus an
+//! optional catch-all) compiles to a jump table instead of testing each arm.
+
+use crate::eval::{literal_value, Evaluator, STACK_RED_ZONE, STACK_SEGMENT};
+use crate::value::{Value, Env};
+use crate::error::{EvalError, EvalResult, OptionExt};
+use goth_ast::expr::{Expr, MatchArm};
+use goth_ast::literal::Literal;
+use goth_ast::op::{BinOp, UnaryOp};
+use goth_ast::pattern::Pattern;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    /// Push a constant
+    Const(Value),
+    /// Push the local at a de Bruijn index
+    Local(u32),
+    /// Push a global by name
+    Global(Box<str>),
+    /// Pop right then left, push `left op right`
+    BinOp(BinOp),
+    /// Pop an operand, push `op operand`
+    UnaryOp(UnaryOp),
+    /// Pop a Bool and jump to the target if it is false
+    JumpIfFalse(usize),
+    /// Jump to the target
+    Jump(usize),
+    /// Pop a value and bind it as local ₀
+    Bind,
+    /// Drop the innermost locals
+    Unbind(u32),
+    /// Pop an argument then a function, push the application
+    Call,
+    /// Pop the given number of arguments and apply the named global to them
+    CallGlobal(Box<str>, usize),
+    /// Pop the given number of values, push them as a tuple
+    Tuple(usize),
+    /// Jump on the Int on top of the stack: pop it and go to `targets[n - base]`,
+    /// or leave any other value in place and go to `default`
+    Switch { base: i128, targets: Vec<usize>, default: usize },
+    /// Fail with a non-exhaustive match
+    NoMatch,
+    /// Evaluate an expression with the interpreter
+    Eval(Box<Expr>),
+}
+
+/// A compiled expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    code: Rc<[Instr]>,
+}
+
+impl Program {
+    pub fn instrs(&self) -> &[Instr] { &self.code }
+}
+
+/// Compile an expression to bytecode
+pub fn compile(expr: &Expr) -> Program {
+    let mut compiler = Compiler { code: Vec::new() };
+    compiler.expr(expr);
+    Program { code: compiler.code.into() }
+}
+
+/// Run a program in `env` with a fresh evaluator
+pub fn run(program: &Program, env: &Env) -> EvalResult<Value> {
+    Evaluator::new().run(program, env)
+}
+
+impl Evaluator {
+    /// Run a compiled program in `env`
+    pub fn run(&mut self, program: &Program, env: &Env) -> EvalResult<Value> {
+        let max_depth = self.max_depth();
+        Vm { evaluator: self, compiled: HashMap::new(), depth: 0, max_depth }.exec(program.clone(), env.clone())
+    }
+}
+
+struct Compiler {
+    code: Vec<Instr>,
+}
+
+impl Compiler {
+    fn emit(&mut self, instr: Instr) -> usize { self.code.push(instr); self.code.len() - 1 }
+
+    fn patch(&mut self, at: usize) {
+        let target = self.code.len();
+        match &mut self.code[at] { Instr::Jump(t) | Instr::JumpIfFalse(t) => *t = target, _ => unreachable!("patching a non-jump") }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(lit) => { self.emit(Instr::Const(literal_value(lit))); }
+            Expr::Idx(i) => { self.emit(Instr::Local(*i)); }
+            Expr::Name(name) => { self.emit(Instr::Global(name.clone())); }
+            // Annotations are left to the evaluator, which checks them in contracts mode
+            Expr::Annot(..) => { self.emit(Instr::Eval(Box::new(expr.clone()))); }
+            Expr::BinOp(BinOp::And, l, r) => {
+                // l ? r : false
+                self.expr(l);
+                let on_false = self.emit(Instr::JumpIfFalse(0));
+                self.expr(r);
+                let end = self.emit(Instr::Jump(0));
+                self.patch(on_false);
+                self.emit(Instr::Const(Value::Bool(false)));
+                self.patch(end);
+            }
+            Expr::BinOp(BinOp::Or, l, r) => {
+                // l ? true : r
+                self.expr(l);
+                let on_false = self.emit(Instr::JumpIfFalse(0));
+                self.emit(Instr::Const(Value::Bool(true)));
+                let end = self.emit(Instr::Jump(0));
+                self.patch(on_false);
+                self.expr(r);
+                self.patch(end);
+            }
+            Expr::BinOp(BinOp::Map | BinOp::Filter | BinOp::Bind | BinOp::Compose, ..) => { self.emit(Instr::Eval(Box::new(expr.clone()))); }
+            Expr::BinOp(op, l, r) => { self.expr(l); self.expr(r); self.emit(Instr::BinOp(op.clone())); }
+            Expr::UnaryOp(op, operand) => { self.expr(operand); self.emit(Instr::UnaryOp(*op)); }
+            Expr::If { cond, then_, else_ } => {
+                self.expr(cond);
+                let on_false = self.emit(Instr::JumpIfFalse(0));
+                self.expr(then_);
+                let end = self.emit(Instr::Jump(0));
+                self.patch(on_false);
+                self.expr(else_);
+                self.patch(end);
+            }
+            // Annotated lets are left to the evaluator, which checks them in contracts mode
+            Expr::Let { pattern: Pattern::Var(_) | Pattern::Wildcard, type_: None, value, body } => {
+                self.expr(value);
+                self.emit(Instr::Bind);
+                self.expr(body);
+                self.emit(Instr::Unbind(1));
+            }
+            Expr::Tuple(es) => { for e in es { self.expr(e); } self.emit(Instr::Tuple(es.len())); }
+            Expr::App(..) => self.app(expr),
+            Expr::Match { scrutinee, arms } => match int_switch(arms) {
+                Some(switch) => self.switch(scrutinee, arms, switch),
+                None => { self.emit(Instr::Eval(Box::new(expr.clone()))); }
+            },
+            _ => { self.emit(Instr::Eval(Box::new(expr.clone()))); }
+        }
+    }
+
+    fn switch(&mut self, scrutinee: &Expr, arms: &[MatchArm], switch: IntSwitch) {
+        self.expr(scrutinee);
+        let at = self.emit(Instr::NoMatch);
+        let mut arm_starts = vec![0; arms.len()];
+        let mut ends = Vec::new();
+        for (i, start) in arm_starts.iter_mut().enumerate() {
+            if !switch.table.contains(&Some(i)) { continue; }
+            *start = self.code.len();
+            self.expr(&arms[i].body);
+            ends.push(self.emit(Instr::Jump(0)));
+        }
+        // The catch-all binds the scrutinee, which the switch leaves on the stack
+        let default = self.code.len();
+        match switch.default {
+            Some(i) => { self.emit(Instr::Bind); self.expr(&arms[i].body); self.emit(Instr::Unbind(1)); }
+            None => { self.emit(Instr::NoMatch); }
+        }
+        for end in ends { self.patch(end); }
+        let targets = switch.table.iter().map(|arm| arm.map_or(default, |i| arm_starts[i])).collect();
+        self.code[at] = Instr::Switch { base: switch.base, targets, default };
+    }
+
+    /// `f a b …` on a global `f` becomes a single `CallGlobal`. The interpreter applies
+    /// `f a` before evaluating `b`, so arguments after the first must be trivially pure
+    /// for the two to agree; otherwise each argument is applied in turn.
+    fn app(&mut self, expr: &Expr) {
+        let mut args = Vec::new();
+        let mut head = expr;
+        while let Expr::App(f, x) = head { args.push(x.as_ref()); head = f; }
+        args.reverse();
+        let trivial = |e: &&Expr| matches!(e, Expr::Lit(_) | Expr::Idx(_) | Expr::Name(_));
+        match head {
+            Expr::Name(name) if args[1..].iter().all(trivial) => {
+                for a in &args { self.expr(a); }
+                self.emit(Instr::CallGlobal(name.clone(), args.len()));
+            }
+            _ => {
+                self.expr(head);
+                for a in args { self.expr(a); self.emit(Instr::Call); }
+            }
+        }
+    }
+}
+
+/// Smallest number of literal arms worth a jump table
+const MIN_SWITCH_ARMS: usize = 4;
+
+/// Jump table for a match over integer literals: `table[n - base]` is the arm taken for `n`
+struct IntSwitch {
+    base: i128,
+    table: Vec<Option<usize>>,
+    /// Wildcard or variable arm taken for everything else
+    default: Option<usize>,
+}
+
+/// A jump table for `arms` if they are unguarded integer literals, at least half
+/// dense over their range, optionally ending in a catch-all
+fn int_switch(arms: &[MatchArm]) -> Option<IntSwitch> {
+    let mut cases: Vec<(i128, usize)> = Vec::new();
+    let mut default = None;
+    for (i, arm) in arms.iter().enumerate() {
+        if arm.guard.is_some() { return None; }
+        match &arm.pattern {
+            // Earlier arms win, so repeated literals are unreachable
+            Pattern::Lit(Literal::Int(n)) => { if !cases.iter().any(|(m, _)| m == n) { cases.push((*n, i)); } }
+            Pattern::Wildcard | Pattern::Var(_) => { default = Some(i); break; }
+            _ => return None,
+        }
+    }
+    let lo = cases.iter().map(|(n, _)| *n).min()?;
+    let hi = cases.iter().map(|(n, _)| *n).max()?;
+    let span = usize::try_from(hi - lo).ok()?.checked_add(1)?;
+    if cases.len() < MIN_SWITCH_ARMS || span > 2 * cases.len() { return None; }
+    let mut table = vec![None; span];
+    for (n, i) in cases { table[(n - lo) as usize] = Some(i); }
+    Some(IntSwitch { base: lo, table, default })
+}
+
+struct Vm<'a> {
+    evaluator: &'a mut Evaluator,
+    /// Compiled bodies of global closures, trusted without checking for the rest of the run
+    /// since globals do not change during evaluation
+    compiled: HashMap<Box<str>, Program>,
+    depth: usize,
+    max_depth: usize,
+}
+
+fn pop(stack: &mut Vec<Value>) -> EvalResult<Value> { stack.pop().ok_or_else(|| EvalError::internal("bytecode stack underflow")) }
+
+/// True if nothing but jumps and unbinds remain before the end of the program
+fn is_tail(code: &[Instr], mut pc: usize) -> bool {
+    loop {
+        match code.get(pc) { None => return true, Some(Instr::Jump(t)) => pc = *t, Some(Instr::Unbind(_)) => pc += 1, Some(_) => return false }
+    }
+}
+
+impl Vm<'_> {
+    /// Count a call or backward jump against the evaluator's step limit and poll for cancellation,
+    /// so loops that never return to the tree-walker are still bounded
+    fn tick(&mut self) -> EvalResult<()> {
+        self.evaluator.take_step()?;
+        self.evaluator.check_cancelled()
+    }
+
+    fn exec(&mut self, mut program: Program, mut env: Env) -> EvalResult<Value> {
+        let mut stack: Vec<Value> = Vec::new();
+        let mut pc = 0;
+        while let Some(instr) = program.code.get(pc) {
+            pc += 1;
+            match instr {
+                Instr::Const(v) => stack.push(v.clone()),
+                Instr::Local(i) => stack.push(self.evaluator.lookup_index(&env, *i)?),
+                Instr::Global(name) => stack.push(env.get_global(name).ok_or_undefined(name)?),
+                Instr::BinOp(op) => {
+                    let r = pop(&mut stack)?; let l = pop(&mut stack)?;
+                    stack.push(self.evaluator.binop_values(op, l, r)?);
+                }
+                Instr::UnaryOp(op) => { let v = pop(&mut stack)?; stack.push(self.evaluator.unaryop_value(op, v)?); }
+                Instr::JumpIfFalse(target) => match pop(&mut stack)? { Value::Bool(true) => {}, Value::Bool(false) => pc = *target, other => return Err(EvalError::type_error("Bool", &other)) },
+                Instr::Jump(target) => { if *target < pc { self.tick()?; } pc = *target }
+                Instr::Bind => { let v = pop(&mut stack)?; env.push(v); }
+                Instr::Unbind(n) => env.pop_n(*n as usize),
+                Instr::Call => { self.tick()?; let arg = pop(&mut stack)?; let func = pop(&mut stack)?; stack.push(self.evaluator.apply(func, arg)?); }
+                Instr::CallGlobal(name, argc) => {
+                    self.tick()?;
+                    let args = stack.split_off(stack.len() - argc);
+                    match self.compiled_callee(name, *argc, &env) {
+                        Some((callee, mut callee_env)) => {
+                            callee_env.push_many(args);
+                            if is_tail(&program.code, pc) { program = callee; env = callee_env; stack.clear(); pc = 0; continue; }
+                            self.depth += 1;
+                            if self.depth > self.max_depth { self.depth -= 1; return Err(EvalError::StackOverflow { limit: self.max_depth }); }
+                            let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || self.exec(callee, callee_env));
+                            self.depth -= 1;
+                            stack.push(result?);
+                        }
+                        None => {
+                            let mut value = env.get_global(name).ok_or_undefined(name)?;
+                            for arg in args { value = self.evaluator.apply(value, arg)?; }
+                            stack.push(value);
+                        }
+                    }
+                }
+                Instr::Tuple(n) => { let values = stack.split_off(stack.len() - n); stack.push(Value::tuple(values)); }
+                Instr::Switch { base, targets, default } => {
+                    let hit = match stack.last() { Some(Value::Int(n)) => n.checked_sub(*base).and_then(|i| usize::try_from(i).ok()).and_then(|i| targets.get(i)).copied(), _ => None };
+                    // Literal arms bind nothing; the catch-all binds the scrutinee
+                    pc = match hit { Some(target) if target != *default => { stack.pop(); target } _ => *default };
+                }
+                Instr::NoMatch => return Err(EvalError::NonExhaustiveMatch),
+                Instr::Eval(expr) => stack.push(self.evaluator.eval_with_env(expr, &env)?),
+            }
+        }
+        pop(&mut stack)
+    }
+
+    /// Compiled body and environment of a global closure taking exactly `argc` arguments and without contracts
+    fn compiled_callee(&mut self, name: &str, argc: usize, env: &Env) -> Option<(Program, Env)> {
+        let globals = env.globals.borrow();
+        let Some(Value::Closure(c)) = globals.get(name) else { return None };
+        if c.arity as usize != argc || !c.preconditions.is_empty() || !c.postconditions.is_empty() { return None; }
+        if let Some(program) = self.compiled.get(name) { return Some((program.clone(), c.env.clone())); }
+        // First call this run: reuse the evaluator's copy if the global still has the same body
+        let program = match self.evaluator.compiled_globals.get(name) {
+            Some((body, program)) if *body == c.body => program.clone(),
+            _ => {
+                let program = compile(&c.body);
+                self.evaluator.compiled_globals.insert(name.into(), (c.body.clone(), program.clone()));
+                program
+            }
+        };
+        self.compiled.insert(name.into(), program.clone());
+        Some((program, c.env.clone()))
+    }
+}
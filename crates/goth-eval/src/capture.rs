@@ -0,0 +1,70 @@
+//! Free-variable analysis for closure capture
+//!
+//! Mirrors how the evaluator extends environments (every `Var` and
+//! `Wildcard` in a pattern occupies a slot), so the indices reported here
+//! are exactly the environment slots a closure body can read.
+
+use goth_ast::expr::{Expr, DoOp};
+use goth_ast::pattern::Pattern;
+use std::collections::BTreeSet;
+
+/// De Bruijn indices that are free in `body` once `binders` parameters are
+/// bound, relative to the enclosing environment.
+pub fn free_variables(body: &Expr, binders: u32) -> BTreeSet<u32> {
+    let mut free = BTreeSet::new();
+    walk(body, binders, &mut free);
+    free
+}
+
+/// Number of environment slots pushed when `pattern` matches.
+/// `Or` branches may differ, so both the smallest and largest counts are returned.
+pub(crate) fn pattern_slots(pattern: &Pattern) -> (u32, u32) {
+    let sum = |pats: &[Pattern]| pats.iter().map(pattern_slots).fold((0, 0), |(lo, hi), (l, h)| (lo + l, hi + h));
+    match pattern {
+        Pattern::Wildcard | Pattern::Var(_) => (1, 1),
+        Pattern::Lit(_) => (0, 0),
+        Pattern::Array(pats) | Pattern::Tuple(pats) => sum(pats),
+        Pattern::ArraySplit { head, tail } => { let (lo, hi) = sum(head); let (tl, th) = pattern_slots(tail); (lo + tl, hi + th) }
+        Pattern::Variant { payload, .. } => payload.as_ref().map_or((0, 0), |p| pattern_slots(p)),
+        Pattern::Typed(p, _) | Pattern::Guard(p, _) => pattern_slots(p),
+        Pattern::Or(p1, p2) => { let (l1, h1) = pattern_slots(p1); let (l2, h2) = pattern_slots(p2); (l1.min(l2), h1.max(h2)) }
+    }
+}
+
+/// Walk `expr` under a binder introduced by `pattern`
+fn walk_under(pattern: &Pattern, expr: &Expr, depth: u32, free: &mut BTreeSet<u32>) {
+    let (lo, hi) = pattern_slots(pattern);
+    walk(expr, depth + lo, free);
+    if hi != lo { walk(expr, depth + hi, free); }
+}
+
+fn walk(expr: &Expr, depth: u32, free: &mut BTreeSet<u32>) {
+    match expr {
+        Expr::Idx(i) => { if *i >= depth { free.insert(*i - depth); } }
+        Expr::Name(_) | Expr::Lit(_) | Expr::Prim(_) | Expr::Hole | Expr::Unknown(_) => {}
+        Expr::App(f, x) | Expr::BinOp(_, f, x) => { walk(f, depth, free); walk(x, depth, free); }
+        Expr::Lam(body) => walk(body, depth + 1, free),
+        Expr::LamN(n, body) => walk(body, depth + n, free),
+        Expr::Let { pattern, value, body, .. } => { walk(value, depth, free); walk_under(pattern, body, depth, free); }
+        Expr::LetRec { bindings, body } => { let inner = depth + bindings.len() as u32; for (_, e) in bindings { walk(e, inner, free); } walk(body, inner, free); }
+        Expr::Match { scrutinee, arms } => {
+            walk(scrutinee, depth, free);
+            for arm in arms { if let Some(guard) = &arm.guard { walk_under(&arm.pattern, guard, depth, free); } walk_under(&arm.pattern, &arm.body, depth, free); }
+        }
+        Expr::If { cond, then_, else_ } => { walk(cond, depth, free); walk(then_, depth, free); walk(else_, depth, free); }
+        Expr::UnaryOp(_, e) | Expr::Norm(e) | Expr::Field(e, _) | Expr::Annot(e, _) | Expr::Cast { expr: e, .. } | Expr::Disabled(e) | Expr::Quote(e) | Expr::Unquote(e) => walk(e, depth, free),
+        Expr::Tuple(es) | Expr::Array(es) => { for e in es { walk(e, depth, free); } }
+        Expr::Record(fields) => { for (_, e) in fields { walk(e, depth, free); } }
+        Expr::AppNamed(f, args) => { walk(f, depth, free); for (_, e) in args { walk(e, depth, free); } }
+        Expr::ArrayFill { shape, value } => { for e in shape { walk(e, depth, free); } walk(value, depth, free); }
+        Expr::Variant { payload, .. } => { if let Some(p) = payload { walk(p, depth, free); } }
+        Expr::Index(base, indices) => { walk(base, depth, free); for e in indices { walk(e, depth, free); } }
+        Expr::Slice { array, start, end } => { walk(array, depth, free); for e in [start, end].into_iter().flatten() { walk(e, depth, free); } }
+        Expr::Update { base, fields } => { walk(base, depth, free); for (_, e) in fields { walk(e, depth, free); } }
+        Expr::Do { init, ops } => {
+            // eval_do evaluates every op in the enclosing environment
+            walk(init, depth, free);
+            for op in ops { match op { DoOp::Map(e) | DoOp::Filter(e) | DoOp::Bind(e) | DoOp::Op(_, e) | DoOp::Let(_, e) => walk(e, depth, free) } }
+        }
+    }
+}
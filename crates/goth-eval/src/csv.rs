@@ -0,0 +1,86 @@
+//! CSV import and export of numeric tensors
+//!
+//! Reading gives a rank-2 Float tensor of rows × columns; every row must have
+//! as many fields as the first, and malformed text is a `Serialization` error.
+//! Writing accepts Int and Float tensors of rank 1, one element per line, or
+//! rank 2 with at least one column, one row per line. Fields are plain
+//! numbers: quoting is not supported.
+
+use crate::error::{EvalError, EvalResult};
+use crate::value::{Tensor, TensorData, Value};
+use std::path::Path;
+
+/// How fields are separated and whether the first line is a header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// Skip the first line when reading; ignored when writing
+    pub skip_header: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self { CsvOptions { delimiter: ',', skip_header: false } }
+}
+
+impl CsvOptions {
+    pub fn with_delimiter(mut self, delimiter: char) -> Self { self.delimiter = delimiter; self }
+    pub fn with_skip_header(mut self, skip: bool) -> Self { self.skip_header = skip; self }
+}
+
+/// The Float matrix in CSV `text`. Blank lines are ignored; errors name the 1-based line.
+pub fn parse_csv(text: &str, options: &CsvOptions) -> EvalResult<Value> {
+    let mut cols = None;
+    let mut data = Vec::new();
+    let lines = text.lines().enumerate().skip(usize::from(options.skip_header));
+    for (i, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+        let row = line.split(options.delimiter).map(|field| {
+            let field = field.trim();
+            field.parse::<f64>().map_err(|_| EvalError::Serialization(format!("CSV line {}: '{}' is not a number", i + 1, field)))
+        }).collect::<EvalResult<Vec<_>>>()?;
+        let expected = *cols.get_or_insert(row.len());
+        if row.len() != expected {
+            return Err(EvalError::Serialization(format!("CSV line {} has {} fields, expected {}", i + 1, row.len(), expected)));
+        }
+        data.extend(row);
+    }
+    let cols = cols.unwrap_or(0);
+    let rows = data.len().checked_div(cols).unwrap_or(0);
+    Ok(Value::Tensor(Tensor { shape: vec![rows, cols], data: Tensor::from_floats(data).data }))
+}
+
+/// The rank-1 or rank-2 numeric tensor `value` as CSV text, each line ending in a newline
+pub fn to_csv(value: &Value, options: &CsvOptions) -> EvalResult<String> {
+    let Value::Tensor(t) = value else { return Err(EvalError::type_error("Tensor", value)) };
+    let fields: Vec<String> = match &t.data {
+        TensorData::Int(v) => v.iter().map(i128::to_string).collect(),
+        TensorData::Float(v) => v.iter().map(|f| f.0.to_string()).collect(),
+        _ => return Err(EvalError::type_error_msg("only Int and Float tensors can be written as CSV")),
+    };
+    let cols = match t.shape.as_slice() {
+        [_] => 1,
+        [rows, 0] if *rows > 0 => return Err(EvalError::shape_mismatch(format!("CSV cannot hold {} rows of no columns", rows))),
+        [_, cols] => *cols,
+        shape => return Err(EvalError::shape_mismatch(format!("CSV needs a rank-1 or rank-2 tensor, got shape {:?}", shape))),
+    };
+    let mut text = String::new();
+    for row in fields.chunks(cols.max(1)) {
+        text.push_str(&row.join(&options.delimiter.to_string()));
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+pub fn read_csv(path: impl AsRef<Path>) -> EvalResult<Value> { read_csv_with(path, &CsvOptions::default()) }
+
+pub fn read_csv_with(path: impl AsRef<Path>, options: &CsvOptions) -> EvalResult<Value> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| EvalError::IoError(format!("Failed to read '{}': {}", path.display(), e)))?;
+    parse_csv(&text, options)
+}
+
+pub fn write_csv(value: &Value, path: impl AsRef<Path>) -> EvalResult<()> { write_csv_with(value, path, &CsvOptions::default()) }
+
+pub fn write_csv_with(value: &Value, path: impl AsRef<Path>, options: &CsvOptions) -> EvalResult<()> {
+    let path = path.as_ref();
+    std::fs::write(path, to_csv(value, options)?).map_err(|e| EvalError::IoError(format!("Failed to write '{}': {}", path.display(), e)))
+}
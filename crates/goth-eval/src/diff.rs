@@ -0,0 +1,109 @@
+//! Structural differences between values
+//!
+//! [`Value::diff`] walks two values side by side and reports the first place
+//! they disagree, as a path from the root plus the two values found there.
+//! Leaves are compared with [`Value::deep_eq`]; tensors of different shape
+//! report their shapes rather than a first differing element.
+
+use crate::value::{Tensor, Value};
+use std::fmt;
+
+/// One step from a value into a part of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathStep {
+    /// Tensor element at a multi-dimensional index, or list element
+    Index(Vec<usize>),
+    /// Tuple position
+    Position(usize),
+    /// Record field
+    Field(String),
+    /// Variant payload
+    Payload,
+    /// Shape of a tensor, compared as a rank-1 tensor of dimensions
+    Shape,
+}
+
+/// The first mismatch between an expected and an actual value
+#[derive(Debug, Clone)]
+pub struct ValueDiff {
+    pub path: Vec<PathStep>,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+impl Value {
+    /// The first mismatch between `self` (expected) and `actual`, or `None` if they are equal
+    pub fn diff(&self, actual: &Value) -> Option<ValueDiff> {
+        let mut path = Vec::new();
+        let (expected, actual) = first_mismatch(self, actual, &mut path)?;
+        Some(ValueDiff { path, expected, actual })
+    }
+}
+
+fn first_mismatch(a: &Value, b: &Value, path: &mut Vec<PathStep>) -> Option<(Value, Value)> {
+    match (a, b) {
+        (Value::Tensor(x), Value::Tensor(y)) if x.shape != y.shape => {
+            path.push(PathStep::Shape);
+            Some((shape_value(x), shape_value(y)))
+        }
+        (Value::Tensor(x), Value::Tensor(y)) => {
+            (0..x.len()).find_map(|i| within(path, PathStep::Index(unravel(i, &x.shape)), &x.get_flat(i)?, &y.get_flat(i)?))
+        }
+        (Value::List(x), Value::List(y)) if x.len() == y.len() => {
+            x.iter().zip(y.iter()).enumerate().find_map(|(i, (p, q))| within(path, PathStep::Index(vec![i]), p, q))
+        }
+        (Value::Tuple(x), Value::Tuple(y)) if x.len() == y.len() => {
+            x.iter().zip(y).enumerate().find_map(|(i, (p, q))| within(path, PathStep::Position(i), p, q))
+        }
+        (Value::Record(x), Value::Record(y)) if x.len() == y.len() && x.keys().all(|k| y.contains_key(k)) => {
+            let mut names: Vec<&String> = x.keys().collect();
+            names.sort();
+            names.into_iter().find_map(|name| within(path, PathStep::Field(name.clone()), &x[name], &y[name]))
+        }
+        (Value::Variant { tag: t1, payload: Some(p1) }, Value::Variant { tag: t2, payload: Some(p2) }) if t1 == t2 => within(path, PathStep::Payload, p1, p2),
+        _ if a.deep_eq(b) => None,
+        _ => Some((a.clone(), b.clone())),
+    }
+}
+
+/// The first mismatch below `step`, leaving `step` on the path only if there is one
+fn within(path: &mut Vec<PathStep>, step: PathStep, a: &Value, b: &Value) -> Option<(Value, Value)> {
+    path.push(step);
+    let found = first_mismatch(a, b, path);
+    if found.is_none() { path.pop(); }
+    found
+}
+
+fn shape_value(t: &Tensor) -> Value {
+    Value::Tensor(Tensor::from_ints(t.shape.iter().map(|&d| d as i128).collect()))
+}
+
+/// Multi-dimensional index of the `flat`th element in row-major order
+fn unravel(mut flat: usize, shape: &[usize]) -> Vec<usize> {
+    let mut index = vec![0; shape.len()];
+    for (slot, &dim) in index.iter_mut().zip(shape).rev() {
+        *slot = flat % dim.max(1);
+        flat /= dim.max(1);
+    }
+    index
+}
+
+impl fmt::Display for PathStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathStep::Index(index) => write!(f, "[{}]", index.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ")),
+            PathStep::Position(i) => write!(f, ".{}", i),
+            PathStep::Field(name) => write!(f, ".{}", name),
+            PathStep::Payload => write!(f, ".payload"),
+            PathStep::Shape => write!(f, ".shape"),
+        }
+    }
+}
+
+impl fmt::Display for ValueDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at value")?;
+        for step in &self.path { write!(f, "{}", step)?; }
+        write!(f, ": expected {}, got {}", self.expected, self.actual)
+    }
+}
@@ -13,14 +13,22 @@ pub enum EvalError {
     TypeError { expected: &'static str, got: &'static str },
     #[error("Type error: {0}")]
     TypeErrorMsg(String),
+    /// A host primitive rejected one of its arguments; `arg` counts from 0
+    #[error("Type error: {prim}: argument {} expected {expected}, got {got}", .arg + 1)]
+    ArgumentType { prim: String, arg: usize, expected: &'static str, got: &'static str },
     #[error("Arity mismatch: expected {expected}, got {got}")]
     ArityMismatch { expected: usize, got: usize },
     #[error("Division by zero")]
     DivisionByZero,
-    #[error("Index out of bounds: {index} for size {size}")]
-    IndexOutOfBounds { index: usize, size: usize },
+    #[error("Integer overflow: {0} does not fit in I64")]
+    IntegerOverflow(String),
+    #[error("Index out of bounds: {index} for length {len}")]
+    IndexOutOfBounds { index: i128, len: usize },
     #[error("Shape mismatch: {0}")]
     ShapeMismatch(String),
+    /// Two operands that must have the same shape do not
+    #[error("Shape mismatch: {left:?} and {right:?}")]
+    IncompatibleShapes { left: Vec<usize>, right: Vec<usize> },
     #[error("Pattern match failed")]
     MatchFailed,
     #[error("Non-exhaustive pattern match")]
@@ -37,10 +45,22 @@ pub enum EvalError {
     IoError(String),
     #[error("Not implemented: {0}")]
     NotImplemented(String),
+    #[error("Allocation limit exceeded: {requested} tensor elements requested, limit is {limit}")]
+    AllocationLimit { requested: usize, limit: usize },
+    #[error("Stack overflow: evaluation nested deeper than {limit}")]
+    StackOverflow { limit: usize },
+    #[error("Step limit exceeded: more than {limit} expressions evaluated")]
+    StepLimitExceeded { limit: usize },
+    #[error("Evaluation cancelled")]
+    Cancelled,
+    #[error("Unsupported construct: {0}")]
+    UnsupportedConstruct(String),
     #[error("Internal error: {0}")]
     Internal(String),
     #[error("User error: {0}")]
     UserError(String),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
 }
 
 pub type EvalResult<T> = Result<T, EvalError>;
@@ -51,6 +71,7 @@ impl EvalError {
     }
     pub fn type_error_msg(msg: impl Into<String>) -> Self { EvalError::TypeErrorMsg(msg.into()) }
     pub fn shape_mismatch(msg: impl Into<String>) -> Self { EvalError::ShapeMismatch(msg.into()) }
+    pub fn incompatible_shapes(left: &[usize], right: &[usize]) -> Self { EvalError::IncompatibleShapes { left: left.to_vec(), right: right.to_vec() } }
     pub fn not_implemented(what: impl Into<String>) -> Self { EvalError::NotImplemented(what.into()) }
     pub fn internal(msg: impl Into<String>) -> Self { EvalError::Internal(msg.into()) }
 }
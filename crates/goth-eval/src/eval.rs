@@ -1,15 +1,19 @@
 //! Evaluator for Goth
 
-use crate::value::{Value, Tensor, Closure, Env, PrimFn};
+use crate::value::{Value, Tensor, List, Closure, Env, PrimFn, NativeFn, NativePrim, PrimContext};
 use crate::error::{EvalError, EvalResult, OptionExt};
 use crate::prim;
+use crate::capture::free_variables;
+use crate::hash::HashableValue;
 use goth_ast::expr::{Expr, MatchArm, FieldAccess, CastKind, DoOp};
 use goth_ast::literal::Literal;
 use goth_ast::pattern::Pattern;
-use goth_ast::op::BinOp;
+use goth_ast::op::{BinOp, UnaryOp};
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Represents either a final value or a tail call that needs to be evaluated.
 /// Used for tail call optimization (TCO) to avoid stack overflow on deep recursion.
@@ -20,81 +24,353 @@ enum TcoResult {
     TailCall { body: Expr, env: Env },
 }
 
+/// Counters collected by [`Evaluator::eval_with_stats`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalStats {
+    /// Function applications, counting each argument applied
+    pub reductions: usize,
+    /// Closures created (lambdas and compositions)
+    pub closures: usize,
+    /// Tensors built by array literals, collection operators (↦, ▸, ⤇) and primitives
+    pub tensors: usize,
+    /// Saturated primitive calls, by primitive
+    pub prim_calls: HashMap<String, usize>,
+}
+
+/// Hook told the name of each built-in shadowed by a definition
+type ShadowHook = Box<dyn FnMut(&str)>;
+
+/// Built-in primitives by global name, several names per primitive
+const PRIMITIVES: &[(&str, PrimFn)] = &[
+    ("add", PrimFn::Add), ("sub", PrimFn::Sub), ("mul", PrimFn::Mul), ("div", PrimFn::Div), ("mod", PrimFn::Mod), ("neg", PrimFn::Neg), ("abs", PrimFn::Abs), ("sign", PrimFn::Sign), ("signum", PrimFn::Sign),
+    ("exp", PrimFn::Exp), ("ln", PrimFn::Ln), ("sqrt", PrimFn::Sqrt), ("sin", PrimFn::Sin), ("cos", PrimFn::Cos), ("tan", PrimFn::Tan), ("pow", PrimFn::Pow), ("floor", PrimFn::Floor), ("ceil", PrimFn::Ceil), ("round", PrimFn::Round),
+    ("eq", PrimFn::Eq), ("neq", PrimFn::Neq), ("lt", PrimFn::Lt), ("gt", PrimFn::Gt), ("leq", PrimFn::Leq), ("geq", PrimFn::Geq),
+    ("and", PrimFn::And), ("or", PrimFn::Or), ("not", PrimFn::Not),
+    ("sum", PrimFn::Sum), ("prod", PrimFn::Prod),
+    ("len", PrimFn::Len),
+    ("shape", PrimFn::Shape), ("ρ", PrimFn::Shape),  // APL rho
+    ("reverse", PrimFn::Reverse), ("⌽", PrimFn::Reverse),  // APL reverse
+    ("sort", PrimFn::Sort), ("sortBy", PrimFn::SortBy), ("sort_by", PrimFn::SortBy),
+    ("concat", PrimFn::Concat), ("concat_axis", PrimFn::ConcatAxis), ("concatAxis", PrimFn::ConcatAxis),
+    ("iota", PrimFn::Iota), ("ι", PrimFn::Iota), ("⍳", PrimFn::Iota),  // APL-style
+    ("range", PrimFn::Range), ("…", PrimFn::Range), ("rangeStep", PrimFn::RangeStep), ("range_step", PrimFn::RangeStep),
+    ("fill", PrimFn::Fill), ("replicate", PrimFn::Fill), ("zeros", PrimFn::Zeros), ("ones", PrimFn::Ones),
+    ("dot", PrimFn::Dot), ("·", PrimFn::Dot),  // middle dot
+    ("norm", PrimFn::Norm), ("matmul", PrimFn::MatMul), ("outer", PrimFn::Outer),
+    ("print", PrimFn::Print), ("println", PrimFn::Print), ("trace", PrimFn::Trace),
+    ("write", PrimFn::Write),  // Print without newline (for TUI)
+    ("flush", PrimFn::Flush),  // Flush stdout
+    ("readLine", PrimFn::ReadLine), ("read_line", PrimFn::ReadLine),
+    ("readKey", PrimFn::ReadKey), ("read_key", PrimFn::ReadKey),  // Read single key
+    ("readFile", PrimFn::ReadFile), ("writeFile", PrimFn::WriteFile),
+    ("rawModeEnter", PrimFn::RawModeEnter), ("rawModeExit", PrimFn::RawModeExit),  // Terminal raw mode
+    ("sleep", PrimFn::Sleep),  // Sleep for milliseconds
+    ("toInt", PrimFn::ToInt), ("toFloat", PrimFn::ToFloat), ("toBool", PrimFn::ToBool), ("toChar", PrimFn::ToChar),
+    ("ord", PrimFn::Ord), ("chr", PrimFn::Chr),
+    ("parseInt", PrimFn::ParseInt), ("parseFloat", PrimFn::ParseFloat),
+    ("toString", PrimFn::ToString), ("str", PrimFn::ToString),
+    ("chars", PrimFn::Chars),
+    ("strConcat", PrimFn::StrConcat), ("concat_str", PrimFn::StrConcat), ("concatStr", PrimFn::StrConcat), ("⧺", PrimFn::StrConcat),  // double plus
+    ("strLen", PrimFn::StrLen), ("str_len", PrimFn::StrLen),
+    ("filter", PrimFn::Filter), ("map", PrimFn::Map), ("fold", PrimFn::Fold), ("scan", PrimFn::Scan),
+    ("index", PrimFn::Index),
+    ("take", PrimFn::Take), ("↑", PrimFn::Take),  // APL take
+    ("drop", PrimFn::Drop), ("↓", PrimFn::Drop),  // APL drop
+    ("zip", PrimFn::Zip), ("zipWith", PrimFn::ZipWith), ("zip_with", PrimFn::ZipWith),
+    ("transpose", PrimFn::Transpose), ("⍉", PrimFn::Transpose),  // APL transpose
+    ("transposeAxes", PrimFn::TransposeAxes),
+    ("reshape", PrimFn::Reshape),
+    // String splitting (for wc-like operations)
+    ("lines", PrimFn::Lines), ("words", PrimFn::Words), ("bytes", PrimFn::Bytes),
+    // String comparison
+    ("strEq", PrimFn::StrEq), ("startsWith", PrimFn::StartsWith),
+    ("endsWith", PrimFn::EndsWith), ("contains", PrimFn::Contains),
+    // Reductions along one axis
+    ("sum_axis", PrimFn::SumAxis), ("sumAxis", PrimFn::SumAxis),
+    ("mean_axis", PrimFn::MeanAxis), ("meanAxis", PrimFn::MeanAxis),
+    ("max_axis", PrimFn::MaxAxis), ("maxAxis", PrimFn::MaxAxis),
+    // Statistics
+    ("mean", PrimFn::Mean), ("variance", PrimFn::Variance),
+    ("variance_sample", PrimFn::VarianceSample), ("varianceSample", PrimFn::VarianceSample), ("std", PrimFn::Std),
+    ("argmin", PrimFn::ArgMin), ("argmax", PrimFn::ArgMax),
+    ("min", PrimFn::Min), ("max", PrimFn::Max), ("clamp", PrimFn::Clamp),
+    ("cons", PrimFn::Cons), ("head", PrimFn::Head), ("tail", PrimFn::Tail), ("isEmpty", PrimFn::IsEmpty), ("is_empty", PrimFn::IsEmpty),
+    ("to_list", PrimFn::ToList), ("toList", PrimFn::ToList), ("to_tensor", PrimFn::ToTensor), ("toTensor", PrimFn::ToTensor),
+    ("normalize", PrimFn::Normalize), ("softmax", PrimFn::Softmax),
+    ("memoize", PrimFn::Memoize),
+];
+
+/// Evaluation steps between polls of the cancellation flag
+const CANCEL_POLL_INTERVAL: u32 = 1024;
+
+/// Stack left when evaluation moves onto a fresh segment, comfortably more than one
+/// level of nested evaluation uses in an unoptimized build
+pub(crate) const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// Size of each stack segment allocated once the red zone is reached
+pub(crate) const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
 pub struct Evaluator {
     globals: Rc<RefCell<HashMap<String, Value>>>,
     max_depth: usize,
     depth: usize,
     trace: bool,
+    /// Check type annotations on lets and typed patterns at runtime
+    contracts: bool,
+    /// Cap on tensor elements allocated over the evaluator's lifetime
+    memory_limit: Option<usize>,
+    elements_allocated: usize,
+    /// Cap on expressions evaluated over the evaluator's lifetime
+    step_limit: Option<usize>,
+    steps_taken: usize,
+    /// Whether `stats` is being updated; off except during `eval_with_stats`
+    collect_stats: bool,
+    stats: EvalStats,
+    /// Buffer receiving `print` and `write` output instead of stdout, if capturing
+    output: Option<String>,
+    /// Reject effectful primitives; set only during `eval_pure`
+    pure: bool,
+    /// `(label, value)` pairs recorded by `trace`, in call order
+    traces: Vec<(String, Value)>,
+    /// Flag polled during `eval_cancellable`, and evaluation steps since it was last polled
+    cancel: Option<Arc<AtomicBool>>,
+    steps_since_poll: u32,
+    /// Evaluate indices past the end of the environment to `()` instead of failing
+    unbound_as_hole: bool,
+    /// Called with the name when a definition shadows a built-in
+    on_shadow: Option<ShadowHook>,
+    /// Compiled bodies of global closures, keyed by name and checked against the body on reuse
+    pub(crate) compiled_globals: HashMap<Box<str>, (Expr, crate::bytecode::Program)>,
 }
 
 impl Evaluator {
     pub fn new() -> Self {
         let globals = Rc::new(RefCell::new(HashMap::new()));
-        let mut eval = Evaluator { globals, max_depth: 10000, depth: 0, trace: false };
+        let mut eval = Evaluator { globals, max_depth: 10000, depth: 0, trace: false, contracts: false, memory_limit: None, elements_allocated: 0, step_limit: None, steps_taken: 0, collect_stats: false, stats: EvalStats::default(), output: None, pure: false, traces: Vec::new(), cancel: None, steps_since_poll: 0, unbound_as_hole: false, on_shadow: None, compiled_globals: HashMap::new() };
         eval.register_primitives();
         eval
     }
 
     pub fn with_trace(mut self, trace: bool) -> Self { self.trace = trace; self }
     pub fn with_max_depth(mut self, depth: usize) -> Self { self.max_depth = depth; self }
+    /// Fail with [`EvalError::StackOverflow`] once evaluation nests deeper than
+    /// `limit` (10_000 by default). Every subexpression being evaluated counts,
+    /// so a call takes a few levels. Deep evaluation continues on stack segments
+    /// allocated as needed, so any limit fits on the calling thread's stack.
+    pub fn with_depth_limit(self, limit: usize) -> Self { self.with_max_depth(limit) }
+    /// Check let and pattern type annotations against the values bound to them
+    pub fn with_contracts(mut self, contracts: bool) -> Self { self.contracts = contracts; self }
+    /// Evaluate a de Bruijn index with no binding to the placeholder `()` rather than failing with
+    /// [`EvalError::UnboundIndex`], so partly built expressions can still be run. Off by default.
+    pub fn treat_unbound_as_hole(mut self, enabled: bool) -> Self { self.unbound_as_hole = enabled; self }
+    pub(crate) fn max_depth(&self) -> usize { self.max_depth }
+
+    /// The value bound to index `i`, or the placeholder if unbound indices are treated as holes
+    pub(crate) fn lookup_index(&self, env: &Env, i: u32) -> EvalResult<Value> {
+        match env.get(i) {
+            None if self.unbound_as_hole => Ok(Value::Unit),
+            found => found.cloned().ok_or_unbound(i),
+        }
+    }
+    /// Limit the total number of tensor elements this evaluator may allocate.
+    /// Exceeding it fails with [`EvalError::AllocationLimit`] before the allocation is made.
+    pub fn with_memory_limit(mut self, elements: usize) -> Self { self.memory_limit = Some(elements); self }
+    /// Tensor elements allocated so far
+    pub fn elements_allocated(&self) -> usize { self.elements_allocated }
+
+    /// Limit the total number of expressions this evaluator may evaluate, counting every node
+    /// visited. Exceeding it fails with [`EvalError::StepLimitExceeded`].
+    pub fn with_step_limit(mut self, steps: usize) -> Self { self.step_limit = Some(steps); self }
+    /// Expressions evaluated so far
+    pub fn steps_taken(&self) -> usize { self.steps_taken }
+    /// Fail every evaluation, and every bytecode run, with [`EvalError::Cancelled`] soon after
+    /// `token` is set. Keep a clone of the `Arc` to set it from another thread.
+    pub fn with_cancel(mut self, token: Arc<AtomicBool>) -> Self { self.cancel = Some(token); self }
+
+    /// Account for one more evaluation step
+    pub(crate) fn take_step(&mut self) -> EvalResult<()> {
+        self.steps_taken += 1;
+        match self.step_limit {
+            Some(limit) if self.steps_taken > limit => Err(EvalError::StepLimitExceeded { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Account for `n` tensor elements about to be allocated
+    fn reserve_elements(&mut self, n: usize) -> EvalResult<()> {
+        let total = self.elements_allocated.saturating_add(n);
+        if let Some(limit) = self.memory_limit { if total > limit { return Err(EvalError::AllocationLimit { requested: n, limit }); } }
+        self.elements_allocated = total;
+        Ok(())
+    }
+
+    /// Run `build`, which makes at most `bound` new tensor elements, reserving them against the
+    /// memory limit beforehand and returning what the result did not use. Without a bound a
+    /// tensor result is counted once it exists.
+    fn with_reservation(&mut self, bound: Option<usize>, build: impl FnOnce(&mut Self) -> EvalResult<Value>) -> EvalResult<Value> {
+        if let Some(n) = bound { self.reserve_elements(n)?; }
+        let result = build(self);
+        let built = match &result { Ok(Value::Tensor(t)) => t.len(), _ => 0 };
+        match bound {
+            Some(n) => self.elements_allocated -= n.saturating_sub(built),
+            None if built > 0 => self.reserve_elements(built)?,
+            None => {}
+        }
+        result
+    }
+
+    /// `left op right` for the operators [`prim::apply_binop`] handles, within the memory limit
+    pub(crate) fn binop_values(&mut self, op: &BinOp, left: Value, right: Value) -> EvalResult<Value> {
+        if let Some(v) = prim::scalar_binop(op, &left, &right) { return Ok(v); }
+        let bound = prim::binop_elements(op, &left, &right);
+        self.with_reservation(Some(bound), |_| prim::apply_binop(op, left, right))
+    }
+
+    /// `op value`, within the memory limit. A unary operator never builds more elements than its operand has.
+    pub(crate) fn unaryop_value(&mut self, op: &UnaryOp, value: Value) -> EvalResult<Value> {
+        let bound = match &value { Value::Tensor(t) => t.len(), _ => 0 };
+        self.with_reservation(Some(bound), |_| prim::apply_unaryop(op, value))
+    }
+    /// Statistics from the last [`Evaluator::eval_with_stats`]
+    pub fn stats(&self) -> &EvalStats { &self.stats }
 
     fn register_primitives(&mut self) {
-        let prims: &[(&str, PrimFn)] = &[
-            ("add", PrimFn::Add), ("sub", PrimFn::Sub), ("mul", PrimFn::Mul), ("div", PrimFn::Div), ("mod", PrimFn::Mod), ("neg", PrimFn::Neg), ("abs", PrimFn::Abs),
-            ("exp", PrimFn::Exp), ("ln", PrimFn::Ln), ("sqrt", PrimFn::Sqrt), ("sin", PrimFn::Sin), ("cos", PrimFn::Cos), ("tan", PrimFn::Tan), ("pow", PrimFn::Pow), ("floor", PrimFn::Floor), ("ceil", PrimFn::Ceil), ("round", PrimFn::Round),
-            ("eq", PrimFn::Eq), ("neq", PrimFn::Neq), ("lt", PrimFn::Lt), ("gt", PrimFn::Gt), ("leq", PrimFn::Leq), ("geq", PrimFn::Geq),
-            ("and", PrimFn::And), ("or", PrimFn::Or), ("not", PrimFn::Not),
-            ("sum", PrimFn::Sum), ("prod", PrimFn::Prod),
-            ("len", PrimFn::Len),
-            ("shape", PrimFn::Shape), ("ρ", PrimFn::Shape),  // APL rho
-            ("reverse", PrimFn::Reverse), ("⌽", PrimFn::Reverse),  // APL reverse
-            ("concat", PrimFn::Concat),
-            ("iota", PrimFn::Iota), ("ι", PrimFn::Iota), ("⍳", PrimFn::Iota),  // APL-style
-            ("range", PrimFn::Range), ("…", PrimFn::Range),
-            ("dot", PrimFn::Dot), ("·", PrimFn::Dot),  // middle dot
-            ("norm", PrimFn::Norm), ("matmul", PrimFn::MatMul),
-            ("print", PrimFn::Print), ("println", PrimFn::Print),
-            ("write", PrimFn::Write),  // Print without newline (for TUI)
-            ("flush", PrimFn::Flush),  // Flush stdout
-            ("readLine", PrimFn::ReadLine), ("read_line", PrimFn::ReadLine),
-            ("readKey", PrimFn::ReadKey), ("read_key", PrimFn::ReadKey),  // Read single key
-            ("readFile", PrimFn::ReadFile), ("writeFile", PrimFn::WriteFile),
-            ("rawModeEnter", PrimFn::RawModeEnter), ("rawModeExit", PrimFn::RawModeExit),  // Terminal raw mode
-            ("sleep", PrimFn::Sleep),  // Sleep for milliseconds
-            ("toInt", PrimFn::ToInt), ("toFloat", PrimFn::ToFloat), ("toBool", PrimFn::ToBool), ("toChar", PrimFn::ToChar),
-            ("parseInt", PrimFn::ParseInt), ("parseFloat", PrimFn::ParseFloat),
-            ("toString", PrimFn::ToString), ("str", PrimFn::ToString),
-            ("chars", PrimFn::Chars),
-            ("strConcat", PrimFn::StrConcat), ("⧺", PrimFn::StrConcat),  // double plus
-            ("filter", PrimFn::Filter), ("map", PrimFn::Map), ("fold", PrimFn::Fold),
-            ("index", PrimFn::Index),
-            ("take", PrimFn::Take), ("↑", PrimFn::Take),  // APL take
-            ("drop", PrimFn::Drop), ("↓", PrimFn::Drop),  // APL drop
-            ("zip", PrimFn::Zip),
-            ("transpose", PrimFn::Transpose), ("⍉", PrimFn::Transpose),  // APL transpose
-            // String splitting (for wc-like operations)
-            ("lines", PrimFn::Lines), ("words", PrimFn::Words), ("bytes", PrimFn::Bytes),
-            // String comparison
-            ("strEq", PrimFn::StrEq), ("startsWith", PrimFn::StartsWith),
-            ("endsWith", PrimFn::EndsWith), ("contains", PrimFn::Contains),
-        ];
-        for (name, prim) in prims { self.globals.borrow_mut().insert(name.to_string(), Value::Primitive(*prim)); }
-    }
-
-    pub fn define(&mut self, name: impl Into<String>, value: Value) { self.globals.borrow_mut().insert(name.into(), value); }
+        let mut globals = self.globals.borrow_mut();
+        for (name, prim) in PRIMITIVES { globals.insert(name.to_string(), Value::Primitive(prim.clone())); }
+        globals.insert("nil".to_string(), Value::List(List::nil()));
+        let namespace = PRIMITIVES.iter().map(|(name, prim)| (name.to_string(), Value::Primitive(prim.clone()))).collect();
+        globals.insert("prim".to_string(), Value::Record(Rc::new(namespace)));
+    }
+
+    /// The built-in bound to `name` before any user definition, also reachable in programs as `prim.name`
+    pub fn builtin(name: &str) -> Option<Value> {
+        if name == "nil" { return Some(Value::List(List::nil())); }
+        PRIMITIVES.iter().find(|(n, _)| *n == name).map(|(_, prim)| Value::Primitive(prim.clone()))
+    }
+
+    /// Call `hook` with the name whenever [`Evaluator::define`] shadows a built-in
+    pub fn with_shadow_warning(mut self, hook: impl FnMut(&str) + 'static) -> Self { self.on_shadow = Some(Box::new(hook)); self }
+
+    /// Bind a global to any value, a function or data such as a tensor, which `Expr::Name`
+    /// then resolves in every later evaluation. User definitions shadow built-ins of the same name, which stay reachable through
+    /// [`Evaluator::builtin`] and the `prim` namespace.
+    pub fn define(&mut self, name: impl Into<String>, value: Value) {
+        let name = name.into();
+        if let Some(hook) = &mut self.on_shadow {
+            if Self::builtin(&name).is_some() { hook(&name); }
+        }
+        self.globals.borrow_mut().insert(name, value);
+    }
     
+    /// Bind `name` to a host function of `arity` arguments. Like a built-in, it is called once all
+    /// arguments are supplied and applying it to fewer gives a partial application. Fails if
+    /// `arity` is 0: a primitive is only called once applied to an argument.
+    pub fn register_prim(&mut self, name: &str, arity: usize, f: impl Fn(&PrimContext, &[Value]) -> EvalResult<Value> + 'static) -> EvalResult<()> {
+        if arity == 0 { return Err(EvalError::type_error_msg(format!("primitive {} must take at least one argument", name))); }
+        let f: NativeFn = Rc::new(f);
+        self.define(name, Value::Primitive(PrimFn::Native(NativePrim { name: name.into(), arity, f })));
+        Ok(())
+    }
+
     pub fn globals(&self) -> Rc<RefCell<HashMap<String, Value>>> { Rc::clone(&self.globals) }
 
+    /// Serialize a value to JSON. Captured closures bound as globals are stored by name.
+    pub fn serialize_value(&self, value: &Value) -> EvalResult<String> { crate::ser::value_to_json(value, &self.globals.borrow()) }
+    /// Deserialize a value produced by [`Evaluator::serialize_value`], resolving global references against this evaluator.
+    pub fn deserialize_value(&self, json: &str) -> EvalResult<Value> { crate::ser::value_from_json(json, &self.globals) }
+
     pub fn eval(&mut self, expr: &Expr) -> EvalResult<Value> {
         let env = Env::with_globals(Rc::clone(&self.globals));
         self.eval_with_env(expr, &env)
     }
 
+    /// Evaluate `expr` while counting reductions and allocations, available afterwards from [`Evaluator::stats`]
+    pub fn eval_with_stats(&mut self, expr: &Expr) -> EvalResult<Value> {
+        self.stats = EvalStats::default();
+        self.collect_stats = true;
+        let result = self.eval(expr);
+        self.collect_stats = false;
+        result
+    }
+
+    /// Send `print` and `write` output to a buffer, drained by [`Evaluator::take_output`], instead of stdout
+    pub fn with_captured_output(mut self) -> Self { self.output = Some(String::new()); self }
+
+    /// Output captured since the last call; empty unless built [`Evaluator::with_captured_output`]
+    pub fn take_output(&mut self) -> String { self.output.as_mut().map(std::mem::take).unwrap_or_default() }
+
+    /// Values recorded by `trace label value`, oldest first, kept across evaluations
+    pub fn traces(&self) -> &[(String, Value)] { &self.traces }
+
+    /// Evaluate `expr`, failing with `EffectNotAllowed` if it calls an effectful primitive such as `print`
+    pub fn eval_pure(&mut self, expr: &Expr) -> EvalResult<Value> {
+        let was_pure = std::mem::replace(&mut self.pure, true);
+        let result = self.eval(expr);
+        self.pure = was_pure;
+        result
+    }
+
+    /// Evaluate `expr`, failing with `EvalError::Cancelled` soon after `token` is set.
+    /// The evaluator stays on its own thread; share the token with others through the `Arc`.
+    pub fn eval_cancellable(&mut self, expr: &Expr, token: Arc<AtomicBool>) -> EvalResult<Value> {
+        let outer = self.cancel.replace(token);
+        let result = self.eval(expr);
+        self.cancel = outer;
+        result
+    }
+
+    /// Poll the cancellation flag every `CANCEL_POLL_INTERVAL` steps
+    pub(crate) fn check_cancelled(&mut self) -> EvalResult<()> {
+        let Some(token) = &self.cancel else { return Ok(()) };
+        self.steps_since_poll += 1;
+        if self.steps_since_poll < CANCEL_POLL_INTERVAL { return Ok(()); }
+        self.steps_since_poll = 0;
+        if token.load(Ordering::Relaxed) { Err(EvalError::Cancelled) } else { Ok(()) }
+    }
+
+    fn count_closure(&mut self) { if self.collect_stats { self.stats.closures += 1; } }
+    fn count_tensor(&mut self) { if self.collect_stats { self.stats.tensors += 1; } }
+
+    /// Apply a primitive to all its arguments
+    fn call_prim(&mut self, prim: PrimFn, args: Vec<Value>) -> EvalResult<Value> {
+        // Results are reserved against the memory limit before they are built, for every
+        // primitive whose size is known from its arguments; the rest are counted afterwards
+        if self.pure && !prim.effects().is_pure() {
+            return Err(EvalError::EffectNotAllowed(format!("{:?} has effect {} in pure evaluation", prim, prim.effects())));
+        }
+        if let (Some(out), PrimFn::Print | PrimFn::Write) = (&mut self.output, &prim) {
+            out.push_str(&prim::printed(&args));
+            if prim == PrimFn::Print { out.push('\n'); }
+            return Ok(if prim == PrimFn::Print { args.into_iter().next().unwrap_or(Value::Unit) } else { Value::Unit });
+        }
+        if let (PrimFn::Trace, [label, value]) = (&prim, args.as_slice()) {
+            // The identity on the traced value, which is not a new allocation
+            self.traces.push((prim::printed(std::slice::from_ref(label)), value.clone()));
+            return Ok(value.clone());
+        }
+        match prim {
+            PrimFn::Fold => return self.eval_fold(args),
+            PrimFn::Scan => return self.eval_scan(args),
+            PrimFn::ZipWith => return self.eval_zip_with(args),
+            PrimFn::SortBy => return self.eval_sort_by(args),
+            _ => {}
+        }
+        let bound = prim::result_elements(&prim, &args);
+        let result = self.with_reservation(bound, |_| prim::apply_prim(prim.clone(), args))?;
+        if self.collect_stats {
+            *self.stats.prim_calls.entry(format!("{:?}", prim)).or_insert(0) += 1;
+            if matches!(result, Value::Tensor(_)) { self.stats.tensors += 1; }
+        }
+        Ok(result)
+    }
+
     pub fn eval_with_env(&mut self, expr: &Expr, env: &Env) -> EvalResult<Value> {
         self.depth += 1;
-        if self.depth > self.max_depth { self.depth -= 1; return Err(EvalError::Internal("Recursion limit exceeded".into())); }
+        if self.depth > self.max_depth { self.depth -= 1; return Err(EvalError::StackOverflow { limit: self.max_depth }); }
+        if let Err(e) = self.take_step().and_then(|()| self.check_cancelled()) { self.depth -= 1; return Err(e); }
         if self.trace { eprintln!("{}eval: {}", "  ".repeat(self.depth), expr); }
-        let result = self.eval_inner(expr, env);
+        // Deep recursion continues on heap-allocated segments, so only `max_depth` bounds it
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || self.eval_inner(expr, env));
         if self.trace { match &result { Ok(v) => eprintln!("{}=> {}", "  ".repeat(self.depth), v), Err(e) => eprintln!("{}=> ERROR: {}", "  ".repeat(self.depth), e), } }
         self.depth -= 1;
         result
@@ -102,36 +378,38 @@ impl Evaluator {
 
     fn eval_inner(&mut self, expr: &Expr, env: &Env) -> EvalResult<Value> {
         match expr {
-            Expr::Idx(i) => env.get(*i).cloned().ok_or_unbound(*i),
+            Expr::Idx(i) => self.lookup_index(env, *i),
             Expr::Name(name) => env.get_global(name).ok_or_undefined(name),
             Expr::Lit(lit) => Ok(self.eval_literal(lit)),
             Expr::Prim(name) => env.get_global(name).ok_or_else(|| EvalError::not_implemented(format!("primitive: {}", name))),
             Expr::App(func, arg) => { let func_val = self.eval_with_env(func, env)?; let arg_val = self.eval_with_env(arg, env)?; self.apply(func_val, arg_val) }
-            Expr::Lam(body) => Ok(Value::Closure(Closure { arity: 1, body: (**body).clone(), env: env.capture(), preconditions: vec![], postconditions: vec![] })),
-            Expr::LamN(n, body) => Ok(Value::Closure(Closure { arity: *n, body: (**body).clone(), env: env.capture(), preconditions: vec![], postconditions: vec![] })),
-            Expr::Let { pattern, type_: _, value, body } => { let val = self.eval_with_env(value, env)?; let mut new_env = env.clone(); self.bind_pattern(pattern, val, &mut new_env)?; self.eval_with_env(body, &new_env) }
+            Expr::AppNamed(func, _) => Err(EvalError::type_error_msg(format!("named-argument application of {} must be resolved before evaluation", func))),
+            Expr::Lam(body) => { self.count_closure(); Ok(Value::Closure(Closure { arity: 1, body: (**body).clone(), env: env.capture_only(&free_variables(body, 1)), preconditions: vec![], postconditions: vec![] })) }
+            Expr::LamN(n, body) => { self.count_closure(); Ok(Value::Closure(Closure { arity: *n, body: (**body).clone(), env: env.capture_only(&free_variables(body, *n)), preconditions: vec![], postconditions: vec![] })) }
+            Expr::Let { pattern, type_, value, body } => { let val = self.eval_with_env(value, env)?; self.check_annotation(&val, type_.as_ref())?; let mut new_env = env.clone(); self.bind_pattern(pattern, val, &mut new_env)?; self.eval_with_env(body, &new_env) }
             Expr::LetRec { bindings, body } => {
                 let mut new_env = env.clone();
                 for _ in bindings { new_env.push(Value::Error("uninitialized letrec".into())); }
                 let values: Vec<Value> = bindings.iter().map(|(_, expr)| self.eval_with_env(expr, &new_env)).collect::<Result<_, _>>()?;
-                let depth = new_env.depth();
-                for (i, val) in values.into_iter().enumerate() { let idx = depth - bindings.len() + i; if let Some(slot) = new_env.values.get_mut(idx) { *slot = val; } }
+                new_env.pop_n(bindings.len());
+                new_env.push_many(values);
                 self.eval_with_env(body, &new_env)
             }
             Expr::Match { scrutinee, arms } => { let val = self.eval_with_env(scrutinee, env)?; self.eval_match(val, arms, env) }
             Expr::If { cond, then_, else_ } => { let cond_val = self.eval_with_env(cond, env)?; match cond_val { Value::Bool(true) => self.eval_with_env(then_, env), Value::Bool(false) => self.eval_with_env(else_, env), _ => Err(EvalError::type_error("Bool", &cond_val)) } }
             Expr::BinOp(op, left, right) => self.eval_binop(op, left, right, env),
-            Expr::UnaryOp(op, operand) => { let val = self.eval_with_env(operand, env)?; prim::apply_unaryop(op, val) }
-            Expr::Norm(inner) => { let val = self.eval_with_env(inner, env)?; prim::apply_prim(PrimFn::Norm, vec![val]) }
+            Expr::UnaryOp(op, operand) => { let val = self.eval_with_env(operand, env)?; self.unaryop_value(op, val) }
+            Expr::Norm(inner) => { let val = self.eval_with_env(inner, env)?; self.call_prim(PrimFn::Norm, vec![val]) }
+            // Elements are evaluated strictly left to right, stopping at the first error
             Expr::Tuple(exprs) => { let values: Vec<Value> = exprs.iter().map(|e| self.eval_with_env(e, env)).collect::<Result<_, _>>()?; Ok(Value::tuple(values)) }
             Expr::Record(fields) => { let map: HashMap<String, Value> = fields.iter().map(|(name, expr)| { let val = self.eval_with_env(expr, env)?; Ok((name.to_string(), val)) }).collect::<EvalResult<_>>()?; Ok(Value::Record(Rc::new(map))) }
-            Expr::Array(exprs) => { let values: Vec<Value> = exprs.iter().map(|e| self.eval_with_env(e, env)).collect::<Result<_, _>>()?; Ok(self.values_to_tensor(values)) }
-            Expr::ArrayFill { shape, value } => { let shape_vals: Vec<usize> = shape.iter().map(|e| { let v = self.eval_with_env(e, env)?; v.as_int().map(|n| n as usize).ok_or_else(|| EvalError::type_error("Int", &v)) }).collect::<Result<_, _>>()?; let fill_val = self.eval_with_env(value, env)?; let size: usize = shape_vals.iter().product(); let data = vec![fill_val; size]; Ok(Value::Tensor(Tensor::from_values(shape_vals, data))) }
+            Expr::Array(exprs) => { let values: Vec<Value> = exprs.iter().map(|e| self.eval_with_env(e, env)).collect::<Result<_, _>>()?; self.reserve_elements(values.len())?; self.count_tensor(); Ok(self.values_to_tensor(values)) }
+            Expr::ArrayFill { shape, value } => { let shape_vals: Vec<usize> = shape.iter().map(|e| { let v = self.eval_with_env(e, env)?; v.as_int().map(|n| n as usize).ok_or_else(|| EvalError::type_error("Int", &v)) }).collect::<Result<_, _>>()?; let fill_val = self.eval_with_env(value, env)?; let size = shape_vals.iter().try_fold(1usize, |n, &d| n.checked_mul(d)).unwrap_or(usize::MAX); self.reserve_elements(size)?; let data = vec![fill_val; size]; self.count_tensor(); Ok(Value::Tensor(Tensor::from_values(shape_vals, data))) }
             Expr::Variant { constructor, payload } => { let payload_val = match payload { Some(p) => Some(self.eval_with_env(p, env)?), None => None }; Ok(Value::variant(constructor.to_string(), payload_val)) }
             Expr::Field(base, access) => { let val = self.eval_with_env(base, env)?; self.access_field(val, access) }
             Expr::Index(base, indices) => { let arr = self.eval_with_env(base, env)?; let idx_vals: Vec<usize> = indices.iter().map(|e| { let v = self.eval_with_env(e, env)?; v.as_int().map(|n| n as usize).ok_or_else(|| EvalError::type_error("Int", &v)) }).collect::<Result<_, _>>()?; self.index_value(arr, &idx_vals) }
             Expr::Slice { array, start, end } => { let arr = self.eval_with_env(array, env)?; let start_idx = match start { Some(e) => { let v = self.eval_with_env(e, env)?; v.as_int().map(|n| n as usize).unwrap_or(0) } None => 0 }; let end_idx = match end { Some(e) => { let v = self.eval_with_env(e, env)?; v.as_int().map(|n| n as usize) } None => None }; self.slice_value(arr, start_idx, end_idx) }
-            Expr::Annot(inner, _ty) => self.eval_with_env(inner, env),
+            Expr::Annot(inner, ty) => { let val = self.eval_with_env(inner, env)?; self.check_annotation(&val, Some(ty))?; Ok(val) }
             Expr::Cast { expr, target: _, kind } => { let val = self.eval_with_env(expr, env)?; match kind { CastKind::Static => Ok(val), CastKind::Try => Ok(Value::variant("Some", Some(val))), CastKind::Force => Ok(val) } }
             Expr::Update { base, fields } => { let base_val = self.eval_with_env(base, env)?; match base_val { Value::Record(map) => { let mut new_map = (*map).clone(); for (name, expr) in fields { let val = self.eval_with_env(expr, env)?; new_map.insert(name.to_string(), val); } Ok(Value::Record(Rc::new(new_map))) } _ => Err(EvalError::type_error("Record", &base_val)) } }
             Expr::Do { init, ops } => self.eval_do(init, ops, env),
@@ -139,13 +417,13 @@ impl Evaluator {
             Expr::Hole => Err(EvalError::not_implemented("hole evaluation")),
             Expr::Quote(_) => Err(EvalError::not_implemented("quote evaluation")),
             Expr::Unquote(_) => Err(EvalError::Internal("unquote outside of quote".into())),
+            Expr::Unknown(raw) => Err(EvalError::UnsupportedConstruct(raw.to_string())),
         }
     }
 
-    fn eval_literal(&self, lit: &Literal) -> Value {
-        match lit { Literal::Int(n) => Value::Int(*n), Literal::Float(f) => Value::float(*f), Literal::Char(c) => Value::Char(*c), Literal::String(s) => Value::string(s), Literal::True => Value::Bool(true), Literal::False => Value::Bool(false), Literal::Unit => Value::Unit }
-    }
+    fn eval_literal(&self, lit: &Literal) -> Value { literal_value(lit) }
 
+    /// The left operand is always evaluated first, so when both sides fail the left error is reported
     fn eval_binop(&mut self, op: &BinOp, left: &Expr, right: &Expr, env: &Env) -> EvalResult<Value> {
         match op {
             BinOp::Map => { let arr = self.eval_with_env(left, env)?; let func = self.eval_with_env(right, env)?; self.eval_map(arr, func) }
@@ -154,13 +432,16 @@ impl Evaluator {
             BinOp::Compose => { let f = self.eval_with_env(left, env)?; let g = self.eval_with_env(right, env)?; self.eval_compose(f, g) }
             BinOp::And => { let left_val = self.eval_with_env(left, env)?; match left_val { Value::Bool(false) => Ok(Value::Bool(false)), Value::Bool(true) => self.eval_with_env(right, env), _ => Err(EvalError::type_error("Bool", &left_val)) } }
             BinOp::Or => { let left_val = self.eval_with_env(left, env)?; match left_val { Value::Bool(true) => Ok(Value::Bool(true)), Value::Bool(false) => self.eval_with_env(right, env), _ => Err(EvalError::type_error("Bool", &left_val)) } }
-            _ => { let left_val = self.eval_with_env(left, env)?; let right_val = self.eval_with_env(right, env)?; prim::apply_binop(op, left_val, right_val) }
+            _ => {
+                let left_val = self.eval_with_env(left, env)?; let right_val = self.eval_with_env(right, env)?;
+                self.binop_values(op, left_val, right_val)
+            }
         }
     }
 
     /// Main apply function with trampoline for tail call optimization.
     /// This prevents stack overflow on deeply recursive tail calls.
-    fn apply(&mut self, func: Value, arg: Value) -> EvalResult<Value> {
+    pub(crate) fn apply(&mut self, func: Value, arg: Value) -> EvalResult<Value> {
         // Use trampoline: loop instead of recurse for tail calls
         let mut tco_result = self.apply_once(func, arg)?;
 
@@ -172,7 +453,7 @@ impl Evaluator {
                     self.depth += 1;
                     if self.depth > self.max_depth {
                         self.depth -= 1;
-                        return Err(EvalError::Internal("Recursion limit exceeded".into()));
+                        return Err(EvalError::StackOverflow { limit: self.max_depth });
                     }
 
                     // Evaluate in tail position - may return another TailCall
@@ -185,6 +466,7 @@ impl Evaluator {
 
     /// Single step of application that may return a tail call for trampolining.
     fn apply_once(&mut self, func: Value, arg: Value) -> EvalResult<TcoResult> {
+        if self.collect_stats { self.stats.reductions += 1; }
         match func {
             Value::Closure(closure) => {
                 if closure.arity == 1 {
@@ -208,6 +490,12 @@ impl Evaluator {
                     Ok(TcoResult::Done(Value::Partial { func: Box::new(Value::Closure(closure)), args: vec![arg], remaining }))
                 }
             }
+            Value::Partial { func, mut args, remaining } if matches!(&*func, Value::Primitive(p) if is_variadic(p)) => {
+                let Value::Primitive(prim) = func.as_ref() else { unreachable!() };
+                if arg == Value::Unit { return Ok(TcoResult::Done(self.call_prim(prim.clone(), args)?)); }
+                args.push(arg);
+                Ok(TcoResult::Done(Value::Partial { func, args, remaining }))
+            }
             Value::Partial { func, mut args, remaining } => {
                 args.push(arg);
                 if remaining == 1 {
@@ -230,21 +518,34 @@ impl Evaluator {
                                 Ok(TcoResult::Done(result))
                             }
                         }
-                        Value::Primitive(prim) => Ok(TcoResult::Done(prim::apply_prim(prim, args)?)),
+                        Value::Primitive(prim) => Ok(TcoResult::Done(self.call_prim(prim, args)?)),
                         _ => Err(EvalError::type_error("function", &func)),
                     }
                 } else {
                     Ok(TcoResult::Done(Value::Partial { func, args, remaining: remaining - 1 }))
                 }
             }
+            Value::Primitive(prim) if is_variadic(&prim) => match arg {
+                Value::Tensor(t) => Ok(TcoResult::Done(self.call_prim(prim, t.iter().collect())?)),
+                Value::Unit => Ok(TcoResult::Done(self.call_prim(prim, vec![])?)),
+                arg => Ok(TcoResult::Done(Value::Partial { func: Box::new(Value::Primitive(prim)), args: vec![arg], remaining: 0 })),
+            },
             Value::Primitive(prim) => {
-                let arity = prim_arity(prim);
+                let arity = prim_arity(&prim);
                 if arity == 1 {
-                    Ok(TcoResult::Done(prim::apply_prim(prim, vec![arg])?))
+                    Ok(TcoResult::Done(self.call_prim(prim, vec![arg])?))
                 } else {
                     Ok(TcoResult::Done(Value::Partial { func: Box::new(Value::Primitive(prim)), args: vec![arg], remaining: arity - 1 }))
                 }
             }
+            Value::Memo(memo) => {
+                // Arguments without a key are recomputed every time
+                let Some(key) = HashableValue::new(arg.clone()) else { return self.apply_once(*memo.func, arg) };
+                if let Some(cached) = memo.cache.borrow().get(&key) { return Ok(TcoResult::Done(cached.clone())); }
+                let result = self.apply(*memo.func, arg)?;
+                memo.cache.borrow_mut().insert(key, result.clone());
+                Ok(TcoResult::Done(result))
+            }
             _ => Err(EvalError::type_error("function", &func)),
         }
     }
@@ -299,8 +600,9 @@ impl Evaluator {
                 }
             }
             // Let: body is in tail position
-            Expr::Let { pattern, value, body, type_: _ } => {
+            Expr::Let { pattern, value, body, type_ } => {
                 let val = self.eval_with_env(value, env)?;
+                self.check_annotation(&val, type_.as_ref())?;
                 let mut new_env = env.clone();
                 self.bind_pattern(pattern, val, &mut new_env)?;
                 self.eval_tail(body, &new_env)
@@ -312,11 +614,8 @@ impl Evaluator {
                 let values: Vec<Value> = bindings.iter()
                     .map(|(_, expr)| self.eval_with_env(expr, &new_env))
                     .collect::<Result<_, _>>()?;
-                let depth = new_env.depth();
-                for (i, val) in values.into_iter().enumerate() {
-                    let idx = depth - bindings.len() + i;
-                    if let Some(slot) = new_env.values.get_mut(idx) { *slot = val; }
-                }
+                new_env.pop_n(bindings.len());
+                new_env.push_many(values);
                 self.eval_tail(body, &new_env)
             }
             // Match: each arm body is in tail position
@@ -324,6 +623,8 @@ impl Evaluator {
                 let val = self.eval_with_env(scrutinee, env)?;
                 self.eval_match_tail(val, arms, env)
             }
+            // Annotation: the inner expression is in tail position unless contracts mode checks its value
+            Expr::Annot(inner, _) if !self.contracts => self.eval_tail(inner, env),
             // Application: this IS the tail call - return for trampolining
             Expr::App(func, arg) => {
                 let func_val = self.eval_with_env(func, env)?;
@@ -365,22 +666,37 @@ impl Evaluator {
         Err(EvalError::NonExhaustiveMatch)
     }
 
+    /// Array patterns match rank-1 tensors and lists alike. `[h | t]` binds `t` to
+    /// the remaining elements in the same form as the scrutinee: a copied tensor,
+    /// or the shared tail of a list.
     fn match_pattern(&self, pattern: &Pattern, val: &Value, env: &mut Env) -> EvalResult<bool> {
         match pattern {
             // Wildcards must push to env to maintain De Bruijn index alignment with resolver
             Pattern::Wildcard => { env.push(val.clone()); Ok(true) }
             Pattern::Var(_) => { env.push(val.clone()); Ok(true) }
             Pattern::Lit(lit) => { let lit_val = self.eval_literal(lit); Ok(val.deep_eq(&lit_val)) }
-            Pattern::Array(pats) => { match val { Value::Tensor(t) => { if t.rank() != 1 || t.len() != pats.len() { return Ok(false); } for (i, pat) in pats.iter().enumerate() { let elem = t.get_flat(i).unwrap(); if !self.match_pattern(pat, &elem, env)? { return Ok(false); } } Ok(true) } _ => Ok(false) } }
-            Pattern::ArraySplit { head, tail } => { match val { Value::Tensor(t) => { if t.rank() != 1 || t.len() < head.len() { return Ok(false); } for (i, pat) in head.iter().enumerate() { let elem = t.get_flat(i).unwrap(); if !self.match_pattern(pat, &elem, env)? { return Ok(false); } } let tail_data: Vec<Value> = (head.len()..t.len()).map(|i| t.get_flat(i).unwrap()).collect(); let tail_tensor = Tensor::from_values(vec![tail_data.len()], tail_data); self.match_pattern(tail, &Value::Tensor(tail_tensor), env) } _ => Ok(false) } }
+            Pattern::Array(pats) => { match val { Value::Tensor(t) => { if t.rank() != 1 || t.len() != pats.len() { return Ok(false); } for (i, pat) in pats.iter().enumerate() { let elem = t.get_flat(i).unwrap(); if !self.match_pattern(pat, &elem, env)? { return Ok(false); } } Ok(true) } Value::List(l) => { if l.len() != pats.len() { return Ok(false); } for (pat, elem) in pats.iter().zip(l.iter()) { if !self.match_pattern(pat, elem, env)? { return Ok(false); } } Ok(true) } _ => Ok(false) } }
+            Pattern::ArraySplit { head, tail } => { match val { Value::Tensor(t) => { if t.rank() != 1 || t.len() < head.len() { return Ok(false); } for (i, pat) in head.iter().enumerate() { let elem = t.get_flat(i).unwrap(); if !self.match_pattern(pat, &elem, env)? { return Ok(false); } } let tail_data: Vec<Value> = (head.len()..t.len()).map(|i| t.get_flat(i).unwrap()).collect(); let tail_tensor = Tensor::from_values(vec![tail_data.len()], tail_data); self.match_pattern(tail, &Value::Tensor(tail_tensor), env) } Value::List(l) => { let mut rest = l; for pat in head { let (Some(elem), Some(next)) = (rest.head(), rest.tail()) else { return Ok(false) }; if !self.match_pattern(pat, elem, env)? { return Ok(false); } rest = next; } self.match_pattern(tail, &Value::List(rest.clone()), env) } _ => Ok(false) } }
             Pattern::Tuple(pats) => { match val { Value::Tuple(vals) => { if vals.len() != pats.len() { return Ok(false); } for (pat, v) in pats.iter().zip(vals) { if !self.match_pattern(pat, v, env)? { return Ok(false); } } Ok(true) } Value::Unit if pats.is_empty() => Ok(true), _ => Ok(false) } }
             Pattern::Variant { constructor, payload } => { match val { Value::Variant { tag, payload: val_payload } => { if tag.as_str() != constructor.as_ref() { return Ok(false); } match (payload, val_payload) { (None, None) => Ok(true), (Some(pat), Some(v)) => self.match_pattern(pat, v, env), _ => Ok(false) } } _ => Ok(false) } }
-            Pattern::Typed(inner, _ty) => self.match_pattern(inner, val, env),
+            Pattern::Typed(inner, ty) => { self.check_annotation(val, Some(ty))?; self.match_pattern(inner, val, env) }
             Pattern::Or(p1, p2) => { let mut env1 = env.clone(); if self.match_pattern(p1, val, &mut env1)? { *env = env1; return Ok(true); } self.match_pattern(p2, val, env) }
             Pattern::Guard(inner, _) => self.match_pattern(inner, val, env),
         }
     }
 
+    fn check_annotation(&self, val: &Value, ty: Option<&goth_ast::types::Type>) -> EvalResult<()> {
+        match ty { Some(ty) if self.contracts => crate::annot::check_annotation(val, ty), _ => Ok(()) }
+    }
+
+    /// Evaluate a top-level `let`, checking its annotation in contracts mode, and define it
+    pub fn eval_let_decl(&mut self, decl: &goth_ast::decl::LetDecl) -> EvalResult<Value> {
+        let val = self.eval(&decl.value)?;
+        self.check_annotation(&val, decl.type_.as_ref())?;
+        self.define(decl.name.to_string(), val.clone());
+        Ok(val)
+    }
+
     fn bind_pattern(&self, pattern: &Pattern, val: Value, env: &mut Env) -> EvalResult<()> { if self.match_pattern(pattern, &val, env)? { Ok(()) } else { Err(EvalError::MatchFailed) } }
 
     fn values_to_tensor(&self, values: Vec<Value>) -> Value {
@@ -398,29 +714,29 @@ impl Evaluator {
 
     fn access_field(&self, val: Value, access: &FieldAccess) -> EvalResult<Value> {
         match access {
-            FieldAccess::Index(i) => { match val { Value::Tuple(vs) => vs.get(*i as usize).cloned().ok_or_else(|| EvalError::IndexOutOfBounds { index: *i as usize, size: vs.len() }), Value::Tensor(t) => t.get_flat(*i as usize).ok_or_else(|| EvalError::IndexOutOfBounds { index: *i as usize, size: t.len() }), _ => Err(EvalError::type_error("Tuple or Tensor", &val)) } }
+            FieldAccess::Index(i) => { match val { Value::Tuple(vs) => vs.get(*i as usize).cloned().ok_or_else(|| EvalError::IndexOutOfBounds { index: *i as i128, len: vs.len() }), Value::Tensor(t) => t.get_flat(*i as usize).ok_or_else(|| EvalError::IndexOutOfBounds { index: *i as i128, len: t.len() }), _ => Err(EvalError::type_error("Tuple or Tensor", &val)) } }
             FieldAccess::Named(name) => { match val { Value::Record(map) => map.get(name.as_ref()).cloned().ok_or_else(|| EvalError::UndefinedName(name.to_string())), _ => Err(EvalError::type_error("Record", &val)) } }
         }
     }
 
     fn index_value(&self, val: Value, indices: &[usize]) -> EvalResult<Value> {
         match val {
-            Value::Tensor(t) => t.get(indices).ok_or_else(|| EvalError::IndexOutOfBounds { index: indices[0], size: t.shape.get(0).copied().unwrap_or(0) }),
-            Value::Tuple(vs) => { if indices.len() != 1 { return Err(EvalError::type_error_msg("Tuple indexing requires single index")); } vs.get(indices[0]).cloned().ok_or_else(|| EvalError::IndexOutOfBounds { index: indices[0], size: vs.len() }) }
+            Value::Tensor(t) => t.get(indices).ok_or_else(|| EvalError::IndexOutOfBounds { index: indices[0] as i128, len: t.shape.get(0).copied().unwrap_or(0) }),
+            Value::Tuple(vs) => { if indices.len() != 1 { return Err(EvalError::type_error_msg("Tuple indexing requires single index")); } vs.get(indices[0]).cloned().ok_or_else(|| EvalError::IndexOutOfBounds { index: indices[0] as i128, len: vs.len() }) }
             _ => Err(EvalError::type_error("Tensor or Tuple", &val)),
         }
     }
 
     fn slice_value(&self, val: Value, start: usize, end: Option<usize>) -> EvalResult<Value> {
         match val {
-            Value::Tensor(t) => { if t.rank() != 1 { return Err(EvalError::not_implemented("slicing rank > 1")); } let end = end.unwrap_or(t.len()); if start > end || end > t.len() { return Err(EvalError::IndexOutOfBounds { index: end, size: t.len() }); } let data: Vec<Value> = (start..end).map(|i| t.get_flat(i).unwrap()).collect(); Ok(Value::Tensor(Tensor::from_values(vec![data.len()], data))) }
+            Value::Tensor(t) => { if t.rank() != 1 { return Err(EvalError::not_implemented("slicing rank > 1")); } let end = end.unwrap_or(t.len()); if start > end || end > t.len() { return Err(EvalError::IndexOutOfBounds { index: end as i128, len: t.len() }); } let data: Vec<Value> = (start..end).map(|i| t.get_flat(i).unwrap()).collect(); Ok(Value::Tensor(Tensor::from_values(vec![data.len()], data))) }
             _ => Err(EvalError::type_error("Tensor", &val)),
         }
     }
 
     fn eval_map(&mut self, arr: Value, func: Value) -> EvalResult<Value> {
         match arr {
-            Value::Tensor(t) => { let results: Vec<Value> = t.iter().map(|elem| self.apply(func.clone(), elem)).collect::<Result<_, _>>()?; Ok(Value::Tensor(Tensor::from_values(t.shape.clone(), results))) }
+            Value::Tensor(t) => { self.reserve_elements(t.len())?; let results: Vec<Value> = t.iter().map(|elem| self.apply(func.clone(), elem)).collect::<Result<_, _>>()?; self.count_tensor(); Ok(Value::Tensor(Tensor::from_values(t.shape.clone(), results))) }
             Value::Tuple(vs) => { let results: Vec<Value> = vs.into_iter().map(|elem| self.apply(func.clone(), elem)).collect::<Result<_, _>>()?; Ok(Value::Tuple(results)) }
             _ => Err(EvalError::type_error("Tensor or Tuple", &arr)),
         }
@@ -428,15 +744,101 @@ impl Evaluator {
 
     fn eval_filter(&mut self, arr: Value, pred: Value) -> EvalResult<Value> {
         match arr {
-            Value::Tensor(t) => { let results: Vec<Value> = t.iter().filter_map(|elem| { let keep = self.apply(pred.clone(), elem.clone()).ok()?; match keep { Value::Bool(true) => Some(elem), _ => None } }).collect(); Ok(Value::Tensor(Tensor::from_values(vec![results.len()], results))) }
+            Value::Tensor(t) => self.with_reservation(Some(t.len()), |ev| { let results: Vec<Value> = t.iter().filter_map(|elem| { let keep = ev.apply(pred.clone(), elem.clone()).ok()?; match keep { Value::Bool(true) => Some(elem), _ => None } }).collect(); ev.count_tensor(); Ok(Value::Tensor(Tensor::from_values(vec![results.len()], results))) }),
             Value::Tuple(vs) => { let results: Vec<Value> = vs.into_iter().filter_map(|elem| { let keep = self.apply(pred.clone(), elem.clone()).ok()?; match keep { Value::Bool(true) => Some(elem), _ => None } }).collect(); Ok(Value::Tuple(results)) }
             _ => Err(EvalError::type_error("Tensor or Tuple", &arr)),
         }
     }
 
+    /// `fold arr init f`: thread an accumulator through the elements left to right as `f acc x`
+    fn eval_fold(&mut self, args: Vec<Value>) -> EvalResult<Value> {
+        let [arr, init, func]: [Value; 3] = args.try_into().map_err(|_| EvalError::internal("fold expects 3 arguments"))?;
+        let elems: Vec<Value> = match arr {
+            Value::Tensor(t) => t.iter().collect(),
+            Value::Tuple(vs) => vs,
+            _ => return Err(EvalError::type_error("Tensor or Tuple", &arr)),
+        };
+        elems.into_iter().try_fold(init, |acc, elem| {
+            let step = self.apply(func.clone(), acc)?;
+            self.apply(step, elem)
+        })
+    }
+
+    /// `scan arr init f`: like `fold`, but keeping every intermediate accumulator in a tensor of `arr`'s shape
+    fn eval_scan(&mut self, args: Vec<Value>) -> EvalResult<Value> {
+        let [arr, init, func]: [Value; 3] = args.try_into().map_err(|_| EvalError::internal("scan expects 3 arguments"))?;
+        let Value::Tensor(t) = arr else { return Err(EvalError::type_error("Tensor", &arr)) };
+        self.reserve_elements(t.len())?;
+        let mut acc = init;
+        let mut results = Vec::with_capacity(t.len());
+        for elem in t.iter() {
+            let step = self.apply(func.clone(), acc)?;
+            acc = self.apply(step, elem)?;
+            results.push(acc.clone());
+        }
+        self.count_tensor();
+        Ok(Value::Tensor(Tensor::pack(t.shape.clone(), results)))
+    }
+
+    /// `zipWith a b f`: the tensor of `f a[i] b[i]` over two tensors of the same shape
+    fn eval_zip_with(&mut self, args: Vec<Value>) -> EvalResult<Value> {
+        let [left, right, func]: [Value; 3] = args.try_into().map_err(|_| EvalError::internal("zipWith expects 3 arguments"))?;
+        let (Value::Tensor(a), Value::Tensor(b)) = (&left, &right) else {
+            return Err(EvalError::type_error_msg(format!("Cannot zip {} and {}", left.type_name(), right.type_name())));
+        };
+        if a.shape != b.shape { return Err(EvalError::incompatible_shapes(&a.shape, &b.shape)); }
+        self.reserve_elements(a.len())?;
+        let results: Vec<Value> = a.iter().zip(b.iter()).map(|(x, y)| {
+            let partial = self.apply(func.clone(), x)?;
+            self.apply(partial, y)
+        }).collect::<Result<_, _>>()?;
+        self.count_tensor();
+        Ok(Value::Tensor(Tensor::pack(a.shape.clone(), results)))
+    }
+
+    /// `sortBy xs less`: the vector `xs` stably sorted so that no element is `less` than the one before it
+    fn eval_sort_by(&mut self, args: Vec<Value>) -> EvalResult<Value> {
+        let [arr, less]: [Value; 2] = args.try_into().map_err(|_| EvalError::internal("sortBy expects 2 arguments"))?;
+        let Value::Tensor(t) = arr else { return Err(EvalError::type_error("Tensor", &arr)) };
+        if t.rank() != 1 { return Err(EvalError::shape_mismatch(format!("sortBy requires a rank-1 tensor, got shape {:?}", t.shape))); }
+        let sorted = self.merge_sort(t.to_vec(), &less)?;
+        self.count_tensor();
+        Ok(Value::Tensor(Tensor::pack(t.shape.clone(), sorted)))
+    }
+
+    /// Merge sort calling `less` for each comparison, so that it may fail
+    fn merge_sort(&mut self, mut elems: Vec<Value>, less: &Value) -> EvalResult<Vec<Value>> {
+        if elems.len() <= 1 { return Ok(elems); }
+        let right = self.merge_sort(elems.split_off(elems.len() / 2), less)?;
+        let left = self.merge_sort(elems, less)?;
+        let mut merged = Vec::with_capacity(left.len() + right.len());
+        let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+        while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+            // Take from the right only when strictly less, keeping equal elements in order
+            let partial = self.apply(less.clone(), r.clone())?;
+            let take_right = match self.apply(partial, l.clone())? { Value::Bool(b) => b, other => return Err(EvalError::type_error("Bool", &other)) };
+            merged.extend(if take_right { right.next() } else { left.next() });
+        }
+        merged.extend(left.chain(right));
+        Ok(merged)
+    }
+
     fn eval_bind(&mut self, arr: Value, func: Value) -> EvalResult<Value> {
         match arr {
-            Value::Tensor(t) => { let mut results = Vec::new(); for elem in t.iter() { let mapped = self.apply(func.clone(), elem)?; match mapped { Value::Tensor(inner) => results.extend(inner.iter()), Value::Tuple(inner) => results.extend(inner), other => results.push(other) } } Ok(Value::Tensor(Tensor::from_values(vec![results.len()], results))) }
+            Value::Tensor(t) => {
+                // The result's size is only known as it grows, so each part is reserved before it is added
+                let mut results = Vec::new();
+                for elem in t.iter() {
+                    let mapped = self.apply(func.clone(), elem)?;
+                    match mapped {
+                        Value::Tensor(inner) => { self.reserve_elements(inner.len())?; results.extend(inner.iter()) }
+                        Value::Tuple(inner) => { self.reserve_elements(inner.len())?; results.extend(inner) }
+                        other => { self.reserve_elements(1)?; results.push(other) }
+                    }
+                }
+                self.count_tensor();
+                Ok(Value::Tensor(Tensor::from_values(vec![results.len()], results)))
+            }
             _ => Err(EvalError::type_error("Tensor", &arr)),
         }
     }
@@ -449,6 +851,7 @@ impl Evaluator {
         let body = Expr::App(Box::new(Expr::Idx(2)), Box::new(Expr::App(Box::new(Expr::Idx(1)), Box::new(Expr::Idx(0)))));
         let mut env = Env::with_globals(Rc::clone(&self.globals));
         env.push(f); env.push(g);  // Push f first, then g, so g is at Idx(1) and f is at Idx(2)
+        self.count_closure();
         Ok(Value::Closure(Closure { arity: 1, body, env, preconditions: vec![], postconditions: vec![] }))
     }
 
@@ -459,7 +862,7 @@ impl Evaluator {
                 DoOp::Map(f) => { let func = self.eval_with_env(f, env)?; self.eval_map(current, func)? }
                 DoOp::Filter(p) => { let pred = self.eval_with_env(p, env)?; self.eval_filter(current, pred)? }
                 DoOp::Bind(f) => { let func = self.eval_with_env(f, env)?; self.eval_bind(current, func)? }
-                DoOp::Op(binop, e) => { let right = self.eval_with_env(e, env)?; prim::apply_binop(binop, current, right)? }
+                DoOp::Op(binop, e) => { let right = self.eval_with_env(e, env)?; self.binop_values(binop, current, right)? }
                 DoOp::Let(_, e) => { self.eval_with_env(e, env)? }
             };
         }
@@ -469,17 +872,44 @@ impl Evaluator {
 
 impl Default for Evaluator { fn default() -> Self { Self::new() } }
 
-fn prim_arity(prim: PrimFn) -> usize {
+pub(crate) fn prim_arity(prim: &PrimFn) -> usize {
     match prim {
-        PrimFn::Neg | PrimFn::Abs | PrimFn::Not | PrimFn::Exp | PrimFn::Ln | PrimFn::Sqrt | PrimFn::Sin | PrimFn::Cos | PrimFn::Tan | PrimFn::Floor | PrimFn::Ceil | PrimFn::Round | PrimFn::Sum | PrimFn::Prod | PrimFn::Len | PrimFn::Shape | PrimFn::Reverse | PrimFn::Transpose | PrimFn::Norm | PrimFn::ToInt | PrimFn::ToFloat | PrimFn::ToBool | PrimFn::ToChar | PrimFn::ParseInt | PrimFn::ParseFloat | PrimFn::Iota | PrimFn::ToString | PrimFn::Chars => 1,
+        PrimFn::Neg | PrimFn::Abs | PrimFn::Sign | PrimFn::Not | PrimFn::Exp | PrimFn::Ln | PrimFn::Sqrt | PrimFn::Sin | PrimFn::Cos | PrimFn::Tan | PrimFn::Floor | PrimFn::Ceil | PrimFn::Round | PrimFn::Sum | PrimFn::Prod | PrimFn::Len | PrimFn::Shape | PrimFn::Reverse | PrimFn::Sort | PrimFn::Transpose | PrimFn::Norm | PrimFn::ToInt | PrimFn::ToFloat | PrimFn::ToBool | PrimFn::ToChar | PrimFn::Ord | PrimFn::Chr | PrimFn::ParseInt | PrimFn::ParseFloat | PrimFn::Iota | PrimFn::ToString | PrimFn::Chars | PrimFn::StrLen => 1,
         PrimFn::Print | PrimFn::Write | PrimFn::ReadLine | PrimFn::ReadKey | PrimFn::ReadFile | PrimFn::Sleep => 1,
         PrimFn::Flush | PrimFn::RawModeEnter | PrimFn::RawModeExit => 1,  // Terminal control (take unit)
         PrimFn::Lines | PrimFn::Words | PrimFn::Bytes => 1,  // String splitting (unary)
+        PrimFn::Mean | PrimFn::Variance | PrimFn::VarianceSample | PrimFn::Std => 1,  // Statistics
+        PrimFn::ArgMin | PrimFn::ArgMax | PrimFn::Normalize | PrimFn::Softmax => 1,
+        PrimFn::Head | PrimFn::Tail | PrimFn::IsEmpty | PrimFn::ToList | PrimFn::ToTensor => 1,  // Lists (cons takes 2)
         PrimFn::WriteFile => 2,  // WriteFile takes path and content
+        PrimFn::Fold => 3,  // Fold takes the tensor, initial accumulator and step function
+        PrimFn::Scan => 3,  // Scan takes the same arguments as Fold
+        PrimFn::ZipWith => 3,  // ZipWith takes both tensors and the combining function
+        PrimFn::SortBy => 2,  // SortBy takes the vector and the less-than function
+        PrimFn::Native(native) => native.arity,
+        PrimFn::Zeros | PrimFn::Ones => 1,  // Zeros and Ones take the shape
+        PrimFn::Memoize => 1,  // Memoize takes the function to wrap
+        PrimFn::RangeStep => 3,  // RangeStep takes start, end and step
+        PrimFn::Clamp => 3,  // Clamp takes the value and both bounds
+        PrimFn::ConcatAxis => 3,  // ConcatAxis takes both tensors and the axis
         PrimFn::StrEq | PrimFn::StartsWith | PrimFn::EndsWith | PrimFn::Contains => 2,  // String comparison (binary)
         _ => 2,  // Range, StrConcat, Take, Drop, Index etc take 2 args
     }
 }
 
+/// Variadic primitives take any number of arguments. Calling convention:
+/// - applied to a tensor, the primitive runs at once on its elements: `max [1, 2, 3]`
+/// - otherwise arguments are collected (as a `Partial` with `remaining: 0`) until
+///   the primitive is applied to `()`, which saturates it: `max 1 2 3 4 ()`
+pub(crate) fn is_variadic(prim: &PrimFn) -> bool {
+    matches!(prim, PrimFn::Min | PrimFn::Max)
+}
+
+pub(crate) fn literal_value(lit: &Literal) -> Value {
+    match lit { Literal::Int(n) => Value::Int(*n), Literal::Float(f) => Value::float(*f), Literal::Char(c) => Value::Char(*c), Literal::String(s) => Value::string(s), Literal::True => Value::Bool(true), Literal::False => Value::Bool(false), Literal::Unit => Value::Unit }
+}
+
 pub fn eval(expr: &Expr) -> EvalResult<Value> { let mut evaluator = Evaluator::new(); evaluator.eval(expr) }
 pub fn eval_trace(expr: &Expr) -> EvalResult<Value> { let mut evaluator = Evaluator::new().with_trace(true); evaluator.eval(expr) }
+/// Like [`eval_trace`], failing with [`EvalError::StepLimitExceeded`] after `max_steps` expressions
+pub fn eval_trace_limited(expr: &Expr, max_steps: usize) -> EvalResult<Value> { let mut evaluator = Evaluator::new().with_trace(true).with_step_limit(max_steps); evaluator.eval(expr) }
@@ -0,0 +1,114 @@
+//! Map and filter fusion
+//!
+//! Rewrites adjacent collection operations so the intermediate tensor is
+//! never built:
+//!
+//! - `arr ↦ g ↦ f` becomes `arr ↦ (f ∘ g)`
+//! - `arr ▸ p ▸ q` becomes `arr ▸ (let (p', q') = (p, q) in λ. p' ₀ ∧ q' ₀)`
+//!
+//! Only lambdas (and functions already fused from lambdas) are fused, since
+//! evaluating them has no effects and cannot fail; the rewrite then only
+//! reorders calls between elements. Unfused, `g` runs over every element
+//! before `f` sees any, so the first failure is the first one `g` meets,
+//! else the first one `f` meets. Fused, the stages interleave per element,
+//! which raises the same error only when one of `f` and `g` cannot fail:
+//! maps are therefore fused only when one side is a lambda whose body just
+//! builds values from its arguments. Errors in the filter predicates drop
+//! the element in both forms, as `▸` does, so filters always fuse.
+
+use goth_ast::expr::{Expr, MatchArm, DoOp};
+use goth_ast::op::BinOp;
+use goth_ast::pattern::Pattern;
+
+/// Fuse adjacent maps and adjacent filters throughout `expr`
+pub fn fuse_maps(expr: Expr) -> Expr {
+    match fuse_children(expr) {
+        Expr::BinOp(BinOp::Map, inner, f) if is_pure_fn(&f) => match *inner {
+            Expr::BinOp(BinOp::Map, arr, g) if is_pure_fn(&g) && (is_total_fn(&f) || is_total_fn(&g)) => Expr::BinOp(BinOp::Map, arr, Box::new(Expr::BinOp(BinOp::Compose, f, g))),
+            inner => Expr::BinOp(BinOp::Map, Box::new(inner), f),
+        },
+        Expr::BinOp(BinOp::Filter, inner, q) if is_pure_fn(&q) => match *inner {
+            Expr::BinOp(BinOp::Filter, arr, p) if is_pure_fn(&p) => Expr::BinOp(BinOp::Filter, arr, Box::new(both(*p, *q))),
+            inner => Expr::BinOp(BinOp::Filter, Box::new(inner), q),
+        },
+        other => other,
+    }
+}
+
+/// `let (p', q') = (p, q) in λx. p' x ∧ q' x`, binding the predicates once so they need no shifting
+fn both(p: Expr, q: Expr) -> Expr {
+    let call = |f: u32| Expr::app(Expr::idx(f), Expr::idx(0));
+    let body = Expr::lam(Expr::binop(BinOp::And, call(2), call(1)));
+    Expr::let_(Pattern::Tuple(vec![Pattern::Wildcard, Pattern::Wildcard]), Expr::tuple(vec![p, q]), body)
+}
+
+/// A unary function expression whose evaluation has no effects and cannot fail
+fn is_pure_fn(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lam(_) | Expr::LamN(1, _) => true,
+        Expr::BinOp(BinOp::Compose, f, g) => is_pure_fn(f) && is_pure_fn(g),
+        Expr::Let { pattern: Pattern::Tuple(_), value, body, .. } => matches!(value.as_ref(), Expr::Tuple(fs) if fs.iter().all(is_pure_fn)) && matches!(body.as_ref(), Expr::Lam(_)),
+        _ => false,
+    }
+}
+
+/// A unary function expression whose application cannot fail
+fn is_total_fn(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lam(body) | Expr::LamN(1, body) => is_total(body),
+        Expr::BinOp(BinOp::Compose, f, g) => is_total_fn(f) && is_total_fn(g),
+        _ => false,
+    }
+}
+
+/// An expression that only builds values from bound variables and literals
+fn is_total(expr: &Expr) -> bool {
+    match expr {
+        Expr::Idx(_) | Expr::Lit(_) | Expr::Lam(_) | Expr::LamN(..) => true,
+        Expr::Tuple(es) | Expr::Array(es) => es.iter().all(is_total),
+        Expr::Record(fields) => fields.iter().all(|(_, e)| is_total(e)),
+        Expr::Variant { payload, .. } => payload.as_deref().is_none_or(is_total),
+        _ => false,
+    }
+}
+
+fn fuse_children(expr: Expr) -> Expr {
+    let fuse = |e: Box<Expr>| Box::new(fuse_maps(*e));
+    let fuse_all = |es: Vec<Expr>| es.into_iter().map(fuse_maps).collect();
+    let fuse_fields = |fs: Vec<(Box<str>, Expr)>| fs.into_iter().map(|(n, e)| (n, fuse_maps(e))).collect();
+    match expr {
+        Expr::Idx(_) | Expr::Name(_) | Expr::Lit(_) | Expr::Prim(_) | Expr::Hole | Expr::Disabled(_) | Expr::Quote(_) | Expr::Unquote(_) | Expr::Unknown(_) => expr,
+        Expr::App(f, x) => Expr::App(fuse(f), fuse(x)),
+        Expr::AppNamed(f, args) => Expr::AppNamed(fuse(f), fuse_fields(args)),
+        Expr::Lam(body) => Expr::Lam(fuse(body)),
+        Expr::LamN(n, body) => Expr::LamN(n, fuse(body)),
+        Expr::Let { pattern, type_, value, body } => Expr::Let { pattern, type_, value: fuse(value), body: fuse(body) },
+        Expr::LetRec { bindings, body } => Expr::LetRec { bindings: bindings.into_iter().map(|(p, e)| (p, fuse_maps(e))).collect(), body: fuse(body) },
+        Expr::Match { scrutinee, arms } => Expr::Match { scrutinee: fuse(scrutinee), arms: arms.into_iter().map(|arm| MatchArm { pattern: arm.pattern, guard: arm.guard.map(fuse_maps), body: fuse_maps(arm.body) }).collect() },
+        Expr::If { cond, then_, else_ } => Expr::If { cond: fuse(cond), then_: fuse(then_), else_: fuse(else_) },
+        Expr::BinOp(op, l, r) => Expr::BinOp(op, fuse(l), fuse(r)),
+        Expr::UnaryOp(op, e) => Expr::UnaryOp(op, fuse(e)),
+        Expr::Norm(e) => Expr::Norm(fuse(e)),
+        Expr::Tuple(es) => Expr::Tuple(fuse_all(es)),
+        Expr::Record(fields) => Expr::Record(fuse_fields(fields)),
+        Expr::Array(es) => Expr::Array(fuse_all(es)),
+        Expr::ArrayFill { shape, value } => Expr::ArrayFill { shape: fuse_all(shape), value: fuse(value) },
+        Expr::Variant { constructor, payload } => Expr::Variant { constructor, payload: payload.map(fuse) },
+        Expr::Field(e, access) => Expr::Field(fuse(e), access),
+        Expr::Index(base, indices) => Expr::Index(fuse(base), fuse_all(indices)),
+        Expr::Slice { array, start, end } => Expr::Slice { array: fuse(array), start: start.map(fuse), end: end.map(fuse) },
+        Expr::Annot(e, ty) => Expr::Annot(fuse(e), ty),
+        Expr::Cast { expr, target, kind } => Expr::Cast { expr: fuse(expr), target, kind },
+        Expr::Update { base, fields } => Expr::Update { base: fuse(base), fields: fuse_fields(fields) },
+        Expr::Do { init, ops } => Expr::Do {
+            init: fuse(init),
+            ops: ops.into_iter().map(|op| match op {
+                DoOp::Map(e) => DoOp::Map(fuse_maps(e)),
+                DoOp::Filter(e) => DoOp::Filter(fuse_maps(e)),
+                DoOp::Bind(e) => DoOp::Bind(fuse_maps(e)),
+                DoOp::Op(op, e) => DoOp::Op(op, fuse_maps(e)),
+                DoOp::Let(p, e) => DoOp::Let(p, fuse_maps(e)),
+            }).collect(),
+        },
+    }
+}
@@ -0,0 +1,102 @@
+//! Values as hash keys
+//!
+//! `f64` is neither `Eq` nor `Hash`, so floats are canonicalized before they
+//! are compared or hashed: `-0.0` is the same key as `+0.0`, and every NaN,
+//! whatever its sign or payload, is the same key as every other NaN. Apart
+//! from that, keys are equal exactly when their values are `deep_eq`; in
+//! particular `1` and `1.0` are different keys. Records compare by field
+//! regardless of order, and tensors by shape and elements regardless of how
+//! they are stored.
+
+use crate::value::Value;
+use std::hash::{Hash, Hasher};
+
+/// A data value usable as a key in hash maps and sets
+#[derive(Debug, Clone)]
+pub struct HashableValue(Value);
+
+impl HashableValue {
+    /// Wrap `value`, or `None` if it contains something without structural
+    /// equality (closures, primitives, thunks, refs, errors)
+    pub fn new(value: Value) -> Option<Self> {
+        hashable(&value).then_some(HashableValue(value))
+    }
+
+    pub fn value(&self) -> &Value { &self.0 }
+    pub fn into_value(self) -> Value { self.0 }
+}
+
+impl PartialEq for HashableValue {
+    fn eq(&self, other: &Self) -> bool { key_eq(&self.0, &other.0) }
+}
+
+impl Eq for HashableValue {}
+
+impl Hash for HashableValue {
+    fn hash<H: Hasher>(&self, state: &mut H) { hash_value(&self.0, state) }
+}
+
+/// Bits of `f`, with both zeros mapped to `+0.0` and every NaN to one canonical NaN
+pub fn canonical_bits(f: f64) -> u64 {
+    if f.is_nan() { f64::NAN.to_bits() } else if f == 0.0 { 0 } else { f.to_bits() }
+}
+
+fn hashable(value: &Value) -> bool {
+    match value {
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::Char(_) | Value::Unit => true,
+        Value::Tensor(t) => t.iter().all(|v| hashable(&v)),
+        Value::List(l) => l.iter().all(hashable),
+        Value::Tuple(vs) => vs.iter().all(hashable),
+        Value::Record(fields) => fields.values().all(hashable),
+        Value::Variant { payload, .. } => payload.as_deref().is_none_or(hashable),
+        Value::Uncertain { value, uncertainty } => hashable(value) && hashable(uncertainty),
+        _ => false,
+    }
+}
+
+fn all_eq<'a>(a: impl Iterator<Item = &'a Value>, b: impl Iterator<Item = &'a Value>) -> bool {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.next(), b.next()) {
+            (None, None) => return true,
+            (Some(x), Some(y)) if key_eq(x, y) => {}
+            _ => return false,
+        }
+    }
+}
+
+fn key_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Float(x), Value::Float(y)) => canonical_bits(x.0) == canonical_bits(y.0),
+        (Value::Tensor(x), Value::Tensor(y)) => x.shape == y.shape && x.iter().zip(y.iter()).all(|(p, q)| key_eq(&p, &q)),
+        (Value::List(x), Value::List(y)) => all_eq(x.iter(), y.iter()),
+        (Value::Tuple(x), Value::Tuple(y)) => all_eq(x.iter(), y.iter()),
+        (Value::Record(x), Value::Record(y)) => x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| key_eq(v, w))),
+        (Value::Variant { tag: t1, payload: p1 }, Value::Variant { tag: t2, payload: p2 }) => {
+            t1 == t2 && match (p1, p2) { (None, None) => true, (Some(x), Some(y)) => key_eq(x, y), _ => false }
+        }
+        (Value::Uncertain { value: v1, uncertainty: u1 }, Value::Uncertain { value: v2, uncertainty: u2 }) => key_eq(v1, v2) && key_eq(u1, u2),
+        _ => a.deep_eq(b),
+    }
+}
+
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    std::mem::discriminant(value).hash(state);
+    match value {
+        Value::Int(n) => n.hash(state),
+        Value::Float(f) => canonical_bits(f.0).hash(state),
+        Value::Bool(b) => b.hash(state),
+        Value::Char(c) => c.hash(state),
+        Value::Tensor(t) => { t.shape.hash(state); for v in t.iter() { hash_value(&v, state); } }
+        Value::List(l) => { l.len().hash(state); for v in l.iter() { hash_value(v, state); } }
+        Value::Tuple(vs) => { vs.len().hash(state); for v in vs { hash_value(v, state); } }
+        Value::Record(fields) => {
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            for name in names { name.hash(state); hash_value(&fields[name], state); }
+        }
+        Value::Variant { tag, payload } => { tag.hash(state); payload.is_some().hash(state); if let Some(p) = payload { hash_value(p, state); } }
+        Value::Uncertain { value, uncertainty } => { hash_value(value, state); hash_value(uncertainty, state); }
+        _ => {}
+    }
+}
@@ -4,11 +4,26 @@ pub mod value;
 pub mod error;
 pub mod prim;
 pub mod eval;
+pub mod capture;
+pub mod arity;
+pub mod bytecode;
+pub mod fuse;
+pub mod ser;
+pub mod buffer;
+pub mod csv;
+pub mod hash;
+pub mod annot;
+pub mod diff;
+pub mod specialize;
 
 pub mod prelude {
-    pub use crate::value::{Value, Tensor, TensorData, Closure, Env, PrimFn};
+    pub use crate::value::{Value, Tensor, TensorData, List, Closure, Env, PrimFn, NativeFn, PrimContext, Memo};
     pub use crate::error::{EvalError, EvalResult};
-    pub use crate::eval::{Evaluator, eval, eval_trace};
+    pub use crate::eval::{Evaluator, EvalStats, eval, eval_trace, eval_trace_limited};
+    pub use crate::arity::{check_arities, ArityError};
+    pub use crate::hash::HashableValue;
+    pub use crate::diff::{ValueDiff, PathStep};
+    pub use crate::specialize::specialize;
 }
 
 #[cfg(test)]
@@ -16,6 +31,15 @@ mod tests {
     use super::prelude::*;
     use goth_ast::prelude::*;
 
+    /// Apply the global `name` to `args` one at a time in `e`
+    fn call_in(e: &mut Evaluator, name: &str, args: Vec<Expr>) -> EvalResult<Value> {
+        e.eval(&args.into_iter().fold(Expr::name(name), Expr::app))
+    }
+
+    fn call(name: &str, args: Vec<Expr>) -> EvalResult<Value> { call_in(&mut Evaluator::new(), name, args) }
+
+    fn ints(xs: &[i128]) -> Expr { Expr::array(xs.iter().copied().map(Expr::int).collect()) }
+
     #[test] fn test_int_literal() { assert_eq!(eval(&Expr::int(42)).unwrap(), Value::Int(42)); }
     #[test] fn test_float_literal() { assert_eq!(eval(&Expr::float(3.14)).unwrap(), Value::float(3.14)); }
     #[test] fn test_bool_literals() { assert_eq!(eval(&Expr::bool(true)).unwrap(), Value::Bool(true)); assert_eq!(eval(&Expr::bool(false)).unwrap(), Value::Bool(false)); }
@@ -48,6 +72,7 @@ mod tests {
     #[test] fn test_match_literal() { let expr = Expr::match_(Expr::int(1), vec![MatchArm::new(Pattern::lit(1i64), Expr::int(10)), MatchArm::new(Pattern::wildcard(), Expr::int(20))]); assert_eq!(eval(&expr).unwrap(), Value::Int(10)); }
     #[test] fn test_match_wildcard() { let expr = Expr::match_(Expr::int(99), vec![MatchArm::new(Pattern::lit(1i64), Expr::int(10)), MatchArm::new(Pattern::wildcard(), Expr::int(20))]); assert_eq!(eval(&expr).unwrap(), Value::Int(20)); }
     #[test] fn test_match_tuple() { let expr = Expr::match_(Expr::tuple(vec![Expr::int(1), Expr::int(2)]), vec![MatchArm::new(Pattern::tuple(vec![Pattern::var("a"), Pattern::var("b")]), Expr::add(Expr::idx(1), Expr::idx(0)))]); assert_eq!(eval(&expr).unwrap(), Value::Int(3)); }
+    #[test] fn test_match_unit() { for scrutinee in [Expr::Lit(Literal::Unit), Expr::tuple(vec![])] { let expr = Expr::match_(scrutinee, vec![MatchArm::new(Pattern::tuple(vec![]), Expr::int(7))]); assert_eq!(eval(&expr).unwrap(), Value::Int(7)); } }
     #[test] fn test_match_variant() { let expr = Expr::match_(Expr::variant("Some", Some(Expr::int(5))), vec![MatchArm::new(Pattern::variant("None", None), Expr::int(0)), MatchArm::new(Pattern::variant("Some", Some(Pattern::var("x"))), Expr::idx(0))]); assert_eq!(eval(&expr).unwrap(), Value::Int(5)); }
 
     #[test] fn test_array_literal() { let result = eval(&Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3)])).unwrap(); match result { Value::Tensor(t) => { assert_eq!(t.shape, vec![3]); assert_eq!(t.get_flat(0), Some(Value::Int(1))); } _ => panic!("Expected tensor") } }
@@ -80,17 +105,46 @@ mod tests {
 
     #[test] fn test_sqrt_primitive() { let mut e = Evaluator::new(); assert_eq!(e.eval(&Expr::app(Expr::name("sqrt"), Expr::float(16.0))).unwrap(), Value::float(4.0)); }
     #[test] fn test_abs_primitive() { let mut e = Evaluator::new(); assert_eq!(e.eval(&Expr::app(Expr::name("abs"), Expr::int(-5))).unwrap(), Value::Int(5)); }
+    #[test] fn test_sqrt_promotes_int() { assert_eq!(eval(&Expr::app(Expr::name("sqrt"), Expr::int(16))).unwrap(), Value::float(4.0)); }
+    #[test] fn test_sign_preserves_int() { assert_eq!(eval(&Expr::UnaryOp(UnaryOp::Sign, Box::new(Expr::int(-7)))).unwrap(), Value::Int(-1)); assert_eq!(eval(&Expr::UnaryOp(UnaryOp::Sign, Box::new(Expr::float(2.5)))).unwrap(), Value::float(1.0)); }
     #[test] fn test_partial_application() { let mut e = Evaluator::new(); let add5 = Expr::app(Expr::name("add"), Expr::int(5)); assert!(e.eval(&add5).unwrap().is_callable()); assert_eq!(e.eval(&Expr::app(add5, Expr::int(3))).unwrap(), Value::Int(8)); }
     #[test] fn test_function_composition() { let add1 = Expr::lam(Expr::add(Expr::idx(0), Expr::int(1))); let mul2 = Expr::lam(Expr::mul(Expr::idx(0), Expr::int(2))); let composed = Expr::binop(BinOp::Compose, add1, mul2); assert_eq!(eval(&Expr::app(composed, Expr::int(3))).unwrap(), Value::Int(7)); }
     #[test] fn test_map_filter_sum() { let expr = Expr::sum(Expr::map(Expr::filter(Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3), Expr::int(4), Expr::int(5)]), Expr::lam(Expr::binop(BinOp::Eq, Expr::binop(BinOp::Mod, Expr::idx(0), Expr::int(2)), Expr::int(0)))), Expr::lam(Expr::mul(Expr::idx(0), Expr::idx(0))))); assert_eq!(eval(&expr).unwrap(), Value::Int(20)); }
     #[test] fn test_nested_lambdas() { let expr = Expr::app(Expr::app(Expr::app(Expr::lam(Expr::lam(Expr::lam(Expr::add(Expr::add(Expr::idx(2), Expr::idx(1)), Expr::idx(0))))), Expr::int(1)), Expr::int(2)), Expr::int(3)); assert_eq!(eval(&expr).unwrap(), Value::Int(6)); }
     #[test] fn test_type_error() { assert!(eval(&Expr::add(Expr::int(1), Expr::bool(true))).is_err()); }
     #[test] fn test_unbound_variable() { assert!(matches!(eval(&Expr::idx(999)), Err(EvalError::UnboundIndex(999)))); }
+    #[test] fn test_unbound_as_hole() {
+        let expr = Expr::tuple(vec![Expr::idx(999), Expr::int(1)]);
+        assert!(matches!(Evaluator::new().treat_unbound_as_hole(false).eval(&expr), Err(EvalError::UnboundIndex(999))));
+        let mut lenient = Evaluator::new().treat_unbound_as_hole(true);
+        assert_eq!(lenient.eval(&expr).unwrap(), Value::Tuple(vec![Value::Unit, Value::Int(1)]));
+        // Bound indices are unaffected
+        assert_eq!(lenient.eval(&Expr::let_(Pattern::var("x"), Expr::int(5), Expr::idx(0))).unwrap(), Value::Int(5));
+        let env = Env::with_globals(lenient.globals());
+        assert_eq!(lenient.run(&crate::bytecode::compile(&Expr::idx(3)), &env).unwrap(), Value::Unit);
+    }
     #[test] fn test_undefined_name() { assert!(matches!(eval(&Expr::name("nonexistent")), Err(EvalError::UndefinedName(_)))); }
     #[test] fn test_de_bruijn_simple() { assert_eq!(eval(&Expr::app(Expr::lam(Expr::idx(0)), Expr::int(5))).unwrap(), Value::Int(5)); }
     #[test] fn test_de_bruijn_nested() { assert_eq!(eval(&Expr::app(Expr::app(Expr::lam(Expr::lam(Expr::add(Expr::idx(0), Expr::idx(1)))), Expr::int(3)), Expr::int(4))).unwrap(), Value::Int(7)); }
     #[test] fn test_de_bruijn_capture_in_closure() { let expr = Expr::let_(Pattern::var("x"), Expr::int(5), Expr::let_(Pattern::var("f"), Expr::lam(Expr::add(Expr::idx(1), Expr::idx(0))), Expr::app(Expr::idx(0), Expr::int(3)))); assert_eq!(eval(&expr).unwrap(), Value::Int(8)); }
     #[test] fn test_dot_product() { let mut e = Evaluator::new(); let a = Expr::array(vec![Expr::float(1.0), Expr::float(2.0), Expr::float(3.0)]); let b = Expr::array(vec![Expr::float(4.0), Expr::float(5.0), Expr::float(6.0)]); let expr = Expr::app(Expr::app(Expr::name("dot"), a), b); assert_eq!(e.eval(&expr).unwrap(), Value::float(32.0)); }
+    #[test] fn test_dot_accumulates_ints_widely() {
+        // The running sum passes I64::MAX, but the result fits
+        let max = i64::MAX as i128;
+        let dot = |a: &[i128], b: &[i128]| eval(&Expr::app(Expr::app(Expr::name("dot"), ints(a)), ints(b)));
+        assert_eq!(dot(&[max, max, -max], &[1, 1, 1]).unwrap(), Value::Int(max));
+        assert_eq!(dot(&[1 << 40, 1 << 40], &[1 << 30, -(1 << 30)]).unwrap(), Value::Int(0));
+        assert!(matches!(dot(&[max, 1], &[1, 1]), Err(EvalError::IntegerOverflow(_))));
+    }
+    #[test] fn test_dot_compensates_float_rounding() {
+        let floats = |xs: &[f64]| Expr::array(xs.iter().map(|&x| Expr::float(x)).collect());
+        let dot = |a: &[f64], b: &[f64]| eval(&Expr::app(Expr::app(Expr::name("dot"), floats(a)), floats(b))).unwrap();
+        assert_eq!(dot(&[0.1; 10_000], &[1.0; 10_000]), Value::float(1000.0));
+        assert_eq!(dot(&[1e16, 1.0, -1e16], &[1.0, 1.0, 1.0]), Value::float(1.0));
+    }
+    #[test] fn test_dot_length_mismatch() { let expr = Expr::app(Expr::app(Expr::name("dot"), Expr::array(vec![Expr::int(1), Expr::int(2)])), Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3)])); assert!(matches!(eval(&expr), Err(EvalError::IncompatibleShapes { left, right }) if left == [2] && right == [3])); }
+    #[test] fn test_dot_scalar_argument() { let expr = Expr::app(Expr::app(Expr::name("dot"), Expr::array(vec![Expr::int(1), Expr::int(2)])), Expr::int(3)); assert!(matches!(eval(&expr), Err(EvalError::TypeError { expected: "Tensor", got: "Int" }))); }
+    #[test] fn test_dot_matrix_rejected() { let m = Expr::ArrayFill { shape: vec![Expr::int(2), Expr::int(2)], value: Box::new(Expr::int(1)) }; let expr = Expr::app(Expr::app(Expr::name("dot"), m.clone()), m); assert!(matches!(eval(&expr), Err(EvalError::ShapeMismatch(msg)) if msg.contains("rank-1"))); }
 
     // ============ Multi-Argument Function Tests ============
 
@@ -407,6 +461,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_concat_str_length_and_equality() {
+        let s = |text: &str| Expr::Lit(Literal::String(text.into()));
+        let joined = eval(&Expr::app_n(Expr::name("concat_str"), vec![s("héllo, "), s("wörld")])).unwrap();
+        assert_eq!(joined, Value::string("héllo, wörld"));
+        assert_eq!(eval(&Expr::app(Expr::name("str_len"), s("héllo"))).unwrap(), Value::Int(5));
+        assert_eq!(eval(&Expr::app(Expr::name("strLen"), s(""))).unwrap(), Value::Int(0));
+        assert!(matches!(eval(&Expr::app(Expr::name("str_len"), ints(&[1, 2, 3]))), Err(EvalError::TypeError { expected: "String", got: "Tensor" })));
+        assert_eq!(eval(&Expr::binop(BinOp::Eq, s("abc"), s("abc"))).unwrap(), Value::Bool(true));
+        assert_eq!(eval(&Expr::binop(BinOp::Eq, s("abc"), s("abd"))).unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_char_literals_and_code_points() {
+        let c = |ch| Expr::Lit(Literal::Char(ch));
+        assert_eq!(eval(&c('a')).unwrap(), Value::Char('a'));
+        assert_eq!(eval(&Expr::binop(BinOp::Eq, c('a'), c('a'))).unwrap(), Value::Bool(true));
+        assert_eq!(eval(&Expr::binop(BinOp::Lt, c('a'), c('b'))).unwrap(), Value::Bool(true));
+        assert_eq!(eval(&Expr::app(Expr::name("ord"), c('A'))).unwrap(), Value::Int(65));
+        assert_eq!(eval(&Expr::app(Expr::name("chr"), Expr::int(955))).unwrap(), Value::Char('λ'));
+        assert!(matches!(eval(&Expr::app(Expr::name("chr"), Expr::int(-1))), Err(EvalError::TypeErrorMsg(_))));
+        // Unlike toInt and toChar, they convert nothing else
+        assert!(matches!(eval(&Expr::app(Expr::name("ord"), Expr::float(65.0))), Err(EvalError::TypeError { expected: "Char", got: "Float" })));
+        assert!(matches!(eval(&Expr::app(Expr::name("ord"), Expr::int(65))), Err(EvalError::TypeError { expected: "Char", got: "Int" })));
+        assert!(matches!(eval(&Expr::app(Expr::name("chr"), c('A'))), Err(EvalError::TypeError { expected: "Int", got: "Char" })));
+        assert!(matches!(eval(&Expr::app(Expr::name("chr"), Expr::bool(true))), Err(EvalError::TypeError { expected: "Int", got: "Bool" })));
+    }
+
     #[test]
     fn test_chars() {
         let mut e = Evaluator::new();
@@ -461,4 +543,1553 @@ mod tests {
         );
         assert_eq!(e.eval(&expr).unwrap(), Value::Int(10));
     }
+
+    // ============ Serialization Tests ============
+
+    #[test]
+    fn test_closure_serialization_roundtrip() {
+        // let x = 10 in (λ→ λ→ ₀ + ₁ + ₂) 5  -- closure capturing x and the first argument
+        let mut e = Evaluator::new();
+        let expr = Expr::let_(Pattern::var("x"), Expr::int(10), Expr::app(Expr::lam(Expr::lam(Expr::add(Expr::add(Expr::idx(0), Expr::idx(1)), Expr::idx(2)))), Expr::int(5)));
+        let closure = e.eval(&expr).unwrap();
+        let json = e.serialize_value(&closure).unwrap();
+
+        let mut e2 = Evaluator::new();
+        let restored = e2.deserialize_value(&json).unwrap();
+        assert_eq!(restored, closure);
+        e2.define("f", restored);
+        assert_eq!(e2.eval(&Expr::app(Expr::name("f"), Expr::int(1))).unwrap(), Value::Int(16));
+    }
+
+    #[test]
+    fn test_partial_application_serialization_roundtrip() {
+        let mut e = Evaluator::new();
+        e.define("sub_two", Value::closure(2, Expr::sub(Expr::idx(1), Expr::idx(0)), Env::with_globals(e.globals())));
+        let partial = e.eval(&Expr::app(Expr::name("sub_two"), Expr::int(10))).unwrap();
+        let json = e.serialize_value(&partial).unwrap();
+
+        let mut e2 = Evaluator::new();
+        e2.define("g", e2.deserialize_value(&json).unwrap());
+        assert_eq!(e2.eval(&Expr::app(Expr::name("g"), Expr::int(3))).unwrap(), Value::Int(7));
+    }
+
+    #[test]
+    fn test_captured_global_serialized_by_name() {
+        let mut e = Evaluator::new();
+        let body = Expr::match_(Expr::idx(0), vec![MatchArm::new(Pattern::lit(0i64), Expr::int(1)), MatchArm::new(Pattern::var("n"), Expr::mul(Expr::idx(0), Expr::app(Expr::name("factorial"), Expr::sub(Expr::idx(0), Expr::int(1)))))]);
+        e.define("factorial", Value::closure(1, body.clone(), Env::with_globals(e.globals())));
+        // let f = factorial in λ→ f ₀
+        let wrapper = e.eval(&Expr::let_(Pattern::var("f"), Expr::name("factorial"), Expr::lam(Expr::app(Expr::idx(1), Expr::idx(0))))).unwrap();
+        let json = e.serialize_value(&wrapper).unwrap();
+        assert!(json.contains("\"Global\":\"factorial\""));
+
+        let mut e2 = Evaluator::new();
+        assert!(matches!(e2.deserialize_value(&json), Err(EvalError::UndefinedName(_))));
+        e2.define("factorial", Value::closure(1, body, Env::with_globals(e2.globals())));
+        e2.define("w", e2.deserialize_value(&json).unwrap());
+        assert_eq!(e2.eval(&Expr::app(Expr::name("w"), Expr::int(5))).unwrap(), Value::Int(120));
+    }
+
+    #[test]
+    fn test_primitive_not_serializable() {
+        let mut e = Evaluator::new();
+        let sqrt = e.eval(&Expr::name("sqrt")).unwrap();
+        assert!(matches!(e.serialize_value(&sqrt), Err(EvalError::Serialization(_))));
+        let add5 = e.eval(&Expr::app(Expr::name("add"), Expr::int(5))).unwrap();
+        assert!(matches!(e.serialize_value(&add5), Err(EvalError::Serialization(_))));
+    }
+
+    // ============ Capture Tests ============
+
+    #[test]
+    fn test_closure_does_not_retain_unused_binding() {
+        // let big = iota 100000 in let x = 7 in λ→ ₀ + ₁   -- only x is referenced
+        let mut e = Evaluator::new();
+        let expr = Expr::let_(Pattern::var("big"), Expr::app(Expr::name("iota"), Expr::int(100000)), Expr::let_(Pattern::var("x"), Expr::int(7), Expr::lam(Expr::add(Expr::idx(0), Expr::idx(1)))));
+        match e.eval(&expr).unwrap() {
+            Value::Closure(c) => {
+                let captures = c.captures();
+                assert_eq!(captures, vec![(0, &Value::Int(7))]);
+                assert!(c.env.iter().all(|v| !v.is_tensor()));
+            }
+            other => panic!("Expected closure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_capture_skips_gap_but_keeps_indices() {
+        // let a = 1 in let unused = [1,2,3] in λ→ ₂ × 10 + ₀
+        let expr = Expr::let_(Pattern::var("a"), Expr::int(1), Expr::let_(Pattern::var("unused"), Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3)]), Expr::app(Expr::lam(Expr::add(Expr::mul(Expr::idx(2), Expr::int(10)), Expr::idx(0))), Expr::int(5))));
+        assert_eq!(eval(&expr).unwrap(), Value::Int(15));
+    }
+
+    #[test]
+    fn test_capture_through_match_and_let_patterns() {
+        // let y = 3 in (λ→ match ₀ { ⟨a, _⟩ → a + ₃ }) ⟨4, 5⟩
+        let body = Expr::match_(Expr::idx(0), vec![MatchArm::new(Pattern::tuple(vec![Pattern::var("a"), Pattern::Wildcard]), Expr::add(Expr::idx(1), Expr::idx(3)))]);
+        let expr = Expr::let_(Pattern::var("y"), Expr::int(3), Expr::app(Expr::lam(body), Expr::tuple(vec![Expr::int(4), Expr::int(5)])));
+        assert_eq!(eval(&expr).unwrap(), Value::Int(7));
+    }
+
+    #[test]
+    fn test_verbose_closure_display() {
+        // let xs = [1,2,3] in let k = 10 in λ→ ₀ + ₁ + len ₂
+        let body = Expr::add(Expr::add(Expr::idx(0), Expr::idx(1)), Expr::app(Expr::name("len"), Expr::idx(2)));
+        let expr = Expr::let_(Pattern::var("xs"), Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3)]), Expr::let_(Pattern::var("k"), Expr::int(10), Expr::lam(body)));
+        let closure = eval(&expr).unwrap();
+        assert_eq!(closure.to_string(), "<closure/1>");
+        assert_eq!(format!("{:#}", closure), "<closure/1 captures [10, [1, 2, 3]]>");
+        assert_eq!(format!("{:#}", Value::Tuple(vec![Value::Int(1), closure])), "⟨1, <closure/1 captures [10, [1, 2, 3]]>⟩");
+        let big = Expr::let_(Pattern::var("xs"), Expr::app(Expr::name("iota"), Expr::int(1000)), Expr::lam(Expr::app(Expr::name("len"), Expr::idx(1))));
+        let shown = format!("{:#}", eval(&big).unwrap());
+        assert!(shown.ends_with("…]>") && shown.chars().count() < 100, "{}", shown);
+    }
+
+    // ============ Axis Reduction Tests ============
+
+    fn matrix_2x3() -> Value { Value::Tensor(Tensor { shape: vec![2, 3], data: TensorData::Int(vec![1, 2, 3, 4, 5, 6]) }) }
+    fn apply_axis(name: &str, axis: i128) -> EvalResult<Value> { let mut e = Evaluator::new(); e.define("m", matrix_2x3()); e.eval(&Expr::app(Expr::app(Expr::name(name), Expr::name("m")), Expr::int(axis))) }
+
+    #[test] fn test_sum_axis_0() { assert_eq!(apply_axis("sum_axis", 0).unwrap(), Value::Tensor(Tensor::from_ints(vec![5, 7, 9]))); }
+    #[test] fn test_sum_axis_1() { assert_eq!(apply_axis("sum_axis", 1).unwrap(), Value::Tensor(Tensor::from_ints(vec![6, 15]))); }
+    #[test] fn test_mean_axis() { assert_eq!(apply_axis("mean_axis", 1).unwrap(), Value::Tensor(Tensor::from_floats(vec![2.0, 5.0]))); }
+    #[test] fn test_max_axis() { assert_eq!(apply_axis("max_axis", 0).unwrap(), Value::Tensor(Tensor::from_ints(vec![4, 5, 6]))); }
+    #[test] fn test_axis_out_of_range() { assert!(matches!(apply_axis("sum_axis", 2), Err(EvalError::ShapeMismatch(_)))); assert!(matches!(apply_axis("max_axis", -1), Err(EvalError::ShapeMismatch(_)))); }
+
+    // ============ Statistics Tests ============
+
+    fn stat(name: &str, xs: Vec<Expr>) -> EvalResult<Value> { eval(&Expr::app(Expr::name(name), Expr::array(xs))) }
+    fn data_2_to_9() -> Vec<Expr> { [2, 4, 4, 4, 5, 5, 7, 9].iter().map(|&n| Expr::int(n)).collect() }
+
+    #[test] fn test_mean() { assert_eq!(stat("mean", data_2_to_9()).unwrap(), Value::float(5.0)); }
+    #[test] fn test_variance_population() { assert_eq!(stat("variance", data_2_to_9()).unwrap(), Value::float(4.0)); }
+    #[test] fn test_variance_sample() { assert_eq!(stat("variance_sample", data_2_to_9()).unwrap(), Value::float(32.0 / 7.0)); }
+    #[test] fn test_std() { assert_eq!(stat("std", data_2_to_9()).unwrap(), Value::float(2.0)); }
+    #[test] fn test_mean_empty_errors() { assert!(stat("mean", vec![]).is_err()); assert!(stat("variance_sample", vec![Expr::float(1.0)]).is_err()); }
+    #[test] fn test_variance_stable_with_large_offset() { let xs = [4.0, 7.0, 13.0, 16.0].iter().map(|x| Expr::float(1e9 + x)).collect(); assert_eq!(stat("variance_sample", xs).unwrap(), Value::float(30.0)); }
+
+    #[test] fn test_argmin_argmax() { let xs = || vec![Expr::int(3), Expr::int(1), Expr::int(4), Expr::int(1), Expr::int(5)]; assert_eq!(stat("argmin", xs()).unwrap(), Value::Int(1)); assert_eq!(stat("argmax", xs()).unwrap(), Value::Int(4)); }
+    #[test] fn test_argmax_tie_returns_first() { assert_eq!(stat("argmax", vec![Expr::float(2.0), Expr::float(7.5), Expr::float(7.5)]).unwrap(), Value::Int(1)); }
+    #[test] fn test_arg_extreme_ties_take_lowest_index() { assert_eq!(stat("argmax", [1, 3, 2, 3].into_iter().map(Expr::int).collect()).unwrap(), Value::Int(1)); assert_eq!(stat("argmin", [5, 5, 1].into_iter().map(Expr::int).collect()).unwrap(), Value::Int(2)); assert_eq!(stat("argmin", [2, 2].into_iter().map(Expr::int).collect()).unwrap(), Value::Int(0)); }
+    #[test] fn test_argmax_mixed_numeric() { assert_eq!(stat("argmax", vec![Expr::int(2), Expr::float(2.5), Expr::int(1)]).unwrap(), Value::Int(1)); }
+    #[test] fn test_argmin_empty_errors() { assert!(matches!(stat("argmin", vec![]), Err(EvalError::ShapeMismatch(_)))); assert!(matches!(stat("argmax", vec![]), Err(EvalError::ShapeMismatch(_)))); }
+
+    fn floats_of(v: Value) -> Vec<f64> { match v { Value::Tensor(t) => t.iter().map(|x| x.coerce_float().unwrap()).collect(), other => panic!("Expected tensor, got {:?}", other) } }
+
+    #[test] fn test_normalize() { assert_eq!(floats_of(stat("normalize", vec![Expr::int(1), Expr::int(3)]).unwrap()), vec![0.25, 0.75]); assert!(matches!(stat("normalize", vec![Expr::float(1.0), Expr::float(-1.0)]), Err(EvalError::DivisionByZero))); }
+
+    #[test]
+    fn test_softmax_sums_to_one_and_is_monotonic() {
+        let ps = floats_of(stat("softmax", vec![Expr::float(1.0), Expr::float(2.0), Expr::float(3.0)]).unwrap());
+        assert!((ps.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!(ps[0] < ps[1] && ps[1] < ps[2]);
+        // Large inputs must not overflow
+        let ps = floats_of(stat("softmax", vec![Expr::float(1000.0), Expr::float(1001.0)]).unwrap());
+        assert!(ps.iter().all(|p| p.is_finite()) && (ps.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    // ============ Elementwise Tests ============
+
+    #[test] fn test_abs_over_tensor() { assert_eq!(eval(&Expr::app(Expr::name("abs"), Expr::array(vec![Expr::int(-1), Expr::int(-2), Expr::int(3)]))).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, 2, 3]))); }
+    #[test] fn test_sqrt_over_tensor() { assert_eq!(eval(&Expr::app(Expr::name("sqrt"), Expr::array(vec![Expr::float(4.0), Expr::float(9.0)]))).unwrap(), Value::Tensor(Tensor::from_floats(vec![2.0, 3.0]))); }
+    #[test] fn test_unary_op_over_tensor() { assert_eq!(eval(&Expr::UnaryOp(UnaryOp::Floor, Box::new(Expr::array(vec![Expr::float(1.5), Expr::float(-0.5)])))).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, -1]))); }
+    #[test]
+    fn test_sign_primitives_over_int_and_float_tensors() {
+        let apply = |name: &str, xs: Expr| eval(&Expr::app(Expr::name(name), xs)).unwrap();
+        let ints = || Expr::array(vec![Expr::int(-1), Expr::int(2), Expr::int(0)]);
+        let floats = || Expr::array(vec![Expr::float(-1.5), Expr::float(2.5), Expr::float(0.0)]);
+        assert_eq!(apply("abs", ints()), Value::Tensor(Tensor::from_ints(vec![1, 2, 0])));
+        assert_eq!(apply("abs", floats()), Value::Tensor(Tensor::from_floats(vec![1.5, 2.5, 0.0])));
+        assert_eq!(apply("neg", ints()), Value::Tensor(Tensor::from_ints(vec![1, -2, 0])));
+        assert_eq!(apply("neg", floats()), Value::Tensor(Tensor::from_floats(vec![1.5, -2.5, -0.0])));
+        assert_eq!(apply("sign", ints()), Value::Tensor(Tensor::from_ints(vec![-1, 1, 0])));
+        assert_eq!(apply("sign", floats()), Value::Tensor(Tensor::from_floats(vec![-1.0, 1.0, 0.0])));
+        assert_eq!(eval(&Expr::UnaryOp(UnaryOp::Neg, Box::new(ints()))).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, -2, 0])));
+    }
+    #[test] fn test_elementwise_sign_error_propagates() { assert!(matches!(eval(&Expr::app(Expr::name("neg"), Expr::array(vec![Expr::int(1), Expr::bool(true)]))), Err(EvalError::TypeError { .. }))); }
+    #[test] fn test_elementwise_error_propagates() { assert!(eval(&Expr::app(Expr::name("sqrt"), Expr::array(vec![Expr::float(4.0), Expr::float(-1.0)]))).is_err()); }
+
+    // ============ Variadic Tests ============
+
+    fn max_applied(args: Vec<Expr>) -> EvalResult<Value> { eval(&args.into_iter().fold(Expr::name("max"), Expr::app)) }
+
+    #[test] fn test_variadic_max_saturated_by_unit() { assert_eq!(max_applied(vec![Expr::int(1), Expr::int(4), Expr::int(2), Expr::int(3), Expr::Lit(Literal::Unit)]).unwrap(), Value::Int(4)); }
+    #[test] fn test_variadic_max_collects_until_unit() { assert!(matches!(max_applied(vec![Expr::int(1), Expr::int(2)]).unwrap(), Value::Partial { remaining: 0, .. })); }
+    #[test] fn test_variadic_max_over_tensor() { assert_eq!(max_applied(vec![Expr::array(vec![Expr::float(1.5), Expr::float(-2.0), Expr::float(0.5)])]).unwrap(), Value::float(1.5)); }
+    #[test] fn test_variadic_max_no_args_errors() { assert!(max_applied(vec![Expr::Lit(Literal::Unit)]).is_err()); }
+    fn reduce(name: &str, elems: Vec<Expr>) -> EvalResult<Value> { eval(&Expr::app(Expr::name(name), Expr::array(elems))) }
+    #[test] fn test_min_max_reduce_tensor() { let xs = || [3, 1, 4, 1, 5].into_iter().map(Expr::int).collect(); assert_eq!(reduce("max", xs()).unwrap(), Value::Int(5)); assert_eq!(reduce("min", xs()).unwrap(), Value::Int(1)); }
+    #[test] fn test_min_max_mixed_numeric() { assert_eq!(reduce("min", vec![Expr::int(2), Expr::float(1.5), Expr::int(3)]).unwrap(), Value::float(1.5)); assert_eq!(reduce("max", vec![Expr::float(2.5), Expr::int(3)]).unwrap(), Value::float(3.0)); }
+    #[test] fn test_min_max_empty_tensor_errors() { assert!(matches!(reduce("max", vec![]), Err(EvalError::ShapeMismatch(_)))); assert!(matches!(reduce("min", vec![]), Err(EvalError::ShapeMismatch(_)))); }
+    #[test] fn test_min_max_non_comparable_errors() { assert!(matches!(reduce("min", vec![Expr::lam(Expr::idx(0)), Expr::lam(Expr::idx(0))]), Err(EvalError::TypeErrorMsg(_)))); }
+    #[test] fn test_variadic_min() { assert_eq!(eval(&Expr::app_n(Expr::name("min"), vec![Expr::int(4), Expr::int(-2), Expr::int(7), Expr::Lit(Literal::Unit)])).unwrap(), Value::Int(-2)); }
+    fn clamp(x: Expr, lo: i128, hi: i128) -> EvalResult<Value> { eval(&Expr::app_n(Expr::name("clamp"), vec![x, Expr::int(lo), Expr::int(hi)])) }
+    #[test] fn test_clamp_scalar() { assert_eq!(clamp(Expr::int(5), 0, 3).unwrap(), Value::Int(3)); assert_eq!(clamp(Expr::int(-1), 0, 3).unwrap(), Value::Int(0)); assert_eq!(clamp(Expr::float(1.5), 0, 3).unwrap(), Value::float(1.5)); }
+    #[test] fn test_clamp_tensor_elementwise() { let xs = Expr::array([-2, 1, 7, 3].into_iter().map(Expr::int).collect()); assert!(clamp(xs, 0, 3).unwrap().deep_eq(&Value::Tensor(Tensor::from_ints(vec![0, 1, 3, 3])))); }
+    #[test] fn test_clamp_bounds_out_of_order() { assert!(matches!(clamp(Expr::int(1), 3, 0), Err(EvalError::TypeErrorMsg(_)))); }
+
+    // ============ Arity Check Tests ============
+
+    #[test]
+    fn test_check_arities_flags_over_applied_primitive() {
+        let e = Evaluator::new();
+        let expr = Expr::app(Expr::app(Expr::app(Expr::name("take"), Expr::int(1)), Expr::array(vec![Expr::int(1)])), Expr::int(2));
+        assert_eq!(check_arities(&expr, &e), Err(vec![ArityError { func: "take".into(), expected: 2, got: 3 }]));
+        assert!(check_arities(&Expr::app(Expr::app(Expr::name("take"), Expr::int(1)), Expr::array(vec![])), &e).is_ok());
+    }
+
+    #[test]
+    fn test_check_arities_closures_and_lambdas() {
+        let mut e = Evaluator::new();
+        let add = e.eval(&Expr::lam(Expr::lam(Expr::add(Expr::idx(1), Expr::idx(0))))).unwrap();
+        e.define("add", add);
+        let call = Expr::app_n(Expr::name("add"), vec![Expr::int(1), Expr::int(2), Expr::int(3)]);
+        assert_eq!(check_arities(&call, &e).unwrap_err()[0], ArityError { func: "add".into(), expected: 2, got: 3 });
+        // Nested inside a lambda literal applied to too many arguments
+        let lam = Expr::app_n(Expr::lam(Expr::int(0)), vec![Expr::int(1), Expr::app(Expr::name("neg"), Expr::int(1))]);
+        assert_eq!(check_arities(&lam, &e).unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn test_check_arities_ignores_higher_order_results() {
+        let mut e = Evaluator::new();
+        // const returns its argument, which may be a function
+        let konst = e.eval(&Expr::lam(Expr::lam(Expr::idx(1)))).unwrap();
+        e.define("const", konst);
+        assert!(check_arities(&Expr::app_n(Expr::name("const"), vec![Expr::name("neg"), Expr::int(0), Expr::int(5)]), &e).is_ok());
+        assert!(check_arities(&Expr::app_n(Expr::name("max"), vec![Expr::int(1), Expr::int(2), Expr::int(3), Expr::Lit(Literal::Unit)]), &e).is_ok());
+    }
+
+    // ============ Scalar Fast Path Tests ============
+
+    #[test]
+    fn test_scalar_fast_path_matches_general_binop() {
+        use crate::prim::{apply_binop, scalar_binop};
+        let values = [Value::Int(-3), Value::Int(7), Value::float(2.5), Value::float(f64::NAN), Value::Bool(true)];
+        for op in [BinOp::Add, BinOp::Sub, BinOp::Mul, BinOp::Div, BinOp::Lt, BinOp::Gt, BinOp::Leq, BinOp::Geq, BinOp::Eq, BinOp::Neq] {
+            for l in &values { for r in &values {
+                if let Some(fast) = scalar_binop(&op, l, r) { assert_eq!(fast, apply_binop(&op, l.clone(), r.clone()).unwrap(), "{:?} {:?} {:?}", op, l, r); }
+            } }
+        }
+        assert_eq!(scalar_binop(&BinOp::Div, &Value::Int(1), &Value::Int(0)), None);
+    }
+
+    // ============ Bytecode Tests ============
+
+    fn fib_evaluator() -> Evaluator {
+        let mut e = Evaluator::new();
+        let body = Expr::if_(Expr::binop(BinOp::Lt, Expr::idx(0), Expr::int(2)), Expr::idx(0), Expr::add(Expr::app(Expr::name("fib"), Expr::sub(Expr::idx(0), Expr::int(1))), Expr::app(Expr::name("fib"), Expr::sub(Expr::idx(0), Expr::int(2)))));
+        let fib = e.eval(&Expr::lam(body)).unwrap();
+        e.define("fib", fib);
+        e
+    }
+
+    #[test]
+    fn test_fib_25() {
+        // Each call extends the closure's environment, which shares its chunks rather than copying them
+        let mut e = fib_evaluator();
+        assert_eq!(e.eval(&Expr::app(Expr::name("fib"), Expr::int(25))).unwrap(), Value::Int(75025));
+    }
+
+    #[test]
+    fn test_global_data_resolves_by_name() {
+        // Globals need not be callable: precomputed data is shared across evaluations by name
+        let mut e = Evaluator::new();
+        e.define("weights", Value::Tensor(Tensor::from_ints(vec![1, 2, 3, 4])));
+        e.define("pair", Value::tuple(vec![Value::Int(7), Value::Bool(true)]));
+        let total = Expr::app(Expr::name("sum"), Expr::name("weights"));
+        assert_eq!(e.eval(&total).unwrap(), Value::Int(10));
+        assert_eq!(e.eval(&Expr::app(Expr::lam(Expr::mul(Expr::idx(0), total.clone())), Expr::int(2))).unwrap(), Value::Int(20));
+        assert_eq!(e.eval(&Expr::field_idx(Expr::name("pair"), 0)).unwrap(), Value::Int(7));
+        assert_compiled_agrees(&mut e, &total);
+    }
+
+    #[test]
+    fn test_env_extension_shares_frames() {
+        let mut outer = Env::new();
+        outer.push_many((0..10_000).map(Value::Int));
+        let mut inner = outer.clone();
+        inner.push(Value::Bool(true));
+        assert_eq!((outer.depth(), inner.depth()), (10_000, 10_001));
+        assert_eq!(outer.get(0), Some(&Value::Int(9_999)));
+        assert_eq!(inner.get(0), Some(&Value::Bool(true)));
+        assert_eq!(inner.get(10_000), Some(&Value::Int(0)));
+        assert_eq!(inner.pop(), Some(Value::Bool(true)));
+        inner.pop_n(9_999);
+        assert_eq!(inner.iter().collect::<Vec<_>>(), [&Value::Int(0)]);
+        assert_eq!(outer.get(9_999), Some(&Value::Int(0)));
+        #[allow(deprecated)]
+        let values = outer.values();
+        assert!(matches!(values, std::borrow::Cow::Borrowed(_)) && values.len() == 10_000);
+    }
+
+    #[test]
+    fn test_env_extension_never_copies_bindings() {
+        // A value deep in the environment is shared, not cloned, however many calls extend it
+        let shared = std::rc::Rc::new(std::collections::HashMap::new());
+        let mut base = Env::new();
+        base.push(Value::Record(std::rc::Rc::clone(&shared)));
+        base.push_many((0..1_000).map(Value::Int));
+        let mut calls = vec![base];
+        for i in 0..1_000 {
+            let mut call = calls[i].clone();
+            call.push_many([Value::Int(-1), Value::Int(-2)]);
+            calls.push(call);
+        }
+        assert_eq!(std::rc::Rc::strong_count(&shared), 2);
+        let last = calls.last().unwrap();
+        assert_eq!(last.depth(), 3_001);
+        assert_eq!((last.get(0), last.get(1), last.get(2_000)), (Some(&Value::Int(-2)), Some(&Value::Int(-1)), Some(&Value::Int(999))));
+        assert!(matches!(last.get(3_000), Some(Value::Record(r)) if std::rc::Rc::ptr_eq(r, &shared)));
+        assert_eq!(last.iter().count(), 3_001);
+    }
+
+    /// Compiled and interpreted results agree, comparing errors by their message
+    fn assert_compiled_agrees(e: &mut Evaluator, expr: &Expr) {
+        let env = Env::with_globals(e.globals());
+        let compiled = e.run(&crate::bytecode::compile(expr), &env);
+        let interpreted = e.eval_with_env(expr, &env);
+        assert_eq!(compiled.map_err(|err| err.to_string()), interpreted.map_err(|err| err.to_string()), "{}", expr);
+    }
+
+    #[test] fn test_bytecode_fib() { let mut e = fib_evaluator(); let env = Env::with_globals(e.globals()); assert_eq!(e.run(&crate::bytecode::compile(&Expr::app(Expr::name("fib"), Expr::int(15))), &env).unwrap(), Value::Int(610)); }
+
+    #[test]
+    fn test_bytecode_tail_calls_do_not_grow_the_stack() {
+        // count n = if n == 0 then 0 else count (n - 1)
+        let mut e = Evaluator::new().with_max_depth(100);
+        let count = e.eval(&Expr::lam(Expr::if_(Expr::binop(BinOp::Eq, Expr::idx(0), Expr::int(0)), Expr::int(0), Expr::app(Expr::name("count"), Expr::sub(Expr::idx(0), Expr::int(1)))))).unwrap();
+        e.define("count", count);
+        assert_compiled_agrees(&mut e, &Expr::app(Expr::name("count"), Expr::int(10_000)));
+    }
+
+    #[test]
+    fn test_bytecode_differential() {
+        // Pseudo-random expressions over arithmetic, comparisons, conditionals, lets, lambdas and calls
+        fn gen(seed: &mut u64, depth: u32, locals: u32) -> Expr {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let pick = (*seed >> 33) % if depth == 0 { 3 } else { 12 };
+            let small = ((*seed >> 40) % 7) as i128 - 2;
+            match pick {
+                0 => Expr::int(small),
+                1 if locals > 0 => Expr::idx((*seed >> 45) as u32 % locals),
+                1 | 2 => Expr::float(small as f64 / 2.0),
+                3 => Expr::add(gen(seed, depth - 1, locals), gen(seed, depth - 1, locals)),
+                4 => Expr::mul(gen(seed, depth - 1, locals), gen(seed, depth - 1, locals)),
+                5 => Expr::div(gen(seed, depth - 1, locals), gen(seed, depth - 1, locals)),
+                6 => Expr::binop(BinOp::Lt, gen(seed, depth - 1, locals), gen(seed, depth - 1, locals)),
+                7 => Expr::if_(Expr::binop(BinOp::Geq, gen(seed, depth - 1, locals), Expr::int(0)), gen(seed, depth - 1, locals), gen(seed, depth - 1, locals)),
+                8 => Expr::let_(Pattern::var("x"), gen(seed, depth - 1, locals), gen(seed, depth - 1, locals + 1)),
+                9 => Expr::app(Expr::lam(gen(seed, depth - 1, locals + 1)), gen(seed, depth - 1, locals)),
+                10 => Expr::app(Expr::name("fib"), Expr::binop(BinOp::Mod, gen(seed, depth - 1, locals), Expr::int(8))),
+                _ => Expr::binop(BinOp::And, Expr::binop(BinOp::Neq, gen(seed, depth - 1, locals), Expr::int(1)), Expr::binop(BinOp::Lt, gen(seed, depth - 1, locals), gen(seed, depth - 1, locals))),
+            }
+        }
+        let mut e = fib_evaluator();
+        let mut seed = 42;
+        for _ in 0..500 { let expr = gen(&mut seed, 4, 0); assert_compiled_agrees(&mut e, &expr); }
+    }
+
+    fn int_match(cases: &[i128], default: bool) -> Expr {
+        let mut arms: Vec<MatchArm> = cases.iter().map(|&n| MatchArm::new(Pattern::Lit(Literal::Int(n)), Expr::int(n * 10))).collect();
+        if default { arms.push(MatchArm::new(Pattern::var("x"), Expr::mul(Expr::idx(0), Expr::int(100)))); }
+        Expr::lam(Expr::match_(Expr::idx(0), arms))
+    }
+
+    fn has_switch(expr: &Expr) -> bool { crate::bytecode::compile(expr).instrs().iter().any(|i| matches!(i, crate::bytecode::Instr::Switch { .. })) }
+
+    #[test]
+    fn test_bytecode_dense_int_match_uses_switch() {
+        let dense: Vec<i128> = (0..8).filter(|&n| n != 5).collect();
+        let mut e = Evaluator::new();
+        for default in [true, false] {
+            let f = int_match(&dense, default);
+            let Expr::Lam(body) = &f else { unreachable!() };
+            assert!(has_switch(body));
+            let closure = e.eval(&f).unwrap(); e.define("f", closure);
+            for arg in (-2..12).map(Expr::int).chain([Expr::float(3.0), Expr::bool(true)]) { assert_compiled_agrees(&mut e, &Expr::app(Expr::name("f"), arg)); }
+        }
+    }
+
+    #[test]
+    fn test_bytecode_sparse_match_stays_linear() {
+        let Expr::Lam(body) = int_match(&[0, 100, 200, 300], true) else { unreachable!() };
+        assert!(!has_switch(&body));
+        let Expr::Lam(body) = int_match(&[0, 1, 2], true) else { unreachable!() };
+        assert!(!has_switch(&body));
+    }
+
+    // ============ Fusion Tests ============
+
+    fn fusion_input() -> Expr { Expr::array((1..=6).map(Expr::int).collect()) }
+
+    /// Result and tensors built for `expr` before and after fusion
+    fn fused_and_unfused(expr: Expr) -> ((EvalResult<Value>, usize), (EvalResult<Value>, usize)) {
+        let run = |e: &Expr| { let mut ev = Evaluator::new(); let r = ev.eval_with_stats(e); (r, ev.stats().tensors) };
+        (run(&expr), run(&crate::fuse::fuse_maps(expr)))
+    }
+
+    #[test]
+    fn test_fuse_map_map() {
+        // Pairing each element cannot fail, so the maps fuse
+        let pair = Expr::lam(Expr::tuple(vec![Expr::idx(0), Expr::idx(0)]));
+        let expr = Expr::map(Expr::map(fusion_input(), pair), Expr::lam(Expr::mul(Expr::field_idx(Expr::idx(0), 0), Expr::field_idx(Expr::idx(0), 1))));
+        let ((unfused, n_unfused), (fused, n_fused)) = fused_and_unfused(expr);
+        assert_eq!(fused.unwrap(), unfused.unwrap());
+        assert_eq!(n_fused + 1, n_unfused);
+    }
+
+    #[test]
+    fn test_fuse_filter_filter() {
+        let expr = Expr::filter(Expr::filter(fusion_input(), Expr::lam(Expr::binop(BinOp::Gt, Expr::idx(0), Expr::int(2)))), Expr::lam(Expr::binop(BinOp::Eq, Expr::binop(BinOp::Mod, Expr::idx(0), Expr::int(2)), Expr::int(0))));
+        let ((unfused, n_unfused), (fused, n_fused)) = fused_and_unfused(expr);
+        assert_eq!(fused.unwrap(), Value::Tensor(Tensor::from_values(vec![2], vec![Value::Int(4), Value::Int(6)])));
+        assert_eq!(unfused.unwrap(), Value::Tensor(Tensor::from_values(vec![2], vec![Value::Int(4), Value::Int(6)])));
+        assert_eq!(n_fused + 1, n_unfused);
+    }
+
+    #[test]
+    fn test_fuse_map_preserves_inner_failure() {
+        // g fails on the second element and f on every element: unfused, g's failure comes first
+        let g = Expr::lam(Expr::div(Expr::int(1), Expr::sub(Expr::idx(0), Expr::int(3))));
+        let f = Expr::lam(Expr::binop(BinOp::And, Expr::idx(0), Expr::bool(true)));
+        let expr = Expr::map(Expr::map(ints(&[1, 3]), g), f);
+        assert_eq!(crate::fuse::fuse_maps(expr.clone()), expr);
+        let ((unfused, _), (fused, _)) = fused_and_unfused(expr);
+        assert!(matches!(unfused, Err(EvalError::DivisionByZero)));
+        assert!(matches!(fused, Err(EvalError::DivisionByZero)));
+    }
+
+    #[test]
+    fn test_fuse_map_keeps_error_order_when_one_side_is_total() {
+        // The pairing map cannot fail, so fusing it leaves f's failure on the second element first
+        let pair = Expr::lam(Expr::tuple(vec![Expr::idx(0), Expr::idx(0)]));
+        let f = Expr::lam(Expr::div(Expr::int(1), Expr::sub(Expr::field_idx(Expr::idx(0), 0), Expr::int(3))));
+        let expr = Expr::map(Expr::map(ints(&[1, 3, 5]), pair), f);
+        assert_ne!(crate::fuse::fuse_maps(expr.clone()), expr);
+        let ((unfused, _), (fused, _)) = fused_and_unfused(expr);
+        assert!(matches!(unfused, Err(EvalError::DivisionByZero)));
+        assert!(matches!(fused, Err(EvalError::DivisionByZero)));
+    }
+
+    #[test] fn test_fuse_leaves_named_functions() { let expr = Expr::map(Expr::map(fusion_input(), Expr::name("neg")), Expr::name("abs")); assert_eq!(crate::fuse::fuse_maps(expr.clone()), expr); }
+
+    // ============ Unknown Node Tests ============
+
+    #[test]
+    fn test_unknown_node_is_unsupported() {
+        let module = goth_ast::ser::from_json_lenient(r#"{ "name": null, "decls": [{ "Let": { "name": "x", "type_": null, "value": { "FutureNode": 1 } } }] }"#).unwrap();
+        let goth_ast::decl::Decl::Let(decl) = &module.decls[0] else { panic!() };
+        assert!(matches!(eval(&decl.value), Err(EvalError::UnsupportedConstruct(_))));
+    }
+
+    // ============ Stats Tests ============
+
+    #[test]
+    fn test_stats_reductions() {
+        // (λx. λy. x * y) 3 4, then sqrt over [1, 4, 9]
+        let mut e = Evaluator::new();
+        let curried = Expr::app(Expr::app(Expr::lam(Expr::lam(Expr::mul(Expr::idx(1), Expr::idx(0)))), Expr::int(3)), Expr::int(4));
+        assert_eq!(e.eval_with_stats(&curried).unwrap(), Value::Int(12));
+        assert_eq!((e.stats().reductions, e.stats().closures, e.stats().tensors), (2, 2, 0));
+        let roots = Expr::map(Expr::array(vec![Expr::int(1), Expr::int(4), Expr::int(9)]), Expr::name("sqrt"));
+        e.eval_with_stats(&roots).unwrap();
+        assert_eq!((e.stats().reductions, e.stats().closures, e.stats().tensors), (3, 0, 2));
+        assert_eq!(e.stats().prim_calls.get("Sqrt"), Some(&3));
+    }
+
+    #[test] fn test_stats_off_by_default() { let mut e = Evaluator::new(); e.eval(&Expr::app(Expr::name("sqrt"), Expr::int(4))).unwrap(); assert_eq!(e.stats(), &EvalStats::default()); }
+
+    // ============ List Tests ============
+
+    /// cons 1 (cons 'a' (cons 2.5 nil))
+    fn mixed_list() -> Expr {
+        let cons = |x: Expr, xs: Expr| Expr::app(Expr::app(Expr::name("cons"), x), xs);
+        cons(Expr::int(1), cons(Expr::Lit(Literal::Char('a')), cons(Expr::float(2.5), Expr::name("nil"))))
+    }
+
+    #[test]
+    fn test_list_recursive_length() {
+        // length = λxs. match xs { [] → 0; [_ | t] → 1 + length t }
+        let mut e = Evaluator::new();
+        let body = Expr::match_(Expr::idx(0), vec![
+            MatchArm::new(Pattern::Array(vec![]), Expr::int(0)),
+            MatchArm::new(Pattern::ArraySplit { head: vec![Pattern::Wildcard], tail: Box::new(Pattern::var("t")) }, Expr::add(Expr::int(1), Expr::app(Expr::name("length"), Expr::idx(0)))),
+        ]);
+        let length = e.eval(&Expr::lam(body)).unwrap();
+        e.define("length", length);
+        assert_eq!(e.eval(&Expr::app(Expr::name("length"), mixed_list())).unwrap(), Value::Int(3));
+        assert_eq!(e.eval(&Expr::app(Expr::name("length"), Expr::array(vec![Expr::int(7), Expr::int(8)]))).unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_value_display() {
+        let nested = Value::Tensor(Tensor { shape: vec![2, 2, 2], data: TensorData::Int((1..=8).collect()) });
+        assert_eq!(nested.to_string(), "[[[1, 2], [3, 4]], [[5, 6], [7, 8]]]");
+        assert_eq!(Value::Tensor(Tensor::from_floats(vec![0.5, 2.0])).to_string(), "[0.5, 2]");
+        assert_eq!(Value::Tensor(Tensor::from_ints(vec![])).to_string(), "[]");
+        let tuple = Value::Tuple(vec![Value::Int(1), Value::Bool(true), Value::Unit, Value::Tensor(Tensor::from_ints(vec![2, 3]))]);
+        assert_eq!(tuple.to_string(), "⟨1, ⊤, (), [2, 3]⟩");
+    }
+
+    #[test]
+    fn test_list_primitives() {
+        let list = eval(&mixed_list()).unwrap();
+        assert_eq!(format!("{}", list), "(1 :: 'a' :: 2.5 :: nil)");
+        assert_eq!(eval(&Expr::app(Expr::name("head"), mixed_list())).unwrap(), Value::Int(1));
+        assert_eq!(eval(&Expr::app(Expr::name("len"), Expr::app(Expr::name("tail"), mixed_list()))).unwrap(), Value::Int(2));
+        assert_eq!(eval(&Expr::app(Expr::name("isEmpty"), Expr::name("nil"))).unwrap(), Value::Bool(true));
+        assert!(matches!(eval(&Expr::app(Expr::name("head"), Expr::name("nil"))), Err(EvalError::IndexOutOfBounds { .. })));
+    }
+
+    #[test] fn test_list_drop_is_iterative() { let long: List = (0..200_000).map(Value::Int).collect(); assert_eq!(long.len(), 200_000); drop(long); }
+
+    #[test]
+    fn test_list_eq_and_debug_are_iterative() {
+        let long = || (0..200_000).map(Value::Int).collect::<List>();
+        assert_eq!(long(), long());
+        assert_ne!(long(), (0..200_000).map(|n| Value::Int(n.min(199_998))).collect::<List>());
+        assert_ne!(long(), List::cons(Value::Int(-1), long()));
+        assert!(format!("{:?}", long()).ends_with("Int(199999)]"));
+        assert_eq!(format!("{:?}", List::cons(Value::Int(1), List::nil())), "[Int(1)]");
+    }
+
+    #[test]
+    fn test_list_tensor_round_trip() {
+        let ints = Expr::array(vec![Expr::int(3), Expr::int(1), Expr::int(2)]);
+        let list = eval(&Expr::app(Expr::name("to_list"), ints.clone())).unwrap();
+        assert_eq!(list, Value::List([3, 1, 2].into_iter().map(Value::Int).collect()));
+        let back = eval(&Expr::app(Expr::name("to_tensor"), Expr::app(Expr::name("to_list"), ints))).unwrap();
+        assert_eq!(back, Value::Tensor(Tensor::from_ints(vec![3, 1, 2])));
+    }
+
+    #[test] fn test_to_tensor_rejects_mixed_list() { assert!(matches!(eval(&Expr::app(Expr::name("to_tensor"), mixed_list())), Err(EvalError::TypeErrorMsg(_)))); }
+
+    // ============ Hashable Value Tests ============
+
+    fn key(v: Value) -> HashableValue { HashableValue::new(v).unwrap() }
+    fn hash_of(k: &HashableValue) -> u64 { use std::hash::{Hash, Hasher}; let mut h = std::collections::hash_map::DefaultHasher::new(); k.hash(&mut h); h.finish() }
+    fn assert_same_key(a: Value, b: Value) { let (a, b) = (key(a), key(b)); assert_eq!(a, b); assert_eq!(hash_of(&a), hash_of(&b)); }
+
+    #[test]
+    fn test_hashable_zero_canonical() {
+        assert_same_key(Value::float(0.0), Value::float(-0.0));
+        assert_same_key(Value::tuple(vec![Value::float(-0.0), Value::Int(1)]), Value::tuple(vec![Value::float(0.0), Value::Int(1)]));
+        assert_ne!(key(Value::float(0.0)), key(Value::Int(0)));
+    }
+
+    #[test]
+    fn test_hashable_nan_canonical() {
+        let nans = [f64::NAN, -f64::NAN, f64::from_bits(0x7ff8_0000_0000_0001), f64::from_bits(0xfff0_0000_0000_0abc)];
+        assert!(nans.iter().all(|f| f.is_nan()));
+        for f in nans { assert_same_key(Value::float(f), Value::float(f64::NAN)); }
+        assert_ne!(key(Value::float(f64::NAN)), key(Value::float(1.0)));
+    }
+
+    #[test] fn test_hashable_rejects_closures() { assert!(HashableValue::new(eval(&Expr::lam(Expr::idx(0))).unwrap()).is_none()); }
+
+    // ============ Memory Limit Tests ============
+
+    #[test]
+    fn test_memory_limit_outer() {
+        let outer = Expr::app(Expr::app(Expr::name("outer"), Expr::app(Expr::name("iota"), Expr::int(2000))), Expr::app(Expr::name("iota"), Expr::int(2000)));
+        let mut e = Evaluator::new().with_memory_limit(1_000_000);
+        assert!(matches!(e.eval(&outer), Err(EvalError::AllocationLimit { requested: 4_000_000, limit: 1_000_000 })));
+        // Only the two input vectors were allocated
+        assert_eq!(e.elements_allocated(), 4000);
+    }
+
+    #[test]
+    fn test_memory_limit_covers_operators() {
+        let iota = |n: i128| Expr::app(Expr::name("iota"), Expr::int(n));
+        let mut e = Evaluator::new().with_memory_limit(200);
+        assert!(matches!(e.eval(&Expr::add(iota(90), iota(90))), Err(EvalError::AllocationLimit { requested: 90, limit: 200 })));
+        let mut e = Evaluator::new().with_memory_limit(200);
+        assert!(matches!(e.eval(&Expr::UnaryOp(UnaryOp::Neg, Box::new(Expr::mul(iota(80), Expr::int(2))))), Err(EvalError::AllocationLimit { .. })));
+        // The same program compiled to bytecode is held to the limit too
+        let mut e = Evaluator::new().with_memory_limit(200);
+        let env = Env::with_globals(e.globals());
+        assert!(matches!(e.run(&crate::bytecode::compile(&Expr::add(iota(90), iota(90))), &env), Err(EvalError::AllocationLimit { .. })));
+    }
+
+    #[test]
+    fn test_memory_limit_reserves_before_building() {
+        // The filter could keep every element, so it is refused before any predicate runs
+        let xs = Value::Tensor(Tensor::from_ints((0..100).collect()));
+        let mut e = Evaluator::new().with_memory_limit(80).with_captured_output();
+        e.define("xs", xs.clone());
+        let noisy = Expr::lam(Expr::app(Expr::name("print"), Expr::bool(true)));
+        assert!(matches!(e.eval(&Expr::filter(Expr::name("xs"), noisy)), Err(EvalError::AllocationLimit { requested: 100, limit: 80 })));
+        assert_eq!(e.take_output(), "");
+        // A filter that fits settles on what it kept
+        let mut e = Evaluator::new().with_memory_limit(150);
+        e.define("xs", xs);
+        let small = Expr::lam(Expr::binop(BinOp::Lt, Expr::idx(0), Expr::int(10)));
+        assert!(e.eval(&Expr::filter(Expr::name("xs"), small)).unwrap().deep_eq(&Value::Tensor(Tensor::from_ints((0..10).collect()))));
+        assert_eq!(e.elements_allocated(), 10);
+    }
+
+    #[test] fn test_outer_product() { assert_eq!(eval(&Expr::app(Expr::app(Expr::name("outer"), Expr::array(vec![Expr::int(1), Expr::int(2)])), Expr::array(vec![Expr::int(3), Expr::int(4), Expr::int(5)]))).unwrap(), Value::Tensor(Tensor { shape: vec![2, 3], data: TensorData::Int(vec![3, 4, 5, 6, 8, 10]) })); }
+    #[test]
+    fn test_memory_limit_skips_shared_storage() {
+        let reshape = |dims: &[i128]| Expr::app_n(Expr::name("reshape"), vec![Expr::app(Expr::name("iota"), Expr::int(150)), ints(dims)]);
+        let mut e = Evaluator::new().with_memory_limit(200);
+        assert_eq!(e.eval(&reshape(&[150])).unwrap(), Value::Tensor(Tensor::from_ints((0..150).collect())));
+        let mut e = Evaluator::new().with_memory_limit(200);
+        assert!(matches!(e.eval(&reshape(&[10, 15])).unwrap(), Value::Tensor(t) if t.shape == [10, 15]));
+    }
+
+    #[test] fn test_memory_limit_iota() { let mut e = Evaluator::new().with_memory_limit(100); assert_eq!(e.eval(&Expr::app(Expr::name("iota"), Expr::int(100))).unwrap(), Value::Tensor(Tensor::from_ints((0..100).collect()))); assert!(matches!(e.eval(&Expr::app(Expr::name("iota"), Expr::int(1))), Err(EvalError::AllocationLimit { .. }))); }
+
+    // ============ Array Pattern Tests ============
+
+    /// match scrutinee { [a, b, c] → a·100 + b·10 + c; _ → -1 }
+    fn match_three(scrutinee: Expr) -> EvalResult<Value> {
+        let abc = Expr::add(Expr::add(Expr::mul(Expr::idx(2), Expr::int(100)), Expr::mul(Expr::idx(1), Expr::int(10))), Expr::idx(0));
+        eval(&Expr::match_(scrutinee, vec![
+            MatchArm::new(Pattern::Array(vec![Pattern::var("a"), Pattern::var("b"), Pattern::var("c")]), abc),
+            MatchArm::new(Pattern::Wildcard, Expr::int(-1)),
+        ]))
+    }
+
+    #[test] fn test_array_pattern_binds_elements() { assert_eq!(match_three(Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3)])).unwrap(), Value::Int(123)); }
+    #[test] fn test_array_pattern_length_mismatch() { assert_eq!(match_three(Expr::array(vec![Expr::int(1), Expr::int(2)])).unwrap(), Value::Int(-1)); assert_eq!(match_three(Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3), Expr::int(4)])).unwrap(), Value::Int(-1)); }
+
+    #[test]
+    fn test_array_pattern_nested() {
+        // match arr { [⟨x, y⟩, _] → x·10 + y; _ → -1 }
+        let arms = || vec![
+            MatchArm::new(Pattern::Array(vec![Pattern::Tuple(vec![Pattern::var("x"), Pattern::var("y")]), Pattern::Wildcard]), Expr::add(Expr::mul(Expr::idx(2), Expr::int(10)), Expr::idx(1))),
+            MatchArm::new(Pattern::Wildcard, Expr::int(-1)),
+        ];
+        let pairs = Expr::array(vec![Expr::tuple(vec![Expr::int(4), Expr::int(2)]), Expr::tuple(vec![Expr::int(0), Expr::int(0)])]);
+        assert_eq!(eval(&Expr::match_(pairs, arms())).unwrap(), Value::Int(42));
+        assert_eq!(eval(&Expr::match_(Expr::array(vec![Expr::int(4), Expr::int(2)]), arms())).unwrap(), Value::Int(-1));
+    }
+
+    // ============ Annotation Check Tests ============
+
+    fn vec3_type() -> Type { Type::Tensor(Shape(vec![Dim::Const(3)]), Box::new(Type::Prim(PrimType::F64))) }
+
+    #[test]
+    fn test_let_annotation_wrong_shape() {
+        // let v : [3]F64 = [1.0, 2.0] in v
+        let expr = Expr::Let { pattern: Pattern::var("v"), type_: Some(vec3_type()), value: Box::new(Expr::array(vec![Expr::float(1.0), Expr::float(2.0)])), body: Box::new(Expr::idx(0)) };
+        assert!(Evaluator::new().eval(&expr).is_ok());
+        assert!(matches!(Evaluator::new().with_contracts(true).eval(&expr), Err(EvalError::TypeErrorMsg(msg)) if msg.contains("[3]")));
+    }
+
+    #[test]
+    fn test_let_annotation_kind() {
+        let typed = |value: Expr| Expr::Let { pattern: Pattern::Typed(Box::new(Pattern::var("x")), Type::Prim(PrimType::F64)), type_: None, value: Box::new(value), body: Box::new(Expr::idx(0)) };
+        let mut e = Evaluator::new().with_contracts(true);
+        assert!(matches!(e.eval(&typed(Expr::int(1))), Err(EvalError::TypeError { expected: "Float", got: "Int" })));
+        assert_eq!(e.eval(&typed(Expr::float(1.5))).unwrap(), Value::float(1.5));
+    }
+
+    #[test]
+    fn test_let_decl_annotation() {
+        let decl = goth_ast::decl::LetDecl { name: "v".into(), type_: Some(vec3_type()), value: Expr::array(vec![Expr::float(1.0), Expr::float(2.0), Expr::float(3.0)]) };
+        let mut e = Evaluator::new().with_contracts(true);
+        assert!(e.eval_let_decl(&decl).is_ok());
+        let bad = goth_ast::decl::LetDecl { type_: Some(Type::Tensor(Shape(vec![Dim::Var("n".into()), Dim::Var("n".into())]), Box::new(Type::Prim(PrimType::F64)))), ..decl };
+        assert!(e.eval_let_decl(&bad).is_err());
+    }
+
+    #[test]
+    fn test_let_interval_annotation() {
+        // let total : F64⊢[0..∞] ← Σ xs in total
+        let non_negative = Type::Prim(PrimType::F64).with_interval(goth_ast::interval::Interval::non_negative());
+        let total = |xs: Vec<f64>| Expr::Let { pattern: Pattern::var("total"), type_: Some(non_negative.clone()), value: Box::new(Expr::sum(Expr::array(xs.into_iter().map(Expr::float).collect()))), body: Box::new(Expr::idx(0)) };
+        let mut e = Evaluator::new().with_contracts(true);
+        assert_eq!(e.eval(&total(vec![1.5, 2.5])).unwrap(), Value::float(4.0));
+        assert!(matches!(e.eval(&total(vec![1.5, -2.5])), Err(EvalError::TypeErrorMsg(msg)) if msg.contains("outside")));
+        assert!(Evaluator::new().eval(&total(vec![1.5, -2.5])).is_ok());
+    }
+
+    #[test]
+    fn test_expr_interval_annotation() {
+        // (Σ xs : F64⊢[0..∞]), checked only in contracts mode
+        let non_negative = Type::Prim(PrimType::F64).with_interval(goth_ast::interval::Interval::non_negative());
+        let total = |xs: Vec<f64>| Expr::Annot(Box::new(Expr::sum(Expr::array(xs.into_iter().map(Expr::float).collect()))), non_negative.clone());
+        let mut e = Evaluator::new().with_contracts(true);
+        assert_eq!(e.eval(&total(vec![1.5, 2.5])).unwrap(), Value::float(4.0));
+        assert!(matches!(e.eval(&total(vec![1.5, -2.5])), Err(EvalError::TypeErrorMsg(msg)) if msg.contains("outside")));
+        // In tail position too
+        assert!(e.eval(&Expr::app(Expr::lam(total(vec![-1.0])), Expr::int(0))).is_err());
+        assert!(Evaluator::new().eval(&total(vec![1.5, -2.5])).is_ok());
+    }
+
+    // ============ Output Tests ============
+
+    #[test]
+    fn test_print_captured() {
+        let print = |e: Expr| Expr::app(Expr::name("print"), e);
+        let expr = Expr::binop(BinOp::Add, print(Expr::int(1)), print(Expr::binop(BinOp::Mul, Expr::int(2), Expr::int(3))));
+        let mut e = Evaluator::new().with_captured_output();
+        assert_eq!(e.eval(&expr).unwrap(), Value::Int(7));
+        assert_eq!(e.take_output(), "1\n6\n");
+        assert_eq!(e.take_output(), "");
+        assert!(matches!(e.eval_pure(&expr), Err(EvalError::EffectNotAllowed(_))));
+        assert_eq!(e.eval_pure(&Expr::binop(BinOp::Add, Expr::int(1), Expr::int(6))).unwrap(), Value::Int(7));
+    }
+
+    #[test]
+    fn test_trace_records() {
+        let trace = |label: &str, e: Expr| Expr::app(Expr::app(Expr::name("trace"), Expr::Lit(Literal::String(label.into()))), e);
+        let expr = Expr::binop(BinOp::Mul, trace("x", Expr::int(3)), trace("y", Expr::binop(BinOp::Add, Expr::int(1), Expr::int(1))));
+        let mut e = Evaluator::new();
+        assert_eq!(e.eval_pure(&expr).unwrap(), Value::Int(6));
+        assert_eq!(e.traces(), &[("x".to_string(), Value::Int(3)), ("y".to_string(), Value::Int(2))]);
+    }
+
+    #[test]
+    fn test_element_evaluation_order() {
+        let trace = |label: &str| Expr::app(Expr::app(Expr::name("trace"), Expr::Lit(Literal::String(label.into()))), Expr::int(0));
+        let labels = |e: &Evaluator| e.traces().iter().map(|(l, _)| l.clone()).collect::<Vec<_>>();
+        let mut e = Evaluator::new();
+        e.eval(&Expr::tuple(vec![trace("a"), Expr::tuple(vec![trace("b"), trace("c")]), trace("d")])).unwrap();
+        assert_eq!(labels(&e), ["a", "b", "c", "d"]);
+        e.eval(&Expr::array(vec![trace("e"), trace("f"), trace("g")])).unwrap();
+        assert_eq!(labels(&e), ["a", "b", "c", "d", "e", "f", "g"]);
+    }
+
+    #[test]
+    fn test_binop_left_error_first() {
+        let traced_div = |label: &str, n: i128| Expr::binop(BinOp::Div, Expr::app(Expr::app(Expr::name("trace"), Expr::Lit(Literal::String(label.into()))), Expr::int(n)), Expr::int(0));
+        let mut e = Evaluator::new();
+        assert!(matches!(e.eval(&Expr::binop(BinOp::Add, traced_div("left", 1), traced_div("right", 2))), Err(EvalError::DivisionByZero)));
+        assert_eq!(e.traces(), &[("left".to_string(), Value::Int(1))]);
+    }
+
+    // ============ Cancellation Tests ============
+
+    #[test]
+    fn test_cancel_infinite_recursion() {
+        use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+        // loop n = loop (n + 1)
+        let mut e = Evaluator::new();
+        let env = Env::with_globals(e.globals());
+        e.define("loop", Value::closure(1, Expr::app(Expr::name("loop"), Expr::add(Expr::idx(0), Expr::int(1))), env));
+        let expr = Expr::app(Expr::name("loop"), Expr::int(0));
+        let token = Arc::new(AtomicBool::new(false));
+        let setter = { let token = Arc::clone(&token); std::thread::spawn(move || { std::thread::sleep(std::time::Duration::from_millis(50)); token.store(true, Ordering::Relaxed) }) };
+        assert!(matches!(e.eval_cancellable(&expr, Arc::clone(&token)), Err(EvalError::Cancelled)));
+        setter.join().unwrap();
+    }
+
+    #[test]
+    fn test_cancel_applies_to_bytecode() {
+        use std::sync::{Arc, atomic::AtomicBool};
+        // A token set before the run stops the loop at the first poll
+        let token = Arc::new(AtomicBool::new(true));
+        let mut e = Evaluator::new().with_cancel(Arc::clone(&token));
+        let env = Env::with_globals(e.globals());
+        e.define("loop", Value::closure(1, Expr::app(Expr::name("loop"), Expr::add(Expr::idx(0), Expr::int(1))), env.clone()));
+        let expr = Expr::app(Expr::name("loop"), Expr::int(0));
+        assert!(matches!(e.run(&crate::bytecode::compile(&expr), &env), Err(EvalError::Cancelled)));
+        assert!(matches!(e.eval(&expr), Err(EvalError::Cancelled)));
+    }
+
+    // ============ Step Limit Tests ============
+
+    #[test]
+    fn test_step_limit_stops_loop() {
+        // loop n = loop (n + 1)
+        let mut e = Evaluator::new().with_step_limit(10_000);
+        let env = Env::with_globals(e.globals());
+        e.define("loop", Value::closure(1, Expr::app(Expr::name("loop"), Expr::add(Expr::idx(0), Expr::int(1))), env));
+        assert!(matches!(e.eval(&Expr::app(Expr::name("loop"), Expr::int(0))), Err(EvalError::StepLimitExceeded { limit: 10_000 })));
+        assert_eq!(e.steps_taken(), 10_001);
+    }
+
+    #[test]
+    fn test_step_limit_counts_every_node() {
+        // 1 + 2 evaluates three expressions
+        let expr = Expr::add(Expr::int(1), Expr::int(2));
+        assert_eq!(eval_trace_limited(&expr, 3).unwrap(), Value::Int(3));
+        assert!(matches!(eval_trace_limited(&expr, 2), Err(EvalError::StepLimitExceeded { limit: 2 })));
+        let mut e = Evaluator::new();
+        e.eval(&expr).unwrap();
+        assert_eq!(e.steps_taken(), 3);
+    }
+
+    #[test]
+    fn test_step_limit_applies_to_bytecode() {
+        // count n = if n < 100000 then count (n + 1) else n, run by both backends
+        let count = |n: Expr| Expr::app(Expr::name("count"), n);
+        let body = Expr::if_(Expr::binop(BinOp::Lt, Expr::idx(0), Expr::int(100_000)), count(Expr::add(Expr::idx(0), Expr::int(1))), Expr::idx(0));
+        let program = count(Expr::int(0));
+        let mut tree = Evaluator::new().with_step_limit(1000);
+        let f = tree.eval(&Expr::lam(body)).unwrap();
+        tree.define("count", f.clone());
+        assert!(matches!(tree.eval(&program), Err(EvalError::StepLimitExceeded { limit: 1000 })));
+        let mut vm = Evaluator::new().with_step_limit(1000);
+        vm.define("count", f);
+        let env = Env::with_globals(vm.globals());
+        assert!(matches!(vm.run(&crate::bytecode::compile(&program), &env), Err(EvalError::StepLimitExceeded { limit: 1000 })));
+        assert_eq!(vm.steps_taken(), 1001);
+    }
+
+    // ============ Diff Tests ============
+
+    #[test]
+    fn test_diff_tensor_element() {
+        let expected = Value::Tensor(Tensor { shape: vec![2, 2], data: TensorData::Int(vec![1, 2, 3, 4]) });
+        let actual = Value::Tensor(Tensor { shape: vec![2, 2], data: TensorData::Int(vec![1, 2, 5, 4]) });
+        assert!(expected.diff(&expected).is_none());
+        let diff = Value::tuple(vec![Value::Int(0), expected]).diff(&Value::tuple(vec![Value::Int(0), actual])).unwrap();
+        assert_eq!(diff.path, vec![PathStep::Position(1), PathStep::Index(vec![1, 0])]);
+        assert_eq!(diff.to_string(), "at value.1[1, 0]: expected 3, got 5");
+        assert_eq!((diff.expected, diff.actual), (Value::Int(3), Value::Int(5)));
+    }
+
+    #[test]
+    fn test_diff_tensor_shape() {
+        let diff = Value::Tensor(Tensor::from_ints(vec![1, 2, 3])).diff(&Value::Tensor(Tensor { shape: vec![3, 1], data: TensorData::Int(vec![1, 2, 3]) })).unwrap();
+        assert_eq!(diff.path, vec![PathStep::Shape]);
+        assert_eq!((diff.expected, diff.actual), (Value::Tensor(Tensor::from_ints(vec![3])), Value::Tensor(Tensor::from_ints(vec![3, 1]))));
+    }
+
+    // ============ Tensor Equality Tests ============
+
+    #[test]
+    fn test_eq_same_data_different_shape() {
+        let mut e = Evaluator::new();
+        e.define("v", Value::Tensor(Tensor::from_ints(vec![1, 2, 3])));
+        e.define("col", Value::Tensor(Tensor { shape: vec![3, 1], data: TensorData::Int(vec![1, 2, 3]) }));
+        assert_eq!(e.eval(&Expr::binop(BinOp::Eq, Expr::name("v"), Expr::name("col"))).unwrap(), Value::Bool(false));
+        assert_eq!(e.eval(&Expr::binop(BinOp::Neq, Expr::name("v"), Expr::name("col"))).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_eq_bool_tensors() {
+        let mut e = Evaluator::new();
+        e.define("p", Value::Tensor(Tensor::from_bools(vec![true, false, true])));
+        e.define("q", Value::Tensor(Tensor::from_values(vec![3], vec![Value::Bool(true), Value::Bool(false), Value::Bool(true)])));
+        e.define("r", Value::Tensor(Tensor::from_bools(vec![true, true, true])));
+        assert_eq!(e.eval(&Expr::binop(BinOp::Eq, Expr::name("p"), Expr::name("q"))).unwrap(), Value::Bool(true));
+        assert_eq!(e.eval(&Expr::binop(BinOp::Eq, Expr::name("p"), Expr::name("r"))).unwrap(), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_eq_array_literals() {
+        let eq = |a: &[i128], b: &[i128]| eval(&Expr::binop(BinOp::Eq, ints(a), ints(b))).unwrap();
+        assert_eq!(eq(&[1, 2, 3], &[1, 2, 3]), Value::Bool(true));
+        assert_eq!(eq(&[1, 2], &[1, 2, 3]), Value::Bool(false));
+        assert_eq!(eq(&[1, 2, 3], &[1, 2, 4]), Value::Bool(false));
+    }
+
+    #[test]
+    fn test_eq_tensors_of_tuples_and_records() {
+        let pairs = |n| Expr::array(vec![Expr::tuple(vec![Expr::int(1), Expr::int(2)]), Expr::tuple(vec![Expr::int(3), Expr::int(n)])]);
+        assert_eq!(eval(&Expr::binop(BinOp::Eq, pairs(4), pairs(4))).unwrap(), Value::Bool(true));
+        assert_eq!(eval(&Expr::binop(BinOp::Eq, pairs(4), pairs(5))).unwrap(), Value::Bool(false));
+        let record = |x| Value::Record(std::rc::Rc::new(std::collections::HashMap::from([("x".to_string(), Value::Int(x)), ("y".to_string(), Value::Int(0))])));
+        let mut e = Evaluator::new();
+        e.define("a", Value::Tensor(Tensor::from_values(vec![1], vec![record(1)])));
+        e.define("b", Value::Tensor(Tensor::from_values(vec![1], vec![record(1)])));
+        e.define("c", Value::Tensor(Tensor::from_values(vec![1], vec![record(2)])));
+        assert_eq!(e.eval(&Expr::binop(BinOp::Eq, Expr::name("a"), Expr::name("b"))).unwrap(), Value::Bool(true));
+        assert_eq!(e.eval(&Expr::binop(BinOp::Eq, Expr::name("a"), Expr::name("c"))).unwrap(), Value::Bool(false));
+    }
+
+    // ============ Element-wise Comparison Tests ============
+
+    #[test]
+    fn test_compare_tensor_with_scalar() {
+        let gt = eval(&Expr::binop(BinOp::Gt, ints(&[1, 2, 3]), Expr::int(2))).unwrap();
+        assert_eq!(gt, Value::Tensor(Tensor::from_bools(vec![false, false, true])));
+        let leq = eval(&Expr::binop(BinOp::Leq, Expr::float(1.5), ints(&[1, 2]))).unwrap();
+        assert_eq!(leq, Value::Tensor(Tensor::from_bools(vec![false, true])));
+        let eq = eval(&Expr::binop(BinOp::Eq, ints(&[1, 2, 1]), Expr::int(1))).unwrap();
+        assert_eq!(eq, Value::Tensor(Tensor::from_bools(vec![true, false, true])));
+    }
+
+    #[test]
+    fn test_compare_tensors_element_wise() {
+        let mut e = Evaluator::new();
+        e.define("a", matrix([2, 2], &[1, 5, 3, 4]));
+        e.define("b", matrix([2, 2], &[2, 2, 3, 3]));
+        let lt = e.eval(&Expr::binop(BinOp::Lt, Expr::name("a"), Expr::name("b"))).unwrap();
+        assert_eq!(lt, Value::Tensor(Tensor { shape: vec![2, 2], data: TensorData::Bool(vec![true, false, false, false]) }));
+        assert!(matches!(eval(&Expr::binop(BinOp::Lt, ints(&[1, 2]), ints(&[1, 2, 3]))), Err(EvalError::IncompatibleShapes { .. })));
+        // Equality of two tensors stays structural
+        assert_eq!(e.eval(&Expr::binop(BinOp::Eq, Expr::name("a"), Expr::name("b"))).unwrap(), Value::Bool(false));
+    }
+
+    // ============ Builtin Shadowing Tests ============
+
+    #[test]
+    fn test_user_sqrt_shadows_builtin() {
+        let shadowed = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let log = std::rc::Rc::clone(&shadowed);
+        let mut e = Evaluator::new().with_shadow_warning(move |name| log.borrow_mut().push(name.to_string()));
+        let env = Env::with_globals(e.globals());
+        e.define("sqrt", Value::closure(1, Expr::mul(Expr::idx(0), Expr::int(10)), env));
+        e.define("double", Value::Int(2));
+        assert_eq!(e.eval(&Expr::app(Expr::name("sqrt"), Expr::int(4))).unwrap(), Value::Int(40));
+        let namespaced = Expr::Field(Box::new(Expr::name("prim")), FieldAccess::Named("sqrt".into()));
+        assert_eq!(e.eval(&Expr::app(namespaced, Expr::float(16.0))).unwrap(), Value::float(4.0));
+        assert!(matches!(Evaluator::builtin("sqrt"), Some(Value::Primitive(PrimFn::Sqrt))));
+        assert_eq!(*shadowed.borrow(), ["sqrt"]);
+    }
+
+    // ============ Reverse Tests ============
+
+    #[test]
+    fn test_reverse_vector() {
+        let rev = |e: Expr| eval(&Expr::app(Expr::name("reverse"), e)).unwrap();
+        assert_eq!(rev(ints(&[1, 2, 3])), Value::Tensor(Tensor::from_ints(vec![3, 2, 1])));
+        assert_eq!(rev(Expr::Lit(Literal::String("goth".into()))), Value::string("htog"));
+        let empty = Value::Tensor(Tensor::from_ints(vec![]));
+        let mut e = Evaluator::new();
+        e.define("empty", empty.clone());
+        assert_eq!(e.eval(&Expr::app(Expr::name("reverse"), Expr::name("empty"))).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_reverse_matrix_rows() {
+        let mut e = Evaluator::new();
+        e.define("m", matrix_2x3());
+        assert_eq!(e.eval(&Expr::app(Expr::name("⌽"), Expr::name("m"))).unwrap(), matrix([2, 3], &[4, 5, 6, 1, 2, 3]));
+    }
+
+    // ============ Take and Drop Tests ============
+
+    #[test]
+    fn test_take_and_drop_prefix() {
+        let slice = |f: &str, n: i128, xs: Expr| eval(&Expr::app(Expr::app(Expr::name(f), Expr::int(n)), xs));
+        assert_eq!(slice("take", 2, ints(&[1, 2, 3, 4])).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, 2])));
+        assert_eq!(slice("drop", 2, ints(&[1, 2, 3, 4])).unwrap(), Value::Tensor(Tensor::from_ints(vec![3, 4])));
+        assert_eq!(slice("↑", 2, Expr::Lit(Literal::String("goth".into()))).unwrap(), Value::string("go"));
+        assert_eq!(slice("↓", 2, Expr::Lit(Literal::String("goth".into()))).unwrap(), Value::string("th"));
+    }
+
+    #[test]
+    fn test_take_and_drop_clamp_and_reject_negative() {
+        let slice = |f: &str, n: i128| eval(&Expr::app(Expr::app(Expr::name(f), Expr::int(n)), ints(&[1, 2, 3])));
+        assert_eq!(slice("take", 10).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, 2, 3])));
+        assert_eq!(slice("drop", 10).unwrap(), Value::Tensor(Tensor::from_ints(vec![])));
+        for f in ["take", "drop"] {
+            let err = slice(f, -1).unwrap_err();
+            assert_eq!(err.to_string(), EvalError::type_error_msg(format!("{} count must be non-negative, got -1", f)).to_string());
+        }
+    }
+
+    #[test]
+    fn test_take_and_drop_matrix_rows() {
+        let mut e = Evaluator::new();
+        e.define("m", matrix([3, 2], &[1, 2, 3, 4, 5, 6]));
+        let slice = |e: &mut Evaluator, f: &str| e.eval(&Expr::app(Expr::app(Expr::name(f), Expr::int(1)), Expr::name("m"))).unwrap();
+        assert_eq!(slice(&mut e, "take"), matrix([1, 2], &[1, 2]));
+        assert_eq!(slice(&mut e, "drop"), matrix([2, 2], &[3, 4, 5, 6]));
+    }
+
+    // ============ Index Tests ============
+
+    #[test]
+    fn test_index_counts_from_either_end() {
+        let at = |i: i128| eval(&Expr::app(Expr::app(Expr::name("index"), ints(&[10, 20, 30])), Expr::int(i)));
+        assert_eq!(at(1).unwrap(), Value::Int(20));
+        assert_eq!(at(-1).unwrap(), Value::Int(30));
+        assert_eq!(at(-3).unwrap(), Value::Int(10));
+        assert!(matches!(at(3), Err(EvalError::IndexOutOfBounds { index: 3, len: 3 })));
+        assert!(matches!(at(-4), Err(EvalError::IndexOutOfBounds { index: -4, len: 3 })));
+    }
+
+    // ============ Sort Tests ============
+
+    #[test]
+    fn test_sort_ascending() {
+        let mut e = Evaluator::new();
+        e.define("xs", Value::Tensor(Tensor::from_ints(vec![3, 1, 2])));
+        assert_eq!(e.eval(&Expr::app(Expr::name("sort"), Expr::name("xs"))).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, 2, 3])));
+        // The original is unchanged
+        assert_eq!(e.eval(&Expr::name("xs")).unwrap(), Value::Tensor(Tensor::from_ints(vec![3, 1, 2])));
+        let mixed = Expr::array(vec![Expr::float(2.5), Expr::int(1), Expr::int(3), Expr::float(-0.5)]);
+        let sorted = eval(&Expr::app(Expr::name("sort"), mixed)).unwrap();
+        assert_eq!(sorted.as_tensor().unwrap().to_vec(), vec![Value::float(-0.5), Value::float(1.0), Value::float(2.5), Value::float(3.0)]);
+        assert_eq!(eval(&Expr::app(Expr::name("sort"), Expr::Lit(Literal::String("goth".into())))).unwrap(), Value::string("ghot"));
+    }
+
+    #[test]
+    fn test_sort_incomparable_elements() {
+        let closures = Expr::array(vec![Expr::lam(Expr::idx(0)), Expr::lam(Expr::idx(0))]);
+        assert!(matches!(eval(&Expr::app(Expr::name("sort"), closures)), Err(EvalError::TypeError { got: "Closure", .. })));
+        let mut e = Evaluator::new();
+        e.define("m", matrix_2x3());
+        assert!(matches!(e.eval(&Expr::app(Expr::name("sort"), Expr::name("m"))), Err(EvalError::ShapeMismatch(_))));
+    }
+
+    #[test]
+    fn test_sort_by_comparator() {
+        // Descending, then stably by the first component of pairs
+        let greater = Expr::lam_n(2, Expr::binop(BinOp::Gt, Expr::idx(1), Expr::idx(0)));
+        assert_eq!(eval(&Expr::app_n(Expr::name("sortBy"), vec![ints(&[3, 1, 2]), greater])).unwrap(), Value::Tensor(Tensor::from_ints(vec![3, 2, 1])));
+        let pair = |k: i128, v: i128| Expr::tuple(vec![Expr::int(k), Expr::int(v)]);
+        let by_key = Expr::lam_n(2, Expr::binop(BinOp::Lt, Expr::field_idx(Expr::idx(1), 0), Expr::field_idx(Expr::idx(0), 0)));
+        let sorted = eval(&Expr::app_n(Expr::name("sort_by"), vec![Expr::array(vec![pair(2, 0), pair(1, 1), pair(2, 2), pair(1, 3)]), by_key])).unwrap();
+        let keys: Vec<Value> = sorted.as_tensor().unwrap().iter().map(|p| p.as_tuple().unwrap()[1].clone()).collect();
+        assert_eq!(keys, vec![Value::Int(1), Value::Int(3), Value::Int(0), Value::Int(2)]);
+    }
+
+    // ============ Raw Buffer Tests ============
+
+    #[test]
+    fn test_tensor_bytes_little_endian_i64() {
+        use crate::buffer::{tensor_to_bytes, Endian};
+        let bytes = tensor_to_bytes(&Value::Tensor(Tensor::from_ints(vec![1, -2, 256])), Endian::Little).unwrap();
+        let mut expected = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend([0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+        expected.extend([0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes, expected);
+        let big = tensor_to_bytes(&Value::Tensor(Tensor::from_ints(vec![1])), Endian::Big).unwrap();
+        assert_eq!(big, vec![0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_tensor_bytes_round_trip() {
+        use crate::buffer::{tensor_from_bytes, tensor_to_bytes, tensor_to_bytes_as, DType, Endian};
+        let m = matrix([2, 3], &[1, -2, 3, 4, 5, i64::MAX as i128]);
+        for endian in [Endian::Little, Endian::Big] {
+            let bytes = tensor_to_bytes(&m, endian).unwrap();
+            assert_eq!(tensor_from_bytes(&bytes, &[2, 3], DType::I64, endian).unwrap(), m);
+            let floats = Value::Tensor(Tensor::from_floats(vec![0.5, -1.25, f64::INFINITY]));
+            let bytes = tensor_to_bytes_as(&floats, DType::F32, endian).unwrap();
+            assert_eq!(bytes.len(), 12);
+            assert_eq!(tensor_from_bytes(&bytes, &[3], DType::F32, endian).unwrap(), floats);
+        }
+        assert!(tensor_to_bytes_as(&m, DType::I32, Endian::Little).is_err());
+        assert!(tensor_from_bytes(&[0; 7], &[1], DType::I64, Endian::Little).is_err());
+        assert!(tensor_to_bytes(&Value::string("abc"), Endian::Little).is_err());
+        assert!(tensor_to_bytes_as(&Value::Tensor(Tensor::from_floats(vec![1.0])), DType::I64, Endian::Little).is_err());
+    }
+
+    #[test]
+    fn test_npy_header_matches_numpy() {
+        // np.save of np.array([1, 2, 3]) on a little-endian machine
+        let bytes = crate::buffer::to_npy(&Value::Tensor(Tensor::from_ints(vec![1, 2, 3]))).unwrap();
+        let dict = "{'descr': '<i8', 'fortran_order': False, 'shape': (3,), }";
+        let mut expected = b"\x93NUMPY\x01\x00\x76\x00".to_vec();
+        expected.extend(dict.bytes());
+        expected.extend(std::iter::repeat_n(b' ', 60));
+        expected.push(b'\n');
+        assert_eq!(bytes[..128], expected[..]);
+        assert_eq!(bytes[128..], crate::buffer::tensor_to_bytes(&Value::Tensor(Tensor::from_ints(vec![1, 2, 3])), crate::buffer::Endian::Little).unwrap()[..]);
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        use crate::buffer::{from_npy, read_npy, to_npy, write_npy};
+        let path = std::env::temp_dir().join(format!("goth_test_{}.npy", std::process::id()));
+        for value in [matrix([2, 3], &[1, -2, 3, 4, 5, 6]), Value::Tensor(Tensor::from_floats(vec![0.5, f64::MAX])), Value::Tensor(Tensor { shape: vec![], data: TensorData::Int(vec![7]) })] {
+            write_npy(&value, &path).unwrap();
+            assert_eq!(read_npy(&path).unwrap(), value);
+            assert_eq!(to_npy(&value).unwrap().len(), 128 + value.as_tensor().unwrap().len() * 8);
+        }
+        let _ = std::fs::remove_file(&path);
+        // Big-endian data written by another machine
+        let mut big = b"\x93NUMPY\x01\x00\x76\x00{'descr': '>i8', 'fortran_order': False, 'shape': (2,), }".to_vec();
+        big.extend(std::iter::repeat_n(b' ', 60));
+        big.push(b'\n');
+        big.extend([0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(from_npy(&big).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, 256])));
+        let at = big.windows(5).position(|w| w == b"False").unwrap();
+        let mut fortran = big.clone();
+        fortran[at..at + 5].copy_from_slice(b"True ");
+        assert!(matches!(from_npy(&fortran), Err(EvalError::Serialization(_))));
+        assert!(to_npy(&Value::string("abc")).is_err());
+        assert!(matches!(read_npy("/nonexistent/goth.npy"), Err(EvalError::IoError(_))));
+    }
+
+    // ============ CSV Tests ============
+
+    #[test]
+    fn test_csv_round_trip() {
+        use crate::csv::{read_csv, read_csv_with, write_csv, write_csv_with, CsvOptions};
+        let path = std::env::temp_dir().join(format!("goth_test_{}.csv", std::process::id()));
+        write_csv(&matrix([2, 3], &[1, 2, 3, 4, 5, 6]), &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1,2,3\n4,5,6\n");
+        let floats = Value::Tensor(Tensor { shape: vec![2, 3], data: Tensor::from_floats(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).data });
+        assert_eq!(read_csv(&path).unwrap(), floats);
+        let tabs = CsvOptions::default().with_delimiter('\t');
+        let fractions = Value::Tensor(Tensor { shape: vec![2, 3], data: Tensor::from_floats(vec![0.1, -2.5, 1e-9, 3.0, 1e300, 0.0]).data });
+        write_csv_with(&fractions, &path, &tabs).unwrap();
+        assert_eq!(read_csv_with(&path, &tabs).unwrap(), fractions);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_csv_parsing() {
+        use crate::csv::{parse_csv, to_csv, CsvOptions};
+        let header = CsvOptions::default().with_skip_header(true);
+        let parsed = parse_csv("x,y\n1, 2\n\n3,4.5\n", &header).unwrap();
+        assert_eq!(parsed, Value::Tensor(Tensor { shape: vec![2, 2], data: Tensor::from_floats(vec![1.0, 2.0, 3.0, 4.5]).data }));
+        assert!(matches!(parse_csv("1,2\n3\n", &CsvOptions::default()), Err(EvalError::Serialization(msg)) if msg.contains("line 2")));
+        assert!(matches!(parse_csv("x,y\n1,2\n", &CsvOptions::default()), Err(EvalError::Serialization(msg)) if msg.contains("line 1")));
+        assert_eq!(to_csv(&Value::Tensor(Tensor::from_ints(vec![1, 2])), &CsvOptions::default()).unwrap(), "1\n2\n");
+        assert!(to_csv(&Value::string("abc"), &CsvOptions::default()).is_err());
+        assert!(matches!(to_csv(&matrix([3, 0], &[]), &CsvOptions::default()), Err(EvalError::ShapeMismatch(_))));
+        assert_eq!(to_csv(&matrix([0, 3], &[]), &CsvOptions::default()).unwrap(), "");
+    }
+
+    // ============ Memoization Tests ============
+
+    /// `memoize (λ→ trace "body" (₀ × 2))`, bound as `f`: each run of the body leaves a trace
+    fn memoized_doubler() -> Evaluator {
+        let mut e = Evaluator::new();
+        let body = Expr::app(Expr::app(Expr::name("trace"), Expr::Lit(Literal::String("body".into()))), Expr::mul(Expr::idx(0), Expr::int(2)));
+        let f = e.eval(&Expr::app(Expr::name("memoize"), Expr::lam(body))).unwrap();
+        e.define("f", f);
+        e
+    }
+
+    #[test]
+    fn test_memoize_runs_body_once_per_input() {
+        let mut e = memoized_doubler();
+        let calls = Expr::tuple([1, 2, 1, 1, 2, 3].into_iter().map(|n| Expr::app(Expr::name("f"), Expr::int(n))).collect());
+        assert_eq!(e.eval(&calls).unwrap(), Value::Tuple([2, 4, 2, 2, 4, 6].into_iter().map(Value::Int).collect()));
+        assert_eq!(e.traces().len(), 3);
+        // Keys are compared as HashableValues, so every NaN is the same argument
+        e.define("nan", Value::float(f64::NAN));
+        let nan = Expr::app(Expr::name("f"), Expr::name("nan"));
+        e.eval(&Expr::tuple(vec![nan.clone(), nan])).unwrap();
+        assert_eq!(e.traces().len(), 4);
+    }
+
+    #[test]
+    fn test_memoize_recomputes_unkeyed_arguments() {
+        // memoize (λ→ trace "body" (₀ 1)), applied twice to the same closure
+        let mut e = Evaluator::new();
+        let body = Expr::app(Expr::app(Expr::name("trace"), Expr::Lit(Literal::String("body".into()))), Expr::app(Expr::idx(0), Expr::int(1)));
+        let f = e.eval(&Expr::app(Expr::name("memoize"), Expr::lam(body))).unwrap();
+        e.define("f", f);
+        let inc = Expr::lam(Expr::add(Expr::idx(0), Expr::int(1)));
+        let calls = Expr::tuple(vec![Expr::app(Expr::name("f"), inc.clone()), Expr::app(Expr::name("f"), inc)]);
+        assert_eq!(e.eval(&calls).unwrap(), Value::Tuple(vec![Value::Int(2), Value::Int(2)]));
+        assert_eq!(e.traces().len(), 2);
+        assert!(matches!(eval(&Expr::app(Expr::name("memoize"), Expr::int(1))), Err(EvalError::TypeError { expected: "function", got: "Int" })));
+    }
+
+    // ============ Native Primitive Tests ============
+
+    fn triple(ctx: &PrimContext, args: &[Value]) -> EvalResult<Value> {
+        match args {
+            [Value::Int(_), _, _] => Ok(Value::Tuple(args.to_vec())),
+            [other, _, _] => Err(ctx.type_error(0, "Int", other)),
+            _ => Err(EvalError::ArityMismatch { expected: 3, got: args.len() }),
+        }
+    }
+
+    #[test]
+    fn test_register_prim_full_and_partial() {
+        let mut e = Evaluator::new();
+        e.register_prim("triple", 3, triple).unwrap();
+        let expected = Value::Tuple(vec![Value::Int(1), Value::Int(2), Value::Int(3)]);
+        assert_eq!(e.eval(&Expr::app_n(Expr::name("triple"), vec![Expr::int(1), Expr::int(2), Expr::int(3)])).unwrap(), expected);
+        let partial = e.eval(&Expr::app(Expr::name("triple"), Expr::int(1))).unwrap();
+        assert!(matches!(&partial, Value::Partial { remaining: 2, .. }), "{}", partial);
+        e.define("t", partial);
+        assert_eq!(e.eval(&Expr::app_n(Expr::name("t"), vec![Expr::int(2), Expr::int(3)])).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_native_prim_error_names_it() {
+        let mut e = Evaluator::new();
+        e.register_prim("triple", 3, triple).unwrap();
+        let err = e.eval(&Expr::app_n(Expr::name("triple"), vec![Expr::bool(true), Expr::int(2), Expr::int(3)])).unwrap_err();
+        assert!(matches!(&err, EvalError::ArgumentType { prim, arg: 0, expected: "Int", got: "Bool" } if prim == "triple"), "{:?}", err);
+        assert_eq!(err.to_string(), "Type error: triple: argument 1 expected Int, got Bool");
+    }
+
+    #[test]
+    fn test_register_prim_captures_state_and_rejects_nullary() {
+        let mut e = Evaluator::new();
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        let seen = std::rc::Rc::clone(&calls);
+        e.register_prim("counted", 1, move |_, args| { seen.set(seen.get() + 1); Ok(args[0].clone()) }).unwrap();
+        assert_eq!(e.eval(&Expr::app(Expr::name("counted"), Expr::int(7))).unwrap(), Value::Int(7));
+        assert_eq!(e.eval(&Expr::app(Expr::name("counted"), Expr::int(8))).unwrap(), Value::Int(8));
+        assert_eq!(calls.get(), 2);
+        assert!(matches!(e.register_prim("nothing", 0, |_, _| Ok(Value::Unit)), Err(EvalError::TypeErrorMsg(_))));
+        assert!(e.eval(&Expr::name("nothing")).is_err());
+    }
+
+    #[test]
+    fn test_register_prim_with_runtime_name() {
+        let mut e = Evaluator::new();
+        let name = format!("triple_{}", 2);
+        e.register_prim(&name, 3, triple).unwrap();
+        let err = e.eval(&Expr::app_n(Expr::name(name.as_str()), vec![Expr::bool(true), Expr::int(2), Expr::int(3)])).unwrap_err();
+        assert!(matches!(&err, EvalError::ArgumentType { prim, .. } if prim == "triple_2"), "{:?}", err);
+    }
+
+    // ============ Closure Equality Tests ============
+
+    #[test]
+    fn test_closure_structural_eq() {
+        // let k = c in λx. let y = x + k in y, with the binder names varied
+        let build = |c: i128, k: &str, y: &str| eval(&Expr::let_var(k, Expr::int(c), Expr::lam(Expr::let_var(y, Expr::add(Expr::idx(0), Expr::idx(1)), Expr::idx(0))))).unwrap();
+        assert_eq!(build(5, "k", "y"), build(5, "offset", "sum"));
+        assert_ne!(build(5, "k", "y"), build(6, "k", "y"));
+        assert_ne!(eval(&Expr::lam(Expr::idx(0))).unwrap(), eval(&Expr::lam_n(2, Expr::idx(0))).unwrap());
+    }
+
+    // ============ Reshape Tests ============
+
+    #[test]
+    fn test_reshape_keeps_flat_order() {
+        let data = Expr::array((1..=6).map(Expr::int).collect());
+        let reshaped = eval(&Expr::app(Expr::app(Expr::name("reshape"), data), Expr::array(vec![Expr::int(2), Expr::int(3)]))).unwrap();
+        let Value::Tensor(t) = &reshaped else { panic!("expected a tensor, got {}", reshaped) };
+        assert_eq!(t.shape, vec![2, 3]);
+        assert_eq!(t.get(&[1, 0]), Some(Value::Int(4)));
+        assert_eq!((0..6).map(|i| t.get_flat(i)).collect::<Vec<_>>(), (1..=6).map(|n| Some(Value::Int(n))).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_reshape_size_mismatch() {
+        let data = Expr::array((1..=6).map(Expr::int).collect());
+        let expr = Expr::app(Expr::app(Expr::name("reshape"), data), Expr::array(vec![Expr::int(4), Expr::int(2)]));
+        assert!(matches!(eval(&expr), Err(EvalError::ShapeMismatch(msg)) if msg.contains("[4, 2]")));
+    }
+
+    // ============ Matmul Tests ============
+
+    #[test]
+    fn test_matmul_2x3_by_3x2() {
+        let a = Tensor::pack(vec![2, 3], (1..=6).map(Value::Int).collect());
+        let b = Tensor::pack(vec![3, 2], (7..=12).map(Value::Int).collect());
+        let expected = Tensor::pack(vec![2, 2], [58, 64, 139, 154].into_iter().map(Value::Int).collect());
+        let mut e = Evaluator::new();
+        e.define("a", Value::Tensor(a));
+        e.define("b", Value::Tensor(b));
+        assert_eq!(call_in(&mut e, "matmul", vec![Expr::name("a"), Expr::name("b")]).unwrap(), Value::Tensor(expected));
+
+        // One float element promotes the whole product
+        let b = Tensor::pack(vec![3, 2], vec![Value::float(0.5), Value::Int(0), Value::Int(0), Value::Int(1), Value::Int(0), Value::Int(0)]);
+        let expected = Tensor::pack(vec![2, 2], [0.5, 2.0, 2.0, 5.0].into_iter().map(Value::float).collect());
+        e.define("b", Value::Tensor(b));
+        assert_eq!(call_in(&mut e, "matmul", vec![Expr::name("a"), Expr::name("b")]).unwrap(), Value::Tensor(expected));
+    }
+
+    #[test]
+    fn test_matmul_inner_dimension_mismatch() {
+        let mut e = Evaluator::new();
+        e.define("a", Value::Tensor(Tensor::pack(vec![2, 3], (1..=6).map(Value::Int).collect())));
+        assert!(matches!(call_in(&mut e, "matmul", vec![Expr::name("a"), Expr::name("a")]), Err(EvalError::ShapeMismatch(msg)) if msg.contains("[2 3] × [2 3]")));
+    }
+
+    // ============ Transpose Tests ============
+
+    #[test]
+    fn test_transpose_matrix() {
+        let mut e = Evaluator::new();
+        e.define("m", matrix_2x3());
+        let transposed = e.eval(&Expr::app(Expr::name("transpose"), Expr::name("m"))).unwrap();
+        let expected = Tensor::pack(vec![3, 2], [1, 4, 2, 5, 3, 6].into_iter().map(Value::Int).collect());
+        assert_eq!(transposed, Value::Tensor(expected));
+        let twice = e.eval(&Expr::app(Expr::name("transpose"), Expr::app(Expr::name("transpose"), Expr::name("m")))).unwrap();
+        assert_eq!(twice, matrix_2x3());
+    }
+
+    #[test]
+    fn test_transpose_axes() {
+        let mut e = Evaluator::new();
+        e.define("t", Value::Tensor(Tensor::pack(vec![2, 3, 4], (0..24).map(Value::Int).collect())));
+        let permuted = e.eval(&Expr::app(Expr::app(Expr::name("transposeAxes"), Expr::name("t")), Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(0)]))).unwrap();
+        let Value::Tensor(p) = permuted else { panic!("expected a tensor") };
+        assert_eq!(p.shape, vec![3, 4, 2]);
+        // p[j, k, i] = t[i, j, k] = 12i + 4j + k
+        assert_eq!(p.get(&[2, 1, 1]), Some(Value::Int(12 + 8 + 1)));
+        let bad = Expr::app(Expr::app(Expr::name("transposeAxes"), Expr::name("t")), Expr::array(vec![Expr::int(0), Expr::int(0), Expr::int(1)]));
+        assert!(matches!(e.eval(&bad), Err(EvalError::ShapeMismatch(_))));
+    }
+
+    #[test]
+    fn test_transpose_rejects_vectors() {
+        let vector = Expr::array(vec![Expr::int(1), Expr::int(2)]);
+        assert!(matches!(eval(&Expr::app(Expr::name("transpose"), vector)), Err(EvalError::ShapeMismatch(msg)) if msg.contains("rank 2")));
+        assert!(matches!(eval(&Expr::app(Expr::name("transpose"), Expr::int(3))), Err(EvalError::TypeError { .. })));
+    }
+
+    // ============ Fold Tests ============
+
+    #[test]
+    fn test_fold_sum_and_product() {
+        let elems = || (1..=5).map(Expr::int).collect::<Vec<_>>();
+        // λ acc x → acc + x, with the accumulator bound as ₁
+        assert_eq!(call("fold", vec![Expr::array(elems()), Expr::int(0), Expr::lam_n(2, Expr::add(Expr::idx(1), Expr::idx(0)))]).unwrap(), Value::Int(15));
+        assert_eq!(call("fold", vec![Expr::array(elems()), Expr::int(1), Expr::lam_n(2, Expr::mul(Expr::idx(1), Expr::idx(0)))]).unwrap(), Value::Int(120));
+        // Left to right: ((0 - 1) - 2) - 3
+        let steps = vec![Expr::int(1), Expr::int(2), Expr::int(3)];
+        assert_eq!(call("fold", vec![Expr::array(steps), Expr::int(0), Expr::lam_n(2, Expr::sub(Expr::idx(1), Expr::idx(0)))]).unwrap(), Value::Int(-6));
+    }
+
+    #[test]
+    fn test_fold_empty_returns_init() {
+        assert_eq!(call("fold", vec![Expr::array(vec![]), Expr::int(0), Expr::lam_n(2, Expr::add(Expr::idx(1), Expr::idx(0)))]).unwrap(), Value::Int(0));
+        assert_eq!(call("fold", vec![Expr::array(vec![]), Expr::int(1), Expr::lam_n(2, Expr::mul(Expr::idx(1), Expr::idx(0)))]).unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_fold_propagates_closure_errors() {
+        let elems = vec![Expr::int(2), Expr::int(0)];
+        let result = call("fold", vec![Expr::array(elems), Expr::int(8), Expr::lam_n(2, Expr::div(Expr::idx(1), Expr::idx(0)))]);
+        assert!(matches!(result, Err(EvalError::DivisionByZero)));
+    }
+
+    // ============ ZipWith Tests ============
+
+    #[test]
+    fn test_zip_with_adds_elementwise() {
+        let sums = call("zip_with", vec![ints(&[1, 2, 3]), ints(&[10, 20, 30]), Expr::name("add")]).unwrap();
+        assert_eq!(sums, Value::Tensor(Tensor::from_ints(vec![11, 22, 33])));
+        // λ a b → a - b, with a bound as ₁
+        let diffs = call("zip_with", vec![ints(&[1, 2, 3]), ints(&[10, 20, 30]), Expr::lam_n(2, Expr::sub(Expr::idx(1), Expr::idx(0)))]).unwrap();
+        assert_eq!(diffs, Value::Tensor(Tensor::from_ints(vec![-9, -18, -27])));
+    }
+
+    #[test]
+    fn test_zip_with_length_mismatch() {
+        let result = call("zip_with", vec![ints(&[1, 2, 3]), ints(&[10, 20]), Expr::name("add")]);
+        assert!(matches!(result, Err(EvalError::IncompatibleShapes { left, right }) if left == [3] && right == [2]));
+    }
+
+    // ============ Scan Tests ============
+
+    #[test]
+    fn test_scan_cumulative_sum_and_product() {
+        assert_eq!(call("scan", vec![ints(&[1, 2, 3, 4]), Expr::int(0), Expr::name("add")]).unwrap(), Value::Tensor(Tensor::from_ints(vec![1, 3, 6, 10])));
+        // λ acc x → acc × x, with the accumulator bound as ₁
+        let running_product = call("scan", vec![ints(&[1, 2, 3, 4]), Expr::int(1), Expr::lam_n(2, Expr::mul(Expr::idx(1), Expr::idx(0)))]).unwrap();
+        assert_eq!(running_product, Value::Tensor(Tensor::from_ints(vec![1, 2, 6, 24])));
+        assert_eq!(call("scan", vec![ints(&[]), Expr::int(0), Expr::name("add")]).unwrap(), Value::Tensor(Tensor::from_ints(vec![])));
+    }
+
+    #[test]
+    fn test_scan_propagates_closure_errors() {
+        let result = call("scan", vec![ints(&[2, 0]), Expr::int(8), Expr::lam_n(2, Expr::div(Expr::idx(1), Expr::idx(0)))]);
+        assert!(matches!(result, Err(EvalError::DivisionByZero)));
+    }
+
+    // ============ Range Tests ============
+
+    #[test]
+    fn test_range_half_open() {
+        assert_eq!(call("range", vec![Expr::int(0), Expr::int(5)]).unwrap(), Value::Tensor(Tensor::from_ints(vec![0, 1, 2, 3, 4])));
+        assert_eq!(call("range", vec![Expr::int(-2), Expr::int(1)]).unwrap(), Value::Tensor(Tensor::from_ints(vec![-2, -1, 0])));
+    }
+
+    #[test]
+    fn test_range_empty_when_end_not_after_start() {
+        for args in [[3, 3], [5, 2]] {
+            let Value::Tensor(t) = call("range", args.into_iter().map(Expr::int).collect()).unwrap() else { panic!("range should return a tensor") };
+            assert_eq!(t.shape, vec![0]);
+        }
+    }
+
+    #[test]
+    fn test_range_step() {
+        assert_eq!(call("rangeStep", vec![Expr::int(0), Expr::int(10), Expr::int(3)]).unwrap(), Value::Tensor(Tensor::from_ints(vec![0, 3, 6, 9])));
+        assert_eq!(call("range_step", vec![Expr::int(5), Expr::int(0), Expr::int(-2)]).unwrap(), Value::Tensor(Tensor::from_ints(vec![5, 3, 1])));
+        assert_eq!(call("rangeStep", vec![Expr::int(0), Expr::int(5), Expr::int(-1)]).unwrap(), Value::Tensor(Tensor::from_ints(vec![])));
+        assert!(matches!(call("rangeStep", vec![Expr::int(0), Expr::int(5), Expr::int(0)]), Err(EvalError::TypeErrorMsg(_))));
+    }
+
+    // ============ Depth Limit Tests ============
+
+    #[test]
+    fn test_infinite_recursion_is_a_stack_overflow() {
+        // f n = 1 + f (n + 1) never returns and is not a tail call
+        let mut e = Evaluator::new().with_depth_limit(50);
+        let f = e.eval(&Expr::lam(Expr::add(Expr::int(1), Expr::app(Expr::name("f"), Expr::add(Expr::idx(0), Expr::int(1)))))).unwrap();
+        e.define("f", f);
+        assert!(matches!(e.eval(&Expr::app(Expr::name("f"), Expr::int(0))), Err(EvalError::StackOverflow { limit: 50 })));
+        // The evaluator unwinds its depth and stays usable
+        assert_eq!(e.eval(&Expr::add(Expr::int(1), Expr::int(2))).unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn test_default_depth_limit_fits_the_stack() {
+        // The default limit is reached long before the test thread's stack runs out
+        let mut e = Evaluator::new();
+        let f = e.eval(&Expr::lam(Expr::add(Expr::int(1), Expr::app(Expr::name("f"), Expr::add(Expr::idx(0), Expr::int(1)))))).unwrap();
+        e.define("f", f);
+        let call = Expr::app(Expr::name("f"), Expr::int(0));
+        assert!(matches!(e.eval(&call), Err(EvalError::StackOverflow { limit: 10000 })));
+        let env = Env::with_globals(e.globals());
+        assert!(matches!(e.run(&crate::bytecode::compile(&call), &env), Err(EvalError::StackOverflow { limit: 10000 })));
+    }
+
+    // ============ Tail Call Tests ============
+
+    #[test]
+    fn test_tail_recursive_countdown() {
+        // countdown n = if n == 0 then 0 else (countdown (n - 1) : I64)
+        let mut e = Evaluator::new();
+        let call = Expr::Annot(Box::new(Expr::app(Expr::name("countdown"), Expr::sub(Expr::idx(0), Expr::int(1)))), goth_ast::types::Type::Prim(goth_ast::types::PrimType::I64));
+        let countdown = e.eval(&Expr::lam(Expr::if_(Expr::binop(BinOp::Eq, Expr::idx(0), Expr::int(0)), Expr::int(0), call))).unwrap();
+        e.define("countdown", countdown);
+        assert_eq!(e.eval(&Expr::app(Expr::name("countdown"), Expr::int(1_000_000))).unwrap(), Value::Int(0));
+    }
+
+    #[test]
+    fn test_tail_recursive_accumulator() {
+        // total acc n = if n == 0 then acc else total (acc + n) (n - 1), with acc bound as ₁
+        let mut e = Evaluator::new();
+        let step = Expr::app(Expr::app(Expr::name("total"), Expr::add(Expr::idx(1), Expr::idx(0))), Expr::sub(Expr::idx(0), Expr::int(1)));
+        let total = e.eval(&Expr::lam_n(2, Expr::if_(Expr::binop(BinOp::Eq, Expr::idx(0), Expr::int(0)), Expr::idx(1), step))).unwrap();
+        e.define("total", total);
+        let result = e.eval(&Expr::app(Expr::app(Expr::name("total"), Expr::int(0)), Expr::int(100_000))).unwrap();
+        assert_eq!(result, Value::Int(5_000_050_000));
+    }
+
+    // ============ Integer Overflow Tests ============
+
+    #[test]
+    fn test_integer_overflow_is_an_error() {
+        let max = Expr::int(i64::MAX as i128);
+        let min = Expr::int(i64::MIN as i128);
+        assert!(matches!(eval(&Expr::add(max.clone(), Expr::int(1))), Err(EvalError::IntegerOverflow(_))));
+        assert!(matches!(eval(&Expr::sub(min, Expr::int(1))), Err(EvalError::IntegerOverflow(_))));
+        assert!(matches!(eval(&Expr::mul(max, Expr::int(2))), Err(EvalError::IntegerOverflow(_))));
+        assert!(matches!(eval(&Expr::binop(BinOp::Pow, Expr::int(2), Expr::int(64))), Err(EvalError::IntegerOverflow(_))));
+    }
+
+    #[test]
+    fn test_elementwise_shape_mismatch_names_both_shapes() {
+        let err = eval(&Expr::add(ints(&[1, 2]), ints(&[1, 2, 3]))).unwrap_err();
+        assert!(matches!(&err, EvalError::IncompatibleShapes { left, right } if left == &[2] && right == &[3]));
+        assert_eq!(err.to_string(), "Shape mismatch: [2] and [3]");
+        let zipped = eval(&Expr::binop(BinOp::ZipWith, ints(&[1]), ints(&[1, 2])));
+        assert!(matches!(zipped, Err(EvalError::IncompatibleShapes { .. })));
+    }
+
+    #[test]
+    fn test_tensor_overflow_is_an_error() {
+        let max = i64::MAX as i128;
+        let overflows = |expr: Expr| matches!(eval(&expr), Err(EvalError::IntegerOverflow(_)));
+        assert!(overflows(Expr::add(ints(&[1, max]), Expr::int(1))));
+        assert!(overflows(Expr::mul(Expr::int(2), ints(&[1, max]))));
+        assert!(overflows(Expr::add(ints(&[1, max]), ints(&[1, 1]))));
+        assert!(overflows(Expr::sub(ints(&[0, -max]), ints(&[0, 2]))));
+        assert!(overflows(Expr::app(Expr::name("sum"), ints(&[max, 1]))));
+        assert!(overflows(Expr::UnaryOp(UnaryOp::Scan, Box::new(ints(&[max, 1])))));
+        assert!(overflows(Expr::app(Expr::name("neg"), Expr::int(i64::MIN as i128))));
+        assert_eq!(eval(&Expr::app(Expr::name("sum"), ints(&[max, -1, 1]))).unwrap(), Value::Int(max));
+    }
+
+    #[test]
+    fn test_arithmetic_within_range_is_unaffected() {
+        assert_eq!(eval(&Expr::add(Expr::int(i64::MAX as i128 - 1), Expr::int(1))).unwrap(), Value::Int(i64::MAX as i128));
+        assert_eq!(eval(&Expr::mul(Expr::int(-4), Expr::int(25))).unwrap(), Value::Int(-100));
+        assert_eq!(eval(&Expr::binop(BinOp::Pow, Expr::int(2), Expr::int(62))).unwrap(), Value::Int(1 << 62));
+        assert!(matches!(eval(&Expr::div(Expr::int(i64::MAX as i128), Expr::int(0))), Err(EvalError::DivisionByZero)));
+        // Floats keep saturating to infinity
+        assert_eq!(eval(&Expr::mul(Expr::float(f64::MAX), Expr::float(2.0))).unwrap(), Value::float(f64::INFINITY));
+    }
+
+    // ============ Exponentiation Tests ============
+
+    fn power(base: Expr, exponent: Expr) -> EvalResult<Value> { eval(&Expr::binop(BinOp::Pow, base, exponent)) }
+
+    #[test]
+    fn test_integer_power() {
+        assert_eq!(power(Expr::int(2), Expr::int(10)).unwrap(), Value::Int(1024));
+        assert_eq!(power(Expr::int(-3), Expr::int(3)).unwrap(), Value::Int(-27));
+        assert_eq!(power(Expr::int(7), Expr::int(0)).unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn test_float_and_mixed_power() {
+        assert_eq!(power(Expr::float(2.0), Expr::float(0.5)).unwrap(), Value::float(2f64.sqrt()));
+        assert_eq!(power(Expr::int(4), Expr::float(0.5)).unwrap(), Value::float(2.0));
+        assert_eq!(power(Expr::float(2.0), Expr::int(-1)).unwrap(), Value::float(0.5));
+    }
+
+    #[test]
+    fn test_negative_integer_exponent_is_an_error() {
+        assert!(matches!(power(Expr::int(2), Expr::int(-1)), Err(EvalError::TypeErrorMsg(_))));
+        assert!(matches!(power(ints(&[2, 3]), Expr::int(-1)), Err(EvalError::TypeErrorMsg(_))));
+        assert!(matches!(power(Expr::int(2), ints(&[1, -1])), Err(EvalError::TypeErrorMsg(_))));
+        assert!(matches!(power(ints(&[2, 2]), ints(&[1, 64])), Err(EvalError::IntegerOverflow(_))));
+    }
+
+    #[test]
+    fn test_power_broadcasts_over_tensors() {
+        let squares = power(Expr::array(vec![Expr::int(1), Expr::int(2), Expr::int(3)]), Expr::int(2)).unwrap();
+        assert!(squares.deep_eq(&Value::Tensor(Tensor::from_ints(vec![1, 4, 9]))), "{:?}", squares);
+        let powers = power(Expr::int(2), Expr::array(vec![Expr::int(0), Expr::int(1), Expr::int(2)])).unwrap();
+        assert!(powers.deep_eq(&Value::Tensor(Tensor::from_ints(vec![1, 2, 4]))), "{:?}", powers);
+    }
+
+    // ============ Concatenation Tests ============
+
+    fn matrix(shape: [usize; 2], data: &[i128]) -> Value { Value::Tensor(Tensor { shape: shape.to_vec(), data: TensorData::Int(data.to_vec()) }) }
+    fn concat_axis(a: Value, b: Value, axis: i128) -> EvalResult<Value> { let mut e = Evaluator::new(); e.define("a", a); e.define("b", b); e.eval(&Expr::app_n(Expr::name("concat_axis"), vec![Expr::name("a"), Expr::name("b"), Expr::int(axis)])) }
+
+    #[test]
+    fn test_concat_vectors() {
+        let joined = eval(&Expr::app_n(Expr::name("concat"), vec![ints(&[1, 2]), ints(&[3, 4])])).unwrap();
+        assert!(joined.deep_eq(&Value::Tensor(Tensor::from_ints(vec![1, 2, 3, 4]))), "{:?}", joined);
+        let empty = eval(&Expr::app_n(Expr::name("concat"), vec![Expr::array(vec![]), ints(&[5])])).unwrap();
+        assert!(empty.deep_eq(&Value::Tensor(Tensor::from_ints(vec![5]))), "{:?}", empty);
+    }
+
+    #[test]
+    fn test_concat_promotes_ints_to_floats() {
+        let joined = eval(&Expr::app_n(Expr::name("concat"), vec![ints(&[1]), Expr::array(vec![Expr::float(2.5)])])).unwrap();
+        assert!(joined.deep_eq(&Value::Tensor(Tensor::from_floats(vec![1.0, 2.5]))), "{:?}", joined);
+    }
+
+    #[test]
+    fn test_concat_incompatible_elements() {
+        let bools = Expr::array(vec![Expr::bool(true)]);
+        assert!(matches!(eval(&Expr::app_n(Expr::name("concat"), vec![ints(&[1]), bools])), Err(EvalError::TypeError { expected: "Int", got: "Bool" })));
+    }
+
+    #[test]
+    fn test_concat_axis_rows_and_columns() {
+        let a = || matrix([2, 2], &[1, 2, 3, 4]);
+        assert_eq!(concat_axis(a(), matrix([1, 2], &[5, 6]), 0).unwrap(), matrix([3, 2], &[1, 2, 3, 4, 5, 6]));
+        assert_eq!(concat_axis(a(), matrix([2, 1], &[7, 8]), 1).unwrap(), matrix([2, 3], &[1, 2, 7, 3, 4, 8]));
+    }
+
+    #[test]
+    fn test_concat_axis_shape_mismatch() {
+        assert!(matches!(concat_axis(matrix([1, 2], &[1, 2]), matrix([2, 1], &[3, 4]), 0), Err(EvalError::ShapeMismatch(_))));
+        assert!(matches!(concat_axis(matrix([1, 2], &[1, 2]), matrix([1, 2], &[3, 4]), 2), Err(EvalError::ShapeMismatch(_))));
+        assert!(matches!(concat_axis(Value::Tensor(Tensor::from_ints(vec![1])), matrix([1, 2], &[3, 4]), 0), Err(EvalError::ShapeMismatch(_))));
+    }
+
+    // ============ Constant Tensor Tests ============
+
+    #[test]
+    fn test_fill_repeats_value() {
+        let filled = eval(&Expr::app_n(Expr::name("fill"), vec![Expr::int(3), Expr::float(0.5)])).unwrap();
+        assert_eq!(filled, Value::Tensor(Tensor::from_floats(vec![0.5, 0.5, 0.5])));
+        let empty = eval(&Expr::app_n(Expr::name("replicate"), vec![Expr::int(0), Expr::int(7)])).unwrap();
+        assert_eq!(empty, Value::Tensor(Tensor::from_ints(vec![])));
+    }
+
+    #[test]
+    fn test_zeros_and_ones_shapes() {
+        assert_eq!(eval(&Expr::app(Expr::name("zeros"), ints(&[2, 3]))).unwrap(), matrix([2, 3], &[0; 6]));
+        assert_eq!(eval(&Expr::app(Expr::name("ones"), ints(&[4]))).unwrap(), Value::Tensor(Tensor::from_ints(vec![1; 4])));
+    }
+
+    #[test]
+    fn test_constant_tensors_reject_negative_sizes() {
+        assert!(matches!(eval(&Expr::app_n(Expr::name("fill"), vec![Expr::int(-1), Expr::int(0)])), Err(EvalError::TypeErrorMsg(_))));
+        assert!(matches!(eval(&Expr::app(Expr::name("zeros"), ints(&[2, -3]))), Err(EvalError::TypeErrorMsg(_))));
+    }
+
+    #[test]
+    fn test_constant_tensors_respect_memory_limit() {
+        let mut e = Evaluator::new().with_memory_limit(1_000);
+        assert!(matches!(e.eval(&Expr::app_n(Expr::name("fill"), vec![Expr::int(1 << 40), Expr::int(0)])), Err(EvalError::AllocationLimit { .. })));
+        assert!(matches!(e.eval(&Expr::app(Expr::name("ones"), ints(&[1 << 40, 1 << 40]))), Err(EvalError::AllocationLimit { .. })));
+    }
 }
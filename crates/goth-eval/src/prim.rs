@@ -1,6 +1,6 @@
 //! Primitive operations for Goth
 
-use crate::value::{Value, Tensor, PrimFn};
+use crate::value::{Value, Tensor, TensorData, List, PrimFn, PrimContext, Memo};
 use crate::error::{EvalError, EvalResult};
 use ordered_float::OrderedFloat;
 
@@ -14,9 +14,9 @@ pub fn apply_binop(op: &goth_ast::op::BinOp, left: Value, right: Value) -> EvalR
         Add => add(left, right), Sub => sub(left, right), Mul => mul(left, right),
         Div => div(left, right), Pow => pow(left, right), Mod => modulo(left, right),
         PlusMinus => Ok(Value::Uncertain { value: Box::new(left), uncertainty: Box::new(right) }),
-        Eq => Ok(Value::Bool(left.deep_eq(&right))), Neq => Ok(Value::Bool(!left.deep_eq(&right))),
-        Lt => compare_lt(left, right), Gt => compare_gt(left, right),
-        Leq => compare_leq(left, right), Geq => compare_geq(left, right),
+        Eq => equal(left, right), Neq => not_equal(left, right),
+        Lt => elementwise_compare(left, right, compare_lt), Gt => elementwise_compare(left, right, compare_gt),
+        Leq => elementwise_compare(left, right, compare_leq), Geq => elementwise_compare(left, right, compare_geq),
         And => logical_and(left, right), Or => logical_or(left, right),
         Compose | Map | Filter | Bind => Err(EvalError::internal("should be handled by evaluator")),
         ZipWith => zip_with(left, right), Concat => concat(left, right),
@@ -24,91 +24,177 @@ pub fn apply_binop(op: &goth_ast::op::BinOp, left: Value, right: Value) -> EvalR
     }
 }
 
+/// Fast path for arithmetic and comparison on two scalars of the same numeric type.
+/// Returns `None` for anything else (including division, which checks for zero,
+/// and integer overflow), leaving it to [`apply_binop`]. Results are identical
+/// to the general path.
+#[inline]
+pub fn scalar_binop(op: &goth_ast::op::BinOp, left: &Value, right: &Value) -> Option<Value> {
+    use goth_ast::op::BinOp::*;
+    Some(match (left, right) {
+        (Value::Int(a), Value::Int(b)) => match op {
+            Add => Value::Int(in_i64(a.checked_add(*b))?), Sub => Value::Int(in_i64(a.checked_sub(*b))?), Mul => Value::Int(in_i64(a.checked_mul(*b))?),
+            Lt => Value::Bool(a < b), Gt => Value::Bool(a > b), Leq => Value::Bool(a <= b), Geq => Value::Bool(a >= b),
+            Eq => Value::Bool(a == b), Neq => Value::Bool(a != b),
+            _ => return None,
+        },
+        (Value::Float(a), Value::Float(b)) => match op {
+            Add => Value::Float(OrderedFloat(a.0 + b.0)), Sub => Value::Float(OrderedFloat(a.0 - b.0)), Mul => Value::Float(OrderedFloat(a.0 * b.0)),
+            Lt => Value::Bool(a < b), Gt => Value::Bool(a > b), Leq => Value::Bool(a <= b), Geq => Value::Bool(a >= b),
+            Eq => Value::Bool(a == b), Neq => Value::Bool(a != b),
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// `n` if it is present and within the `I64` range
+fn in_i64(n: Option<i128>) -> Option<i128> {
+    n.filter(|n| i64::try_from(*n).is_ok())
+}
+
+/// The integer result of `a op b`, or `IntegerOverflow` when it does not fit in `I64`
+fn checked_int(result: Option<i128>, a: i128, op: &str, b: i128) -> EvalResult<Value> {
+    in_i64(result).map(Value::Int).ok_or_else(|| EvalError::IntegerOverflow(format!("{} {} {}", a, op, b)))
+}
+
 pub fn apply_unaryop(op: &goth_ast::op::UnaryOp, value: Value) -> EvalResult<Value> {
     use goth_ast::op::UnaryOp::*;
-    match op {
-        Neg => negate(value),
-        Not => logical_not(value),
-        Sum => sum(value),
-        Prod => product(value),
-        Scan => scan(value),
-        Sqrt => sqrt(value),
-        Floor => floor(value),
-        Ceil => ceil(value),
-        Round => round(value),
-        Gamma => gamma(value),
-        Ln => ln(value),
-        Log10 => log10(value),
-        Log2 => log2(value),
-        Exp => exp(value),
-        Sin => sin(value),
-        Cos => cos(value),
-        Tan => tan(value),
-        Asin => asin(value),
-        Acos => acos(value),
-        Atan => atan(value),
-        Sinh => sinh(value),
-        Cosh => cosh(value),
-        Tanh => tanh(value),
-        Abs => abs(value),
-        Sign => sign(value),
+    let f: fn(Value) -> EvalResult<Value> = match op {
+        Not => return logical_not(value),
+        Sum => return sum(value),
+        Prod => return product(value),
+        Scan => return scan(value),
+        Neg => negate,
+        Sqrt => sqrt,
+        Floor => floor,
+        Ceil => ceil,
+        Round => round,
+        Gamma => gamma,
+        Ln => ln,
+        Log10 => log10,
+        Log2 => log2,
+        Exp => exp,
+        Sin => sin,
+        Cos => cos,
+        Tan => tan,
+        Asin => asin,
+        Acos => acos,
+        Atan => atan,
+        Sinh => sinh,
+        Cosh => cosh,
+        Tanh => tanh,
+        Abs => abs,
+        Sign => sign,
+    };
+    map_prim(value, f)
+}
+
+/// Scalar implementation of a unary numeric primitive, which `apply_prim` lifts over tensors
+fn elementwise_fn(prim: &PrimFn) -> Option<fn(Value) -> EvalResult<Value>> {
+    Some(match prim {
+        PrimFn::Neg => negate, PrimFn::Abs => abs, PrimFn::Sign => sign,
+        PrimFn::Exp => exp, PrimFn::Ln => ln, PrimFn::Sqrt => sqrt,
+        PrimFn::Sin => sin, PrimFn::Cos => cos, PrimFn::Tan => tan,
+        PrimFn::Floor => floor, PrimFn::Ceil => ceil, PrimFn::Round => round,
+        _ => return None,
+    })
+}
+
+/// `f` applied to corresponding elements of two tensors of the same shape; the first error is returned
+fn zip_prim(a: &Tensor, b: &Tensor, f: fn(Value, Value) -> EvalResult<Value>) -> EvalResult<Value> {
+    if a.shape != b.shape { return Err(EvalError::incompatible_shapes(&a.shape, &b.shape)); }
+    let data = a.iter().zip(b.iter()).map(|(x, y)| f(x, y)).collect::<EvalResult<Vec<_>>>()?;
+    Ok(Value::Tensor(Tensor::pack(a.shape.clone(), data)))
+}
+
+/// `f` applied to each element of `t` and `scalar`, with the scalar on the left when `scalar_first`
+fn map_scalar(t: &Tensor, scalar: &Value, scalar_first: bool, f: fn(Value, Value) -> EvalResult<Value>) -> EvalResult<Value> {
+    let data = t.iter().map(|x| if scalar_first { f(scalar.clone(), x) } else { f(x, scalar.clone()) }).collect::<EvalResult<Vec<_>>>()?;
+    Ok(Value::Tensor(Tensor::pack(t.shape.clone(), data)))
+}
+
+/// Apply a scalar function to a value, or to every element when given a tensor
+fn map_prim(value: Value, f: fn(Value) -> EvalResult<Value>) -> EvalResult<Value> {
+    match value {
+        Value::Tensor(t) => { let data = t.iter().map(f).collect::<EvalResult<Vec<_>>>()?; Ok(Value::Tensor(Tensor::pack(t.shape.clone(), data))) }
+        other => f(other),
+    }
+}
+
+/// Most tensor elements `prim` will allocate when applied to `args`, for the
+/// primitives whose result size follows from their arguments. Used to enforce
+/// the evaluator's memory limit before allocating.
+pub(crate) fn result_elements(prim: &PrimFn, args: &[Value]) -> Option<usize> {
+    let len = |v: &Value| match v { Value::Tensor(t) => Some(t.len()), _ => None };
+    let count = |n: i128| usize::try_from(n).unwrap_or(0);
+    match (prim, args) {
+        (PrimFn::Iota, [Value::Int(n)]) => Some(count(*n)),
+        (PrimFn::Range, [Value::Int(start), Value::Int(end)]) => Some(count(end.saturating_sub(*start))),
+        (PrimFn::RangeStep, [Value::Int(start), Value::Int(end), Value::Int(step)]) if *step != 0 => Some(count(step_count(*start, *end, *step))),
+        (PrimFn::Fill, [Value::Int(n), _]) => Some(count(*n)),
+        (PrimFn::Zeros | PrimFn::Ones, [Value::Tensor(t)]) => t.iter().try_fold(1usize, |acc, d| Some(acc.saturating_mul(count(d.as_int()?)))),
+        (PrimFn::Outer, [a, b]) => len(a)?.checked_mul(len(b)?).or(Some(usize::MAX)),
+        (PrimFn::MatMul, [Value::Tensor(a), Value::Tensor(b)]) if a.rank() == 2 && b.rank() == 2 => a.shape[0].checked_mul(b.shape[1]).or(Some(usize::MAX)),
+        (PrimFn::Concat, [a, b]) | (PrimFn::ConcatAxis, [a, b, _]) => Some(len(a)? + len(b)?),
+        (PrimFn::Cons, [_, b]) => Some(len(b)? + 1),
+        // Reshaping keeps the argument's storage and chars returns its argument, so neither builds anything
+        (PrimFn::Reshape | PrimFn::Chars, _) => Some(0),
+        // Elementwise, reordering and shrinking primitives build no more than their largest argument
+        (PrimFn::Add | PrimFn::Sub | PrimFn::Mul | PrimFn::Div | PrimFn::Mod | PrimFn::Pow | PrimFn::Neg | PrimFn::Abs | PrimFn::Sign
+            | PrimFn::Eq | PrimFn::Neq | PrimFn::Lt | PrimFn::Gt | PrimFn::Leq | PrimFn::Geq | PrimFn::And | PrimFn::Or | PrimFn::Not
+            | PrimFn::Exp | PrimFn::Ln | PrimFn::Sqrt | PrimFn::Sin | PrimFn::Cos | PrimFn::Tan | PrimFn::Floor | PrimFn::Ceil | PrimFn::Round
+            | PrimFn::ToInt | PrimFn::ToFloat | PrimFn::ToBool | PrimFn::ToChar | PrimFn::Min | PrimFn::Max | PrimFn::Clamp
+            | PrimFn::Normalize | PrimFn::Softmax | PrimFn::Zip | PrimFn::Reverse | PrimFn::Sort | PrimFn::Transpose | PrimFn::TransposeAxes
+            | PrimFn::Take | PrimFn::Drop | PrimFn::Slice | PrimFn::Tail | PrimFn::SumAxis | PrimFn::MeanAxis | PrimFn::MaxAxis, _) => {
+            Some(args.iter().filter_map(len).max().unwrap_or(0))
+        }
+        _ => None,
     }
 }
 
+/// Most tensor elements `left op right` builds: the larger operand, or both when concatenating
+pub(crate) fn binop_elements(op: &goth_ast::op::BinOp, left: &Value, right: &Value) -> usize {
+    let len = |v: &Value| match v { Value::Tensor(t) => t.len(), _ => 0 };
+    match op { goth_ast::op::BinOp::Concat => len(left) + len(right), _ => len(left).max(len(right)) }
+}
+
 pub fn apply_prim(prim: PrimFn, args: Vec<Value>) -> EvalResult<Value> {
+    if let Some(f) = elementwise_fn(&prim) { return unary_args(&args, |v| map_prim(v, f)); }
     match prim {
         PrimFn::Add => binary_args(&args, add), PrimFn::Sub => binary_args(&args, sub),
         PrimFn::Mul => binary_args(&args, mul), PrimFn::Div => binary_args(&args, div),
-        PrimFn::Mod => binary_args(&args, modulo), PrimFn::Neg => unary_args(&args, negate),
-        PrimFn::Abs => unary_args(&args, abs),
-        PrimFn::Exp => unary_args(&args, exp), PrimFn::Ln => unary_args(&args, ln),
-        PrimFn::Sqrt => unary_args(&args, sqrt), PrimFn::Sin => unary_args(&args, sin),
-        PrimFn::Cos => unary_args(&args, cos), PrimFn::Tan => unary_args(&args, tan),
-        PrimFn::Pow => binary_args(&args, pow), PrimFn::Floor => unary_args(&args, floor),
-        PrimFn::Ceil => unary_args(&args, ceil), PrimFn::Round => unary_args(&args, round),
-        PrimFn::Eq => binary_args(&args, |a, b| Ok(Value::Bool(a.deep_eq(&b)))),
-        PrimFn::Neq => binary_args(&args, |a, b| Ok(Value::Bool(!a.deep_eq(&b)))),
-        PrimFn::Lt => binary_args(&args, compare_lt), PrimFn::Gt => binary_args(&args, compare_gt),
-        PrimFn::Leq => binary_args(&args, compare_leq), PrimFn::Geq => binary_args(&args, compare_geq),
+        PrimFn::Mod => binary_args(&args, modulo),
+        PrimFn::Pow => binary_args(&args, pow),
+        PrimFn::Eq => binary_args(&args, equal), PrimFn::Neq => binary_args(&args, not_equal),
+        PrimFn::Lt => binary_args(&args, |a, b| elementwise_compare(a, b, compare_lt)), PrimFn::Gt => binary_args(&args, |a, b| elementwise_compare(a, b, compare_gt)),
+        PrimFn::Leq => binary_args(&args, |a, b| elementwise_compare(a, b, compare_leq)), PrimFn::Geq => binary_args(&args, |a, b| elementwise_compare(a, b, compare_geq)),
         PrimFn::And => binary_args(&args, logical_and), PrimFn::Or => binary_args(&args, logical_or),
         PrimFn::Not => unary_args(&args, logical_not),
         PrimFn::Sum => unary_args(&args, sum), PrimFn::Prod => unary_args(&args, product),
         PrimFn::Len => unary_args(&args, len), PrimFn::Shape => unary_args(&args, shape),
         PrimFn::Reverse => unary_args(&args, reverse), PrimFn::Concat => binary_args(&args, concat),
+        PrimFn::Sort => unary_args(&args, sort),
         PrimFn::Dot => binary_args(&args, dot), PrimFn::Norm => unary_args(&args, norm),
-        PrimFn::MatMul => binary_args(&args, matmul), PrimFn::Transpose => unary_args(&args, transpose),
+        PrimFn::MatMul => binary_args(&args, matmul), PrimFn::Outer => binary_args(&args, outer), PrimFn::Transpose => unary_args(&args, transpose),
+        PrimFn::TransposeAxes => binary_args(&args, transpose_axes),
+        PrimFn::Reshape => binary_args(&args, reshape),
         PrimFn::ToInt => unary_args(&args, to_int), PrimFn::ToFloat => unary_args(&args, to_float),
         PrimFn::ToBool => unary_args(&args, to_bool), PrimFn::ToChar => unary_args(&args, to_char),
+        PrimFn::Ord => unary_args(&args, ord), PrimFn::Chr => unary_args(&args, chr),
         PrimFn::ParseInt => unary_args(&args, parse_int), PrimFn::ParseFloat => unary_args(&args, parse_float),
         PrimFn::Print => {
-            for arg in &args {
-                // Print strings without quotes for raw output
-                if let Value::Tensor(t) = arg {
-                    if let Some(s) = t.to_string_value() {
-                        print!("{}", s);
-                        continue;
-                    }
-                }
-                print!("{}", arg);
-            }
-            println!();
-            Ok(Value::Unit)
+            println!("{}", printed(&args));
+            Ok(args.into_iter().next().unwrap_or(Value::Unit))
         }
         PrimFn::Write => {
             use std::io::Write;
-            for arg in &args {
-                // Write strings without quotes for raw output (no trailing newline)
-                if let Value::Tensor(t) = arg {
-                    if let Some(s) = t.to_string_value() {
-                        print!("{}", s);
-                        continue;
-                    }
-                }
-                print!("{}", arg);
-            }
+            print!("{}", printed(&args));
             std::io::stdout().flush().map_err(|e| EvalError::IoError(e.to_string()))?;
             Ok(Value::Unit)
         }
+        PrimFn::Trace => binary_args(&args, |_, value| Ok(value)),
+        PrimFn::Memoize => unary_args(&args, memoize),
         PrimFn::Flush => {
             use std::io::Write;
             std::io::stdout().flush().map_err(|e| EvalError::IoError(e.to_string()))?;
@@ -220,9 +306,18 @@ pub fn apply_prim(prim: PrimFn, args: Vec<Value>) -> EvalResult<Value> {
         }
         PrimFn::Iota => unary_args(&args, iota),
         PrimFn::Range => binary_args(&args, range),
+        PrimFn::RangeStep => match args.as_slice() {
+            [start, end, step] => range_step(start, end, step),
+            _ => Err(EvalError::ArityMismatch { expected: 3, got: args.len() }),
+        },
+        PrimFn::Fill => binary_args(&args, fill),
+        PrimFn::Native(native) => (native.f)(&PrimContext { name: native.name.clone() }, &args),
+        PrimFn::Zeros => unary_args(&args, |shape| constant_tensor("zeros", shape, 0)),
+        PrimFn::Ones => unary_args(&args, |shape| constant_tensor("ones", shape, 1)),
         PrimFn::ToString => unary_args(&args, to_string),
         PrimFn::Chars => unary_args(&args, chars),
         PrimFn::StrConcat => binary_args(&args, str_concat),
+        PrimFn::StrLen => unary_args(&args, str_len),
         PrimFn::Take => binary_args(&args, take),
         PrimFn::Drop => binary_args(&args, drop_fn),
         PrimFn::Index => binary_args(&args, index),
@@ -233,10 +328,42 @@ pub fn apply_prim(prim: PrimFn, args: Vec<Value>) -> EvalResult<Value> {
         PrimFn::StartsWith => binary_args(&args, starts_with),
         PrimFn::EndsWith => binary_args(&args, ends_with),
         PrimFn::Contains => binary_args(&args, str_contains),
+        PrimFn::SumAxis => binary_args(&args, sum_axis),
+        PrimFn::MeanAxis => binary_args(&args, mean_axis),
+        PrimFn::MaxAxis => binary_args(&args, max_axis),
+        PrimFn::Mean => unary_args(&args, mean),
+        PrimFn::Variance => unary_args(&args, variance),
+        PrimFn::VarianceSample => unary_args(&args, variance_sample),
+        PrimFn::Std => unary_args(&args, std_dev),
+        PrimFn::ArgMin => unary_args(&args, |v| arg_extreme("argmin", v, compare_lt)),
+        PrimFn::ArgMax => unary_args(&args, |v| arg_extreme("argmax", v, compare_gt)),
+        PrimFn::Normalize => unary_args(&args, normalize),
+        PrimFn::Softmax => unary_args(&args, softmax),
+        PrimFn::Min => extreme_of("min", args, compare_lt),
+        PrimFn::Max => extreme_of("max", args, compare_gt),
+        PrimFn::ConcatAxis => match args.as_slice() {
+            [a, b, axis] => concat_axis(a, b, axis),
+            _ => Err(EvalError::ArityMismatch { expected: 3, got: args.len() }),
+        },
+        PrimFn::Clamp => match args.as_slice() {
+            [value, lo, hi] => clamp(value, lo, hi),
+            _ => Err(EvalError::ArityMismatch { expected: 3, got: args.len() }),
+        },
+        PrimFn::Cons => binary_args(&args, cons), PrimFn::Head => unary_args(&args, head),
+        PrimFn::Tail => unary_args(&args, tail), PrimFn::IsEmpty => unary_args(&args, is_empty),
+        PrimFn::ToList => unary_args(&args, to_list), PrimFn::ToTensor => unary_args(&args, to_tensor),
         _ => Err(EvalError::not_implemented(format!("primitive: {:?}", prim))),
     }
 }
 
+/// Text written by `print` and `write`: strings without quotes, anything else as displayed
+pub fn printed(args: &[Value]) -> String {
+    args.iter().map(|arg| match arg {
+        Value::Tensor(t) => t.to_string_value().unwrap_or_else(|| arg.to_string()),
+        _ => arg.to_string(),
+    }).collect()
+}
+
 fn unary_args<F>(args: &[Value], f: F) -> EvalResult<Value> where F: FnOnce(Value) -> EvalResult<Value> {
     if args.len() != 1 { return Err(EvalError::ArityMismatch { expected: 1, got: args.len() }); }
     f(args[0].clone())
@@ -249,49 +376,37 @@ fn binary_args<F>(args: &[Value], f: F) -> EvalResult<Value> where F: FnOnce(Val
 
 fn add(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
-        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
+        (Value::Int(a), Value::Int(b)) => checked_int(a.checked_add(*b), *a, "+", *b),
         (Value::Float(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(a.0 + b.0))),
         (Value::Int(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(*a as f64 + b.0))),
         (Value::Float(a), Value::Int(b)) => Ok(Value::Float(OrderedFloat(a.0 + *b as f64))),
-        (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.shape != b.shape { return Err(EvalError::shape_mismatch(format!("Cannot add tensors with shapes {:?} and {:?}", a.shape, b.shape))); }
-            let result = a.zip_with(b, |x, y| add(x, y).unwrap_or(Value::Error("add failed".into()))).ok_or_else(|| EvalError::shape_mismatch("zip failed"))?;
-            Ok(Value::Tensor(result))
-        }
-        (Value::Tensor(t), scalar) if scalar.is_numeric() => Ok(Value::Tensor(t.map(|x| add(x, scalar.clone()).unwrap_or(Value::Error("add failed".into()))))),
-        (scalar, Value::Tensor(t)) if scalar.is_numeric() => Ok(Value::Tensor(t.map(|x| add(scalar.clone(), x).unwrap_or(Value::Error("add failed".into()))))),
+        (Value::Tensor(a), Value::Tensor(b)) => zip_prim(a, b, add),
+        (Value::Tensor(t), scalar) if scalar.is_numeric() => map_scalar(t, scalar, false, add),
+        (scalar, Value::Tensor(t)) if scalar.is_numeric() => map_scalar(t, scalar, true, add),
         _ => Err(EvalError::type_error_msg(format!("Cannot add {} and {}", left.type_name(), right.type_name()))),
     }
 }
 
 fn sub(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
-        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a - b)),
+        (Value::Int(a), Value::Int(b)) => checked_int(a.checked_sub(*b), *a, "-", *b),
         (Value::Float(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(a.0 - b.0))),
         (Value::Int(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(*a as f64 - b.0))),
         (Value::Float(a), Value::Int(b)) => Ok(Value::Float(OrderedFloat(a.0 - *b as f64))),
-        (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.shape != b.shape { return Err(EvalError::shape_mismatch("Tensor shapes must match for subtraction")); }
-            let result = a.zip_with(b, |x, y| sub(x, y).unwrap_or(Value::Error("sub failed".into()))).ok_or_else(|| EvalError::shape_mismatch("zip failed"))?;
-            Ok(Value::Tensor(result))
-        }
+        (Value::Tensor(a), Value::Tensor(b)) => zip_prim(a, b, sub),
         _ => Err(EvalError::type_error_msg(format!("Cannot subtract {} and {}", left.type_name(), right.type_name()))),
     }
 }
 
 fn mul(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
-        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a * b)),
+        (Value::Int(a), Value::Int(b)) => checked_int(a.checked_mul(*b), *a, "×", *b),
         (Value::Float(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(a.0 * b.0))),
         (Value::Int(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(*a as f64 * b.0))),
         (Value::Float(a), Value::Int(b)) => Ok(Value::Float(OrderedFloat(a.0 * *b as f64))),
-        (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.shape != b.shape { return Err(EvalError::shape_mismatch("Tensor shapes must match for multiplication")); }
-            let result = a.zip_with(b, |x, y| mul(x, y).unwrap_or(Value::Error("mul failed".into()))).ok_or_else(|| EvalError::shape_mismatch("zip failed"))?;
-            Ok(Value::Tensor(result))
-        }
-        (Value::Tensor(t), scalar) if scalar.is_numeric() => Ok(Value::Tensor(t.map(|x| mul(x, scalar.clone()).unwrap_or(Value::Error("mul failed".into()))))),
-        (scalar, Value::Tensor(t)) if scalar.is_numeric() => Ok(Value::Tensor(t.map(|x| mul(scalar.clone(), x).unwrap_or(Value::Error("mul failed".into()))))),
+        (Value::Tensor(a), Value::Tensor(b)) => zip_prim(a, b, mul),
+        (Value::Tensor(t), scalar) if scalar.is_numeric() => map_scalar(t, scalar, false, mul),
+        (scalar, Value::Tensor(t)) if scalar.is_numeric() => map_scalar(t, scalar, true, mul),
         _ => Err(EvalError::type_error_msg(format!("Cannot multiply {} and {}", left.type_name(), right.type_name()))),
     }
 }
@@ -302,7 +417,7 @@ fn div(left: Value, right: Value) -> EvalResult<Value> {
         (Value::Float(a), Value::Float(b)) => if b.0 == 0.0 { Err(EvalError::DivisionByZero) } else { Ok(Value::Float(OrderedFloat(a.0 / b.0))) },
         (Value::Int(a), Value::Float(b)) => if b.0 == 0.0 { Err(EvalError::DivisionByZero) } else { Ok(Value::Float(OrderedFloat(*a as f64 / b.0))) },
         (Value::Float(a), Value::Int(b)) => if *b == 0 { Err(EvalError::DivisionByZero) } else { Ok(Value::Float(OrderedFloat(a.0 / *b as f64))) },
-        (Value::Tensor(t), scalar) if scalar.is_numeric() => Ok(Value::Tensor(t.map(|x| div(x, scalar.clone()).unwrap_or(Value::Error("div failed".into()))))),
+        (Value::Tensor(t), scalar) if scalar.is_numeric() => map_scalar(t, scalar, false, div),
         _ => Err(EvalError::type_error_msg(format!("Cannot divide {} by {}", left.type_name(), right.type_name()))),
     }
 }
@@ -317,49 +432,68 @@ fn modulo(left: Value, right: Value) -> EvalResult<Value> {
 
 fn pow(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
-        (Value::Int(a), Value::Int(b)) => if *b >= 0 { Ok(Value::Int(a.pow(*b as u32))) } else { Ok(Value::Float(OrderedFloat((*a as f64).powi(*b as i32)))) },
+        (Value::Int(a), Value::Int(b)) => match u32::try_from(*b) {
+            Ok(e) => checked_int(a.checked_pow(e), *a, "^", *b),
+            Err(_) if *b < 0 => Err(EvalError::type_error_msg(format!("Negative exponent in {} ^ {}: use a float base", a, b))),
+            Err(_) => Err(EvalError::IntegerOverflow(format!("{} ^ {}", a, b))),
+        },
         (Value::Float(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat(a.0.powf(b.0)))),
-        (Value::Float(a), Value::Int(b)) => Ok(Value::Float(OrderedFloat(a.0.powi(*b as i32)))),
+        (Value::Float(a), Value::Int(b)) => Ok(Value::Float(OrderedFloat(a.0.powf(*b as f64)))),
         (Value::Int(a), Value::Float(b)) => Ok(Value::Float(OrderedFloat((*a as f64).powf(b.0)))),
+        (Value::Tensor(a), Value::Tensor(b)) => zip_prim(a, b, pow),
+        (Value::Tensor(t), scalar) if scalar.is_numeric() => map_scalar(t, scalar, false, pow),
+        (scalar, Value::Tensor(t)) if scalar.is_numeric() => map_scalar(t, scalar, true, pow),
         _ => Err(EvalError::type_error_msg(format!("Cannot raise {} to power {}", left.type_name(), right.type_name()))),
     }
 }
 
 fn negate(value: Value) -> EvalResult<Value> {
     match value {
-        Value::Int(n) => Ok(Value::Int(-n)),
+        Value::Int(n) => in_i64(n.checked_neg()).map(Value::Int).ok_or_else(|| EvalError::IntegerOverflow(format!("-({})", n))),
         Value::Float(f) => Ok(Value::Float(OrderedFloat(-f.0))),
-        Value::Tensor(t) => Ok(Value::Tensor(t.map(|x| negate(x).unwrap_or(Value::Error("negate failed".into()))))),
+        Value::Tensor(_) => map_prim(value, negate),
         _ => Err(EvalError::type_error("numeric", &value)),
     }
 }
 
+/// Argument of a float-math primitive.
+///
+/// Numeric promotion rule for unary primitives: float math (`sqrt`, `exp`,
+/// `ln`, trig, `gamma`, ...) accepts `Int` by promoting it to `Float` and
+/// always returns `Float`; `neg`, `abs` and `sign` keep the type of their
+/// argument; `floor`, `ceil` and `round` always return `Int`.
+fn float_arg(value: &Value) -> EvalResult<f64> {
+    value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", value))
+}
+
 fn abs(value: Value) -> EvalResult<Value> {
-    match value { Value::Int(n) => Ok(Value::Int(n.abs())), Value::Float(f) => Ok(Value::Float(OrderedFloat(f.0.abs()))), _ => Err(EvalError::type_error("numeric", &value)) }
-}
-
-fn exp(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.exp()))) }
-fn ln(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; if f <= 0.0 { Err(EvalError::type_error_msg("ln requires positive argument")) } else { Ok(Value::Float(OrderedFloat(f.ln()))) } }
-fn log10(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; if f <= 0.0 { Err(EvalError::type_error_msg("log10 requires positive argument")) } else { Ok(Value::Float(OrderedFloat(f.log10()))) } }
-fn log2(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; if f <= 0.0 { Err(EvalError::type_error_msg("log2 requires positive argument")) } else { Ok(Value::Float(OrderedFloat(f.log2()))) } }
-fn sqrt(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; if f < 0.0 { Err(EvalError::type_error_msg("sqrt requires non-negative argument")) } else { Ok(Value::Float(OrderedFloat(f.sqrt()))) } }
-fn sin(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.sin()))) }
-fn cos(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.cos()))) }
-fn tan(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.tan()))) }
-fn asin(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; if f < -1.0 || f > 1.0 { Err(EvalError::type_error_msg("asin requires argument in [-1, 1]")) } else { Ok(Value::Float(OrderedFloat(f.asin()))) } }
-fn acos(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; if f < -1.0 || f > 1.0 { Err(EvalError::type_error_msg("acos requires argument in [-1, 1]")) } else { Ok(Value::Float(OrderedFloat(f.acos()))) } }
-fn atan(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.atan()))) }
-fn sinh(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.sinh()))) }
-fn cosh(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.cosh()))) }
-fn tanh(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(f.tanh()))) }
-fn floor(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Int(f.floor() as i128)) }
-fn ceil(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Int(f.ceil() as i128)) }
-fn round(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Int(f.round() as i128)) }
-fn sign(value: Value) -> EvalResult<Value> { let f = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?; Ok(Value::Float(OrderedFloat(if f > 0.0 { 1.0 } else if f < 0.0 { -1.0 } else { 0.0 }))) }
+    match value { Value::Int(n) => in_i64(n.checked_abs()).map(Value::Int).ok_or_else(|| EvalError::IntegerOverflow(format!("abs {}", n))), Value::Float(f) => Ok(Value::Float(OrderedFloat(f.0.abs()))), _ => Err(EvalError::type_error("numeric", &value)) }
+}
+
+fn exp(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.exp()))) }
+fn ln(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; if f <= 0.0 { Err(EvalError::type_error_msg("ln requires positive argument")) } else { Ok(Value::Float(OrderedFloat(f.ln()))) } }
+fn log10(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; if f <= 0.0 { Err(EvalError::type_error_msg("log10 requires positive argument")) } else { Ok(Value::Float(OrderedFloat(f.log10()))) } }
+fn log2(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; if f <= 0.0 { Err(EvalError::type_error_msg("log2 requires positive argument")) } else { Ok(Value::Float(OrderedFloat(f.log2()))) } }
+fn sqrt(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; if f < 0.0 { Err(EvalError::type_error_msg("sqrt requires non-negative argument")) } else { Ok(Value::Float(OrderedFloat(f.sqrt()))) } }
+fn sin(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.sin()))) }
+fn cos(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.cos()))) }
+fn tan(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.tan()))) }
+fn asin(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; if f < -1.0 || f > 1.0 { Err(EvalError::type_error_msg("asin requires argument in [-1, 1]")) } else { Ok(Value::Float(OrderedFloat(f.asin()))) } }
+fn acos(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; if f < -1.0 || f > 1.0 { Err(EvalError::type_error_msg("acos requires argument in [-1, 1]")) } else { Ok(Value::Float(OrderedFloat(f.acos()))) } }
+fn atan(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.atan()))) }
+fn sinh(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.sinh()))) }
+fn cosh(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.cosh()))) }
+fn tanh(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Float(OrderedFloat(f.tanh()))) }
+fn floor(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Int(f.floor() as i128)) }
+fn ceil(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Int(f.ceil() as i128)) }
+fn round(value: Value) -> EvalResult<Value> { let f = float_arg(&value)?; Ok(Value::Int(f.round() as i128)) }
+fn sign(value: Value) -> EvalResult<Value> {
+    match value { Value::Int(n) => Ok(Value::Int(n.signum())), Value::Float(f) => Ok(Value::Float(OrderedFloat(if f.0 > 0.0 { 1.0 } else if f.0 < 0.0 { -1.0 } else { 0.0 }))), _ => Err(EvalError::type_error("numeric", &value)) }
+}
 
 // Gamma function using Lanczos approximation
 fn gamma(value: Value) -> EvalResult<Value> {
-    let x = value.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &value))?;
+    let x = float_arg(&value)?;
     if x <= 0.0 && x.fract() == 0.0 {
         return Err(EvalError::type_error_msg("gamma undefined for non-positive integers"));
     }
@@ -415,6 +549,36 @@ fn compare_gt(left: Value, right: Value) -> EvalResult<Value> { compare_lt(right
 fn compare_leq(left: Value, right: Value) -> EvalResult<Value> { logical_not(compare_gt(left, right)?) }
 fn compare_geq(left: Value, right: Value) -> EvalResult<Value> { logical_not(compare_lt(left, right)?) }
 
+/// Apply the scalar comparison `compare` element-wise when either side is a tensor, giving a
+/// Bool tensor of the tensor's shape. Two tensors must have the same shape.
+fn elementwise_compare(left: Value, right: Value, compare: fn(Value, Value) -> EvalResult<Value>) -> EvalResult<Value> {
+    let (shape, data) = match (&left, &right) {
+        (Value::Tensor(a), Value::Tensor(b)) => {
+            if a.shape != b.shape { return Err(EvalError::incompatible_shapes(&a.shape, &b.shape)); }
+            (a.shape.clone(), a.iter().zip(b.iter()).map(|(x, y)| compare(x, y)).collect::<EvalResult<Vec<_>>>()?)
+        }
+        (Value::Tensor(t), scalar) => (t.shape.clone(), t.iter().map(|x| compare(x, scalar.clone())).collect::<EvalResult<Vec<_>>>()?),
+        (scalar, Value::Tensor(t)) => (t.shape.clone(), t.iter().map(|x| compare(scalar.clone(), x)).collect::<EvalResult<Vec<_>>>()?),
+        _ => return compare(left, right),
+    };
+    Ok(Value::Tensor(Tensor::pack(shape, data)))
+}
+
+/// Structural equality of two tensors as whole values; a tensor against a scalar compares element-wise
+fn equal(left: Value, right: Value) -> EvalResult<Value> {
+    match (&left, &right) {
+        (Value::Tensor(_), Value::Tensor(_)) => Ok(Value::Bool(left.deep_eq(&right))),
+        _ => elementwise_compare(left, right, |a, b| Ok(Value::Bool(a.deep_eq(&b)))),
+    }
+}
+
+fn not_equal(left: Value, right: Value) -> EvalResult<Value> {
+    match (&left, &right) {
+        (Value::Tensor(_), Value::Tensor(_)) => Ok(Value::Bool(!left.deep_eq(&right))),
+        _ => elementwise_compare(left, right, |a, b| Ok(Value::Bool(!a.deep_eq(&b)))),
+    }
+}
+
 fn logical_and(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) { (Value::Bool(a), Value::Bool(b)) => Ok(Value::Bool(*a && *b)), _ => Err(EvalError::type_error_msg(format!("Cannot AND {} and {}", left.type_name(), right.type_name()))) }
 }
@@ -427,6 +591,8 @@ fn logical_not(value: Value) -> EvalResult<Value> { match value { Value::Bool(b)
 
 fn sum(value: Value) -> EvalResult<Value> {
     match value {
+        // Int elements go through `add` so overflow is caught; other data sums directly
+        Value::Tensor(t) if matches!(t.data, TensorData::Int(_) | TensorData::Generic(_)) => t.iter().try_fold(Value::Int(0), add),
         Value::Tensor(t) => Ok(t.sum()),
         Value::Tuple(vs) => { let mut acc = Value::Int(0); for v in vs { acc = add(acc, v)?; } Ok(acc) }
         _ => Err(EvalError::type_error("Tensor or Tuple", &value)),
@@ -435,6 +601,7 @@ fn sum(value: Value) -> EvalResult<Value> {
 
 fn product(value: Value) -> EvalResult<Value> {
     match value {
+        Value::Tensor(t) if matches!(t.data, TensorData::Int(_) | TensorData::Generic(_)) => t.iter().try_fold(Value::Int(1), mul),
         Value::Tensor(t) => Ok(t.product()),
         Value::Tuple(vs) => { let mut acc = Value::Int(1); for v in vs { acc = mul(acc, v)?; } Ok(acc) }
         _ => Err(EvalError::type_error("Tensor or Tuple", &value)),
@@ -445,33 +612,112 @@ fn scan(value: Value) -> EvalResult<Value> {
     match value {
         Value::Tensor(t) => {
             let mut running = Value::Int(0);
-            let scanned: Vec<Value> = t.iter().map(|v| { running = add(running.clone(), v).unwrap_or(Value::Error("scan failed".into())); running.clone() }).collect();
+            let scanned = t.iter().map(|v| { running = add(running.clone(), v)?; Ok(running.clone()) }).collect::<EvalResult<Vec<_>>>()?;
             Ok(Value::Tensor(Tensor::from_values(t.shape.clone(), scanned)))
         }
         _ => Err(EvalError::type_error("Tensor", &value)),
     }
 }
 
-fn len(value: Value) -> EvalResult<Value> { match value { Value::Tensor(t) => Ok(Value::Int(t.len() as i128)), Value::Tuple(vs) => Ok(Value::Int(vs.len() as i128)), _ => Err(EvalError::type_error("Tensor or Tuple", &value)) } }
+fn len(value: Value) -> EvalResult<Value> { match value { Value::Tensor(t) => Ok(Value::Int(t.len() as i128)), Value::List(l) => Ok(Value::Int(l.len() as i128)), Value::Tuple(vs) => Ok(Value::Int(vs.len() as i128)), _ => Err(EvalError::type_error("Tensor or Tuple", &value)) } }
 fn shape(value: Value) -> EvalResult<Value> { match value { Value::Tensor(t) => Ok(Value::Tensor(Tensor::from_ints(t.shape.iter().map(|&d| d as i128).collect()))), _ => Err(EvalError::type_error("Tensor", &value)) } }
-fn reverse(value: Value) -> EvalResult<Value> { match value { Value::Tensor(t) => { let mut data = t.to_vec(); data.reverse(); Ok(Value::Tensor(Tensor::from_values(t.shape.clone(), data))) } Value::Tuple(mut vs) => { vs.reverse(); Ok(Value::Tuple(vs)) } _ => Err(EvalError::type_error("Tensor or Tuple", &value)) } }
+
+/// reverse xs: The tensor with its first axis reversed (the rows of a matrix, the elements of a vector), or the tuple backwards
+fn reverse(value: Value) -> EvalResult<Value> {
+    match value {
+        Value::Tensor(t) => {
+            // Each block of `row` flat elements is one entry along the first axis
+            let row: usize = t.shape.iter().skip(1).product();
+            if t.rank() == 0 || row == 0 { return Ok(Value::Tensor(t)); }
+            let data = match &t.data {
+                TensorData::Int(v) => TensorData::Int(rows_reversed(v, row)),
+                TensorData::Float(v) => TensorData::Float(rows_reversed(v, row)),
+                TensorData::Bool(v) => TensorData::Bool(rows_reversed(v, row)),
+                TensorData::Char(v) => TensorData::Char(rows_reversed(v, row)),
+                TensorData::Generic(v) => TensorData::Generic(rows_reversed(v, row)),
+            };
+            Ok(Value::Tensor(Tensor { shape: t.shape, data }))
+        }
+        Value::Tuple(mut vs) => { vs.reverse(); Ok(Value::Tuple(vs)) }
+        _ => Err(EvalError::type_error("Tensor or Tuple", &value)),
+    }
+}
+
+/// `data` with its blocks of `row` elements in reverse order
+fn rows_reversed<T: Clone>(data: &[T], row: usize) -> Vec<T> { data.chunks(row).rev().flatten().cloned().collect() }
+
+/// sort xs: The elements of a vector in ascending order. Numbers compare by value, ints
+/// and floats alike, and chars by code point; any other element is a type error.
+fn sort(value: Value) -> EvalResult<Value> {
+    let Value::Tensor(t) = &value else { return Err(EvalError::type_error("Tensor", &value)) };
+    if t.rank() != 1 { return Err(EvalError::shape_mismatch(format!("sort requires a rank-1 tensor, got shape {:?}", t.shape))); }
+    let mut elems = t.to_vec();
+    if let Some(bad) = elems.iter().find(|v| !matches!(v, Value::Int(_) | Value::Float(_) | Value::Char(_))) {
+        return Err(EvalError::type_error("Int, Float or Char", bad));
+    }
+    if let (Some(c), Some(n)) = (elems.iter().find(|v| v.as_char().is_some()), elems.iter().find(|v| v.is_numeric())) {
+        return Err(EvalError::TypeError { expected: c.type_name(), got: n.type_name() });
+    }
+    // Once any float is present every number compares as a float, so the order stays total
+    let floats = elems.iter().any(Value::is_float);
+    elems.sort_by(|a, b| match (a, b) {
+        (Value::Int(x), Value::Int(y)) if !floats => x.cmp(y),
+        (Value::Char(x), Value::Char(y)) => x.cmp(y),
+        _ => a.coerce_float().unwrap_or(0.0).total_cmp(&b.coerce_float().unwrap_or(0.0)),
+    });
+    Ok(Value::Tensor(Tensor::pack(t.shape.clone(), elems)))
+}
 
 fn concat(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
-        (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.rank() != 1 || b.rank() != 1 { return Err(EvalError::not_implemented("concat for rank > 1")); }
-            let mut data = a.to_vec(); data.extend(b.iter()); let new_len = a.len() + b.len();
-            Ok(Value::Tensor(Tensor::from_values(vec![new_len], data)))
-        }
+        (Value::Tensor(a), Value::Tensor(b)) => concat_along(a, b, 0),
         (Value::Tuple(a), Value::Tuple(b)) => { let mut result = a.clone(); result.extend(b.iter().cloned()); Ok(Value::Tuple(result)) }
         _ => Err(EvalError::type_error_msg(format!("Cannot concat {} and {}", left.type_name(), right.type_name()))),
     }
 }
 
+/// concat_axis a b axis: Join two tensors of equal rank along one axis
+fn concat_axis(a: &Value, b: &Value, axis: &Value) -> EvalResult<Value> {
+    match (a, b, axis) {
+        (Value::Tensor(a), Value::Tensor(b), Value::Int(axis)) => match usize::try_from(*axis) {
+            Ok(axis) => concat_along(a, b, axis),
+            Err(_) => Err(EvalError::shape_mismatch(format!("concat_axis: axis {} out of range for tensor of rank {}", axis, a.rank()))),
+        },
+        (Value::Tensor(_), Value::Tensor(_), other) => Err(EvalError::type_error("Int", other)),
+        (Value::Tensor(_), other, _) | (other, _, _) => Err(EvalError::type_error("Tensor", other)),
+    }
+}
+
+/// Join `a` and `b` along `axis`; all other dimensions must agree
+fn concat_along(a: &Tensor, b: &Tensor, axis: usize) -> EvalResult<Value> {
+    if axis >= a.rank() { return Err(EvalError::shape_mismatch(format!("concat: axis {} out of range for tensor of rank {}", axis, a.rank()))); }
+    let agree = a.rank() == b.rank() && a.shape.iter().zip(&b.shape).enumerate().all(|(k, (m, n))| k == axis || m == n);
+    if !agree { return Err(EvalError::shape_mismatch(format!("Cannot concat tensors with shapes {:?} and {:?} along axis {}", a.shape, b.shape, axis))); }
+    let (xs, ys) = common_elements(a.to_vec(), b.to_vec())?;
+    let outer: usize = a.shape[..axis].iter().product();
+    let (na, nb): (usize, usize) = (a.shape[axis..].iter().product(), b.shape[axis..].iter().product());
+    let mut data = Vec::with_capacity(xs.len() + ys.len());
+    for o in 0..outer { data.extend_from_slice(&xs[o * na..(o + 1) * na]); data.extend_from_slice(&ys[o * nb..(o + 1) * nb]); }
+    let mut shape = a.shape.clone(); shape[axis] += b.shape[axis];
+    Ok(Value::Tensor(Tensor::pack(shape, data)))
+}
+
+/// The elements of two tensors in a common kind: ints promote to floats
+/// when joined with floats, and any other mix is a type error
+fn common_elements(xs: Vec<Value>, ys: Vec<Value>) -> EvalResult<(Vec<Value>, Vec<Value>)> {
+    let kind = |v: &[Value]| v.first().map(Value::type_name);
+    let to_float = |v: Vec<Value>| v.into_iter().map(|x| x.coerce_float().map(Value::float).ok_or_else(|| EvalError::type_error("numeric", &x))).collect::<EvalResult<Vec<_>>>();
+    match (kind(&xs), kind(&ys)) {
+        (Some("Int"), Some("Float")) | (Some("Float"), Some("Int")) => Ok((to_float(xs)?, to_float(ys)?)),
+        (Some(a), Some(b)) if a != b => Err(EvalError::TypeError { expected: a, got: b }),
+        _ => Ok((xs, ys)),
+    }
+}
+
 fn zip_with(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
         (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.shape != b.shape { return Err(EvalError::shape_mismatch(format!("Cannot zip tensors with shapes {:?} and {:?}", a.shape, b.shape))); }
+            if a.shape != b.shape { return Err(EvalError::incompatible_shapes(&a.shape, &b.shape)); }
             let zipped: Vec<Value> = a.iter().zip(b.iter()).map(|(x, y)| Value::Tuple(vec![x, y])).collect();
             Ok(Value::Tensor(Tensor::from_values(a.shape.clone(), zipped)))
         }
@@ -482,14 +728,42 @@ fn zip_with(left: Value, right: Value) -> EvalResult<Value> {
 fn dot(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
         (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.shape != b.shape || a.rank() != 1 { return Err(EvalError::shape_mismatch("Dot product requires vectors of same length")); }
-            let zipped = a.zip_with(b, |x, y| mul(x, y).unwrap_or(Value::Error("mul failed".into()))).ok_or_else(|| EvalError::shape_mismatch("zip failed"))?;
-            Ok(zipped.sum())
+            if a.rank() != 1 || b.rank() != 1 { return Err(EvalError::shape_mismatch(format!("Dot product requires rank-1 tensors, got shapes {:?} and {:?}", a.shape, b.shape))); }
+            if a.shape != b.shape { return Err(EvalError::incompatible_shapes(&a.shape, &b.shape)); }
+            match (&a.data, &b.data) {
+                (TensorData::Int(xs), TensorData::Int(ys)) => {
+                    // Products of I64 values fit in i128, so only the final sum can overflow
+                    let total = xs.iter().zip(ys).try_fold(0i128, |acc, (x, y)| acc.checked_add(x.checked_mul(*y)?));
+                    in_i64(total).map(Value::Int).ok_or_else(|| EvalError::IntegerOverflow("dot product".into()))
+                }
+                (TensorData::Int(_) | TensorData::Float(_), TensorData::Int(_) | TensorData::Float(_)) => {
+                    let products = a.iter().zip(b.iter()).map(|(x, y)| x.coerce_float().unwrap_or(0.0) * y.coerce_float().unwrap_or(0.0));
+                    Ok(Value::float(compensated_sum(products)))
+                }
+                _ => a.iter().zip(b.iter()).try_fold(Value::Int(0), |acc, (x, y)| add(acc, mul(x, y)?)),
+            }
         }
+        (Value::Tensor(_), _) => Err(EvalError::type_error("Tensor", &right)),
         _ => Err(EvalError::type_error("Tensor", &left)),
     }
 }
 
+/// memoize f: `f`, caching each result by argument
+fn memoize(func: Value) -> EvalResult<Value> {
+    if func.is_callable() { Ok(Value::Memo(Memo::new(func))) } else { Err(EvalError::type_error("function", &func)) }
+}
+
+/// Neumaier's compensated sum, which keeps the low-order bits lost when adding terms of very different magnitude
+fn compensated_sum(xs: impl Iterator<Item = f64>) -> f64 {
+    let (mut sum, mut compensation) = (0.0f64, 0.0f64);
+    for x in xs {
+        let t = sum + x;
+        compensation += if sum.abs() >= x.abs() { (sum - t) + x } else { (x - t) + sum };
+        sum = t;
+    }
+    sum + compensation
+}
+
 fn norm(value: Value) -> EvalResult<Value> {
     match &value {
         Value::Tensor(t) => { let sum_sq = t.map(|x| { let f = x.coerce_float().unwrap_or(0.0); Value::Float(OrderedFloat(f * f)) }).sum(); sqrt(sum_sq) }
@@ -497,33 +771,101 @@ fn norm(value: Value) -> EvalResult<Value> {
     }
 }
 
+/// outer a b: Matrix of products a[i] * b[j]
+fn outer(left: Value, right: Value) -> EvalResult<Value> {
+    match (&left, &right) {
+        (Value::Tensor(a), Value::Tensor(b)) => {
+            if a.rank() != 1 || b.rank() != 1 { return Err(EvalError::shape_mismatch(format!("Outer product requires rank-1 tensors, got shapes {:?} and {:?}", a.shape, b.shape))); }
+            let data = a.iter().flat_map(|x| b.iter().map(move |y| mul(x.clone(), y))).collect::<EvalResult<Vec<_>>>()?;
+            Ok(Value::Tensor(Tensor::pack(vec![a.len(), b.len()], data)))
+        }
+        (Value::Tensor(_), _) => Err(EvalError::type_error("Tensor", &right)),
+        _ => Err(EvalError::type_error("Tensor", &left)),
+    }
+}
+
+/// matmul a b: [m k] × [k n] → [m n]. Integer matrices give integers; any float element makes every element a float
 fn matmul(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
         (Value::Tensor(a), Value::Tensor(b)) => {
-            if a.rank() != 2 || b.rank() != 2 { return Err(EvalError::shape_mismatch("Matrix multiplication requires 2D tensors")); }
+            if a.rank() != 2 || b.rank() != 2 { return Err(EvalError::shape_mismatch(format!("Matrix multiplication requires 2D tensors, got shapes {:?} and {:?}", a.shape, b.shape))); }
             let m = a.shape[0]; let n = a.shape[1]; let p = b.shape[1];
             if n != b.shape[0] { return Err(EvalError::shape_mismatch(format!("Matrix dimensions incompatible: [{} {}] × [{} {}]", m, n, b.shape[0], p))); }
-            let mut result = vec![Value::Float(OrderedFloat(0.0)); m * p];
-            for i in 0..m { for j in 0..p { let mut sum = 0.0f64; for k in 0..n { let a_ik = a.get(&[i, k]).and_then(|v| v.coerce_float()).unwrap_or(0.0); let b_kj = b.get(&[k, j]).and_then(|v| v.coerce_float()).unwrap_or(0.0); sum += a_ik * b_kj; } result[i * p + j] = Value::Float(OrderedFloat(sum)); } }
-            Ok(Value::Tensor(Tensor::from_values(vec![m, p], result)))
+            let element = |t: &Tensor, i: usize, j: usize| t.get(&[i, j]).filter(Value::is_numeric).ok_or_else(|| EvalError::type_error_msg("matmul expects numeric matrices"));
+            let promote = a.iter().chain(b.iter()).any(|v| matches!(v, Value::Float(_)));
+            let mut result = Vec::with_capacity(m * p);
+            for i in 0..m { for j in 0..p {
+                let mut sum = if promote { Value::float(0.0) } else { Value::Int(0) };
+                for k in 0..n { sum = add(sum, mul(element(a, i, k)?, element(b, k, j)?)?)?; }
+                result.push(sum);
+            } }
+            Ok(Value::Tensor(Tensor::pack(vec![m, p], result)))
         }
+        (Value::Tensor(_), _) => Err(EvalError::type_error("Tensor", &right)),
         _ => Err(EvalError::type_error("Tensor", &left)),
     }
 }
 
+/// transpose t: t with its axes reversed, so an [m n] matrix becomes [n m]
 fn transpose(value: Value) -> EvalResult<Value> {
     match &value {
         Value::Tensor(t) => {
-            if t.rank() != 2 { return Err(EvalError::shape_mismatch("Transpose requires 2D tensor")); }
-            let m = t.shape[0]; let n = t.shape[1];
-            let mut result = vec![Value::Float(OrderedFloat(0.0)); m * n];
-            for i in 0..m { for j in 0..n { if let Some(v) = t.get(&[i, j]) { result[j * m + i] = v; } } }
-            Ok(Value::Tensor(Tensor::from_values(vec![n, m], result)))
+            let axes: Vec<usize> = (0..t.rank()).rev().collect();
+            Ok(Value::Tensor(permute_axes(t, &axes)?))
         }
         _ => Err(EvalError::type_error("Tensor", &value)),
     }
 }
 
+/// transposeAxes t perm: axis i of the result is axis perm[i] of t
+fn transpose_axes(value: Value, perm: Value) -> EvalResult<Value> {
+    let Value::Tensor(t) = &value else { return Err(EvalError::type_error("Tensor", &value)) };
+    let axes = match &perm {
+        Value::Tensor(p) if p.rank() == 1 => p.iter().map(|v| v.as_int().and_then(|n| usize::try_from(n).ok())).collect::<Option<Vec<_>>>()
+            .ok_or_else(|| EvalError::type_error_msg("transposeAxes expects a permutation of axis numbers"))?,
+        _ => return Err(EvalError::type_error("Tensor", &perm)),
+    };
+    Ok(Value::Tensor(permute_axes(t, &axes)?))
+}
+
+fn permute_axes(t: &Tensor, axes: &[usize]) -> EvalResult<Tensor> {
+    if t.rank() < 2 { return Err(EvalError::shape_mismatch(format!("Transpose requires a tensor of rank 2 or more, got shape {:?}", t.shape))); }
+    let mut sorted = axes.to_vec();
+    sorted.sort_unstable();
+    if !sorted.iter().copied().eq(0..t.rank()) {
+        return Err(EvalError::shape_mismatch(format!("{:?} is not a permutation of the axes of shape {:?}", axes, t.shape)));
+    }
+    let shape: Vec<usize> = axes.iter().map(|&a| t.shape[a]).collect();
+    let mut index = vec![0; shape.len()];
+    let mut source = vec![0; shape.len()];
+    let mut data = Vec::with_capacity(t.len());
+    for _ in 0..t.len() {
+        for (i, &a) in axes.iter().enumerate() { source[a] = index[i]; }
+        data.push(t.get(&source).ok_or_else(|| EvalError::shape_mismatch("transpose index out of range"))?);
+        // Advance the result index in row-major order
+        for (slot, &dim) in index.iter_mut().zip(&shape).rev() {
+            *slot += 1;
+            if *slot < dim { break; }
+            *slot = 0;
+        }
+    }
+    Ok(Tensor::pack(shape, data))
+}
+
+/// reshape t dims: the elements of t in row-major order under the shape dims, sharing t's storage
+fn reshape(value: Value, dims: Value) -> EvalResult<Value> {
+    let Value::Tensor(t) = value else { return Err(EvalError::type_error("Tensor", &value)) };
+    let shape = match &dims {
+        Value::Tensor(d) if d.rank() == 1 => d.iter().map(|v| v.as_int().and_then(|n| usize::try_from(n).ok())).collect::<Option<Vec<_>>>()
+            .ok_or_else(|| EvalError::type_error_msg("reshape expects non-negative integer dimensions"))?,
+        _ => return Err(EvalError::type_error("Tensor", &dims)),
+    };
+    if shape.iter().try_fold(1usize, |n, &d| n.checked_mul(d)) != Some(t.len()) {
+        return Err(EvalError::shape_mismatch(format!("Cannot reshape {} elements of shape {:?} to {:?}", t.len(), t.shape, shape)));
+    }
+    Ok(Value::Tensor(Tensor { shape, data: t.data }))
+}
+
 fn to_int(value: Value) -> EvalResult<Value> {
     match value {
         Value::Int(n) => Ok(Value::Int(n)), Value::Float(f) => Ok(Value::Int(f.0 as i128)),
@@ -557,6 +899,16 @@ fn to_char(value: Value) -> EvalResult<Value> {
     }
 }
 
+/// ord c: The code point of the character c
+fn ord(value: Value) -> EvalResult<Value> {
+    match value { Value::Char(c) => Ok(Value::Int(c as i128)), _ => Err(EvalError::type_error("Char", &value)) }
+}
+
+/// chr n: The character with code point n, which must be a Unicode scalar value
+fn chr(value: Value) -> EvalResult<Value> {
+    match value { Value::Int(_) => to_char(value), _ => Err(EvalError::type_error("Int", &value)) }
+}
+
 /// parseInt str: Parse a string as an integer
 fn parse_int(value: Value) -> EvalResult<Value> {
     match value {
@@ -620,6 +972,55 @@ fn range(start: Value, end: Value) -> EvalResult<Value> {
     }
 }
 
+/// rangeStep start end step: Generate [start, start+step, ...] up to but excluding end,
+/// counting down when step is negative
+fn range_step(start: &Value, end: &Value, step: &Value) -> EvalResult<Value> {
+    match (start, end, step) {
+        (Value::Int(_), Value::Int(_), Value::Int(0)) => Err(EvalError::type_error_msg("rangeStep requires a non-zero step")),
+        (Value::Int(s), Value::Int(e), Value::Int(k)) => {
+            let data: Vec<i128> = (0..step_count(*s, *e, *k)).map(|i| s + i * k).collect();
+            Ok(Value::Tensor(Tensor::from_ints(data)))
+        }
+        _ => Err(EvalError::type_error_msg(format!(
+            "rangeStep requires three integers, got {}, {} and {}",
+            start.type_name(),
+            end.type_name(),
+            step.type_name()
+        ))),
+    }
+}
+
+/// fill n x: A rank-1 tensor of n copies of x
+fn fill(n: Value, x: Value) -> EvalResult<Value> {
+    let n = dimension("fill", &n)?;
+    Ok(Value::Tensor(Tensor::pack(vec![n], vec![x; n])))
+}
+
+/// zeros shape / ones shape: An Int tensor of the given shape filled with `x`
+fn constant_tensor(name: &str, shape: Value, x: i128) -> EvalResult<Value> {
+    let dims = match &shape {
+        Value::Tensor(t) if t.rank() == 1 => t.iter().map(|d| dimension(name, &d)).collect::<EvalResult<Vec<_>>>()?,
+        _ => return Err(EvalError::type_error("shape array", &shape)),
+    };
+    let size = dims.iter().try_fold(1usize, |acc, &d| acc.checked_mul(d))
+        .ok_or_else(|| EvalError::shape_mismatch(format!("{}: shape {:?} is too large", name, dims)))?;
+    Ok(Value::Tensor(Tensor { shape: dims, data: TensorData::Int(vec![x; size]) }))
+}
+
+/// A tensor size argument: a non-negative integer
+fn dimension(name: &str, n: &Value) -> EvalResult<usize> {
+    match n {
+        Value::Int(k) => usize::try_from(*k).map_err(|_| EvalError::type_error_msg(format!("{} requires non-negative sizes, got {}", name, k))),
+        _ => Err(EvalError::type_error("integer", n)),
+    }
+}
+
+/// Number of elements of `start, start+step, ...` before reaching `end`
+fn step_count(start: i128, end: i128, step: i128) -> i128 {
+    let span = if step > 0 { end.saturating_sub(start) } else { start.saturating_sub(end) };
+    if span <= 0 { 0 } else { (span - 1) / step.saturating_abs() + 1 }
+}
+
 /// toString: Convert any value to a string representation
 fn to_string(value: Value) -> EvalResult<Value> {
     // Handle Char specially to avoid quotes (Display adds quotes for REPL)
@@ -644,6 +1045,14 @@ fn chars(value: Value) -> EvalResult<Value> {
     }
 }
 
+/// strLen s: The number of characters in the string `s`; other tensors are rejected
+fn str_len(value: Value) -> EvalResult<Value> {
+    match &value {
+        Value::Tensor(t) if t.rank() == 1 && matches!(t.data, TensorData::Char(_)) => Ok(Value::Int(t.len() as i128)),
+        _ => Err(EvalError::type_error("String", &value)),
+    }
+}
+
 /// strConcat: Concatenate two strings
 fn str_concat(left: Value, right: Value) -> EvalResult<Value> {
     match (&left, &right) {
@@ -667,77 +1076,51 @@ fn str_concat(left: Value, right: Value) -> EvalResult<Value> {
     }
 }
 
-/// take n arr: Take the first n elements from an array
-fn take(n: Value, arr: Value) -> EvalResult<Value> {
-    match (&n, &arr) {
-        (Value::Int(count), Value::Tensor(t)) => {
-            if t.rank() != 1 {
-                return Err(EvalError::not_implemented("take for rank > 1"));
-            }
-            let count = (*count).max(0) as usize;
-            let count = count.min(t.len());
-            // Preserve string type when taking from strings
-            if let Some(s) = t.to_string_value() {
-                let taken: String = s.chars().take(count).collect();
-                return Ok(Value::string(&taken));
-            }
-            let data: Vec<Value> = (0..count).map(|i| t.get_flat(i).unwrap()).collect();
-            Ok(Value::Tensor(Tensor::from_values(vec![data.len()], data)))
-        }
-        (Value::Int(count), Value::Tuple(vs)) => {
-            let count = (*count).max(0) as usize;
-            let count = count.min(vs.len());
-            Ok(Value::Tuple(vs[..count].to_vec()))
-        }
-        _ => Err(EvalError::type_error_msg(format!(
-            "take requires (Int, Tensor/Tuple), got ({}, {})",
-            n.type_name(),
-            arr.type_name()
-        ))),
-    }
-}
+/// take n arr: The first n entries along the first axis (elements of a vector, rows of a matrix)
+fn take(n: Value, arr: Value) -> EvalResult<Value> { split_first_axis("take", n, arr, true) }
 
-/// drop n arr: Drop the first n elements from an array
-fn drop_fn(n: Value, arr: Value) -> EvalResult<Value> {
-    match (&n, &arr) {
-        (Value::Int(count), Value::Tensor(t)) => {
-            if t.rank() != 1 {
-                return Err(EvalError::not_implemented("drop for rank > 1"));
-            }
-            let count = (*count).max(0) as usize;
-            let count = count.min(t.len());
-            // Preserve string type when dropping from strings
-            if let Some(s) = t.to_string_value() {
-                let dropped: String = s.chars().skip(count).collect();
-                return Ok(Value::string(&dropped));
-            }
-            let data: Vec<Value> = (count..t.len()).map(|i| t.get_flat(i).unwrap()).collect();
-            Ok(Value::Tensor(Tensor::from_values(vec![data.len()], data)))
+/// drop n arr: Everything after the first n entries along the first axis
+fn drop_fn(n: Value, arr: Value) -> EvalResult<Value> { split_first_axis("drop", n, arr, false) }
+
+/// The part of `arr` before (`prefix`) or after its first `n` entries. A negative `n` is an
+/// error; one past the end clamps, so take gives everything and drop nothing.
+fn split_first_axis(name: &str, n: Value, arr: Value, prefix: bool) -> EvalResult<Value> {
+    let type_error = |arr: &Value| EvalError::type_error_msg(format!("{} requires (Int, Tensor/Tuple), got ({}, {})", name, n.type_name(), arr.type_name()));
+    let Value::Int(count) = n else { return Err(type_error(&arr)) };
+    if count < 0 {
+        return Err(EvalError::type_error_msg(format!("{} count must be non-negative, got {}", name, count)));
+    }
+    let count = usize::try_from(count).unwrap_or(usize::MAX);
+    match arr {
+        Value::Tensor(t) if t.rank() > 0 => {
+            let at = count.min(t.shape[0]);
+            // Each block of `row` flat elements is one entry along the first axis
+            let row: usize = t.shape.iter().skip(1).product();
+            let range = if prefix { 0..at * row } else { at * row..t.len() };
+            let data = match &t.data {
+                TensorData::Int(v) => TensorData::Int(v[range].to_vec()),
+                TensorData::Float(v) => TensorData::Float(v[range].to_vec()),
+                TensorData::Bool(v) => TensorData::Bool(v[range].to_vec()),
+                TensorData::Char(v) => TensorData::Char(v[range].to_vec()),
+                TensorData::Generic(v) => TensorData::Generic(v[range].to_vec()),
+            };
+            let mut shape = t.shape;
+            shape[0] = if prefix { at } else { shape[0] - at };
+            Ok(Value::Tensor(Tensor { shape, data }))
         }
-        (Value::Int(count), Value::Tuple(vs)) => {
-            let count = (*count).max(0) as usize;
-            let count = count.min(vs.len());
-            Ok(Value::Tuple(vs[count..].to_vec()))
+        Value::Tuple(mut vs) => {
+            let at = count.min(vs.len());
+            Ok(Value::Tuple(if prefix { vs.truncate(at); vs } else { vs.split_off(at) }))
         }
-        _ => Err(EvalError::type_error_msg(format!(
-            "drop requires (Int, Tensor/Tuple), got ({}, {})",
-            n.type_name(),
-            arr.type_name()
-        ))),
+        _ => Err(type_error(&arr)),
     }
 }
 
-/// index arr idx: Get element at index
+/// index arr idx: The element at `idx`, counting from the end when negative (-1 is the last)
 fn index(arr: Value, idx: Value) -> EvalResult<Value> {
     match (&arr, &idx) {
-        (Value::Tensor(t), Value::Int(i)) => {
-            let i = *i as usize;
-            t.get_flat(i).ok_or_else(|| EvalError::IndexOutOfBounds { index: i, size: t.len() })
-        }
-        (Value::Tuple(vs), Value::Int(i)) => {
-            let i = *i as usize;
-            vs.get(i).cloned().ok_or_else(|| EvalError::IndexOutOfBounds { index: i, size: vs.len() })
-        }
+        (Value::Tensor(t), Value::Int(i)) => Ok(t.get_flat(resolve_index(*i, t.len())?).expect("index within bounds")),
+        (Value::Tuple(vs), Value::Int(i)) => Ok(vs[resolve_index(*i, vs.len())?].clone()),
         _ => Err(EvalError::type_error_msg(format!(
             "index requires (Tensor/Tuple, Int), got ({}, {})",
             arr.type_name(),
@@ -746,6 +1129,12 @@ fn index(arr: Value, idx: Value) -> EvalResult<Value> {
     }
 }
 
+/// The position `i` names in a sequence of `len` elements, Python-style
+fn resolve_index(i: i128, len: usize) -> EvalResult<usize> {
+    let from_start = if i < 0 { i + len as i128 } else { i };
+    usize::try_from(from_start).ok().filter(|&j| j < len).ok_or(EvalError::IndexOutOfBounds { index: i, len })
+}
+
 /// lines str: Split a string by newlines into an array of strings
 fn lines(value: Value) -> EvalResult<Value> {
     match value {
@@ -858,3 +1247,212 @@ fn str_contains(str_val: Value, substr_val: Value) -> EvalResult<Value> {
         ))),
     }
 }
+
+/// Reduce `arr` along `axis`, calling `f` on the elements of each lane.
+/// The result has the shape of `arr` with `axis` removed.
+fn reduce_axis<F>(name: &str, arr: Value, axis: Value, f: F) -> EvalResult<Value> where F: Fn(Vec<Value>) -> EvalResult<Value> {
+    let t = match arr { Value::Tensor(t) => t, other => return Err(EvalError::type_error("Tensor", &other)) };
+    let axis = match axis { Value::Int(n) => n, other => return Err(EvalError::type_error("Int", &other)) };
+    if axis < 0 || axis as usize >= t.rank() { return Err(EvalError::shape_mismatch(format!("{}: axis {} out of range for tensor of rank {}", name, axis, t.rank()))); }
+    let axis = axis as usize;
+    let outer: usize = t.shape[..axis].iter().product();
+    let n = t.shape[axis];
+    let inner: usize = t.shape[axis + 1..].iter().product();
+    let data = t.to_vec();
+    let mut result = Vec::with_capacity(outer * inner);
+    for o in 0..outer { for i in 0..inner { result.push(f((0..n).map(|k| data[(o * n + k) * inner + i].clone()).collect())?); } }
+    let mut shape = t.shape.clone(); shape.remove(axis);
+    Ok(Value::Tensor(Tensor::pack(shape, result)))
+}
+
+/// sum_axis arr axis: Sum along one axis
+fn sum_axis(arr: Value, axis: Value) -> EvalResult<Value> {
+    reduce_axis("sum_axis", arr, axis, |lane| lane.into_iter().try_fold(Value::Int(0), add))
+}
+
+/// mean_axis arr axis: Arithmetic mean along one axis (always Float)
+fn mean_axis(arr: Value, axis: Value) -> EvalResult<Value> {
+    reduce_axis("mean_axis", arr, axis, |lane| {
+        if lane.is_empty() { return Err(EvalError::shape_mismatch("mean_axis: cannot take mean of empty axis")); }
+        let n = lane.len() as f64;
+        let total = lane.into_iter().try_fold(Value::Int(0), add)?;
+        let total = total.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &total))?;
+        Ok(Value::Float(OrderedFloat(total / n)))
+    })
+}
+
+/// max_axis arr axis: Maximum along one axis
+fn max_axis(arr: Value, axis: Value) -> EvalResult<Value> {
+    reduce_axis("max_axis", arr, axis, |lane| {
+        let mut it = lane.into_iter();
+        let first = it.next().ok_or_else(|| EvalError::shape_mismatch("max_axis: cannot take max of empty axis"))?;
+        it.try_fold(first, |best, v| Ok(if compare_gt(v.clone(), best.clone())? == Value::Bool(true) { v } else { best }))
+    })
+}
+
+/// Elements of a rank-1 numeric tensor as floats
+fn float_elems(name: &str, value: &Value) -> EvalResult<Vec<f64>> {
+    match value {
+        Value::Tensor(t) if t.rank() == 1 => t.iter().map(|v| v.coerce_float().ok_or_else(|| EvalError::type_error("numeric", &v))).collect(),
+        Value::Tensor(t) => Err(EvalError::shape_mismatch(format!("{} requires a rank-1 tensor, got rank {}", name, t.rank()))),
+        _ => Err(EvalError::type_error("Tensor", value)),
+    }
+}
+
+/// One-pass Welford accumulation: (count, mean, sum of squared deviations)
+fn welford(xs: &[f64]) -> (usize, f64, f64) {
+    let (mut mean, mut m2) = (0.0, 0.0);
+    for (i, &x) in xs.iter().enumerate() {
+        let delta = x - mean;
+        mean += delta / (i + 1) as f64;
+        m2 += delta * (x - mean);
+    }
+    (xs.len(), mean, m2)
+}
+
+/// mean arr: Arithmetic mean of a rank-1 tensor
+fn mean(value: Value) -> EvalResult<Value> {
+    let (n, mean, _) = welford(&float_elems("mean", &value)?);
+    if n == 0 { return Err(EvalError::shape_mismatch("mean of empty tensor")); }
+    Ok(Value::Float(OrderedFloat(mean)))
+}
+
+/// variance arr: Population variance (divides by n)
+fn variance(value: Value) -> EvalResult<Value> {
+    let (n, _, m2) = welford(&float_elems("variance", &value)?);
+    if n == 0 { return Err(EvalError::shape_mismatch("variance of empty tensor")); }
+    Ok(Value::Float(OrderedFloat(m2 / n as f64)))
+}
+
+/// variance_sample arr: Sample variance (divides by n - 1)
+fn variance_sample(value: Value) -> EvalResult<Value> {
+    let (n, _, m2) = welford(&float_elems("variance_sample", &value)?);
+    if n < 2 { return Err(EvalError::shape_mismatch("variance_sample requires at least two elements")); }
+    Ok(Value::Float(OrderedFloat(m2 / (n - 1) as f64)))
+}
+
+/// std arr: Population standard deviation
+fn std_dev(value: Value) -> EvalResult<Value> {
+    match variance(value)? { Value::Float(v) => Ok(Value::Float(OrderedFloat(v.0.sqrt()))), other => Err(EvalError::type_error("Float", &other)) }
+}
+
+/// argmin / argmax: Index of the extreme element of a rank-1 tensor.
+/// `better(candidate, best)` decides whether to replace the current best; it is strict,
+/// so ties keep the first occurrence.
+fn arg_extreme(name: &str, value: Value, better: fn(Value, Value) -> EvalResult<Value>) -> EvalResult<Value> {
+    let t = match &value { Value::Tensor(t) if t.rank() == 1 => t, Value::Tensor(t) => return Err(EvalError::shape_mismatch(format!("{} requires a rank-1 tensor, got rank {}", name, t.rank()))), _ => return Err(EvalError::type_error("Tensor", &value)) };
+    let mut elems = t.iter().enumerate();
+    let (mut best_idx, mut best) = elems.next().ok_or_else(|| EvalError::shape_mismatch(format!("{} of empty tensor", name)))?;
+    for (i, v) in elems { if better(v.clone(), best.clone())? == Value::Bool(true) { best_idx = i; best = v; } }
+    Ok(Value::Int(best_idx as i128))
+}
+
+/// min / max x₁ x₂ …: Smallest or largest of the arguments (variadic), ordered as by `<`;
+/// ties keep the first. There is no identity, so no arguments is an error.
+fn extreme_of(name: &str, args: Vec<Value>, better: fn(Value, Value) -> EvalResult<Value>) -> EvalResult<Value> {
+    let mut it = args.into_iter();
+    let first = it.next().ok_or_else(|| EvalError::shape_mismatch(format!("{} of no values", name)))?;
+    it.try_fold(first, |best, v| Ok(if better(v.clone(), best.clone())? == Value::Bool(true) { v } else { best }))
+}
+
+/// clamp x lo hi: x limited to [lo, hi], element-wise for a tensor
+fn clamp(value: &Value, lo: &Value, hi: &Value) -> EvalResult<Value> {
+    if compare_lt(hi.clone(), lo.clone())? == Value::Bool(true) {
+        return Err(EvalError::type_error_msg(format!("clamp bounds out of order: {} > {}", lo, hi)));
+    }
+    let bound = |x: Value| -> EvalResult<Value> {
+        if compare_lt(x.clone(), lo.clone())? == Value::Bool(true) { Ok(lo.clone()) }
+        else if compare_gt(x.clone(), hi.clone())? == Value::Bool(true) { Ok(hi.clone()) }
+        else { Ok(x) }
+    };
+    match value {
+        Value::Tensor(t) => { let data = t.iter().map(bound).collect::<EvalResult<Vec<_>>>()?; Ok(Value::Tensor(Tensor::pack(t.shape.clone(), data))) }
+        other => bound(other.clone()),
+    }
+}
+
+/// normalize arr: Divide each element by the sum so the result sums to 1
+fn normalize(value: Value) -> EvalResult<Value> {
+    let xs = float_elems("normalize", &value)?;
+    let total: f64 = xs.iter().sum();
+    if total == 0.0 { return Err(EvalError::DivisionByZero); }
+    Ok(Value::Tensor(Tensor::from_floats(xs.iter().map(|x| x / total).collect())))
+}
+
+/// softmax arr: exp-normalize, subtracting the max first so large inputs don't overflow
+fn softmax(value: Value) -> EvalResult<Value> {
+    let xs = float_elems("softmax", &value)?;
+    let max = xs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exps: Vec<f64> = xs.iter().map(|x| (x - max).exp()).collect();
+    let total: f64 = exps.iter().sum();
+    Ok(Value::Tensor(Tensor::from_floats(exps.iter().map(|e| e / total).collect())))
+}
+
+// ============ Lists ============
+// head, tail, isEmpty and cons work on cons lists and on rank-1 tensors.
+// On a list they share structure; on a tensor they copy.
+
+/// cons x xs: Prepend x to a list (O(1)) or tensor
+fn cons(x: Value, xs: Value) -> EvalResult<Value> {
+    match xs {
+        Value::List(l) => Ok(Value::List(List::cons(x, l))),
+        Value::Tensor(t) if t.rank() == 1 => Ok(Value::Tensor(Tensor::pack(vec![t.len() + 1], std::iter::once(x).chain(t.iter()).collect()))),
+        _ => Err(EvalError::type_error("List", &xs)),
+    }
+}
+
+/// head xs: First element
+fn head(xs: Value) -> EvalResult<Value> {
+    match &xs {
+        Value::List(l) => l.head().cloned().ok_or(EvalError::IndexOutOfBounds { index: 0, len: 0 }),
+        Value::Tensor(t) if t.rank() == 1 => t.get_flat(0).ok_or(EvalError::IndexOutOfBounds { index: 0, len: 0 }),
+        _ => Err(EvalError::type_error("List", &xs)),
+    }
+}
+
+/// tail xs: All but the first element
+fn tail(xs: Value) -> EvalResult<Value> {
+    match &xs {
+        Value::List(l) => l.tail().cloned().map(Value::List).ok_or(EvalError::IndexOutOfBounds { index: 0, len: 0 }),
+        Value::Tensor(t) if t.rank() == 1 => {
+            if t.is_empty() { return Err(EvalError::IndexOutOfBounds { index: 0, len: 0 }); }
+            Ok(Value::Tensor(Tensor::pack(vec![t.len() - 1], t.iter().skip(1).collect())))
+        }
+        _ => Err(EvalError::type_error("List", &xs)),
+    }
+}
+
+/// isEmpty xs: Whether xs has no elements
+fn is_empty(xs: Value) -> EvalResult<Value> {
+    match &xs {
+        Value::List(l) => Ok(Value::Bool(l.is_empty())),
+        Value::Tensor(t) => Ok(Value::Bool(t.is_empty())),
+        _ => Err(EvalError::type_error("List", &xs)),
+    }
+}
+
+/// to_list arr: Elements of a rank-1 tensor as a list, in order
+fn to_list(value: Value) -> EvalResult<Value> {
+    match &value {
+        Value::Tensor(t) if t.rank() == 1 => Ok(Value::List(t.iter().collect())),
+        Value::Tensor(t) => Err(EvalError::shape_mismatch(format!("to_list requires a rank-1 tensor, got rank {}", t.rank()))),
+        Value::List(_) => Ok(value),
+        _ => Err(EvalError::type_error("Tensor", &value)),
+    }
+}
+
+/// to_tensor xs: A list whose elements are all of one kind as a rank-1 tensor
+/// (typed storage for numbers, booleans and chars)
+fn to_tensor(value: Value) -> EvalResult<Value> {
+    match &value {
+        Value::List(l) => {
+            let kind = l.head().map(Value::type_name);
+            if let Some(other) = l.iter().find(|v| Some(v.type_name()) != kind) {
+                return Err(EvalError::type_error_msg(format!("to_tensor of a list mixing {} and {}", kind.unwrap_or_default(), other.type_name())));
+            }
+            Ok(Value::Tensor(if l.is_empty() { Tensor::from_ints(vec![]) } else { Tensor::pack(vec![l.len()], l.iter().cloned().collect()) }))
+        }
+        Value::Tensor(_) => Ok(value),
+        _ => Err(EvalError::type_error("List", &value)),
+    }
+}
@@ -0,0 +1,153 @@
+//! Serialization of runtime values
+//!
+//! Closures are stored as their originating expression: arity, body,
+//! contracts and the captured environment. Captured values must themselves
+//! be serializable.
+//!
+//! Limits:
+//! - Primitives (and partial applications of primitives) cannot be serialized.
+//! - Thunks and refs cannot be serialized.
+//! - A captured closure that is also bound as a global is stored as a
+//!   reference to the global name, which breaks the cycle of a recursive
+//!   function referring to itself. The name is resolved against the
+//!   globals of the evaluator that deserializes the value.
+
+use crate::value::{Value, Tensor, TensorData, Closure, Env};
+use crate::error::{EvalError, EvalResult};
+use goth_ast::expr::Expr;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+/// Serializable mirror of [`Value`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SerValue {
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Unit,
+    Tensor { shape: Vec<usize>, data: SerTensorData },
+    List(Vec<SerValue>),
+    Tuple(Vec<SerValue>),
+    Record(BTreeMap<String, SerValue>),
+    Variant { tag: String, payload: Option<Box<SerValue>> },
+    Closure { arity: u32, body: Expr, env: Vec<SerValue>, preconditions: Vec<Expr>, postconditions: Vec<Expr> },
+    Partial { func: Box<SerValue>, args: Vec<SerValue>, remaining: usize },
+    Uncertain { value: Box<SerValue>, uncertainty: Box<SerValue> },
+    Error(String),
+    /// Reference to a global binding, resolved on deserialization
+    Global(String),
+}
+
+/// Serializable mirror of [`TensorData`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SerTensorData {
+    Int(Vec<i128>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+    Char(Vec<char>),
+    Generic(Vec<SerValue>),
+}
+
+/// Convert a value to its serializable form.
+///
+/// `globals` is used to replace captured closures that are bound as globals
+/// with a [`SerValue::Global`] reference.
+pub fn to_ser_value(value: &Value, globals: &HashMap<String, Value>) -> EvalResult<SerValue> {
+    convert(value, globals, false)
+}
+
+/// Rebuild a value from its serializable form. Closures get an environment
+/// sharing `globals`, and global references are looked up in it.
+pub fn from_ser_value(ser: &SerValue, globals: &Rc<RefCell<HashMap<String, Value>>>) -> EvalResult<Value> {
+    let rebuild_all = |vs: &[SerValue]| vs.iter().map(|v| from_ser_value(v, globals)).collect::<EvalResult<Vec<_>>>();
+    Ok(match ser {
+        SerValue::Int(n) => Value::Int(*n),
+        SerValue::Float(f) => Value::float(*f),
+        SerValue::Bool(b) => Value::Bool(*b),
+        SerValue::Char(c) => Value::Char(*c),
+        SerValue::Unit => Value::Unit,
+        SerValue::Tensor { shape, data } => {
+            let data = match data {
+                SerTensorData::Int(v) => TensorData::Int(v.clone()),
+                SerTensorData::Float(v) => TensorData::Float(v.iter().copied().map(OrderedFloat).collect()),
+                SerTensorData::Bool(v) => TensorData::Bool(v.clone()),
+                SerTensorData::Char(v) => TensorData::Char(v.clone()),
+                SerTensorData::Generic(v) => TensorData::Generic(rebuild_all(v)?),
+            };
+            Value::Tensor(Tensor { shape: shape.clone(), data })
+        }
+        SerValue::List(vs) => Value::List(rebuild_all(vs)?.into_iter().collect()),
+        SerValue::Tuple(vs) => Value::Tuple(rebuild_all(vs)?),
+        SerValue::Record(fields) => Value::Record(Rc::new(fields.iter().map(|(k, v)| Ok((k.clone(), from_ser_value(v, globals)?))).collect::<EvalResult<_>>()?)),
+        SerValue::Variant { tag, payload } => Value::Variant { tag: tag.clone(), payload: match payload { Some(p) => Some(Box::new(from_ser_value(p, globals)?)), None => None } },
+        SerValue::Closure { arity, body, env, preconditions, postconditions } => {
+            let mut closure_env = Env::with_globals(Rc::clone(globals));
+            closure_env.push_many(rebuild_all(env)?);
+            Value::Closure(Closure { arity: *arity, body: body.clone(), env: closure_env, preconditions: preconditions.clone(), postconditions: postconditions.clone() })
+        }
+        SerValue::Partial { func, args, remaining } => Value::Partial { func: Box::new(from_ser_value(func, globals)?), args: rebuild_all(args)?, remaining: *remaining },
+        SerValue::Uncertain { value, uncertainty } => Value::Uncertain { value: Box::new(from_ser_value(value, globals)?), uncertainty: Box::new(from_ser_value(uncertainty, globals)?) },
+        SerValue::Error(msg) => Value::Error(msg.clone()),
+        SerValue::Global(name) => globals.borrow().get(name).cloned().ok_or_else(|| EvalError::UndefinedName(name.clone()))?,
+    })
+}
+
+/// Serialize a value to JSON
+pub fn value_to_json(value: &Value, globals: &HashMap<String, Value>) -> EvalResult<String> {
+    serde_json::to_string(&to_ser_value(value, globals)?).map_err(|e| EvalError::Serialization(e.to_string()))
+}
+
+/// Deserialize a value from JSON
+pub fn value_from_json(json: &str, globals: &Rc<RefCell<HashMap<String, Value>>>) -> EvalResult<Value> {
+    let ser: SerValue = serde_json::from_str(json).map_err(|e| EvalError::Serialization(e.to_string()))?;
+    from_ser_value(&ser, globals)
+}
+
+/// `captured` is true for values reachable from a closure environment or
+/// partial application, where global references are allowed.
+fn convert(value: &Value, globals: &HashMap<String, Value>, captured: bool) -> EvalResult<SerValue> {
+    let convert_all = |vs: &[Value], captured: bool| vs.iter().map(|v| convert(v, globals, captured)).collect::<EvalResult<Vec<_>>>();
+    Ok(match value {
+        Value::Int(n) => SerValue::Int(*n),
+        Value::Float(f) => SerValue::Float(f.0),
+        Value::Bool(b) => SerValue::Bool(*b),
+        Value::Char(c) => SerValue::Char(*c),
+        Value::Unit => SerValue::Unit,
+        Value::Tensor(t) => {
+            let data = match &t.data {
+                TensorData::Int(v) => SerTensorData::Int(v.clone()),
+                TensorData::Float(v) => SerTensorData::Float(v.iter().map(|f| f.0).collect()),
+                TensorData::Bool(v) => SerTensorData::Bool(v.clone()),
+                TensorData::Char(v) => SerTensorData::Char(v.clone()),
+                TensorData::Generic(v) => SerTensorData::Generic(convert_all(v, captured)?),
+            };
+            SerValue::Tensor { shape: t.shape.clone(), data }
+        }
+        Value::List(l) => SerValue::List(l.iter().map(|v| convert(v, globals, captured)).collect::<EvalResult<_>>()?),
+        Value::Tuple(vs) => SerValue::Tuple(convert_all(vs, captured)?),
+        Value::Record(fields) => SerValue::Record(fields.iter().map(|(k, v)| Ok((k.clone(), convert(v, globals, captured)?))).collect::<EvalResult<_>>()?),
+        Value::Variant { tag, payload } => SerValue::Variant { tag: tag.clone(), payload: match payload { Some(p) => Some(Box::new(convert(p, globals, captured)?)), None => None } },
+        Value::Closure(c) => {
+            if captured { if let Some(name) = global_name(value, globals) { return Ok(SerValue::Global(name)); } }
+            SerValue::Closure { arity: c.arity, body: c.body.clone(), env: convert_all(&c.env.outermost_first(), true)?, preconditions: c.preconditions.clone(), postconditions: c.postconditions.clone() }
+        }
+        Value::Partial { func, args, remaining } => {
+            if let Value::Primitive(p) = func.as_ref() { return Err(EvalError::Serialization(format!("cannot serialize partial application of primitive {:?}", p))); }
+            SerValue::Partial { func: Box::new(convert(func, globals, captured)?), args: convert_all(args, true)?, remaining: *remaining }
+        }
+        Value::Uncertain { value, uncertainty } => SerValue::Uncertain { value: Box::new(convert(value, globals, captured)?), uncertainty: Box::new(convert(uncertainty, globals, captured)?) },
+        Value::Error(msg) => SerValue::Error(msg.clone()),
+        Value::Primitive(p) => return Err(EvalError::Serialization(format!("cannot serialize primitive {:?}", p))),
+        Value::Thunk(_) | Value::Memo(_) | Value::Ref(_) => return Err(EvalError::Serialization(format!("cannot serialize {}", value.type_name()))),
+    })
+}
+
+/// Name of a global bound to a closure equal to `value`, if any.
+/// The smallest name wins so the output is deterministic.
+fn global_name(value: &Value, globals: &HashMap<String, Value>) -> Option<String> {
+    globals.iter().filter(|(_, g)| matches!(g, Value::Closure(_)) && *g == value).map(|(name, _)| name).min().cloned()
+}
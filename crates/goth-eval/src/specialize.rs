@@ -0,0 +1,202 @@
+//! Partial evaluation of closures at known arguments
+//!
+//! [`specialize`] substitutes the arguments known ahead of time into a
+//! closure body and folds the constants this exposes, leaving a function of
+//! the remaining arguments. Arguments are given in application order, so of a
+//! body under `n` parameters, argument `k` is the index `n - 1 - k`.
+//!
+//! Only values with a literal form (scalars, strings, and tuples and vectors
+//! of them) are substituted; any other known argument stays a parameter.
+//! Folding is limited to operators applied to literals and `if` on a literal
+//! condition, and leaves anything that fails to evaluate for run time.
+
+use goth_ast::expr::{Expr, MatchArm, DoOp};
+use goth_ast::literal::Literal;
+use goth_ast::pattern::Pattern;
+use crate::capture::pattern_slots;
+use crate::eval::literal_value;
+use crate::prim;
+use crate::value::{Value, Tensor};
+
+/// Specialize `body`, the body of a closure over `known_args.len()` parameters,
+/// to the arguments that are known. The result is a lambda over the unknown
+/// arguments in their original order, or the simplified body when all are known.
+pub fn specialize(body: &Expr, known_args: &[Option<Value>]) -> Expr {
+    // Indexed by de Bruijn index, so the last argument comes first
+    let consts: Vec<Option<Expr>> = known_args.iter().rev().map(|arg| arg.as_ref().and_then(value_expr)).collect();
+    let unknown = consts.iter().filter(|c| c.is_none()).count() as u32;
+    let body = Specializer { consts, unknown }.rewrite(body, 0);
+    if unknown == 0 { body } else { Expr::lam_n(unknown, body) }
+}
+
+/// The literal expression evaluating to `value`, if it has one
+fn value_expr(value: &Value) -> Option<Expr> {
+    match value {
+        Value::Int(n) => Some(Expr::Lit(Literal::Int(*n))),
+        Value::Float(f) => Some(Expr::Lit(Literal::Float(f.0))),
+        Value::Bool(b) => Some(Expr::Lit(Literal::bool(*b))),
+        Value::Char(c) => Some(Expr::Lit(Literal::Char(*c))),
+        Value::Unit => Some(Expr::Lit(Literal::Unit)),
+        Value::Tensor(t) => tensor_expr(t),
+        Value::Tuple(vs) => vs.iter().map(value_expr).collect::<Option<_>>().map(Expr::Tuple),
+        _ => None,
+    }
+}
+
+fn tensor_expr(t: &Tensor) -> Option<Expr> {
+    if let Some(s) = t.to_string_value() { return Some(Expr::Lit(Literal::String(s.into()))); }
+    if t.rank() != 1 { return None; }
+    t.iter().map(|v| value_expr(&v)).collect::<Option<_>>().map(Expr::Array)
+}
+
+struct Specializer {
+    /// Replacement for each parameter by de Bruijn index, `None` where unknown
+    consts: Vec<Option<Expr>>,
+    /// Parameters of the specialized function
+    unknown: u32,
+}
+
+impl Specializer {
+    /// Rewrite `expr` found under `depth` binders of its own
+    fn rewrite(&self, expr: &Expr, depth: u32) -> Expr {
+        let go = |e: &Expr| Box::new(self.rewrite(e, depth));
+        let all = |es: &[Expr]| es.iter().map(|e| self.rewrite(e, depth)).collect();
+        let fields = |fs: &[(Box<str>, Expr)]| fs.iter().map(|(n, e)| (n.clone(), self.rewrite(e, depth))).collect();
+        let under = |p: &Pattern, e: &Expr| self.rewrite(e, depth + pattern_slots(p).0);
+        let rewritten = match expr {
+            Expr::Idx(i) => return self.index(*i, depth),
+            Expr::Name(_) | Expr::Lit(_) | Expr::Prim(_) | Expr::Hole | Expr::Unknown(_) => return expr.clone(),
+            Expr::App(f, x) => Expr::App(go(f), go(x)),
+            Expr::AppNamed(f, args) => Expr::AppNamed(go(f), fields(args)),
+            Expr::Lam(body) => Expr::Lam(Box::new(self.rewrite(body, depth + 1))),
+            Expr::LamN(n, body) => Expr::LamN(*n, Box::new(self.rewrite(body, depth + n))),
+            Expr::Let { pattern, type_, value, body } => Expr::Let { pattern: pattern.clone(), type_: type_.clone(), value: go(value), body: Box::new(under(pattern, body)) },
+            Expr::LetRec { bindings, body } => {
+                let inner = depth + bindings.len() as u32;
+                Expr::LetRec {
+                    bindings: bindings.iter().map(|(p, e)| (p.clone(), self.rewrite(e, inner))).collect(),
+                    body: Box::new(self.rewrite(body, inner)),
+                }
+            }
+            Expr::Match { scrutinee, arms } => Expr::Match {
+                scrutinee: go(scrutinee),
+                arms: arms.iter().map(|arm| MatchArm {
+                    pattern: arm.pattern.clone(),
+                    guard: arm.guard.as_ref().map(|g| under(&arm.pattern, g)),
+                    body: under(&arm.pattern, &arm.body),
+                }).collect(),
+            },
+            Expr::If { cond, then_, else_ } => Expr::If { cond: go(cond), then_: go(then_), else_: go(else_) },
+            Expr::BinOp(op, l, r) => Expr::BinOp(op.clone(), go(l), go(r)),
+            Expr::UnaryOp(op, e) => Expr::UnaryOp(*op, go(e)),
+            Expr::Norm(e) => Expr::Norm(go(e)),
+            Expr::Tuple(es) => Expr::Tuple(all(es)),
+            Expr::Record(fs) => Expr::Record(fields(fs)),
+            Expr::Array(es) => Expr::Array(all(es)),
+            Expr::ArrayFill { shape, value } => Expr::ArrayFill { shape: all(shape), value: go(value) },
+            Expr::Variant { constructor, payload } => Expr::Variant { constructor: constructor.clone(), payload: payload.as_deref().map(go) },
+            Expr::Field(e, access) => Expr::Field(go(e), access.clone()),
+            Expr::Index(base, indices) => Expr::Index(go(base), all(indices)),
+            Expr::Slice { array, start, end } => Expr::Slice { array: go(array), start: start.as_deref().map(go), end: end.as_deref().map(go) },
+            Expr::Annot(e, ty) => Expr::Annot(go(e), ty.clone()),
+            Expr::Cast { expr, target, kind } => Expr::Cast { expr: go(expr), target: target.clone(), kind: *kind },
+            Expr::Update { base, fields: fs } => Expr::Update { base: go(base), fields: fields(fs) },
+            Expr::Disabled(e) => Expr::Disabled(go(e)),
+            Expr::Quote(e) => Expr::Quote(go(e)),
+            Expr::Unquote(e) => Expr::Unquote(go(e)),
+            Expr::Do { init, ops } => Expr::Do {
+                init: go(init),
+                ops: ops.iter().map(|op| match op {
+                    DoOp::Map(e) => DoOp::Map(self.rewrite(e, depth)),
+                    DoOp::Filter(e) => DoOp::Filter(self.rewrite(e, depth)),
+                    DoOp::Bind(e) => DoOp::Bind(self.rewrite(e, depth)),
+                    DoOp::Op(op, e) => DoOp::Op(op.clone(), self.rewrite(e, depth)),
+                    DoOp::Let(p, e) => DoOp::Let(p.clone(), self.rewrite(e, depth)),
+                }).collect(),
+            },
+        };
+        fold_constants(rewritten)
+    }
+
+    /// Index `i` under `depth` local binders: locals are kept, known parameters
+    /// become their constants, and the rest shift past the removed parameters
+    fn index(&self, i: u32, depth: u32) -> Expr {
+        if i < depth { return Expr::Idx(i); }
+        let param = (i - depth) as usize;
+        match self.consts.get(param) {
+            Some(Some(c)) => c.clone(),
+            Some(None) => Expr::Idx(depth + self.consts[..param].iter().filter(|c| c.is_none()).count() as u32),
+            None => Expr::Idx(i - self.consts.len() as u32 + self.unknown),
+        }
+    }
+}
+
+/// Evaluate `expr` if it is an operator applied to literals or a choice on a literal
+fn fold_constants(expr: Expr) -> Expr {
+    let folded = match &expr {
+        Expr::BinOp(op, l, r) => match (l.as_ref(), r.as_ref()) {
+            (Expr::Lit(a), Expr::Lit(b)) => prim::apply_binop(op, literal_value(a), literal_value(b)).ok(),
+            _ => None,
+        },
+        Expr::UnaryOp(op, e) => match e.as_ref() {
+            Expr::Lit(a) => prim::apply_unaryop(op, literal_value(a)).ok(),
+            _ => None,
+        },
+        Expr::If { cond, then_, else_ } => match cond.as_ref() {
+            Expr::Lit(Literal::True) => return *then_.clone(),
+            Expr::Lit(Literal::False) => return *else_.clone(),
+            _ => None,
+        },
+        _ => None,
+    };
+    match folded.as_ref().and_then(value_expr) {
+        Some(lit @ Expr::Lit(_)) => lit,
+        _ => expr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::eval;
+    use goth_ast::op::BinOp;
+
+    #[test]
+    fn test_specialize_first_argument() {
+        // λ→ λ→ ₁ + ₀ with ₁ = 10 is λ→ 10 + ₀
+        let body = Expr::add(Expr::idx(1), Expr::idx(0));
+        let spec = specialize(&body, &[Some(Value::Int(10)), None]);
+        assert_eq!(spec, Expr::lam(Expr::add(Expr::int(10), Expr::idx(0))));
+        assert_eq!(eval(&Expr::app(spec, Expr::int(5))).unwrap(), Value::Int(15));
+    }
+
+    #[test]
+    fn test_specialize_folds_constants() {
+        // λ→ λ→ if ₁ > 0 then ₁ × 2 + ₀ else ₀, with ₁ = 3
+        let body = Expr::if_(
+            Expr::binop(BinOp::Gt, Expr::idx(1), Expr::int(0)),
+            Expr::add(Expr::mul(Expr::idx(1), Expr::int(2)), Expr::idx(0)),
+            Expr::idx(0),
+        );
+        let spec = specialize(&body, &[Some(Value::Int(3)), None]);
+        assert_eq!(spec, Expr::lam(Expr::add(Expr::int(6), Expr::idx(0))));
+        assert_eq!(specialize(&body, &[Some(Value::Int(3)), Some(Value::Int(1))]), Expr::int(7));
+    }
+
+    #[test]
+    fn test_specialize_reindexes_locals_and_free_variables() {
+        // Under λ→ λ→ λ→ with the middle argument known, ₂ and ₀ become ₁ and ₀
+        // and the free ₃ (the first variable outside the closure) becomes ₂
+        let body = Expr::let_(Pattern::var("y"), Expr::idx(3), Expr::tuple(vec![Expr::idx(0), Expr::idx(1), Expr::idx(2), Expr::idx(3)]));
+        let spec = specialize(&body, &[None, Some(Value::Bool(true)), None]);
+        let expected = Expr::lam_n(2, Expr::let_(Pattern::var("y"), Expr::idx(2), Expr::tuple(vec![Expr::idx(0), Expr::idx(1), Expr::bool(true), Expr::idx(2)])));
+        assert_eq!(spec, expected);
+    }
+
+    #[test]
+    fn test_specialize_keeps_values_without_literals() {
+        let body = Expr::app(Expr::idx(1), Expr::idx(0));
+        let closure = eval(&Expr::lam(Expr::idx(0))).unwrap();
+        assert_eq!(specialize(&body, &[Some(closure), Some(Value::Int(1))]), Expr::lam(Expr::app(Expr::idx(0), Expr::int(1))));
+    }
+}
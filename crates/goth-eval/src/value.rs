@@ -1,9 +1,10 @@
 //! Runtime values for Goth
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::rc::Rc;
 use std::cell::RefCell;
 use ordered_float::OrderedFloat;
+use crate::error::{EvalError, EvalResult};
 
 /// Runtime value
 #[derive(Debug, Clone, PartialEq)]
@@ -14,13 +15,18 @@ pub enum Value {
     Char(char),
     Unit,
     Tensor(Tensor),
+    /// Persistent cons list: elements may differ in type and prepending is O(1)
+    List(List),
     Tuple(Vec<Value>),
     Record(Rc<HashMap<String, Value>>),
     Variant { tag: String, payload: Option<Box<Value>> },
     Closure(Closure),
     Primitive(PrimFn),
+    /// `remaining` is 0 while a variadic primitive is still collecting arguments
     Partial { func: Box<Value>, args: Vec<Value>, remaining: usize },
     Thunk(Thunk),
+    /// A function whose results are cached by argument, made by `memoize`
+    Memo(Memo),
     Ref(Rc<RefCell<Value>>),
     Uncertain { value: Box<Value>, uncertainty: Box<Value> },
     Error(String),
@@ -50,24 +56,138 @@ pub struct Closure {
     pub postconditions: Vec<goth_ast::expr::Expr>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Persistent singly linked list; clones and `cons` share the tail
+#[derive(Clone, Default)]
+pub struct List(Option<Rc<(Value, List)>>);
+
+impl List {
+    pub fn nil() -> Self { List(None) }
+    pub fn cons(head: Value, tail: List) -> Self { List(Some(Rc::new((head, tail)))) }
+    pub fn head(&self) -> Option<&Value> { self.0.as_ref().map(|cell| &cell.0) }
+    pub fn tail(&self) -> Option<&List> { self.0.as_ref().map(|cell| &cell.1) }
+    pub fn is_empty(&self) -> bool { self.0.is_none() }
+    pub fn len(&self) -> usize { self.iter().count() }
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        std::iter::successors(self.0.as_deref(), |(_, tail)| tail.0.as_deref()).map(|(head, _)| head)
+    }
+}
+
+impl FromIterator<Value> for List {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        let items: Vec<Value> = iter.into_iter().collect();
+        items.into_iter().rev().fold(List::nil(), |tail, head| List::cons(head, tail))
+    }
+}
+
+/// Cell by cell, so comparing long lists cannot overflow the stack; a shared tail is equal without walking it
+impl PartialEq for List {
+    fn eq(&self, other: &Self) -> bool {
+        let (mut a, mut b) = (self, other);
+        loop {
+            match (&a.0, &b.0) {
+                (None, None) => return true,
+                (Some(x), Some(y)) if Rc::ptr_eq(x, y) => return true,
+                (Some(x), Some(y)) if x.0 == y.0 => { a = &x.1; b = &y.1; }
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.debug_list().entries(self.iter()).finish() }
+}
+
+impl Drop for List {
+    // Unlink cells iteratively so dropping a long list cannot overflow the stack
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(cell) = next {
+            next = match Rc::try_unwrap(cell) { Ok((_, mut tail)) => tail.0.take(), Err(_) => None };
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PrimFn {
-    Add, Sub, Mul, Div, Mod, Neg, Abs,
+    Add, Sub, Mul, Div, Mod, Neg, Abs, Sign,
     Eq, Neq, Lt, Gt, Leq, Geq,
     And, Or, Not,
     Exp, Ln, Sqrt, Sin, Cos, Tan, Pow, Floor, Ceil, Round,
-    Map, Filter, Fold, Scan, Zip, Concat, Reverse,
-    Sum, Prod, Len, Shape, Reshape, Transpose,
+    Map, Filter, Fold, Scan, Zip, ZipWith, Concat, ConcatAxis, Reverse, Sort, SortBy,
+    Sum, Prod, Len, Shape, Reshape, Transpose, TransposeAxes,
     Index, Slice, Take, Drop,
-    Iota, Range,  // Sequence generation
+    Iota, Range, RangeStep,  // Sequence generation
+    Fill, Zeros, Ones,  // Constant tensors
     MatMul, Dot, Outer, Inner, Norm,
-    Chars, ToString, StrConcat,
+    Chars, ToString, StrConcat, StrLen,
     Print, Write, Flush, ReadLine, ReadKey, ReadFile, WriteFile,
     RawModeEnter, RawModeExit, Sleep,
+    Trace,  // Identity that records a labelled value for the host
     ToInt, ToFloat, ToBool, ToChar,
+    Ord, Chr,  // Code points, converting only Char to Int and back
     ParseInt, ParseFloat,  // String parsing
     Lines, Words, Bytes,  // String splitting for wc
     StrEq, StartsWith, EndsWith, Contains,  // String comparison
+    SumAxis, MeanAxis, MaxAxis,  // Reductions along one axis
+    Mean, Variance, VarianceSample, Std,  // Statistics
+    ArgMin, ArgMax,
+    Normalize, Softmax,
+    Min, Max,  // Variadic
+    Clamp,
+    Cons, Head, Tail, IsEmpty, ToList, ToTensor,  // Lists
+    Memoize,
+    Native(NativePrim),  // Registered by the host
+}
+
+/// A host function: receives the evaluated arguments, all at once. It may capture host state.
+pub type NativeFn = Rc<dyn Fn(&PrimContext, &[Value]) -> EvalResult<Value>>;
+
+/// A host function registered with [`Evaluator::register_prim`](crate::eval::Evaluator::register_prim)
+#[derive(Clone)]
+pub struct NativePrim {
+    pub name: Rc<str>,
+    pub arity: usize,
+    pub f: NativeFn,
+}
+
+/// By name and identity: copies of one registration are equal, separate registrations are not
+impl PartialEq for NativePrim {
+    fn eq(&self, other: &Self) -> bool { self.name == other.name && self.arity == other.arity && Rc::ptr_eq(&self.f, &other.f) }
+}
+impl Eq for NativePrim {}
+impl std::hash::Hash for NativePrim {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) { self.name.hash(state); self.arity.hash(state); }
+}
+
+impl std::fmt::Debug for NativePrim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "native {}/{}", self.name, self.arity) }
+}
+
+/// Passed to a [`NativeFn`] to build errors that name the primitive
+#[derive(Debug, Clone)]
+pub struct PrimContext {
+    pub name: Rc<str>,
+}
+
+impl PrimContext {
+    /// Argument `arg_index` (counting from 0) is not what the primitive accepts,
+    /// e.g. `triple: argument 1 expected Int, got Bool`
+    pub fn type_error(&self, arg_index: usize, expected: &'static str, actual: &Value) -> EvalError {
+        EvalError::ArgumentType { prim: self.name.to_string(), arg: arg_index, expected, got: actual.type_name() }
+    }
+}
+
+impl PrimFn {
+    /// Effects of calling the primitive: console, file and terminal access are `◇io`, the rest pure
+    pub fn effects(&self) -> goth_ast::effect::Effects {
+        use goth_ast::effect::{Effect, Effects};
+        match self {
+            PrimFn::Print | PrimFn::Write | PrimFn::Flush | PrimFn::ReadLine | PrimFn::ReadKey | PrimFn::ReadFile | PrimFn::WriteFile
+            | PrimFn::RawModeEnter | PrimFn::RawModeExit | PrimFn::Sleep => Effects::single(Effect::Io),
+            _ => Effects::pure(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -77,10 +197,39 @@ pub struct Thunk {
     pub cached: Option<Rc<Value>>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// A function wrapped by `memoize`. Copies share one cache, filled on each call whose argument is a
+/// [`HashableValue`](crate::hash::HashableValue); other arguments are recomputed every time.
+#[derive(Debug, Clone)]
+pub struct Memo {
+    pub func: Box<Value>,
+    pub cache: Rc<RefCell<HashMap<crate::hash::HashableValue, Value>>>,
+}
+
+impl Memo {
+    pub fn new(func: Value) -> Self { Memo { func: Box::new(func), cache: Rc::default() } }
+}
+
+/// Two memoized functions are equal when they share a cache
+impl PartialEq for Memo { fn eq(&self, other: &Self) -> bool { Rc::ptr_eq(&self.cache, &other.cache) } }
+
+/// Local bindings as a persistent stack of chunks. Pushing appends to the top chunk while this
+/// environment owns it alone and starts a new chunk once it is shared, so pushing and cloning are
+/// O(1), closures capture frames without copying them, and a lookup only walks the chunks above it.
+#[derive(Clone, Default)]
 pub struct Env {
-    pub values: Vec<Value>,
-    globals: Rc<RefCell<HashMap<String, Value>>>,
+    top: Option<Rc<Chunk>>,
+    /// How many of the top chunk's values this environment sees
+    top_len: usize,
+    len: usize,
+    pub(crate) globals: Rc<RefCell<HashMap<String, Value>>>,
+}
+
+/// Bindings pushed in a row, outermost first, linked to the chunk beneath them
+struct Chunk {
+    values: Vec<Value>,
+    below: Option<Rc<Chunk>>,
+    /// How many of the lower chunk's values are visible from this one
+    below_len: usize,
 }
 
 impl Value {
@@ -110,7 +259,7 @@ impl Value {
     pub fn is_numeric(&self) -> bool { matches!(self, Value::Int(_) | Value::Float(_)) }
     pub fn is_bool(&self) -> bool { matches!(self, Value::Bool(_)) }
     pub fn is_tensor(&self) -> bool { matches!(self, Value::Tensor(_)) }
-    pub fn is_callable(&self) -> bool { matches!(self, Value::Closure(_) | Value::Primitive(_) | Value::Partial { .. }) }
+    pub fn is_callable(&self) -> bool { matches!(self, Value::Closure(_) | Value::Primitive(_) | Value::Partial { .. } | Value::Memo(_)) }
     pub fn is_error(&self) -> bool { matches!(self, Value::Error(_)) }
 
     pub fn as_int(&self) -> Option<i128> { match self { Value::Int(n) => Some(*n), _ => None } }
@@ -124,10 +273,10 @@ impl Value {
     pub fn type_name(&self) -> &'static str {
         match self {
             Value::Int(_) => "Int", Value::Float(_) => "Float", Value::Bool(_) => "Bool",
-            Value::Char(_) => "Char", Value::Unit => "Unit", Value::Tensor(_) => "Tensor",
+            Value::Char(_) => "Char", Value::Unit => "Unit", Value::Tensor(_) => "Tensor", Value::List(_) => "List",
             Value::Tuple(_) => "Tuple", Value::Record(_) => "Record", Value::Variant { .. } => "Variant",
             Value::Closure(_) => "Closure", Value::Primitive(_) => "Primitive",
-            Value::Partial { .. } => "Partial", Value::Thunk(_) => "Thunk",
+            Value::Partial { .. } => "Partial", Value::Thunk(_) => "Thunk", Value::Memo(_) => "Memo",
             Value::Ref(_) => "Ref", Value::Uncertain { .. } => "Uncertain", Value::Error(_) => "Error",
         }
     }
@@ -139,8 +288,11 @@ impl Value {
             (Value::Bool(a), Value::Bool(b)) => a == b,
             (Value::Char(a), Value::Char(b)) => a == b,
             (Value::Unit, Value::Unit) => true,
-            (Value::Tensor(a), Value::Tensor(b)) => a == b,
+            // Shapes first, so equal data in a different shape is unequal; then elements, whatever their storage
+            (Value::Tensor(a), Value::Tensor(b)) => a.shape == b.shape && (a.data == b.data || a.iter().zip(b.iter()).all(|(x, y)| x.deep_eq(&y))),
+            (Value::List(a), Value::List(b)) => a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.deep_eq(y)),
             (Value::Tuple(a), Value::Tuple(b)) => a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.deep_eq(y)),
+            (Value::Record(a), Value::Record(b)) => a.len() == b.len() && a.iter().all(|(k, x)| b.get(k).is_some_and(|y| x.deep_eq(y))),
             (Value::Variant { tag: t1, payload: p1 }, Value::Variant { tag: t2, payload: p2 }) => {
                 t1 == t2 && match (p1, p2) { (None, None) => true, (Some(a), Some(b)) => a.deep_eq(b), _ => false }
             }
@@ -167,6 +319,15 @@ impl Tensor {
     pub fn from_bools(data: Vec<bool>) -> Self { let len = data.len(); Tensor { shape: vec![len], data: TensorData::Bool(data) } }
     pub fn from_string(s: &str) -> Self { let chars: Vec<char> = s.chars().collect(); let len = chars.len(); Tensor { shape: vec![len], data: TensorData::Char(chars) } }
     pub fn from_values(shape: Vec<usize>, data: Vec<Value>) -> Self { Tensor { shape, data: TensorData::Generic(data) } }
+    /// Like `from_values`, but uses typed storage when all elements are Int, Float, Bool or Char
+    pub fn pack(shape: Vec<usize>, data: Vec<Value>) -> Self {
+        let data = if data.iter().all(|v| matches!(v, Value::Int(_))) { TensorData::Int(data.iter().filter_map(Value::as_int).collect()) }
+            else if data.iter().all(|v| matches!(v, Value::Float(_))) { TensorData::Float(data.iter().filter_map(|v| v.as_float().map(OrderedFloat)).collect()) }
+            else if data.iter().all(|v| matches!(v, Value::Bool(_))) { TensorData::Bool(data.iter().filter_map(Value::as_bool).collect()) }
+            else if data.iter().all(|v| matches!(v, Value::Char(_))) { TensorData::Char(data.iter().filter_map(Value::as_char).collect()) }
+            else { TensorData::Generic(data) };
+        Tensor { shape, data }
+    }
     pub fn from_matrix(rows: Vec<Vec<f64>>) -> Self {
         let m = rows.len(); let n = rows.first().map(|r| r.len()).unwrap_or(0);
         let data: Vec<OrderedFloat<f64>> = rows.into_iter().flat_map(|row| row.into_iter().map(OrderedFloat)).collect();
@@ -224,7 +385,7 @@ impl Tensor {
 
     pub fn sum(&self) -> Value {
         match &self.data {
-            TensorData::Int(v) => Value::Int(v.iter().copied().sum()),
+            TensorData::Int(v) => v.iter().try_fold(0i128, |acc, &x| acc.checked_add(x).filter(|n| i64::try_from(*n).is_ok())).map_or_else(|| Value::Error("Integer overflow in sum".into()), Value::Int),
             TensorData::Float(v) => Value::Float(OrderedFloat(v.iter().map(|x| x.0).sum())),
             TensorData::Bool(v) => Value::Int(v.iter().filter(|&&b| b).count() as i128),
             TensorData::Generic(v) => {
@@ -252,7 +413,7 @@ impl Tensor {
 
     pub fn product(&self) -> Value {
         match &self.data {
-            TensorData::Int(v) => Value::Int(v.iter().copied().product()),
+            TensorData::Int(v) => v.iter().try_fold(1i128, |acc, &x| acc.checked_mul(x).filter(|n| i64::try_from(*n).is_ok())).map_or_else(|| Value::Error("Integer overflow in product".into()), Value::Int),
             TensorData::Float(v) => Value::Float(OrderedFloat(v.iter().map(|x| x.0).product())),
             _ => Value::Error("Cannot multiply non-numeric tensor".into()),
         }
@@ -260,23 +421,140 @@ impl Tensor {
 }
 
 impl Env {
-    pub fn new() -> Self { Env { values: Vec::new(), globals: Rc::new(RefCell::new(HashMap::new())) } }
-    pub fn with_globals(globals: Rc<RefCell<HashMap<String, Value>>>) -> Self { Env { values: Vec::new(), globals } }
-    pub fn push(&mut self, value: Value) { self.values.push(value); }
-    pub fn push_many(&mut self, values: impl IntoIterator<Item = Value>) { self.values.extend(values); }
-    pub fn pop(&mut self) -> Option<Value> { self.values.pop() }
-    pub fn pop_n(&mut self, n: usize) { for _ in 0..n { self.values.pop(); } }
-    pub fn get(&self, idx: u32) -> Option<&Value> { let len = self.values.len(); if (idx as usize) < len { Some(&self.values[len - 1 - idx as usize]) } else { None } }
+    pub fn new() -> Self { Env::with_globals(Rc::new(RefCell::new(HashMap::new()))) }
+    pub fn with_globals(globals: Rc<RefCell<HashMap<String, Value>>>) -> Self { Env { top: None, top_len: 0, len: 0, globals } }
+    pub fn push(&mut self, value: Value) {
+        match self.top.as_mut().and_then(Rc::get_mut) {
+            Some(chunk) => { chunk.values.truncate(self.top_len); chunk.values.push(value); self.top_len += 1; }
+            None => { self.top = Some(Rc::new(Chunk { values: vec![value], below: self.top.take(), below_len: self.top_len })); self.top_len = 1; }
+        }
+        self.len += 1;
+    }
+    pub fn push_many(&mut self, values: impl IntoIterator<Item = Value>) { for value in values { self.push(value); } }
+    /// Remove the innermost binding, moving it out unless another environment shares its chunk
+    pub fn pop(&mut self) -> Option<Value> {
+        let top = self.top.as_mut()?;
+        self.top_len -= 1;
+        self.len -= 1;
+        let value = match Rc::get_mut(top) {
+            Some(chunk) => { chunk.values.truncate(self.top_len + 1); chunk.values.pop() }
+            None => top.values.get(self.top_len).cloned(),
+        };
+        if self.top_len == 0 { self.step_down(); }
+        value
+    }
+    pub fn pop_n(&mut self, n: usize) {
+        let mut n = n.min(self.len);
+        while n > 0 {
+            let drop = n.min(self.top_len);
+            self.top_len -= drop;
+            self.len -= drop;
+            n -= drop;
+            if self.top_len == 0 { self.step_down(); }
+            else if let Some(chunk) = self.top.as_mut().and_then(Rc::get_mut) { chunk.values.truncate(self.top_len); }
+        }
+    }
+    /// Leave the emptied top chunk for the one beneath it
+    fn step_down(&mut self) {
+        if let Some(chunk) = self.top.take() { self.top = chunk.below.clone(); self.top_len = chunk.below_len; }
+    }
+    pub fn get(&self, idx: u32) -> Option<&Value> {
+        let mut idx = idx as usize;
+        let (mut chunk, mut visible) = (self.top.as_deref()?, self.top_len);
+        while idx >= visible {
+            idx -= visible;
+            visible = chunk.below_len;
+            chunk = chunk.below.as_deref()?;
+        }
+        chunk.values.get(visible - 1 - idx)
+    }
     pub fn get_global(&self, name: &str) -> Option<Value> { self.globals.borrow().get(name).cloned() }
     pub fn define_global(&self, name: impl Into<String>, value: Value) { self.globals.borrow_mut().insert(name.into(), value); }
-    pub fn depth(&self) -> usize { self.values.len() }
-    pub fn capture(&self) -> Self { Env { values: self.values.clone(), globals: Rc::clone(&self.globals) } }
-    pub fn extend(&self, other: &Env) -> Self { let mut values = self.values.clone(); values.extend(other.values.iter().cloned()); Env { values, globals: Rc::clone(&self.globals) } }
+    pub fn depth(&self) -> usize { self.len }
+    /// Bound values from innermost (index 0) outwards
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        std::iter::successors(self.top.as_deref().map(|chunk| (chunk, self.top_len)), |(chunk, _)| chunk.below.as_deref().map(|below| (below, chunk.below_len)))
+            .flat_map(|(chunk, visible)| chunk.values[..visible].iter().rev())
+    }
+    /// Bound values from outermost to innermost, the order they were pushed in. Borrowed when they
+    /// all sit in one chunk; otherwise this copies every binding.
+    #[deprecated(note = "bindings are no longer stored in one vector; use `Env::iter` or `Env::get`")]
+    pub fn values(&self) -> std::borrow::Cow<'_, [Value]> {
+        match self.top.as_deref() {
+            Some(chunk) if chunk.below.is_none() => std::borrow::Cow::Borrowed(&chunk.values[..self.top_len]),
+            None => std::borrow::Cow::Borrowed(&[]),
+            Some(_) => std::borrow::Cow::Owned(self.outermost_first()),
+        }
+    }
+    pub(crate) fn outermost_first(&self) -> Vec<Value> { let mut values: Vec<Value> = self.iter().cloned().collect(); values.reverse(); values }
+    pub fn capture(&self) -> Self { self.clone() }
+    pub fn extend(&self, other: &Env) -> Self { let mut env = self.clone(); env.push_many(other.outermost_first()); env }
+    /// Capture only the slots at `indices`. Slots deeper than the largest index are dropped and
+    /// unreferenced slots in between hold `Unit`, so de Bruijn indices into the result stay valid.
+    pub fn capture_only(&self, indices: &BTreeSet<u32>) -> Self {
+        let keep = indices.iter().next_back().map_or(0, |&max| (max as usize + 1).min(self.len));
+        let kept: Vec<Value> = self.iter().take(keep).enumerate().map(|(i, v)| if indices.contains(&(i as u32)) { v.clone() } else { Value::Unit }).collect();
+        let mut env = Env::with_globals(Rc::clone(&self.globals));
+        env.push_many(kept.into_iter().rev());
+        env
+    }
+}
+
+/// Unlink chunks one at a time so dropping a long chain of environments doesn't recurse once per chunk
+impl Drop for Env {
+    fn drop(&mut self) {
+        let mut next = self.top.take();
+        while let Some(chunk) = next {
+            next = match Rc::try_unwrap(chunk) { Ok(mut chunk) => chunk.below.take(), Err(_) => None };
+        }
+    }
+}
+
+impl std::fmt::Debug for Env {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Env").field("values", &self.outermost_first()).field("globals", &self.globals).finish()
+    }
+}
+
+impl Closure {
+    /// Captured values the body and contracts can reach, as (de Bruijn index, value) pairs
+    pub fn captures(&self) -> Vec<(u32, &Value)> {
+        let mut free = crate::capture::free_variables(&self.body, self.arity);
+        for pre in &self.preconditions { free.extend(crate::capture::free_variables(pre, self.arity)); }
+        for post in &self.postconditions { free.extend(crate::capture::free_variables(post, self.arity + 1)); }
+        free.into_iter().filter_map(|i| self.env.get(i).map(|v| (i, v))).collect()
+    }
+
+    /// Captured values rendered for display, cut off after `MAX_CAPTURE_SUMMARY` characters
+    pub fn capture_summary(&self) -> String {
+        let mut summary = String::new();
+        for (i, (_, v)) in self.captures().into_iter().enumerate() {
+            if i > 0 { summary.push_str(", "); }
+            summary.push_str(&v.to_string());
+            if summary.chars().count() > MAX_CAPTURE_SUMMARY {
+                return summary.chars().take(MAX_CAPTURE_SUMMARY).chain(std::iter::once('…')).collect();
+            }
+        }
+        summary
+    }
 }
 
-impl PartialEq for Closure { fn eq(&self, other: &Self) -> bool { self.arity == other.arity && self.body == other.body } }
+/// Longest capture summary shown by the verbose closure form before truncating
+const MAX_CAPTURE_SUMMARY: usize = 60;
+
+/// Structural: same arity, alpha-equal body and contracts, and equal captured values.
+/// Captures are compared recursively, so this can be as expensive as comparing the whole environment.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        let contracts_eq = |a: &[goth_ast::expr::Expr], b: &[goth_ast::expr::Expr]| a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.alpha_eq(y));
+        self.arity == other.arity && self.body.alpha_eq(&other.body) && self.env.iter().eq(other.env.iter())
+            && contracts_eq(&self.preconditions, &other.preconditions) && contracts_eq(&self.postconditions, &other.postconditions)
+    }
+}
 impl PartialEq for Thunk { fn eq(&self, other: &Self) -> bool { self.expr == other.expr } }
 
+/// The alternate form (`{:#}`) renders closures with a summary of their captures,
+/// e.g. `<closure/2 captures [10, [1, 2, 3]]>`; the default form stays `<closure/2>`.
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -285,15 +563,18 @@ impl std::fmt::Display for Value {
             Value::Bool(true) => write!(f, "⊤"),
             Value::Bool(false) => write!(f, "⊥"),
             Value::Char(c) => write!(f, "'{}'", c),
-            Value::Unit => write!(f, "⟨⟩"),
+            Value::Unit => write!(f, "()"),
             Value::Tensor(t) => write!(f, "{}", t),
-            Value::Tuple(vs) => { write!(f, "⟨")?; for (i, v) in vs.iter().enumerate() { if i > 0 { write!(f, ", ")?; } write!(f, "{}", v)?; } write!(f, "⟩") }
-            Value::Record(fields) => { write!(f, "⟨")?; for (i, (k, v)) in fields.iter().enumerate() { if i > 0 { write!(f, ", ")?; } write!(f, "{}: {}", k, v)?; } write!(f, "⟩") }
-            Value::Variant { tag, payload } => { write!(f, "{}", tag)?; if let Some(p) = payload { write!(f, " {}", p)?; } Ok(()) }
+            Value::List(l) => { write!(f, "(")?; for v in l.iter() { write!(f, "{} :: ", v)?; } write!(f, "nil)") }
+            Value::Tuple(vs) => { write!(f, "⟨")?; for (i, v) in vs.iter().enumerate() { if i > 0 { write!(f, ", ")?; } v.fmt(f)?; } write!(f, "⟩") }
+            Value::Record(fields) => { write!(f, "⟨")?; for (i, (k, v)) in fields.iter().enumerate() { if i > 0 { write!(f, ", ")?; } write!(f, "{}: ", k)?; v.fmt(f)?; } write!(f, "⟩") }
+            Value::Variant { tag, payload } => { write!(f, "{}", tag)?; if let Some(p) = payload { write!(f, " ")?; p.fmt(f)?; } Ok(()) }
+            Value::Closure(c) if f.alternate() => write!(f, "<closure/{} captures [{}]>", c.arity, c.capture_summary()),
             Value::Closure(c) => write!(f, "<closure/{}>", c.arity),
             Value::Primitive(p) => write!(f, "<prim:{:?}>", p),
             Value::Partial { remaining, .. } => write!(f, "<partial/{}>", remaining),
             Value::Thunk(_) => write!(f, "<thunk>"),
+            Value::Memo(_) => write!(f, "<memo>"),
             Value::Ref(_) => write!(f, "<ref>"),
             Value::Uncertain { value, uncertainty } => write!(f, "{}±{}", value, uncertainty),
             Value::Error(msg) => write!(f, "Error: {}", msg),
@@ -304,8 +585,21 @@ impl std::fmt::Display for Value {
 impl std::fmt::Display for Tensor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(s) = self.to_string_value() { return write!(f, "\"{}\"", s); }
-        if self.shape.is_empty() { if let Some(v) = self.get_flat(0) { return write!(f, "{}", v); } }
-        if self.shape.len() == 1 { write!(f, "[")?; for (i, v) in self.iter().enumerate() { if i > 0 { write!(f, " ")?; } write!(f, "{}", v)?; } return write!(f, "]"); }
-        write!(f, "<tensor {:?}>", self.shape)
+        if self.shape.is_empty() { return match self.get_flat(0) { Some(v) => v.fmt(f), None => write!(f, "[]") }; }
+        self.fmt_axis(f, 0, 0)
+    }
+}
+
+impl Tensor {
+    /// Write the slice along `axis` starting at flat index `offset`, one bracket level per axis
+    fn fmt_axis(&self, f: &mut std::fmt::Formatter<'_>, axis: usize, offset: usize) -> std::fmt::Result {
+        let stride: usize = self.shape[axis + 1..].iter().product();
+        write!(f, "[")?;
+        for i in 0..self.shape[axis] {
+            if i > 0 { write!(f, ", ")?; }
+            if axis + 1 < self.shape.len() { self.fmt_axis(f, axis + 1, offset + i * stride)?; }
+            else if let Some(v) = self.get_flat(offset + i) { std::fmt::Display::fmt(&v, f)?; }
+        }
+        write!(f, "]")
     }
 }
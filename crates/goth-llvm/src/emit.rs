@@ -176,6 +176,7 @@ fn find_multi_block_locals(func: &Function) -> HashMap<LocalId, Type> {
                 collect_operand_locals(right, block_id, uses);
             }
             Rhs::UnaryOp(_, op) => collect_operand_locals(op, block_id, uses),
+            Rhs::Cast { operand, .. } => collect_operand_locals(operand, block_id, uses),
             Rhs::Call { args, .. } => {
                 for arg in args {
                     collect_operand_locals(arg, block_id, uses);
@@ -447,6 +448,9 @@ fn emit_constant(ctx: &mut LlvmContext, constant: &Constant, ty: &Type) -> Resul
             let val = if *b { "1" } else { "0" };
             return Ok((val.to_string(), String::new()));
         }
+        Constant::Char(c) => {
+            return Ok(((*c as u32).to_string(), String::new()));
+        }
         Constant::String(s) => {
             // Add string to the global string literals
             // Return a GEP to get i8* from the array constant
@@ -725,6 +729,7 @@ fn emit_stmt(ctx: &mut LlvmContext, stmt: &Stmt, output: &mut String) -> Result<
                                 Constant::Int(_) => Type::Prim(PrimType::I64),
                                 Constant::Float(_) => Type::Prim(PrimType::F64),
                                 Constant::Bool(_) => Type::Prim(PrimType::Bool),
+                                Constant::Char(_) => Type::Prim(PrimType::Char),
                                 Constant::String(_) => Type::Prim(PrimType::String),
                                 Constant::Unit => Type::Tuple(vec![]),
                             },
@@ -812,6 +817,18 @@ fn emit_stmt(ctx: &mut LlvmContext, stmt: &Stmt, output: &mut String) -> Result<
             }
         }
 
+        Rhs::Cast { from, to, operand } => {
+            let op_val = emit_operand(ctx, operand, from, output)?;
+            let cast_op = match (from, to) {
+                (Type::Prim(f), Type::Prim(t)) if f.is_int() && t.is_float() => "sitofp",
+                (Type::Prim(f), Type::Prim(t)) if f.is_float() && t.is_int() => "fptosi",
+                _ => return Err(LlvmError::UnsupportedOp(format!("Cast from {} to {}", from, to))),
+            };
+            let ssa = ctx.fresh_ssa();
+            let code = format!("  {} = {} {} {} to {}\n", ssa, cast_op, emit_type(from)?, op_val, emit_type(to)?);
+            (ssa, code)
+        }
+
         Rhs::UnaryOp(op, operand) => {
             let op_val = emit_operand(ctx, operand, &stmt.ty, output)?;
             let llvm_ty = emit_type(&stmt.ty)?;
@@ -1391,6 +1408,7 @@ fn emit_stmt(ctx: &mut LlvmContext, stmt: &Stmt, output: &mut String) -> Result<
                                 Constant::Int(_) => Type::Prim(PrimType::I64),
                                 Constant::Float(_) => Type::Prim(PrimType::F64),
                                 Constant::Bool(_) => Type::Prim(PrimType::Bool),
+                                Constant::Char(_) => Type::Prim(PrimType::Char),
                                 Constant::String(_) => Type::Prim(PrimType::String),
                                 Constant::Unit => Type::Tuple(vec![]),
                             },
@@ -1513,6 +1531,7 @@ fn emit_stmt(ctx: &mut LlvmContext, stmt: &Stmt, output: &mut String) -> Result<
                         Constant::Int(_) => Type::Prim(PrimType::I64),
                         Constant::Float(_) => Type::Prim(PrimType::F64),
                         Constant::Bool(_) => Type::Prim(PrimType::Bool),
+                        Constant::Char(_) => Type::Prim(PrimType::Char),
                         Constant::String(_) => Type::Prim(PrimType::String),
                         Constant::Unit => Type::Tuple(vec![]),
                     },
@@ -1837,10 +1856,7 @@ fn emit_block(
                 scrut_val, default.0
             ));
             for (val, block_id) in cases {
-                let val_str = match val {
-                    Constant::Int(n) => n.to_string(),
-                    _ => "0".to_string(),
-                };
+                let val_str = val.as_int().unwrap_or(0).to_string();
                 output.push_str(&format!("    i64 {}, label %bb{}\n", val_str, block_id.0));
             }
             output.push_str("  ]\n");
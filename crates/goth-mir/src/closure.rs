@@ -31,22 +31,30 @@
 
 use crate::mir::*;
 use crate::error::{MirError, MirResult};
-use goth_ast::expr::Expr;
-use std::collections::HashSet;
+use goth_ast::expr::{DoOp, Expr};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
-/// Free variable analysis
+/// De Bruijn indices that refer outside `expr`, relative to the scope `expr` appears in
 pub fn free_variables(expr: &Expr) -> HashSet<u32> {
     let mut free = HashSet::new();
-    free_vars_impl(expr, 0, &mut free);
+    free_vars_impl(expr, 0, &mut |idx, _| { free.insert(idx); });
     free
 }
 
-fn free_vars_impl(expr: &Expr, depth: u32, free: &mut HashSet<u32>) {
+/// Each free index of `expr`, with the binder depths it occurs at. An occurrence
+/// under `d` binders is written `idx + d` but still refers to outer binding `idx`.
+pub fn free_variables_with_depth(expr: &Expr) -> BTreeMap<u32, BTreeSet<u32>> {
+    let mut free: BTreeMap<u32, BTreeSet<u32>> = BTreeMap::new();
+    free_vars_impl(expr, 0, &mut |idx, depth| { free.entry(idx).or_default().insert(depth); });
+    free
+}
+
+fn free_vars_impl(expr: &Expr, depth: u32, free: &mut impl FnMut(u32, u32)) {
     match expr {
         Expr::Idx(idx) => {
             // If de Bruijn index points outside current depth, it's free
             if *idx >= depth {
-                free.insert(*idx - depth);
+                free(*idx - depth, depth);
             }
         }
         
@@ -54,6 +62,10 @@ fn free_vars_impl(expr: &Expr, depth: u32, free: &mut HashSet<u32>) {
             // Increase depth under lambda
             free_vars_impl(body, depth + 1, free);
         }
+
+        Expr::LamN(arity, body) => {
+            free_vars_impl(body, depth + arity, free);
+        }
         
         Expr::App(func, arg) => {
             free_vars_impl(func, depth, free);
@@ -61,16 +73,39 @@ fn free_vars_impl(expr: &Expr, depth: u32, free: &mut HashSet<u32>) {
         }
         
         Expr::Let { value, body, .. } => {
+            // Lowering binds the whole value to one local, whatever the pattern
             free_vars_impl(value, depth, free);
             free_vars_impl(body, depth + 1, free);
         }
+
+        Expr::LetRec { bindings, body } => {
+            // Every binding is in scope in every right-hand side and the body
+            let inner = depth + bindings.len() as u32;
+            for (_, value) in bindings {
+                free_vars_impl(value, inner, free);
+            }
+            free_vars_impl(body, inner, free);
+        }
+
+        Expr::Match { scrutinee, arms } => {
+            free_vars_impl(scrutinee, depth, free);
+            for arm in arms {
+                // The guard and body see the variables the pattern binds
+                let inner = depth + arm.pattern.binding_count() as u32;
+                if let Some(guard) = &arm.guard {
+                    free_vars_impl(guard, inner, free);
+                }
+                free_vars_impl(&arm.body, inner, free);
+            }
+        }
         
         Expr::BinOp(_, left, right) => {
             free_vars_impl(left, depth, free);
             free_vars_impl(right, depth, free);
         }
         
-        Expr::UnaryOp(_, operand) => {
+        Expr::UnaryOp(_, operand) | Expr::Norm(operand) | Expr::Field(operand, _) | Expr::Annot(operand, _)
+        | Expr::Cast { expr: operand, .. } | Expr::Disabled(operand) | Expr::Quote(operand) | Expr::Unquote(operand) => {
             free_vars_impl(operand, depth, free);
         }
         
@@ -85,9 +120,59 @@ fn free_vars_impl(expr: &Expr, depth: u32, free: &mut HashSet<u32>) {
                 free_vars_impl(expr, depth, free);
             }
         }
+
+        Expr::Record(fields) => {
+            for (_, expr) in fields {
+                free_vars_impl(expr, depth, free);
+            }
+        }
+
+        Expr::Update { base, fields } | Expr::AppNamed(base, fields) => {
+            free_vars_impl(base, depth, free);
+            for (_, expr) in fields {
+                free_vars_impl(expr, depth, free);
+            }
+        }
+
+        Expr::ArrayFill { shape, value } => {
+            for expr in shape {
+                free_vars_impl(expr, depth, free);
+            }
+            free_vars_impl(value, depth, free);
+        }
+
+        Expr::Variant { payload, .. } => {
+            if let Some(payload) = payload {
+                free_vars_impl(payload, depth, free);
+            }
+        }
+
+        Expr::Index(base, indices) => {
+            free_vars_impl(base, depth, free);
+            for index in indices {
+                free_vars_impl(index, depth, free);
+            }
+        }
+
+        Expr::Slice { array, start, end } => {
+            free_vars_impl(array, depth, free);
+            for bound in [start, end].into_iter().flatten() {
+                free_vars_impl(bound, depth, free);
+            }
+        }
+
+        Expr::Do { init, ops } => {
+            // Every step is evaluated in the enclosing scope
+            free_vars_impl(init, depth, free);
+            for op in ops {
+                match op {
+                    DoOp::Map(e) | DoOp::Filter(e) | DoOp::Bind(e) | DoOp::Op(_, e) | DoOp::Let(_, e) => free_vars_impl(e, depth, free),
+                }
+            }
+        }
         
-        // Literals, names, etc. have no free variables
-        _ => {}
+        // Literals, names and primitives have no free variables
+        Expr::Name(_) | Expr::Lit(_) | Expr::Prim(_) | Expr::Hole | Expr::Unknown(_) => {}
     }
 }
 
@@ -95,6 +180,9 @@ fn free_vars_impl(expr: &Expr, depth: u32, free: &mut HashSet<u32>) {
 mod tests {
     use super::*;
     use goth_ast::literal::Literal;
+    use goth_ast::op::BinOp;
+    use goth_ast::expr::MatchArm;
+    use goth_ast::pattern::Pattern;
     
     #[test]
     fn test_free_vars_closed() {
@@ -123,4 +211,61 @@ mod tests {
         assert_eq!(free.len(), 1);
         assert!(free.contains(&0));
     }
+
+    #[test]
+    fn test_free_vars_with_depth() {
+        // λ→ λ→ ₂ + ₃: outer bindings 0 and 1, both seen under two binders
+        let expr = Expr::Lam(Box::new(Expr::Lam(Box::new(Expr::BinOp(BinOp::Add, Box::new(Expr::Idx(2)), Box::new(Expr::Idx(3)))))));
+        let free = free_variables_with_depth(&expr);
+        assert_eq!(free, BTreeMap::from([(0, BTreeSet::from([2])), (1, BTreeSet::from([2]))]));
+        assert!(free_variables_with_depth(&Expr::Lam(Box::new(Expr::Idx(0)))).is_empty());
+    }
+
+    #[test]
+    fn test_free_vars_let_body_is_one_deeper() {
+        // let x = ₀ in x + ₁: both ₀ in the value and ₁ in the body are outer binding 0
+        let body = Expr::BinOp(BinOp::Add, Box::new(Expr::Idx(0)), Box::new(Expr::Idx(1)));
+        let expr = Expr::let_var("x", Expr::Idx(0), body);
+        assert_eq!(free_variables(&expr), HashSet::from([0]));
+        assert_eq!(free_variables_with_depth(&expr), BTreeMap::from([(0, BTreeSet::from([0, 1]))]));
+    }
+
+    fn arm(pattern: Pattern, body: Expr) -> MatchArm {
+        MatchArm { pattern, guard: None, body }
+    }
+
+    #[test]
+    fn test_free_vars_in_match_arm() {
+        // λ→ match ₀ { 0 → ₁, n → n + ₂ }: both ₁ and ₂ are outer binding 0
+        let arms = vec![
+            arm(Pattern::Lit(Literal::Int(0)), Expr::Idx(1)),
+            arm(Pattern::Var(Some("n".into())), Expr::BinOp(BinOp::Add, Box::new(Expr::Idx(0)), Box::new(Expr::Idx(2)))),
+        ];
+        let expr = Expr::Lam(Box::new(Expr::Match { scrutinee: Box::new(Expr::Idx(0)), arms }));
+        assert_eq!(free_variables(&expr), HashSet::from([0]));
+        assert_eq!(free_variables_with_depth(&expr), BTreeMap::from([(0, BTreeSet::from([1, 2]))]));
+    }
+
+    #[test]
+    fn test_free_vars_in_nested_pattern() {
+        // match ₀ { ⟨a, Some b⟩ → a + b + ₂ }: the pattern binds two, so ₂ is outer binding 0
+        let pattern = Pattern::Tuple(vec![
+            Pattern::Var(Some("a".into())),
+            Pattern::Variant { constructor: "Some".into(), payload: Some(Box::new(Pattern::Var(Some("b".into())))) },
+        ]);
+        let sum = Expr::BinOp(BinOp::Add, Box::new(Expr::BinOp(BinOp::Add, Box::new(Expr::Idx(1)), Box::new(Expr::Idx(0)))), Box::new(Expr::Idx(2)));
+        let expr = Expr::Match { scrutinee: Box::new(Expr::Idx(0)), arms: vec![arm(pattern, sum)] };
+        assert_eq!(free_variables(&expr), HashSet::from([0]));
+        // A wildcard binds nothing, so ₀ under it is already free
+        let wildcard = Expr::Match { scrutinee: Box::new(Expr::Idx(3)), arms: vec![arm(Pattern::Wildcard, Expr::Idx(0))] };
+        assert_eq!(free_variables(&wildcard), HashSet::from([0, 3]));
+    }
+
+    #[test]
+    fn test_free_vars_in_let_rec() {
+        // let rec f = λ→ f ₀ + ₂ in f ₁: f occupies one slot everywhere
+        let f = Expr::Lam(Box::new(Expr::BinOp(BinOp::Add, Box::new(Expr::App(Box::new(Expr::Idx(1)), Box::new(Expr::Idx(0)))), Box::new(Expr::Idx(2)))));
+        let expr = Expr::LetRec { bindings: vec![(Pattern::Var(Some("f".into())), f)], body: Box::new(Expr::App(Box::new(Expr::Idx(0)), Box::new(Expr::Idx(1)))) };
+        assert_eq!(free_variables(&expr), HashSet::from([0]));
+    }
 }
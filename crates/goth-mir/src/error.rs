@@ -24,6 +24,15 @@ pub enum MirError {
     #[error("Type error during lowering: {0}")]
     TypeError(String),
     
+    #[error("Invalid MIR: {0}")]
+    Invalid(String),
+    
+    #[error("Invalid cast from {from} to {to}")]
+    InvalidCast { from: String, to: String },
+    
+    #[error("Runtime error: {0}")]
+    Runtime(String),
+    
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -0,0 +1,648 @@
+//! Direct interpreter for MIR
+//!
+//! Runs lowered programs without going through MLIR, so lowering can be
+//! checked against the tree-walking evaluator. Locals live in a map per call
+//! frame; a local assigned in several blocks (the result of an `if`) simply
+//! takes the last value written.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use goth_ast::op::{BinOp, UnaryOp};
+use goth_ast::types::{PrimType, Type};
+use crate::error::{MirError, MirResult};
+use crate::mir::*;
+use crate::opt::param_locals;
+
+/// Calls deeper than this fail instead of overflowing the native stack
+const MAX_CALL_DEPTH: usize = 1_000;
+
+/// A value computed by MIR
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Unit,
+    Tuple(Vec<Value>),
+    /// Elements of the outermost dimension; a matrix holds arrays
+    Array(Vec<Value>),
+    Closure { func: String, captures: Vec<Value> },
+    Variant { tag: u32, constructor: String, payload: Option<Box<Value>> },
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "Int", Value::Float(_) => "Float", Value::Bool(_) => "Bool",
+            Value::Char(_) => "Char", Value::String(_) => "String", Value::Unit => "Unit",
+            Value::Tuple(_) => "Tuple", Value::Array(_) => "Array",
+            Value::Closure { .. } => "Closure", Value::Variant { .. } => "Variant",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, open: &str, items: &[Value], close: &str| {
+            write!(f, "{}", open)?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 { write!(f, ", ")?; }
+                write!(f, "{}", item)?;
+            }
+            write!(f, "{}", close)
+        };
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Char(c) => write!(f, "{}", c),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Unit => write!(f, "⟨⟩"),
+            Value::Tuple(items) => list(f, "⟨", items, "⟩"),
+            Value::Array(items) => list(f, "[", items, "]"),
+            Value::Closure { func, .. } => write!(f, "<closure {}>", func),
+            Value::Variant { constructor, payload: Some(p), .. } => write!(f, "{} {}", constructor, p),
+            Value::Variant { constructor, payload: None, .. } => write!(f, "{}", constructor),
+        }
+    }
+}
+
+/// Run `program`'s entry function on `args`, one per parameter
+pub fn run_mir(program: &Program, args: Vec<Value>) -> MirResult<Value> {
+    let mut interp = Interpreter { functions: program.functions.iter().map(|f| (f.name.as_str(), f)).collect(), depth: 0 };
+    interp.call(&program.entry, args)
+}
+
+fn runtime(msg: impl Into<String>) -> MirError {
+    MirError::Runtime(msg.into())
+}
+
+fn mismatch(op: &str, values: &[&Value]) -> MirError {
+    let kinds: Vec<&str> = values.iter().map(|v| v.kind()).collect();
+    runtime(format!("{} is not defined on {}", op, kinds.join(" and ")))
+}
+
+struct Interpreter<'p> {
+    functions: HashMap<&'p str, &'p Function>,
+    depth: usize,
+}
+
+type Frame = HashMap<LocalId, Value>;
+
+impl<'p> Interpreter<'p> {
+    fn call(&mut self, name: &str, args: Vec<Value>) -> MirResult<Value> {
+        let func = *self.functions.get(name).ok_or_else(|| MirError::UndefinedName(name.to_string()))?;
+        let params = param_locals(func);
+        if params.len() != args.len() {
+            return Err(runtime(format!("{} takes {} arguments but was given {}", name, params.len(), args.len())));
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(runtime(format!("call depth exceeded {} in {}", MAX_CALL_DEPTH, name)));
+        }
+        self.depth += 1;
+        let result = self.run_body(func, params.into_iter().zip(args).collect());
+        self.depth -= 1;
+        result
+    }
+
+    fn call_closure(&mut self, closure: Value, args: Vec<Value>) -> MirResult<Value> {
+        let Value::Closure { func, captures } = closure else { return Err(mismatch("a call", &[&closure])) };
+        let is_closure = self.functions.get(func.as_str()).is_some_and(|f| f.is_closure);
+        let args = if is_closure { std::iter::once(Value::Tuple(captures)).chain(args).collect() } else { args };
+        self.call(&func, args)
+    }
+
+    fn run_body(&mut self, func: &'p Function, mut frame: Frame) -> MirResult<Value> {
+        let blocks: HashMap<BlockId, &Block> = func.blocks.iter().map(|(id, b)| (*id, b)).collect();
+        let mut block = &func.body;
+        loop {
+            for stmt in &block.stmts {
+                let value = self.eval_rhs(&stmt.rhs, &stmt.ty, &frame)?;
+                frame.insert(stmt.dest, value);
+            }
+            let next = match &block.term {
+                Terminator::Return(op) => return operand(op, &frame),
+                Terminator::Goto(target) => *target,
+                Terminator::If { cond, then_block, else_block } => match operand(cond, &frame)? {
+                    Value::Bool(true) => *then_block,
+                    Value::Bool(false) => *else_block,
+                    other => return Err(mismatch("a branch", &[&other])),
+                },
+                Terminator::Switch { scrutinee, cases, default } => {
+                    let key = match operand(scrutinee, &frame)? {
+                        Value::Int(n) => n,
+                        Value::Char(c) => c as i64,
+                        Value::Bool(b) => b as i64,
+                        other => return Err(mismatch("a switch", &[&other])),
+                    };
+                    cases.iter().find(|(c, _)| c.as_int() == Some(key)).map_or(*default, |(_, target)| *target)
+                }
+                Terminator::Unreachable => return Err(runtime(format!("reached unreachable code in {}", func.name))),
+            };
+            block = blocks.get(&next).ok_or_else(|| runtime(format!("{} branches to missing block {}", func.name, next)))?;
+        }
+    }
+
+    fn eval_rhs(&mut self, rhs: &Rhs, ty: &Type, frame: &Frame) -> MirResult<Value> {
+        let get = |op: &Operand| operand(op, frame);
+        let all = |ops: &[Operand]| ops.iter().map(|op| operand(op, frame)).collect::<MirResult<Vec<_>>>();
+        match rhs {
+            Rhs::Use(op) => get(op),
+            Rhs::Const(c) => Ok(constant(c)),
+            Rhs::BinOp(op, a, b) => binop(op, get(a)?, get(b)?),
+            Rhs::UnaryOp(op, a) => match (unaryop(*op, get(a)?)?, ty) {
+                // Rounding lowered at an integer type yields the integer
+                (Value::Float(x), Type::Prim(p)) if p.is_int() => Ok(Value::Int(x as i64)),
+                (value, _) => Ok(value),
+            },
+            Rhs::Cast { to, operand: a, .. } => cast(get(a)?, to),
+            Rhs::Call { func, args, .. } => { let args = all(args)?; self.call(func, args) }
+            Rhs::ClosureCall { closure, args } => { let args = all(args)?; self.call_closure(get(closure)?, args) }
+            Rhs::MakeClosure { func, captures } => Ok(Value::Closure { func: func.clone(), captures: all(captures)? }),
+            Rhs::Tuple(ops) => Ok(Value::Tuple(all(ops)?)),
+            Rhs::TupleField(a, i) => match get(a)? {
+                Value::Tuple(mut fields) if *i < fields.len() => Ok(fields.swap_remove(*i)),
+                other => Err(runtime(format!("no field {} in {}", i, other.kind()))),
+            },
+            Rhs::Array(ops) => Ok(Value::Array(all(ops)?)),
+            Rhs::ArrayFill { size, value } => {
+                let n = length(get(size)?)?;
+                Ok(Value::Array(vec![get(value)?; n]))
+            }
+            Rhs::Iota(n) => Ok(Value::Array((0..length(get(n)?)? as i64).map(Value::Int).collect())),
+            Rhs::Range(a, b) => match (get(a)?, get(b)?) {
+                (Value::Int(start), Value::Int(end)) => Ok(Value::Array((start..end).map(Value::Int).collect())),
+                (a, b) => Err(mismatch("range", &[&a, &b])),
+            },
+            Rhs::Index(a, i) => {
+                let (elems, i) = (elements(get(a)?)?, get(i)?);
+                let Value::Int(n) = i else { return Err(mismatch("indexing", &[&i])) };
+                usize::try_from(n).ok().and_then(|n| elems.get(n).cloned())
+                    .ok_or_else(|| runtime(format!("index {} out of bounds for length {}", n, elems.len())))
+            }
+            Rhs::Slice { array, start, end } => {
+                let elems = elements(get(array)?)?;
+                let start = start.as_ref().map(|s| get(s).and_then(length)).transpose()?.unwrap_or(0);
+                let end = end.as_ref().map(|e| get(e).and_then(length)).transpose()?.unwrap_or(elems.len());
+                if start > end || end > elems.len() {
+                    return Err(runtime(format!("slice {}..{} out of bounds for length {}", start, end, elems.len())));
+                }
+                Ok(Value::Array(elems[start..end].to_vec()))
+            }
+            Rhs::TensorMap { tensor, func } => {
+                let func = get(func)?;
+                let mapped = elements(get(tensor)?)?.into_iter().map(|x| self.call_closure(func.clone(), vec![x])).collect::<MirResult<_>>()?;
+                Ok(Value::Array(mapped))
+            }
+            Rhs::TensorFilter { tensor, pred } => {
+                let pred = get(pred)?;
+                let mut kept = Vec::new();
+                for x in elements(get(tensor)?)? {
+                    match self.call_closure(pred.clone(), vec![x.clone()])? {
+                        Value::Bool(true) => kept.push(x),
+                        Value::Bool(false) => {}
+                        other => return Err(mismatch("a filter predicate result", &[&other])),
+                    }
+                }
+                Ok(Value::Array(kept))
+            }
+            Rhs::TensorReduce { tensor, op } => reduce(elements(get(tensor)?)?, *op, ty),
+            Rhs::TensorZip { left, right } => {
+                let (a, b) = (elements(get(left)?)?, elements(get(right)?)?);
+                if a.len() != b.len() { return Err(runtime(format!("cannot zip lengths {} and {}", a.len(), b.len()))); }
+                Ok(Value::Array(a.into_iter().zip(b).map(|(x, y)| Value::Tuple(vec![x, y])).collect()))
+            }
+            Rhs::ContractCheck { predicate, message, is_precondition } => match get(predicate)? {
+                Value::Bool(true) => Ok(Value::Unit),
+                Value::Bool(false) => {
+                    let kind = if *is_precondition { "precondition" } else { "postcondition" };
+                    Err(runtime(format!("{} violated: {}", kind, message)))
+                }
+                other => Err(mismatch("a contract", &[&other])),
+            },
+            Rhs::MakeVariant { tag, constructor, payload } => Ok(Value::Variant {
+                tag: *tag,
+                constructor: constructor.clone(),
+                payload: payload.as_ref().map(|p| get(p).map(Box::new)).transpose()?,
+            }),
+            Rhs::GetTag(a) => match get(a)? {
+                Value::Variant { tag, .. } => Ok(Value::Int(tag as i64)),
+                other => Err(mismatch("a tag", &[&other])),
+            },
+            Rhs::GetPayload(a) => match get(a)? {
+                Value::Variant { payload: Some(p), .. } => Ok(*p),
+                other => Err(runtime(format!("{} has no payload", other.kind()))),
+            },
+            Rhs::Uncertain { .. } => Err(runtime("uncertain values are not supported by the MIR interpreter")),
+            Rhs::Prim { name, args } => prim(name, all(args)?),
+        }
+    }
+}
+
+fn operand(op: &Operand, frame: &Frame) -> MirResult<Value> {
+    match op {
+        Operand::Local(l) => frame.get(l).cloned().ok_or_else(|| runtime(format!("{} is read before it is assigned", l))),
+        Operand::Const(c) => Ok(constant(c)),
+    }
+}
+
+fn constant(c: &Constant) -> Value {
+    match c {
+        Constant::Int(n) => Value::Int(*n),
+        Constant::Float(f) => Value::Float(*f),
+        Constant::Bool(b) => Value::Bool(*b),
+        Constant::Char(c) => Value::Char(*c),
+        Constant::String(s) => Value::String(s.clone()),
+        Constant::Unit => Value::Unit,
+    }
+}
+
+fn elements(value: Value) -> MirResult<Vec<Value>> {
+    match value {
+        Value::Array(elems) => Ok(elems),
+        Value::String(s) => Ok(s.chars().map(Value::Char).collect()),
+        other => Err(mismatch("an array operation", &[&other])),
+    }
+}
+
+/// A non-negative integer used as a size, index or bound
+fn length(value: Value) -> MirResult<usize> {
+    match value {
+        Value::Int(n) if n >= 0 => Ok(n as usize),
+        other => Err(runtime(format!("expected a non-negative Int, got {:?}", other))),
+    }
+}
+
+fn binop(op: &BinOp, a: Value, b: Value) -> MirResult<Value> {
+    use Value::{Bool, Float, Int};
+    let name = || format!("{:?}", op);
+    match (op, &a, &b) {
+        (BinOp::And, Bool(x), Bool(y)) => return Ok(Bool(*x && *y)),
+        (BinOp::Or, Bool(x), Bool(y)) => return Ok(Bool(*x || *y)),
+        (BinOp::Eq, _, _) if a.kind() == b.kind() => return Ok(Bool(a == b)),
+        (BinOp::Neq, _, _) if a.kind() == b.kind() => return Ok(Bool(a != b)),
+        _ => {}
+    }
+    match (&a, &b) {
+        (Int(x), Int(y)) => {
+            let (x, y) = (*x, *y);
+            let checked = match op {
+                BinOp::Add => x.checked_add(y),
+                BinOp::Sub => x.checked_sub(y),
+                BinOp::Mul => x.checked_mul(y),
+                BinOp::Div | BinOp::Mod if y == 0 => return Err(runtime("division by zero")),
+                BinOp::Div => x.checked_div(y),
+                BinOp::Mod => x.checked_rem(y),
+                BinOp::Pow if y < 0 => return Err(runtime(format!("negative exponent in {} ^ {}", x, y))),
+                BinOp::Pow => u32::try_from(y).ok().and_then(|y| x.checked_pow(y)),
+                _ => return compare(op, x.cmp(&y)).map(Bool).ok_or_else(|| mismatch(&name(), &[&a, &b])),
+            };
+            checked.map(Int).ok_or_else(|| runtime(format!("integer overflow in {} {} {}", x, op.glyph(), y)))
+        }
+        (Int(_) | Float(_), Int(_) | Float(_)) => {
+            let (x, y) = (as_float(&a), as_float(&b));
+            match op {
+                BinOp::Add => Ok(Float(x + y)),
+                BinOp::Sub => Ok(Float(x - y)),
+                BinOp::Mul => Ok(Float(x * y)),
+                BinOp::Div if y == 0.0 => Err(runtime("division by zero")),
+                BinOp::Div => Ok(Float(x / y)),
+                BinOp::Mod if y == 0.0 => Err(runtime("division by zero")),
+                BinOp::Mod => Ok(Float(x % y)),
+                BinOp::Pow => Ok(Float(x.powf(y))),
+                _ => x.partial_cmp(&y).and_then(|o| compare(op, o)).map(Bool).ok_or_else(|| mismatch(&name(), &[&a, &b])),
+            }
+        }
+        (Value::Char(x), Value::Char(y)) => compare(op, x.cmp(y)).map(Bool).ok_or_else(|| mismatch(&name(), &[&a, &b])),
+        _ => Err(mismatch(&name(), &[&a, &b])),
+    }
+}
+
+fn compare(op: &BinOp, ordering: std::cmp::Ordering) -> Option<bool> {
+    match op {
+        BinOp::Lt => Some(ordering.is_lt()),
+        BinOp::Gt => Some(ordering.is_gt()),
+        BinOp::Leq => Some(ordering.is_le()),
+        BinOp::Geq => Some(ordering.is_ge()),
+        BinOp::Eq => Some(ordering.is_eq()),
+        BinOp::Neq => Some(ordering.is_ne()),
+        _ => None,
+    }
+}
+
+fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(n) => *n as f64,
+        Value::Float(f) => *f,
+        _ => f64::NAN,
+    }
+}
+
+fn unaryop(op: UnaryOp, value: Value) -> MirResult<Value> {
+    match (op, &value) {
+        (UnaryOp::Neg, Value::Int(n)) => n.checked_neg().map(Value::Int).ok_or_else(|| runtime(format!("integer overflow in -{}", n))),
+        (UnaryOp::Neg, Value::Float(f)) => Ok(Value::Float(-f)),
+        (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        (UnaryOp::Abs, Value::Int(n)) => n.checked_abs().map(Value::Int).ok_or_else(|| runtime(format!("integer overflow in |{}|", n))),
+        (UnaryOp::Sign, Value::Int(n)) => Ok(Value::Int(n.signum())),
+        (UnaryOp::Gamma, Value::Int(_) | Value::Float(_)) if as_float(&value) <= 0.0 && as_float(&value).fract() == 0.0 => {
+            Err(runtime(format!("gamma is undefined at {}", value)))
+        }
+        (UnaryOp::Sum, Value::Array(_)) => reduce(elements(value)?, ReduceOp::Sum, &Type::Hole),
+        (UnaryOp::Prod, Value::Array(_)) => reduce(elements(value)?, ReduceOp::Prod, &Type::Hole),
+        (_, Value::Int(_) | Value::Float(_)) => {
+            let x = as_float(&value);
+            let y = match op {
+                UnaryOp::Sqrt => x.sqrt(), UnaryOp::Floor => x.floor(), UnaryOp::Ceil => x.ceil(), UnaryOp::Round => x.round(),
+                UnaryOp::Ln => x.ln(), UnaryOp::Log10 => x.log10(), UnaryOp::Log2 => x.log2(), UnaryOp::Exp => x.exp(),
+                UnaryOp::Sin => x.sin(), UnaryOp::Cos => x.cos(), UnaryOp::Tan => x.tan(),
+                UnaryOp::Asin => x.asin(), UnaryOp::Acos => x.acos(), UnaryOp::Atan => x.atan(),
+                UnaryOp::Sinh => x.sinh(), UnaryOp::Cosh => x.cosh(), UnaryOp::Tanh => x.tanh(),
+                UnaryOp::Abs => x.abs(),
+                UnaryOp::Gamma => gamma(x),
+                UnaryOp::Sign => if x > 0.0 { 1.0 } else if x < 0.0 { -1.0 } else { 0.0 },
+                _ => return Err(mismatch(&format!("{:?}", op), &[&value])),
+            };
+            Ok(Value::Float(y))
+        }
+        _ => Err(mismatch(&format!("{:?}", op), &[&value])),
+    }
+}
+
+fn cast(value: Value, to: &Type) -> MirResult<Value> {
+    match (value, to) {
+        (Value::Int(n), Type::Prim(p)) if p.is_float() => Ok(Value::Float(n as f64)),
+        (Value::Float(f), Type::Prim(p)) if p.is_int() => Ok(Value::Int(f as i64)),
+        (v @ Value::Int(_), Type::Prim(p)) if p.is_int() => Ok(v),
+        (v @ Value::Float(_), Type::Prim(p)) if p.is_float() => Ok(v),
+        (value, to) => Err(MirError::InvalidCast { from: value.kind().into(), to: to.to_string() }),
+    }
+}
+
+/// Fold `elems` with `op`; an empty reduction takes its identity in the statement's type
+fn reduce(elems: Vec<Value>, op: ReduceOp, ty: &Type) -> MirResult<Value> {
+    let (bin, identity) = match op {
+        ReduceOp::Sum => (BinOp::Add, 0),
+        ReduceOp::Prod => (BinOp::Mul, 1),
+        ReduceOp::Min | ReduceOp::Max => {
+            let mut elems = elems.into_iter();
+            let first = elems.next().ok_or_else(|| runtime(format!("{:?} of an empty array", op)))?;
+            let pick = if op == ReduceOp::Min { BinOp::Lt } else { BinOp::Gt };
+            return elems.try_fold(first, |best, x| Ok(if binop(&pick, x.clone(), best.clone())? == Value::Bool(true) { x } else { best }));
+        }
+    };
+    let init = match ty {
+        Type::Prim(PrimType::F64 | PrimType::F32) => Value::Float(identity as f64),
+        _ => Value::Int(identity),
+    };
+    let mut elems = elems.into_iter();
+    let first = elems.next().unwrap_or(init);
+    elems.try_fold(first, |acc, x| binop(&bin, acc, x))
+}
+
+/// Γ(x) by the Lanczos approximation, as the evaluator computes it
+fn gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const C: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection: Γ(x) Γ(1 - x) = π / sin(πx)
+        return std::f64::consts::PI / ((std::f64::consts::PI * x).sin() * gamma(1.0 - x));
+    }
+    let z = x - 1.0;
+    let sum = C[1..].iter().enumerate().fold(C[0], |sum, (i, c)| sum + c / (z + i as f64 + 1.0));
+    let t = z + G + 0.5;
+    (2.0 * std::f64::consts::PI).sqrt() * t.powf(z + 0.5) * (-t).exp() * sum
+}
+
+/// The text of a string constant or of an array of characters
+fn text(value: &Value) -> MirResult<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Array(elems) => elems.iter().map(|c| match c {
+            Value::Char(c) => Ok(*c),
+            _ => Err(mismatch("a string operation", &[value])),
+        }).collect(),
+        _ => Err(mismatch("a string operation", &[value])),
+    }
+}
+
+fn strings<'a>(parts: impl Iterator<Item = &'a str>) -> Value {
+    Value::Array(parts.map(|s| Value::String(s.to_string())).collect())
+}
+
+fn io(result: std::io::Result<()>) -> MirResult<Value> {
+    result.map(|_| Value::Unit).map_err(|e| runtime(e.to_string()))
+}
+
+/// The dimensions of a nested array, read along its first elements
+fn dims(value: &Value) -> Vec<i64> {
+    let mut dims = Vec::new();
+    let mut value = value;
+    while let Value::Array(elems) = value {
+        dims.push(elems.len() as i64);
+        match elems.first() { Some(first) => value = first, None => break }
+    }
+    dims
+}
+
+fn rows(value: &Value) -> MirResult<Vec<Vec<Value>>> {
+    elements(value.clone())?.into_iter().map(elements).collect()
+}
+
+fn dot(a: Vec<Value>, b: Vec<Value>) -> MirResult<Value> {
+    if a.len() != b.len() { return Err(runtime(format!("dot product of lengths {} and {}", a.len(), b.len()))); }
+    let products = a.into_iter().zip(b).map(|(x, y)| binop(&BinOp::Mul, x, y)).collect::<MirResult<Vec<_>>>()?;
+    reduce(products, ReduceOp::Sum, &Type::Hole)
+}
+
+fn prim(name: &str, args: Vec<Value>) -> MirResult<Value> {
+    let unsupported = || runtime(format!("primitive {} is not supported by the MIR interpreter", name));
+    let [first, rest @ ..] = args.as_slice() else { return Err(unsupported()) };
+    match (name, rest) {
+        ("len", []) => match first {
+            Value::String(s) => Ok(Value::Int(s.chars().count() as i64)),
+            other => Ok(Value::Int(elements(other.clone())?.len() as i64)),
+        },
+        ("strLen", []) => Ok(Value::Int(text(first)?.chars().count() as i64)),
+        ("reverse", []) => match first {
+            Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+            other => Ok(Value::Array(elements(other.clone())?.into_iter().rev().collect())),
+        },
+        ("transpose", []) => {
+            let rows = rows(first)?;
+            let width = rows.first().map_or(0, Vec::len);
+            if rows.iter().any(|r| r.len() != width) { return Err(runtime("transpose of a ragged array")); }
+            Ok(Value::Array((0..width).map(|j| Value::Array(rows.iter().map(|r| r[j].clone()).collect())).collect()))
+        }
+        ("shape", []) => Ok(Value::Array(dims(first).into_iter().map(Value::Int).collect())),
+        ("norm", []) => {
+            let squares = elements(first.clone())?.into_iter().map(|x| { let f = as_float(&x); Value::Float(f * f) }).collect();
+            unaryop(UnaryOp::Sqrt, reduce(squares, ReduceOp::Sum, &Type::Prim(PrimType::F64))?)
+        }
+        ("abs", []) => unaryop(UnaryOp::Abs, first.clone()),
+        ("toFloat", []) => cast(first.clone(), &Type::Prim(PrimType::F64)),
+        ("toInt", []) => match first {
+            Value::Bool(b) => Ok(Value::Int(*b as i64)),
+            Value::Char(c) => Ok(Value::Int(*c as i64)),
+            other => cast(other.clone(), &Type::Prim(PrimType::I64)),
+        },
+        ("toBool", []) => match first {
+            Value::Bool(b) => Ok(Value::Bool(*b)),
+            Value::Int(n) => Ok(Value::Bool(*n != 0)),
+            Value::Float(x) => Ok(Value::Bool(*x != 0.0)),
+            other => Err(mismatch("toBool", &[other])),
+        },
+        ("toChar", []) => match first {
+            Value::Char(c) => Ok(Value::Char(*c)),
+            Value::Int(n) => u32::try_from(*n).ok().and_then(char::from_u32).map(Value::Char)
+                .ok_or_else(|| runtime(format!("{} is not a Unicode scalar value", n))),
+            other => Err(mismatch("toChar", &[other])),
+        },
+        ("toString", []) => Ok(Value::String(first.to_string())),
+        ("parseInt", []) => { let s = text(first)?; s.trim().parse().map(Value::Int).map_err(|_| runtime(format!("cannot parse '{}' as an integer", s.trim()))) }
+        ("parseFloat", []) => { let s = text(first)?; s.trim().parse().map(Value::Float).map_err(|_| runtime(format!("cannot parse '{}' as a float", s.trim()))) }
+        ("chars", []) => Ok(Value::Array(text(first)?.chars().map(Value::Char).collect())),
+        ("lines", []) => Ok(strings(text(first)?.lines())),
+        ("words", []) => Ok(strings(text(first)?.split_whitespace())),
+        ("bytes", []) => Ok(Value::Array(text(first)?.bytes().map(|b| Value::Int(b as i64)).collect())),
+        ("joinStrings", []) => Ok(Value::String(elements(first.clone())?.iter().map(text).collect::<MirResult<String>>()?)),
+        ("print", []) => { println!("{}", first); Ok(first.clone()) }
+        ("write", []) => { print!("{}", first); io(std::io::stdout().flush()) }
+        ("flush", []) => io(std::io::stdout().flush()),
+        ("readLine", []) => {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line).map_err(|e| runtime(e.to_string()))?;
+            Ok(Value::String(line.trim_end_matches(['\n', '\r']).to_string()))
+        }
+        ("readFile", []) => { let path = text(first)?; std::fs::read_to_string(&path).map(Value::String).map_err(|e| runtime(format!("cannot read '{}': {}", path, e))) }
+        ("sleep", []) => { std::thread::sleep(std::time::Duration::from_millis(length(first.clone())? as u64)); Ok(Value::Unit) }
+        ("take" | "drop", [array]) => {
+            let n = match first { Value::Int(n) => (*n).max(0) as usize, other => return Err(mismatch(name, &[other])) };
+            if let Value::String(s) = array {
+                let chars = s.chars();
+                return Ok(Value::String(if name == "take" { chars.take(n).collect() } else { chars.skip(n).collect() }));
+            }
+            let elems = elements(array.clone())?;
+            let n = n.min(elems.len());
+            Ok(Value::Array(if name == "take" { elems[..n].to_vec() } else { elems[n..].to_vec() }))
+        }
+        ("concat", [b]) => match (first, b) {
+            (Value::String(x), Value::String(y)) => Ok(Value::String(format!("{}{}", x, y))),
+            (a, b) => Ok(Value::Array(elements(a.clone())?.into_iter().chain(elements(b.clone())?).collect())),
+        },
+        ("dot", [b]) => dot(elements(first.clone())?, elements(b.clone())?),
+        ("matmul", [b]) => {
+            let (a, b) = (rows(first)?, rows(b)?);
+            let inner = b.first().map_or(0, Vec::len);
+            let columns: Vec<Vec<Value>> = (0..inner).map(|j| b.iter().map(|r| r.get(j).cloned().unwrap_or(Value::Unit)).collect()).collect();
+            a.into_iter().map(|row| {
+                columns.iter().map(|col| dot(row.clone(), col.clone())).collect::<MirResult<Vec<_>>>().map(Value::Array)
+            }).collect::<MirResult<Vec<_>>>().map(Value::Array)
+        }
+        ("reshape", [shape]) => {
+            let shape = elements(shape.clone())?.into_iter().map(length).collect::<MirResult<Vec<_>>>()?;
+            let mut flat = Vec::new();
+            let mut stack = vec![first.clone()];
+            while let Some(v) = stack.pop() {
+                match v { Value::Array(elems) => stack.extend(elems.into_iter().rev()), scalar => flat.push(scalar) }
+            }
+            if shape.iter().product::<usize>() != flat.len() {
+                return Err(runtime(format!("cannot reshape {} elements to {:?}", flat.len(), shape)));
+            }
+            Ok(shape.iter().rev().fold(flat, |elems, &d| {
+                if d == 0 { return vec![] }
+                elems.chunks(d).map(|c| Value::Array(c.to_vec())).collect()
+            }).pop().unwrap_or(Value::Array(vec![])))
+        }
+        ("strConcat", [b]) => Ok(Value::String(text(first)? + &text(b)?)),
+        ("strEq", [b]) => Ok(Value::Bool(text(first)? == text(b)?)),
+        ("startsWith", [b]) => Ok(Value::Bool(text(first)?.starts_with(&text(b)?))),
+        ("endsWith", [b]) => Ok(Value::Bool(text(first)?.ends_with(&text(b)?))),
+        ("contains", [b]) => Ok(Value::Bool(text(first)?.contains(&text(b)?))),
+        ("replicate", [x]) => Ok(Value::Array(vec![x.clone(); length(first.clone())?])),
+        ("zip", [b]) => {
+            let (a, b) = (elements(first.clone())?, elements(b.clone())?);
+            if a.len() != b.len() { return Err(runtime(format!("cannot zip lengths {} and {}", a.len(), b.len()))); }
+            Ok(Value::Array(a.into_iter().zip(b).map(|(x, y)| Value::Tuple(vec![x, y])).collect()))
+        }
+        ("writeFile", [contents]) => {
+            let path = text(first)?;
+            std::fs::write(&path, text(contents)?).map(|_| Value::Unit).map_err(|e| runtime(format!("cannot write '{}': {}", path, e)))
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::{lower_expr, lower_module};
+    use goth_ast::expr::{Expr, MatchArm};
+    use goth_ast::pattern::Pattern;
+
+    fn run(expr: &Expr) -> MirResult<Value> {
+        run_mir(&lower_expr(expr)?, vec![])
+    }
+
+    fn ints(ns: &[i64]) -> Value { Value::Array(ns.iter().copied().map(Value::Int).collect()) }
+    fn text_lit(s: &str) -> Expr { Expr::Lit(goth_ast::literal::Literal::String(s.into())) }
+    fn int_array(ns: &[i128]) -> Expr { Expr::array(ns.iter().copied().map(Expr::int).collect()) }
+
+    fn run_module(source: &str, args: Vec<Value>) -> MirResult<Value> {
+        let module = goth_parse::prelude::parse_module(source, "test").expect("module parses");
+        run_mir(&lower_module(&goth_parse::prelude::resolve_module(module))?, args)
+    }
+
+    #[test] fn test_int_literal() { assert_eq!(run(&Expr::int(42)).unwrap(), Value::Int(42)); }
+    #[test] fn test_float_literal() { assert_eq!(run(&Expr::float(2.5)).unwrap(), Value::Float(2.5)); }
+    #[test] fn test_bool_literals() { assert_eq!(run(&Expr::bool(true)).unwrap(), Value::Bool(true)); assert_eq!(run(&Expr::bool(false)).unwrap(), Value::Bool(false)); }
+    #[test] fn test_arithmetic() { assert_eq!(run(&Expr::mul(Expr::add(Expr::int(2), Expr::int(3)), Expr::sub(Expr::int(4), Expr::int(1)))).unwrap(), Value::Int(15)); assert_eq!(run(&Expr::div(Expr::int(20), Expr::int(4))).unwrap(), Value::Int(5)); }
+    #[test] fn test_float_arithmetic() { assert_eq!(run(&Expr::add(Expr::float(1.5), Expr::float(2.5))).unwrap(), Value::Float(4.0)); }
+    #[test] fn test_mixed_arithmetic() { assert_eq!(run(&Expr::add(Expr::int(1), Expr::float(0.5))).unwrap(), Value::Float(1.5)); }
+    #[test] fn test_comparisons_and_logic() { assert_eq!(run(&Expr::binop(BinOp::Eq, Expr::int(5), Expr::int(5))).unwrap(), Value::Bool(true)); assert_eq!(run(&Expr::binop(BinOp::And, Expr::bool(true), Expr::binop(BinOp::Lt, Expr::int(5), Expr::int(3)))).unwrap(), Value::Bool(false)); }
+    #[test] fn test_simple_let() { assert_eq!(run(&Expr::let_(Pattern::var("x"), Expr::int(5), Expr::add(Expr::idx(0), Expr::int(3)))).unwrap(), Value::Int(8)); }
+    #[test] fn test_nested_let() { assert_eq!(run(&Expr::let_(Pattern::var("x"), Expr::int(5), Expr::let_(Pattern::var("y"), Expr::int(3), Expr::sub(Expr::idx(1), Expr::idx(0))))).unwrap(), Value::Int(2)); }
+    #[test] fn test_if_with_comparison() { assert_eq!(run(&Expr::if_(Expr::binop(BinOp::Lt, Expr::int(1), Expr::int(2)), Expr::mul(Expr::int(6), Expr::int(7)), Expr::int(0))).unwrap(), Value::Int(42)); }
+    #[test] fn test_identity_function() { assert_eq!(run(&Expr::app(Expr::lam(Expr::idx(0)), Expr::int(42))).unwrap(), Value::Int(42)); }
+    #[test] fn test_closure_capture() { assert_eq!(run(&Expr::let_var("k", Expr::int(1), Expr::let_var("f", Expr::lam(Expr::add(Expr::idx(0), Expr::idx(1))), Expr::app(Expr::idx(0), Expr::int(41))))).unwrap(), Value::Int(42)); }
+    #[test] fn test_match_variant() { let expr = Expr::match_(Expr::variant("Some", Some(Expr::int(5))), vec![MatchArm::new(Pattern::variant("None", None), Expr::int(0)), MatchArm::new(Pattern::variant("Some", Some(Pattern::var("x"))), Expr::idx(0))]); assert_eq!(run(&expr).unwrap(), Value::Int(5)); }
+    #[test] fn test_match_literal() { let expr = Expr::match_(Expr::int(99), vec![MatchArm::new(Pattern::lit(1i64), Expr::int(10)), MatchArm::new(Pattern::wildcard(), Expr::int(20))]); assert_eq!(run(&expr).unwrap(), Value::Int(20)); }
+    #[test] fn test_tuple_construction_and_field() { assert_eq!(run(&Expr::tuple(vec![Expr::int(1), Expr::bool(true)])).unwrap(), Value::Tuple(vec![Value::Int(1), Value::Bool(true)])); assert_eq!(run(&Expr::field_idx(Expr::tuple(vec![Expr::int(1), Expr::int(2), Expr::int(3)]), 1)).unwrap(), Value::Int(2)); }
+    #[test] fn test_array_sum() { assert_eq!(run(&Expr::sum(int_array(&[1, 2, 3, 4]))).unwrap(), Value::Int(10)); }
+    #[test] fn test_array_map_filter() { let expr = Expr::map(Expr::filter(int_array(&[1, 2, 3, 4, 5]), Expr::lam(Expr::binop(BinOp::Gt, Expr::idx(0), Expr::int(2)))), Expr::lam(Expr::mul(Expr::idx(0), Expr::int(2)))); assert_eq!(run(&expr).unwrap(), ints(&[6, 8, 10])); }
+    #[test] fn test_dot_product() { let expr = Expr::app(Expr::app(Expr::name("dot"), int_array(&[1, 2, 3])), int_array(&[4, 5, 6])); assert_eq!(run(&expr).unwrap(), Value::Int(32)); }
+    #[test] fn test_take_drop_and_reverse() { assert_eq!(run(&Expr::app(Expr::app(Expr::name("take"), Expr::int(2)), int_array(&[1, 2, 3]))).unwrap(), ints(&[1, 2])); assert_eq!(run(&Expr::app(Expr::app(Expr::name("drop"), Expr::int(2)), int_array(&[1, 2, 3]))).unwrap(), ints(&[3])); assert_eq!(run(&Expr::app(Expr::name("reverse"), int_array(&[1, 2, 3]))).unwrap(), ints(&[3, 2, 1])); }
+    #[test] fn test_string_primitives() { assert_eq!(run(&Expr::app(Expr::app(Expr::name("strConcat"), text_lit("ab")), text_lit("cd"))).unwrap(), Value::String("abcd".into())); assert_eq!(run(&Expr::app(Expr::name("words"), text_lit("a b"))).unwrap(), Value::Array(vec![Value::String("a".into()), Value::String("b".into())])); }
+    #[test] fn test_cast_between_int_and_float() { assert_eq!(cast(Value::Int(3), &Type::Prim(PrimType::F64)).unwrap(), Value::Float(3.0)); assert_eq!(cast(Value::Float(-2.7), &Type::Prim(PrimType::I64)).unwrap(), Value::Int(-2)); }
+    #[test] fn test_invalid_cast_is_an_error() { assert!(matches!(cast(Value::Tuple(vec![Value::Int(1)]), &Type::Prim(PrimType::I64)), Err(MirError::InvalidCast { .. }))); assert!(matches!(cast(Value::Bool(true), &Type::Prim(PrimType::F64)), Err(MirError::InvalidCast { .. }))); }
+    #[test] fn test_round_at_integer_type() { assert_eq!(run(&Expr::app(Expr::name("round"), Expr::float(2.6))).unwrap(), Value::Int(3)); }
+    #[test] fn test_gamma() { let Value::Float(x) = run(&Expr::app(Expr::name("gamma"), Expr::float(5.0))).unwrap() else { panic!("gamma should be a float") }; assert!((x - 24.0).abs() < 1e-9); }
+
+    #[test]
+    fn test_main_with_arguments() {
+        let source = "╭─ main : I64 → I64 → I64\n╰─ ₁ × 10 + ₀";
+        assert_eq!(run_module(source, vec![Value::Int(4), Value::Int(2)]).unwrap(), Value::Int(42));
+        assert!(matches!(run_module(source, vec![Value::Int(4)]), Err(MirError::Runtime(msg)) if msg.contains("2 arguments")));
+    }
+
+    #[test]
+    fn test_recursive_function() {
+        let source = "╭─ fact : I64 → I64\n╰─ if ₀ < 2 then 1 else ₀ × fact (₀ - 1)\n\n╭─ main : I64 → I64\n╰─ fact ₀";
+        assert_eq!(run_module(source, vec![Value::Int(10)]).unwrap(), Value::Int(3_628_800));
+    }
+
+    #[test]
+    fn test_run_reports_runtime_errors() {
+        assert!(matches!(run(&Expr::div(Expr::int(1), Expr::int(0))), Err(MirError::Runtime(msg)) if msg.contains("division by zero")));
+        assert!(matches!(run(&Expr::add(Expr::int(i64::MAX as i128), Expr::int(1))), Err(MirError::Runtime(msg)) if msg.contains("overflow")));
+        let missing = Program { functions: vec![], entry: "main".into() };
+        assert!(matches!(run_mir(&missing, vec![]), Err(MirError::UndefinedName(_))));
+    }
+}
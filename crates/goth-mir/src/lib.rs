@@ -32,9 +32,18 @@ pub mod lower;
 pub mod closure;
 pub mod print;
 pub mod error;
+pub mod verify;
+pub mod ser;
+pub mod opt;
+pub mod interp;
 
 pub use mir::*;
 pub use error::{MirError, MirResult};
+pub use verify::verify;
+pub use ser::{to_mir_json, from_mir_json};
+pub use opt::{prune_unreachable, inline_functions, merge_duplicate_functions};
+pub use interp::run_mir;
+pub use closure::{free_variables, free_variables_with_depth};
 
 /// Pretty-print a MIR program
 pub fn print_program(program: &Program) -> String {
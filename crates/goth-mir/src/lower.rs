@@ -12,6 +12,7 @@ use goth_ast::expr::Expr;
 use goth_ast::literal::Literal;
 use goth_ast::decl::{Module, Decl};
 use goth_ast::types::Type;
+use goth_ast::shape::{Dim, Shape};
 
 /// Lowering context
 pub struct LoweringContext {
@@ -37,8 +38,6 @@ pub struct LoweringContext {
     enum_constructors: std::collections::HashMap<String, (u32, bool, String)>,
     /// Type information for locals (needed for de Bruijn lookup)
     pub local_types: std::collections::HashMap<LocalId, Type>,
-    /// Pending entry block (set by if expressions)
-    pending_entry_block: Option<Block>,
     /// Entry block ID for the function (first block created)
     entry_block_id: Option<BlockId>,
     /// Named local variables (from let bindings with named patterns)
@@ -59,7 +58,6 @@ impl LoweringContext {
             global_constants: std::collections::HashMap::new(),
             enum_constructors: std::collections::HashMap::new(),
             local_types: std::collections::HashMap::new(),
-            pending_entry_block: None,
             entry_block_id: None,
             named_locals: std::collections::HashMap::new(),
         }
@@ -119,6 +117,31 @@ impl LoweringContext {
         self.stmts.push(Stmt { dest, ty, rhs });
     }
     
+    /// The statement defining `op` in the current block, looking through copies
+    fn definition(&self, op: &Operand) -> Option<&Rhs> {
+        let Operand::Local(local) = op else { return None };
+        match &self.stmts.iter().rev().find(|s| s.dest == *local)?.rhs {
+            Rhs::Use(src @ Operand::Local(_)) => self.definition(src),
+            rhs => Some(rhs),
+        }
+    }
+
+    /// The integer `op` holds, if it is known while lowering
+    fn constant_int(&self, op: &Operand) -> Option<i64> {
+        match (op, self.definition(op)) {
+            (Operand::Const(Constant::Int(n)), _) | (_, Some(Rhs::Use(Operand::Const(Constant::Int(n))) | Rhs::Const(Constant::Int(n)))) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The dimensions an array operand holds, if they are all known while lowering
+    fn constant_dims(&self, op: &Operand) -> Option<Vec<Dim>> {
+        match self.definition(op)? {
+            Rhs::Array(elems) => elems.iter().map(|e| self.constant_int(e).and_then(|n| u64::try_from(n).ok()).map(Dim::Const)).collect(),
+            _ => None,
+        }
+    }
+
     /// Take all accumulated statements and reset
     fn take_stmts(&mut self) -> Vec<Stmt> {
         std::mem::take(&mut self.stmts)
@@ -136,6 +159,13 @@ impl LoweringContext {
     fn add_block(&mut self, id: BlockId, block: Block) {
         self.blocks.push((id, block));
     }
+
+    /// Record `id` as where the function starts unless control flow began earlier
+    fn mark_entry(&mut self, id: BlockId) {
+        if self.entry_block_id.is_none() {
+            self.entry_block_id = Some(id);
+        }
+    }
 }
 
 /// Check if a name is a known primitive
@@ -179,7 +209,7 @@ fn is_primitive(name: &str) -> bool {
 
         // Type conversions
         "toString" | "str" |
-        "toInt" | "toFloat" | "toBool" | "toChar" |
+        "toInt" | "toFloat" | "toBool" | "toChar" | "ord" | "chr" |
         "parseInt" | "parseFloat" |
 
         // String operations
@@ -293,30 +323,17 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
         
         Expr::BinOp(op, left, right) => {
             let (left_op, left_ty) = lower_expr_to_operand(ctx, left)?;
-            let (right_op, _right_ty) = lower_expr_to_operand(ctx, right)?;
-
-            // Result type depends on operation category
-            let result_ty = match op {
-                // Comparison operators always return Bool
-                goth_ast::op::BinOp::Eq
-                | goth_ast::op::BinOp::Neq
-                | goth_ast::op::BinOp::Lt
-                | goth_ast::op::BinOp::Gt
-                | goth_ast::op::BinOp::Leq
-                | goth_ast::op::BinOp::Geq => {
-                    Type::Prim(goth_ast::types::PrimType::Bool)
-                }
-                // Logical operators always return Bool
-                goth_ast::op::BinOp::And
-                | goth_ast::op::BinOp::Or => {
-                    Type::Prim(goth_ast::types::PrimType::Bool)
-                }
-                // Arithmetic and other operators preserve operand type
-                _ => left_ty.clone(),
-            };
+            let (right_op, right_ty) = lower_expr_to_operand(ctx, right)?;
+            let (left_op, left_ty) = promote_to_float(ctx, op, left_op, left_ty, &right_ty);
+            let (right_op, right_ty) = promote_to_float(ctx, op, right_op, right_ty, &left_ty);
 
             let dest = ctx.fresh_local();
-            ctx.emit(dest, result_ty.clone(), Rhs::BinOp(op.clone(), left_op, right_op));
+            let (result_ty, rhs) = match op {
+                goth_ast::op::BinOp::Map => (map_result_type(&left_ty, &right_ty), Rhs::TensorMap { tensor: left_op, func: right_op }),
+                goth_ast::op::BinOp::Filter => (filter_result_type(&left_ty), Rhs::TensorFilter { tensor: left_op, pred: right_op }),
+                _ => (binop_result_type(op, &left_ty, &right_ty)?, Rhs::BinOp(op.clone(), left_op, right_op)),
+            };
+            ctx.emit(dest, result_ty.clone(), rhs);
 
             Ok((Operand::Local(dest), result_ty))
         }
@@ -359,11 +376,14 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
         
         // ============ Let Bindings ============
         
-        Expr::Let { pattern, value, body, type_: _ } => {
+        Expr::Let { pattern, value, body, type_ } => {
             use goth_ast::pattern::Pattern;
 
             // Lower the value
-            let (val_op, val_ty) = lower_expr_to_operand(ctx, value)?;
+            let (val_op, val_ty) = match type_ {
+                Some(ty) => lower_expr_expecting(ctx, value, ty)?,
+                None => lower_expr_to_operand(ctx, value)?,
+            };
 
             // For now, only handle simple variable patterns
             // TODO: Pattern compilation for complex patterns
@@ -424,9 +444,7 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
             ctx.add_block(cond_block_id, cond_block);
 
             // Track the entry block (first condition block we create)
-            if ctx.entry_block_id.is_none() {
-                ctx.entry_block_id = Some(cond_block_id);
-            }
+            ctx.mark_entry(cond_block_id);
 
             // === Lower THEN branch ===
             let blocks_before_then = ctx.blocks.len();
@@ -769,6 +787,26 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
             
             Ok((Operand::Local(dest), tuple_ty))
         }
+
+        // ============ Records ============
+
+        Expr::Record(fields) => {
+            // A record is a tuple with labelled fields, kept in source order
+            let mut ops = Vec::new();
+            let mut field_tys = Vec::new();
+
+            for (name, expr) in fields {
+                let (op, ty) = lower_expr_to_operand(ctx, expr)?;
+                ops.push(op);
+                field_tys.push(goth_ast::types::TupleField { label: Some(name.clone()), ty });
+            }
+
+            let record_ty = Type::Tuple(field_tys);
+            let dest = ctx.fresh_local();
+            ctx.emit(dest, record_ty.clone(), Rhs::Tuple(ops));
+
+            Ok((Operand::Local(dest), record_ty))
+        }
         
         // ============ Arrays ============
         
@@ -812,10 +850,7 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
             let (value_op, value_ty) = lower_expr_to_operand(ctx, value)?;
 
             // Result type is an array of the value type
-            let array_ty = Type::vector(
-                goth_ast::shape::Dim::Var("n".into()), // Dynamic size
-                value_ty.clone()
-            );
+            let array_ty = Type::vector(size_dim(ctx.constant_int(&size_op)), value_ty.clone());
 
             let dest = ctx.fresh_local();
             ctx.emit(dest, array_ty.clone(), Rhs::ArrayFill {
@@ -839,24 +874,23 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
                         Type::Tuple(fields) => {
                             fields.get(*idx as usize)
                                 .map(|f| f.ty.clone())
-                                .unwrap_or(Type::Prim(goth_ast::types::PrimType::I64))
+                                .ok_or_else(|| MirError::TypeError(format!("no field {} in {}", idx, base_ty)))?
                         }
                         _ => Type::Prim(goth_ast::types::PrimType::I64),
                     };
                     (*idx as usize, elem_ty)
                 }
                 goth_ast::expr::FieldAccess::Named(name) => {
-                    // Named field - find index in tuple
-                    let (idx, elem_ty) = match &base_ty {
+                    // Named field of a record - find its position among the labels
+                    match &base_ty {
                         Type::Tuple(fields) => {
                             fields.iter().enumerate()
-                                .find(|(_, f)| f.label.as_ref().map(|l| l.as_ref()) == Some(name.as_ref()))
+                                .find(|(_, f)| f.label.as_deref() == Some(name.as_ref()))
                                 .map(|(i, f)| (i, f.ty.clone()))
-                                .unwrap_or((0, Type::Prim(goth_ast::types::PrimType::I64)))
+                                .ok_or_else(|| MirError::TypeError(format!("no field {} in {}", name, base_ty)))?
                         }
                         _ => (0, Type::Prim(goth_ast::types::PrimType::I64)),
-                    };
-                    (idx, elem_ty)
+                    }
                 }
             };
 
@@ -903,9 +937,11 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
                 (None, None)
             };
 
-            // Tag index is computed from constructor name hash for now
-            // In a full implementation, this would be looked up from enum definition
-            let tag = constructor_tag(constructor);
+            // Declared enums number their constructors in order; others use the fixed table
+            let tag = match ctx.enum_constructors.get(constructor.as_ref()) {
+                Some((tag, _, _)) => *tag,
+                None => constructor_tag(constructor),
+            };
 
             // Build variant type - single arm for now (would need full enum info for complete type)
             let variant_ty = Type::Variant(vec![
@@ -925,6 +961,10 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
             Ok((Operand::Local(dest), variant_ty))
         }
 
+        // ============ Type Annotations ============
+
+        Expr::Annot(inner, ty) => lower_expr_expecting(ctx, inner, ty),
+
         // ============ Match Expressions ============
 
         Expr::Match { scrutinee, arms } => {
@@ -933,10 +973,144 @@ pub fn lower_expr_to_operand(ctx: &mut LoweringContext, expr: &Expr) -> MirResul
 
         // ============ TODO: More expressions ============
 
-        _ => Err(MirError::CannotLower(format!("Expression type not yet implemented: {:?}", expr))),
+        _ => Err(MirError::CannotLower(format!("{} is not supported yet", expr.variant_name()))),
+    }
+}
+
+/// A dimension of `n` elements, or a dynamic one when `n` isn't known while lowering
+fn size_dim(n: Option<i64>) -> Dim {
+    n.and_then(|n| u64::try_from(n).ok()).map_or(Dim::Dynamic, Dim::Const)
+}
+
+/// Type of `tensor ↦ f`: the tensor's shape, holding what `f` returns
+fn map_result_type(tensor: &Type, func: &Type) -> Type {
+    match tensor {
+        Type::Tensor(shape, elem) => {
+            let elem = match func {
+                Type::Fn(_, ret) => (**ret).clone(),
+                _ => (**elem).clone(),
+            };
+            Type::Tensor(shape.clone(), Box::new(elem))
+        }
+        _ => tensor.clone(),
     }
 }
 
+/// Type of `tensor ▸ pred`: the tensor's elements, as many as pass
+fn filter_result_type(tensor: &Type) -> Type {
+    match tensor {
+        Type::Tensor(_, elem) => Type::Tensor(Shape(vec![Dim::Dynamic]), elem.clone()),
+        _ => tensor.clone(),
+    }
+}
+
+/// Lower `expr` where the context expects a value of type `expected`.
+///
+/// Floor and ceiling produce `F64`; when an integer is expected their result
+/// is cast, so `⌊x⌋ : I64` lowers to a floor followed by a float-to-int cast.
+/// Other expressions are lowered unchanged.
+fn lower_expr_expecting(ctx: &mut LoweringContext, expr: &Expr, expected: &Type) -> MirResult<(Operand, Type)> {
+    let (op, ty) = lower_expr_to_operand(ctx, expr)?;
+    let wants_int = matches!(expected, Type::Prim(p) if p.is_int());
+    if !(wants_int && is_rounding(expr) && ty == Type::Prim(goth_ast::types::PrimType::F64)) {
+        return Ok((op, ty));
+    }
+    let dest = ctx.fresh_local();
+    ctx.emit(dest, expected.clone(), Rhs::Cast { from: ty, to: expected.clone(), operand: op });
+    Ok((Operand::Local(dest), expected.clone()))
+}
+
+/// Cast an integer scalar operand of an arithmetic or comparison operator to
+/// `F64` when the other operand is a float, so both share a type
+fn promote_to_float(ctx: &mut LoweringContext, op: &goth_ast::op::BinOp, operand: Operand, ty: Type, other: &Type) -> (Operand, Type) {
+    use goth_ast::op::BinOp;
+    use goth_ast::types::PrimType;
+
+    let numeric = matches!(op,
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod | BinOp::Pow
+        | BinOp::Eq | BinOp::Neq | BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq);
+    let mixed = matches!((&ty, other), (Type::Prim(a), Type::Prim(b)) if a.is_int() && b.is_float());
+    if !(numeric && mixed) {
+        return (operand, ty);
+    }
+    let float = Type::Prim(PrimType::F64);
+    let dest = ctx.fresh_local();
+    ctx.emit(dest, float.clone(), Rhs::Cast { from: ty, to: float.clone(), operand });
+    (Operand::Local(dest), float)
+}
+
+/// Whether `expr` is a floor or ceiling, written as an operator or a call
+fn is_rounding(expr: &Expr) -> bool {
+    use goth_ast::op::UnaryOp;
+    match expr {
+        Expr::UnaryOp(UnaryOp::Floor | UnaryOp::Ceil, _) => true,
+        Expr::App(func, _) => matches!(func.as_ref(), Expr::Name(name) if &**name == "floor" || &**name == "ceil"),
+        _ => false,
+    }
+}
+
+/// Result type of a binary operation.
+///
+/// Comparisons and logical operators yield `Bool`. Arithmetic follows the
+/// evaluator: an `F64` operand promotes the result to `F64`, otherwise the
+/// left operand's type is kept, and tensor operands broadcast their shapes.
+/// Operands of unknown type (variables, holes) are not checked.
+fn binop_result_type(op: &goth_ast::op::BinOp, left: &Type, right: &Type) -> MirResult<Type> {
+    use goth_ast::op::BinOp;
+    use goth_ast::types::PrimType;
+
+    match op {
+        BinOp::Eq | BinOp::Neq | BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq
+        | BinOp::And | BinOp::Or => Ok(Type::Prim(PrimType::Bool)),
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Pow | BinOp::Mod => {
+            let mismatch = || MirError::TypeError(format!("cannot apply {:?} to {} and {}", op, left, right));
+            match (left, right) {
+                (Type::Tensor(s1, e1), Type::Tensor(s2, e2)) => {
+                    let shape = broadcast_shapes(s1, s2).ok_or_else(mismatch)?;
+                    Ok(Type::Tensor(shape, Box::new(binop_result_type(op, e1, e2)?)))
+                }
+                (Type::Tensor(shape, elem), scalar) => Ok(Type::Tensor(shape.clone(), Box::new(binop_result_type(op, elem, scalar)?))),
+                (scalar, Type::Tensor(shape, elem)) => Ok(Type::Tensor(shape.clone(), Box::new(binop_result_type(op, scalar, elem)?))),
+                (Type::Prim(a), Type::Prim(b)) => {
+                    let numeric = |p: &PrimType| p.is_int() || p.is_float();
+                    if numeric(a) != numeric(b) {
+                        Err(mismatch())
+                    } else if b.is_float() && !a.is_float() {
+                        Ok(Type::Prim(PrimType::F64))
+                    } else {
+                        Ok(left.clone())
+                    }
+                }
+                _ => Ok(left.clone()),
+            }
+        }
+        _ => Ok(left.clone()),
+    }
+}
+
+/// Broadcast two tensor shapes, aligning them from the innermost dimension.
+/// A dimension of 1 stretches to match; a named dimension takes the other's constant;
+/// anything broadcast against a dynamic dimension stays dynamic.
+fn broadcast_shapes(a: &goth_ast::shape::Shape, b: &goth_ast::shape::Shape) -> Option<goth_ast::shape::Shape> {
+    use goth_ast::shape::{Dim, Shape};
+
+    let (long, short) = if a.0.len() >= b.0.len() { (a, b) } else { (b, a) };
+    let offset = long.0.len() - short.0.len();
+    let mut dims = long.0.clone();
+    for (i, dim) in short.0.iter().enumerate() {
+        let slot = &mut dims[offset + i];
+        match (&*slot, dim) {
+            (x, y) if x == y => {}
+            (Dim::Dynamic, _) | (_, Dim::Dynamic) => *slot = Dim::Dynamic,
+            (Dim::Const(1), _) | (Dim::Var(_), Dim::Const(_)) => *slot = dim.clone(),
+            (_, Dim::Const(1)) | (Dim::Const(_), Dim::Var(_)) => {}
+            (Dim::Const(_), Dim::Const(_)) => return None,
+            _ => {}
+        }
+    }
+    Some(Shape(dims))
+}
+
 /// Lower a literal to a constant and its type
 fn lower_literal(lit: &Literal) -> (Constant, Type) {
     match lit {
@@ -956,8 +1130,7 @@ fn lower_literal(lit: &Literal) -> (Constant, Type) {
             (Constant::Unit, Type::Tuple(vec![]))
         }
         Literal::Char(c) => {
-            // Char as 32-bit unicode scalar
-            (Constant::Int(*c as i64), Type::Prim(goth_ast::types::PrimType::Char))
+            (Constant::Char(*c), Type::Prim(goth_ast::types::PrimType::Char))
         }
         Literal::String(s) => {
             (Constant::String(s.to_string()), Type::Prim(goth_ast::types::PrimType::String))
@@ -1049,16 +1222,17 @@ fn lower_match_expr(
         let default_block_id = ctx.fresh_block();
 
         // Save current statements and create the switch terminator
+        let switch_block_id = ctx.fresh_block();
         let current_stmts = ctx.take_stmts();
-        let switch_block = Block {
+        ctx.add_block(switch_block_id, Block {
             stmts: current_stmts,
             term: Terminator::Switch {
                 scrutinee: Operand::Local(tag_local),
                 cases,
                 default: default_block_id,
             },
-        };
-        ctx.pending_entry_block = Some(switch_block);
+        });
+        ctx.mark_entry(switch_block_id);
 
         // Lower each arm
         let mut result_ty = None;
@@ -1161,6 +1335,12 @@ fn lower_match_expr(
                         default_arm = Some(arm);
                     }
                 }
+                Pattern::Tuple(pats) if pats.is_empty() => {
+                    // ⟨⟩ matches the only unit value and doesn't bind
+                    if default_arm.is_none() {
+                        default_arm = Some(arm);
+                    }
+                }
                 _ => {
                     // Other patterns (tuple, array, etc.) not yet supported
                     return Err(MirError::CannotLower(
@@ -1333,11 +1513,8 @@ fn lower_match_expr(
             term: Terminator::Unreachable, // Will be replaced by caller
         });
 
-        // Set the first check block as the pending entry
-        ctx.pending_entry_block = Some(Block {
-            stmts: vec![],
-            term: Terminator::Goto(check_block_ids[0]),
-        });
+        // The first check block holds the statements before the match
+        ctx.mark_entry(check_block_ids[0]);
 
         let result_ty = result_ty.unwrap_or(Type::Tuple(vec![]));
         ctx.local_types.insert(result, result_ty.clone());
@@ -1354,10 +1531,7 @@ fn lower_primitive_app(ctx: &mut LoweringContext, prim_name: &str, arg: &Expr) -
     match prim_name {
         // Sequence generation
         "iota" | "ι" | "⍳" => {
-            let result_ty = Type::vector(
-                goth_ast::shape::Dim::Var("n".into()),
-                Type::Prim(goth_ast::types::PrimType::I64)
-            );
+            let result_ty = Type::vector(size_dim(ctx.constant_int(&arg_op)), Type::Prim(goth_ast::types::PrimType::I64));
             ctx.emit(dest, result_ty.clone(), Rhs::Iota(arg_op));
             Ok((Operand::Local(dest), result_ty))
         }
@@ -1470,7 +1644,7 @@ fn lower_primitive_app(ctx: &mut LoweringContext, prim_name: &str, arg: &Expr) -
             });
             Ok((Operand::Local(dest), result_ty))
         }
-        "toInt" => {
+        "toInt" | "ord" => {
             let result_ty = Type::Prim(goth_ast::types::PrimType::I64);
             ctx.emit(dest, result_ty.clone(), Rhs::Prim {
                 name: "toInt".to_string(),
@@ -1494,7 +1668,7 @@ fn lower_primitive_app(ctx: &mut LoweringContext, prim_name: &str, arg: &Expr) -
             });
             Ok((Operand::Local(dest), result_ty))
         }
-        "toChar" => {
+        "toChar" | "chr" => {
             let result_ty = Type::Prim(goth_ast::types::PrimType::Char);
             ctx.emit(dest, result_ty.clone(), Rhs::Prim {
                 name: "toChar".to_string(),
@@ -1827,16 +2001,14 @@ fn lower_primitive_app2(ctx: &mut LoweringContext, prim_name: &str, arg1: &Expr,
 
     match prim_name {
         "range" | "…" => {
-            let result_ty = Type::vector(
-                goth_ast::shape::Dim::Var("n".into()),
-                Type::Prim(goth_ast::types::PrimType::I64)
-            );
+            let len = ctx.constant_int(&arg1_op).zip(ctx.constant_int(&arg2_op)).map(|(start, end)| (end - start).max(0));
+            let result_ty = Type::vector(size_dim(len), Type::Prim(goth_ast::types::PrimType::I64));
             ctx.emit(dest, result_ty.clone(), Rhs::Range(arg1_op, arg2_op));
             Ok((Operand::Local(dest), result_ty))
         }
 
         "map" | "↦" => {
-            let result_ty = arg1_ty.clone(); // Tensor type preserved
+            let result_ty = map_result_type(&arg1_ty, &arg2_ty);
             ctx.emit(dest, result_ty.clone(), Rhs::TensorMap {
                 tensor: arg1_op,
                 func: arg2_op,
@@ -1845,7 +2017,7 @@ fn lower_primitive_app2(ctx: &mut LoweringContext, prim_name: &str, arg1: &Expr,
         }
 
         "filter" | "▸" => {
-            let result_ty = arg1_ty.clone();
+            let result_ty = filter_result_type(&arg1_ty);
             ctx.emit(dest, result_ty.clone(), Rhs::TensorFilter {
                 tensor: arg1_op,
                 pred: arg2_op,
@@ -1909,7 +2081,20 @@ fn lower_primitive_app2(ctx: &mut LoweringContext, prim_name: &str, arg1: &Expr,
         }
 
         "reshape" => {
-            let result_ty = arg2_ty.clone();
+            // The new shape, dimension by dimension where it is known
+            let shape = match (ctx.constant_dims(&arg2_op), &arg2_ty) {
+                (Some(dims), _) => dims,
+                (None, Type::Tensor(s, _)) => match s.0.as_slice() {
+                    [Dim::Const(rank)] => vec![Dim::Dynamic; *rank as usize],
+                    _ => vec![Dim::Dynamic],
+                },
+                (None, _) => vec![Dim::Dynamic],
+            };
+            let elem = match &arg1_ty {
+                Type::Tensor(_, elem) => (**elem).clone(),
+                other => other.clone(),
+            };
+            let result_ty = Type::Tensor(Shape(shape), Box::new(elem));
             ctx.emit(dest, result_ty.clone(), Rhs::Prim {
                 name: "reshape".to_string(),
                 args: vec![arg1_op, arg2_op],
@@ -2104,6 +2289,138 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_lower_literal_char() {
+        let program = lower_expr(&Expr::Lit(Literal::Char('λ'))).unwrap();
+        let main = &program.functions[0];
+        assert_eq!(main.ret_ty, Type::Prim(PrimType::Char));
+        match &main.body.term {
+            Terminator::Return(Operand::Const(c)) => {
+                assert_eq!(*c, Constant::Char('λ'));
+                assert_eq!(c.as_int(), Some(0x3bb));
+            }
+            _ => panic!("Expected return of a char constant"),
+        }
+    }
+
+    #[test]
+    fn test_lower_literal_string() {
+        let program = lower_expr(&Expr::Lit(Literal::String("hi".into()))).unwrap();
+        let main = &program.functions[0];
+        assert_eq!(main.ret_ty, Type::Prim(PrimType::String));
+        assert!(matches!(&main.body.term, Terminator::Return(Operand::Const(Constant::String(s))) if s == "hi"));
+    }
+
+    #[test]
+    fn test_lower_comparison_type() {
+        // 1 < 2 : Bool
+        let expr = Expr::BinOp(BinOp::Lt, Box::new(Expr::Lit(Literal::Int(1))), Box::new(Expr::Lit(Literal::Int(2))));
+        let program = lower_expr(&expr).unwrap();
+        let main = &program.functions[0];
+        assert_eq!(main.body.stmts[0].ty, Type::Prim(PrimType::Bool));
+        assert_eq!(main.ret_ty, Type::Prim(PrimType::Bool));
+    }
+
+    #[test]
+    fn test_lower_mixed_arithmetic_type() {
+        // 1 + 2.5 promotes to F64
+        let expr = Expr::BinOp(BinOp::Add, Box::new(Expr::Lit(Literal::Int(1))), Box::new(Expr::Lit(Literal::Float(2.5))));
+        let program = lower_expr(&expr).unwrap();
+        assert_eq!(program.functions[0].body.stmts[0].ty, Type::Prim(PrimType::F64));
+
+        // ⊤ + 1 cannot be reconciled
+        let expr = Expr::BinOp(BinOp::Add, Box::new(Expr::Lit(Literal::True)), Box::new(Expr::Lit(Literal::Int(1))));
+        assert!(matches!(lower_expr(&expr), Err(MirError::TypeError(_))));
+    }
+
+    #[test]
+    fn test_lower_floor_float_result() {
+        // ⌊2.7⌋ stays F64
+        let expr = Expr::UnaryOp(UnaryOp::Floor, Box::new(Expr::Lit(Literal::Float(2.7))));
+        let program = lower_expr(&expr).unwrap();
+        let main = &program.functions[0];
+        assert_eq!(main.body.stmts.len(), 1);
+        assert!(matches!(main.body.stmts[0].rhs, Rhs::UnaryOp(UnaryOp::Floor, _)));
+        assert_eq!(main.ret_ty, Type::Prim(PrimType::F64));
+    }
+
+    #[test]
+    fn test_lower_division_by_zero_is_kept() {
+        // Lowering leaves the division for the backend, which traps on a zero divisor
+        let stmts = lower_expr(&Expr::div(Expr::int(1), Expr::int(0))).unwrap().functions[0].body.stmts.clone();
+        assert!(matches!(&stmts[0].rhs, Rhs::BinOp(BinOp::Div, _, Operand::Const(Constant::Int(0)))));
+    }
+
+    #[test]
+    fn test_lower_unsupported_expression_names_variant() {
+        // A large unsupported subtree is reported by name, not dumped
+        let body = Expr::tuple((0..50).map(Expr::int).collect());
+        let err = lower_expr(&Expr::add(Expr::int(1), Expr::Quote(Box::new(body)))).unwrap_err();
+        assert_eq!(err.to_string(), "Cannot lower expression: Quote is not supported yet");
+    }
+
+    #[test]
+    fn test_lower_mixed_comparison_casts_int() {
+        // 1 < 2.5 compares two floats
+        let expr = Expr::binop(BinOp::Lt, Expr::int(1), Expr::float(2.5));
+        let program = lower_expr(&expr).unwrap();
+        let main = &program.functions[0];
+        assert_eq!(main.body.stmts.len(), 2);
+        let cast = &main.body.stmts[0];
+        match &cast.rhs {
+            Rhs::Cast { from, to, operand: Operand::Const(Constant::Int(1)) } => {
+                assert_eq!(*from, Type::Prim(PrimType::I64));
+                assert_eq!(*to, Type::Prim(PrimType::F64));
+            }
+            other => panic!("Expected Cast, got {:?}", other),
+        }
+        assert!(matches!(&main.body.stmts[1].rhs, Rhs::BinOp(BinOp::Lt, Operand::Local(l), Operand::Const(Constant::Float(_))) if *l == cast.dest));
+        assert_eq!(main.ret_ty, Type::Prim(PrimType::Bool));
+
+        // Either side may be the integer
+        let flipped = lower_expr(&Expr::binop(BinOp::Geq, Expr::float(0.5), Expr::int(3))).unwrap();
+        assert!(matches!(&flipped.functions[0].body.stmts[1].rhs, Rhs::BinOp(BinOp::Geq, Operand::Const(Constant::Float(_)), Operand::Local(_))));
+    }
+
+    #[test]
+    fn test_lower_floor_int_result() {
+        // ⌈2.2⌉ : I64 rounds, then casts
+        let ceil = Expr::UnaryOp(UnaryOp::Ceil, Box::new(Expr::Lit(Literal::Float(2.2))));
+        let expr = Expr::Annot(Box::new(ceil), Type::Prim(PrimType::I64));
+        let program = lower_expr(&expr).unwrap();
+        let main = &program.functions[0];
+        assert_eq!(main.body.stmts.len(), 2);
+        assert_eq!(main.body.stmts[0].ty, Type::Prim(PrimType::F64));
+        match &main.body.stmts[1].rhs {
+            Rhs::Cast { from, to, operand: Operand::Local(src) } => {
+                assert_eq!(*from, Type::Prim(PrimType::F64));
+                assert_eq!(*to, Type::Prim(PrimType::I64));
+                assert_eq!(*src, main.body.stmts[0].dest);
+            }
+            other => panic!("Expected Cast, got {:?}", other),
+        }
+        assert_eq!(main.ret_ty, Type::Prim(PrimType::I64));
+    }
+
+    #[test]
+    fn test_lower_shapes_through_tensor_ops() {
+        let result_ty = |expr: Expr| lower_expr(&expr).unwrap().functions.last().unwrap().ret_ty.clone();
+        let int_vector = |n: u64| Type::vector(Dim::Const(n), Type::Prim(PrimType::I64));
+
+        // iota 4, and range 2 7
+        assert_eq!(result_ty(Expr::app(Expr::name("iota"), Expr::int(4))), int_vector(4));
+        assert_eq!(result_ty(Expr::app(Expr::app(Expr::name("range"), Expr::int(2)), Expr::int(7))), int_vector(5));
+
+        // reshape [1, 2, 3, 4, 5, 6] [2, 3]
+        let data = Expr::Array((1..=6).map(Expr::int).collect());
+        let reshape = Expr::app(Expr::app(Expr::name("reshape"), data), Expr::Array(vec![Expr::int(2), Expr::int(3)]));
+        assert_eq!(result_ty(reshape), Type::Tensor(Shape::concrete(&[2, 3]), Box::new(Type::Prim(PrimType::I64))));
+
+        // iota n for an unknown n
+        let unknown = Expr::let_var("n", Expr::app(Expr::name("len"), Expr::Array(vec![Expr::int(1)])), Expr::app(Expr::name("iota"), Expr::idx(0)));
+        assert_eq!(result_ty(unknown), Type::vector(Dim::Dynamic, Type::Prim(PrimType::I64)));
+    }
+
     #[test]
     fn test_lower_binop() {
         // 1 + 2
@@ -2238,6 +2555,40 @@ mod tests {
         }
     }
     
+    #[test]
+    fn test_lower_field_access() {
+        // ⟨1, true⟩.1 : Bool
+        let tuple = Expr::Tuple(vec![Expr::Lit(Literal::Int(1)), Expr::Lit(Literal::True)]);
+        let program = lower_expr(&Expr::field_idx(tuple.clone(), 1)).unwrap();
+        let stmt = &program.functions[0].body.stmts[1];
+        assert!(matches!(stmt.rhs, Rhs::TupleField(_, 1)));
+        assert_eq!(stmt.ty, Type::Prim(PrimType::Bool));
+        assert!(matches!(lower_expr(&Expr::field_idx(tuple, 2)), Err(MirError::TypeError(_))));
+
+        // ⟨x: 1, y: 2.5⟩.y : F64, at position 1
+        let record = Expr::Record(vec![("x".into(), Expr::Lit(Literal::Int(1))), ("y".into(), Expr::Lit(Literal::Float(2.5)))]);
+        let program = lower_expr(&Expr::field(record, "y")).unwrap();
+        let stmt = &program.functions[0].body.stmts[1];
+        assert!(matches!(stmt.rhs, Rhs::TupleField(_, 1)));
+        assert_eq!(stmt.ty, Type::Prim(PrimType::F64));
+    }
+
+    #[test]
+    fn test_lower_variant_construction() {
+        // Some 3
+        let expr = Expr::variant("Some", Some(Expr::Lit(Literal::Int(3))));
+        let program = lower_expr(&expr).unwrap();
+        let stmt = &program.functions[0].body.stmts[0];
+        match &stmt.rhs {
+            Rhs::MakeVariant { tag, constructor, payload } => {
+                assert_eq!((*tag, constructor.as_str()), (1, "Some"));
+                assert!(matches!(payload, Some(Operand::Const(Constant::Int(3)))));
+            }
+            other => panic!("Expected MakeVariant, got {:?}", other),
+        }
+        assert!(matches!(&stmt.ty, Type::Variant(arms) if arms[0].payload == Some(Type::Prim(PrimType::I64))));
+    }
+
     #[test]
     fn test_lower_array() {
         // [1, 2, 3]
@@ -8,9 +8,10 @@
 
 use goth_ast::types::Type;
 use goth_ast::op::{BinOp, UnaryOp};
+use serde::{Deserialize, Serialize};
 
 /// A MIR program is a collection of functions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Program {
     /// All functions (including lifted lambdas)
     pub functions: Vec<Function>,
@@ -19,7 +20,7 @@ pub struct Program {
 }
 
 /// A function in MIR
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Function {
     /// Unique name (may be generated for lambdas)
     pub name: String,
@@ -36,7 +37,7 @@ pub struct Function {
 }
 
 /// A basic block (sequence of statements + terminator)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     /// Statements in order
     pub stmts: Vec<Stmt>,
@@ -45,7 +46,7 @@ pub struct Block {
 }
 
 /// Statement (defines a local variable)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stmt {
     /// Local variable being defined
     pub dest: LocalId,
@@ -56,7 +57,7 @@ pub struct Stmt {
 }
 
 /// Right-hand side of a statement
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Rhs {
     /// Use another local
     Use(Operand),
@@ -69,6 +70,13 @@ pub enum Rhs {
     
     /// Unary operation
     UnaryOp(UnaryOp, Operand),
+
+    /// Numeric conversion: Cast { from: F64, to: I64, operand } truncates toward zero
+    Cast {
+        from: Type,
+        to: Type,
+        operand: Operand,
+    },
     
     /// Function call
     Call {
@@ -181,7 +189,7 @@ pub enum Rhs {
 }
 
 /// Reduction operations
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReduceOp {
     Sum,
     Prod,
@@ -190,7 +198,7 @@ pub enum ReduceOp {
 }
 
 /// Block terminator
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Terminator {
     /// Return value
     Return(Operand),
@@ -217,7 +225,7 @@ pub enum Terminator {
 }
 
 /// An operand (value reference)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Operand {
     /// Local variable
     Local(LocalId),
@@ -226,21 +234,70 @@ pub enum Operand {
 }
 
 /// Constant values
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Constant {
     Int(i64),
     Float(f64),
     Bool(bool),
+    /// Unicode scalar value, emitted as an i32 code point
+    Char(char),
     String(String),
     Unit,
 }
 
+impl Rhs {
+    /// Every operand read by this right-hand side
+    pub fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Rhs::Use(a) | Rhs::UnaryOp(_, a) | Rhs::Cast { operand: a, .. } | Rhs::TupleField(a, _) | Rhs::Iota(a)
+            | Rhs::GetTag(a) | Rhs::GetPayload(a) | Rhs::TensorReduce { tensor: a, .. } | Rhs::ContractCheck { predicate: a, .. } => vec![a],
+            Rhs::BinOp(_, a, b) | Rhs::Index(a, b) | Rhs::Range(a, b) | Rhs::ArrayFill { size: a, value: b }
+            | Rhs::TensorMap { tensor: a, func: b } | Rhs::TensorFilter { tensor: a, pred: b }
+            | Rhs::TensorZip { left: a, right: b } | Rhs::Uncertain { value: a, uncertainty: b } => vec![a, b],
+            Rhs::Call { args, .. } | Rhs::Tuple(args) | Rhs::Array(args) | Rhs::Prim { args, .. } => args.iter_mut().collect(),
+            Rhs::MakeClosure { captures, .. } => captures.iter_mut().collect(),
+            Rhs::ClosureCall { closure, args } => std::iter::once(closure).chain(args.iter_mut()).collect(),
+            Rhs::Slice { array, start, end } => std::iter::once(array).chain(start.as_mut()).chain(end.as_mut()).collect(),
+            Rhs::MakeVariant { payload, .. } => payload.iter_mut().collect(),
+            Rhs::Const(_) => vec![],
+        }
+    }
+}
+
+impl Terminator {
+    /// The operand this terminator reads, if any
+    pub fn operand_mut(&mut self) -> Option<&mut Operand> {
+        match self {
+            Terminator::Return(op) | Terminator::If { cond: op, .. } | Terminator::Switch { scrutinee: op, .. } => Some(op),
+            Terminator::Goto(_) | Terminator::Unreachable => None,
+        }
+    }
+}
+
+impl Constant {
+    /// The integer a switch compares against: the value of an `Int`, or the code point of a `Char`
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Constant::Int(n) => Some(*n),
+            Constant::Char(c) => Some(*c as i64),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `Rhs::Cast` can convert a value of type `from` to `to`: only
+/// conversions between integer and float scalars are defined
+pub fn is_valid_cast(from: &Type, to: &Type) -> bool {
+    let numeric = |ty: &Type| matches!(ty, Type::Prim(p) if p.is_int() || p.is_float());
+    numeric(from) && numeric(to)
+}
+
 /// Local variable ID
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct LocalId(pub u32);
 
 /// Block ID  
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BlockId(pub u32);
 
 impl Block {
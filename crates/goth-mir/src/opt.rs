@@ -0,0 +1,359 @@
+//! MIR optimization passes
+//!
+//! Each pass rewrites a [`Program`] in place and leaves it well-formed, as
+//! checked by [`verify`](crate::verify::verify).
+
+use std::collections::{HashMap, HashSet};
+use goth_ast::types::Type;
+use crate::mir::*;
+
+/// Remove blocks that cannot be reached from their function's entry block
+pub fn prune_unreachable(program: &mut Program) {
+    for func in &mut program.functions {
+        let reachable = reachable_blocks(func);
+        func.blocks.retain(|(id, _)| reachable.contains(id));
+    }
+}
+
+/// Blocks reachable from the entry block of `func`, following every branch
+fn reachable_blocks(func: &Function) -> HashSet<BlockId> {
+    let mut reachable = HashSet::new();
+    let mut work = successors(&func.body.term);
+    while let Some(id) = work.pop() {
+        if !reachable.insert(id) { continue; }
+        if let Some((_, block)) = func.blocks.iter().find(|(b, _)| *b == id) {
+            work.extend(successors(&block.term));
+        }
+    }
+    reachable
+}
+
+/// Blocks `term` may branch to
+pub(crate) fn successors(term: &Terminator) -> Vec<BlockId> {
+    match term {
+        Terminator::Goto(b) => vec![*b],
+        Terminator::If { then_block, else_block, .. } => vec![*then_block, *else_block],
+        Terminator::Switch { cases, default, .. } => cases.iter().map(|(_, b)| *b).chain(std::iter::once(*default)).collect(),
+        Terminator::Return(_) | Terminator::Unreachable => vec![],
+    }
+}
+
+/// Statement count at or below which [`inline_functions`] inlines a callee
+pub const DEFAULT_INLINE_THRESHOLD: usize = 8;
+
+/// Inline small, non-recursive functions at their call sites
+pub fn inline_functions(program: &mut Program) {
+    inline_functions_with_threshold(program, DEFAULT_INLINE_THRESHOLD)
+}
+
+/// Inline calls to functions of at most `max_stmts` statements.
+///
+/// A callee is inlined when its body is a single block ending in a return
+/// and it neither calls nor closes over itself. Direct calls are inlined, as
+/// are closure calls on a closure built in the calling function; the
+/// closure's captures are packed into the environment tuple the callee
+/// expects. Callees stay in the program, since other code may still use them.
+pub fn inline_functions_with_threshold(program: &mut Program, max_stmts: usize) {
+    let callees: HashMap<String, Function> = program.functions.iter()
+        .filter(|f| is_inlinable(f, max_stmts))
+        .map(|f| (f.name.clone(), f.clone()))
+        .collect();
+    for func in &mut program.functions {
+        inline_calls(func, &callees);
+    }
+}
+
+fn is_inlinable(func: &Function, max_stmts: usize) -> bool {
+    let calls_self = func.body.stmts.iter().any(|s| matches!(&s.rhs,
+        Rhs::Call { func: name, .. } | Rhs::MakeClosure { func: name, .. } if *name == func.name));
+    func.blocks.is_empty() && matches!(func.body.term, Terminator::Return(_)) && func.body.stmts.len() <= max_stmts && !calls_self
+}
+
+/// The locals bound to `func`'s parameters, in order.
+///
+/// Lowering numbers locals from zero, so parameters come first. A closure's
+/// environment is followed by one local per capture, and then its argument.
+pub(crate) fn param_locals(func: &Function) -> Vec<LocalId> {
+    let captures = match func.params.first() {
+        Some(Type::Tuple(fields)) if func.is_closure => fields.len() as u32,
+        _ => 0,
+    };
+    (0..func.params.len() as u32)
+        .map(|i| if func.is_closure && i > 0 { LocalId(i + captures) } else { LocalId(i) })
+        .collect()
+}
+
+fn inline_calls(func: &mut Function, callees: &HashMap<String, Function>) {
+    let closures = closure_defs(func);
+    let mut next_local = std::iter::once(&func.body).chain(func.blocks.iter().map(|(_, b)| b))
+        .flat_map(|b| b.stmts.iter().map(|s| s.dest.0))
+        .chain(param_locals(func).into_iter().map(|l| l.0))
+        .max().map_or(0, |n| n + 1);
+    let caller = func.name.clone();
+
+    for block in std::iter::once(&mut func.body).chain(func.blocks.iter_mut().map(|(_, b)| b)) {
+        let mut stmts = Vec::with_capacity(block.stmts.len());
+        for stmt in std::mem::take(&mut block.stmts) {
+            let call = match &stmt.rhs {
+                Rhs::Call { func: name, args, .. } => callees.get(name).map(|c| (c, args.clone(), Vec::new())),
+                Rhs::ClosureCall { closure: Operand::Local(l), args } => closures.get(l)
+                    .and_then(|(name, captures)| callees.get(name).map(|c| (c, args.clone(), captures.clone()))),
+                _ => None,
+            };
+            match call {
+                Some((callee, args, captures)) if callee.name != caller => {
+                    inline_call(callee, args, captures, stmt, &mut next_local, &mut stmts);
+                }
+                _ => stmts.push(stmt),
+            }
+        }
+        block.stmts = stmts;
+    }
+}
+
+/// Append `callee`'s body to `out` in place of the call `stmt`, or `stmt` itself if the arguments don't fit
+fn inline_call(callee: &Function, args: Vec<Operand>, captures: Vec<Operand>, stmt: Stmt, next_local: &mut u32, out: &mut Vec<Stmt>) {
+    let params = param_locals(callee);
+    if params.len() != args.len() + callee.is_closure as usize {
+        out.push(stmt);
+        return;
+    }
+    let mut fresh = || { let l = LocalId(*next_local); *next_local += 1; l };
+
+    let mut bound = Vec::with_capacity(params.len());
+    if callee.is_closure {
+        let env = fresh();
+        out.push(Stmt { dest: env, ty: callee.params[0].clone(), rhs: Rhs::Tuple(captures) });
+        bound.push(Operand::Local(env));
+    }
+    bound.extend(args);
+    let mut renamed: HashMap<LocalId, Operand> = params.into_iter().zip(bound).collect();
+    let rename = |op: &mut Operand, renamed: &HashMap<LocalId, Operand>| {
+        if let Operand::Local(l) = op {
+            if let Some(new) = renamed.get(l) { *op = new.clone(); }
+        }
+    };
+
+    for callee_stmt in &callee.body.stmts {
+        let mut rhs = callee_stmt.rhs.clone();
+        for op in rhs.operands_mut() { rename(op, &renamed); }
+        let dest = fresh();
+        renamed.insert(callee_stmt.dest, Operand::Local(dest));
+        out.push(Stmt { dest, ty: callee_stmt.ty.clone(), rhs });
+    }
+    let mut term = callee.body.term.clone();
+    if let Some(op) = term.operand_mut() { rename(op, &renamed); }
+    if let Terminator::Return(result) = term {
+        out.push(Stmt { dest: stmt.dest, ty: stmt.ty, rhs: Rhs::Use(result) });
+    }
+}
+
+/// Closures built in `func`, by the locals holding them: the lifted function and its captures
+fn closure_defs(func: &Function) -> HashMap<LocalId, (String, Vec<Operand>)> {
+    let stmts: Vec<&Stmt> = std::iter::once(&func.body).chain(func.blocks.iter().map(|(_, b)| b))
+        .flat_map(|b| &b.stmts)
+        .collect();
+    let mut defs: HashMap<LocalId, usize> = HashMap::new();
+    for stmt in &stmts { *defs.entry(stmt.dest).or_default() += 1; }
+
+    // Follow copies, which lowering emits for let-bound closures
+    let mut closures = HashMap::new();
+    for stmt in stmts.into_iter().filter(|s| defs[&s.dest] == 1) {
+        match &stmt.rhs {
+            Rhs::MakeClosure { func, captures } => { closures.insert(stmt.dest, (func.clone(), captures.clone())); }
+            Rhs::Use(Operand::Local(src)) => {
+                if let Some(def) = closures.get(src).cloned() { closures.insert(stmt.dest, def); }
+            }
+            _ => {}
+        }
+    }
+    closures
+}
+
+/// Merge lifted lambdas that are identical up to their name and the
+/// numbering of their locals and blocks, redirecting calls and closures to
+/// the first of each group. Named functions are left alone, since callers
+/// outside the program may refer to them.
+///
+/// Repeats until nothing merges, because merging two callees can make their
+/// callers identical as well.
+pub fn merge_duplicate_functions(program: &mut Program) {
+    loop {
+        let mut canonical: HashMap<String, String> = HashMap::new();
+        let mut merged: HashMap<String, String> = HashMap::new();
+        for func in program.functions.iter().filter(|f| f.name.starts_with("lambda_") && f.name != program.entry) {
+            let Some(key) = fingerprint(func) else { continue };
+            match canonical.get(&key) {
+                Some(kept) => { merged.insert(func.name.clone(), kept.clone()); }
+                None => { canonical.insert(key, func.name.clone()); }
+            }
+        }
+        if merged.is_empty() { return; }
+
+        program.functions.retain(|f| !merged.contains_key(&f.name));
+        for func in &mut program.functions {
+            for block in std::iter::once(&mut func.body).chain(func.blocks.iter_mut().map(|(_, b)| b)) {
+                for stmt in &mut block.stmts {
+                    if let Rhs::Call { func: name, .. } | Rhs::MakeClosure { func: name, .. } = &mut stmt.rhs {
+                        if let Some(kept) = merged.get(name) { *name = kept.clone(); }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `func` without its name, with locals and blocks renumbered in order of
+/// definition, serialized. Functions with equal fingerprints have the same
+/// signature and alpha-equivalent bodies.
+fn fingerprint(func: &Function) -> Option<String> {
+    let mut func = func.clone();
+    func.name.clear();
+
+    let mut locals: HashMap<LocalId, LocalId> = HashMap::new();
+    let defined = std::iter::once(&func.body).chain(func.blocks.iter().map(|(_, b)| b)).flat_map(|b| b.stmts.iter().map(|s| s.dest));
+    for local in param_locals(&func).into_iter().chain(defined.collect::<Vec<_>>()) {
+        let next = LocalId(locals.len() as u32);
+        locals.entry(local).or_insert(next);
+    }
+    let blocks: HashMap<BlockId, BlockId> = func.blocks.iter().enumerate().map(|(i, (id, _))| (*id, BlockId(i as u32))).collect();
+
+    let renumber = |op: &mut Operand| if let Operand::Local(l) = op { if let Some(new) = locals.get(l) { *l = *new; } };
+    for (id, _) in &mut func.blocks { *id = blocks[id]; }
+    for block in std::iter::once(&mut func.body).chain(func.blocks.iter_mut().map(|(_, b)| b)) {
+        for stmt in &mut block.stmts {
+            stmt.dest = locals[&stmt.dest];
+            stmt.rhs.operands_mut().into_iter().for_each(renumber);
+        }
+        block.term.operand_mut().into_iter().for_each(renumber);
+        let target = |b: &mut BlockId| if let Some(new) = blocks.get(b) { *b = *new; };
+        match &mut block.term {
+            Terminator::Goto(b) => target(b),
+            Terminator::If { then_block, else_block, .. } => { target(then_block); target(else_block); }
+            Terminator::Switch { cases, default, .. } => { cases.iter_mut().for_each(|(_, b)| target(b)); target(default); }
+            Terminator::Return(_) | Terminator::Unreachable => {}
+        }
+    }
+    serde_json::to_string(&func).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::lower_expr;
+    use crate::verify::verify;
+    use goth_ast::expr::Expr;
+    use goth_ast::op::BinOp;
+    use goth_ast::types::PrimType;
+
+    fn block_ids(func: &Function) -> Vec<u32> {
+        func.blocks.iter().map(|(id, _)| id.0).collect()
+    }
+
+    #[test]
+    fn test_prune_dead_block() {
+        // if 1 < 2 then 3 else 4, plus a trap block nothing jumps to
+        let expr = Expr::if_(Expr::binop(BinOp::Lt, Expr::int(1), Expr::int(2)), Expr::int(3), Expr::int(4));
+        let mut program = lower_expr(&expr).unwrap();
+        let live = block_ids(&program.functions[0]);
+        program.functions[0].blocks.push((BlockId(99), Block { stmts: vec![], term: Terminator::Unreachable }));
+
+        prune_unreachable(&mut program);
+        verify(&program).unwrap();
+        assert_eq!(block_ids(&program.functions[0]), live);
+    }
+
+    #[test]
+    fn test_prune_keeps_single_branch_targets() {
+        // entry → bb0, which branches to bb1 or bb2; bb3 → bb1 is dead
+        let int = Type::Prim(PrimType::I64);
+        let body = Block { stmts: vec![], term: Terminator::Goto(BlockId(0)) };
+        let blocks = vec![
+            (BlockId(0), Block { stmts: vec![], term: Terminator::If { cond: Operand::Const(Constant::Bool(true)), then_block: BlockId(1), else_block: BlockId(2) } }),
+            (BlockId(1), Block::with_return(Operand::Const(Constant::Int(1)))),
+            (BlockId(2), Block::with_return(Operand::Const(Constant::Int(2)))),
+            (BlockId(3), Block { stmts: vec![], term: Terminator::Goto(BlockId(1)) }),
+        ];
+        let func = Function { name: "main".into(), params: vec![], ret_ty: int, body, blocks, is_closure: false };
+        let mut program = Program { functions: vec![func], entry: "main".into() };
+
+        prune_unreachable(&mut program);
+        verify(&program).unwrap();
+        assert_eq!(block_ids(&program.functions[0]), vec![0, 1, 2]);
+    }
+
+    fn calls(func: &Function) -> usize {
+        func.body.stmts.iter().filter(|s| matches!(s.rhs, Rhs::Call { .. } | Rhs::ClosureCall { .. })).count()
+    }
+
+    #[test]
+    fn test_inline_one_statement_helper() {
+        // let k = 1 in let f = λ→ ₀ + k in f 41
+        let f = Expr::lam(Expr::binop(BinOp::Add, Expr::idx(0), Expr::idx(1)));
+        let expr = Expr::let_var("k", Expr::int(1), Expr::let_var("f", f, Expr::app(Expr::idx(0), Expr::int(41))));
+        let mut program = lower_expr(&expr).unwrap();
+        assert_eq!(calls(&program.functions[1]), 1);
+
+        inline_functions(&mut program);
+        verify(&program).unwrap();
+        let main = &program.functions[1];
+        assert_eq!(calls(main), 0);
+        assert!(main.body.stmts.iter().any(|s| matches!(&s.rhs, Rhs::BinOp(BinOp::Add, Operand::Const(Constant::Int(41)), Operand::Local(_)))));
+    }
+
+    #[test]
+    fn test_inline_skips_recursive_and_large() {
+        let int = Type::Prim(PrimType::I64);
+        let call = |func: &str| Stmt { dest: LocalId(1), ty: int.clone(), rhs: Rhs::Call { func: func.into(), args: vec![Operand::Local(LocalId(0))], arg_tys: vec![int.clone()] } };
+        let function = |name: &str, stmts: Vec<Stmt>| Function {
+            name: name.into(), params: vec![int.clone()], ret_ty: int.clone(),
+            body: Block { stmts, term: Terminator::Return(Operand::Local(LocalId(1))) }, blocks: vec![], is_closure: false,
+        };
+        let negate = Stmt { dest: LocalId(1), ty: int.clone(), rhs: Rhs::UnaryOp(goth_ast::op::UnaryOp::Neg, Operand::Local(LocalId(0))) };
+        let mut program = Program {
+            functions: vec![function("spin", vec![call("spin")]), function("neg", vec![negate]), function("main", vec![call("spin")]), function("main2", vec![call("neg")])],
+            entry: "main".into(),
+        };
+
+        inline_functions_with_threshold(&mut program, 0);
+        assert_eq!(program.functions.iter().map(calls).collect::<Vec<_>>(), vec![1, 0, 1, 1]);
+
+        inline_functions(&mut program);
+        verify(&program).unwrap();
+        assert_eq!(program.functions.iter().map(calls).collect::<Vec<_>>(), vec![1, 0, 1, 0]);
+    }
+
+    fn closure_targets(func: &Function) -> Vec<&str> {
+        func.body.stmts.iter().filter_map(|s| match &s.rhs { Rhs::MakeClosure { func, .. } => Some(func.as_str()), _ => None }).collect()
+    }
+
+    #[test]
+    fn test_merge_identical_lambdas() {
+        // let f = λ→ ₀ × 2 in let g = λ→ ₀ × 2 in let h = λ→ ₀ + 2 in f 1 + g 2 + h 3
+        let double = || Expr::lam(Expr::binop(BinOp::Mul, Expr::idx(0), Expr::int(2)));
+        let calls = Expr::binop(BinOp::Add, Expr::binop(BinOp::Add, Expr::app(Expr::idx(2), Expr::int(1)), Expr::app(Expr::idx(1), Expr::int(2))), Expr::app(Expr::idx(0), Expr::int(3)));
+        let expr = Expr::let_var("f", double(), Expr::let_var("g", double(), Expr::let_var("h", Expr::lam(Expr::binop(BinOp::Add, Expr::idx(0), Expr::int(2))), calls)));
+        let mut program = lower_expr(&expr).unwrap();
+        assert_eq!(program.functions.len(), 4);
+
+        merge_duplicate_functions(&mut program);
+        verify(&program).unwrap();
+        let names: Vec<&str> = program.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["lambda_0", "lambda_2", "main"]);
+        assert_eq!(closure_targets(&program.functions[2]), vec!["lambda_0", "lambda_0", "lambda_2"]);
+        assert!(matches!(crate::run_mir(&program, vec![]), Ok(crate::interp::Value::Int(11))));
+    }
+
+    #[test]
+    fn test_merge_ignores_local_numbering() {
+        let int = Type::Prim(PrimType::I64);
+        let negate = |param: u32, dest: u32| Function {
+            name: format!("lambda_{}", param), params: vec![int.clone()], ret_ty: int.clone(),
+            body: Block { stmts: vec![Stmt { dest: LocalId(dest), ty: int.clone(), rhs: Rhs::UnaryOp(goth_ast::op::UnaryOp::Neg, Operand::Local(LocalId(0))) }], term: Terminator::Return(Operand::Local(LocalId(dest))) },
+            blocks: vec![], is_closure: false,
+        };
+        let mut program = Program { functions: vec![negate(0, 1), negate(1, 7)], entry: "main".into() };
+        merge_duplicate_functions(&mut program);
+        assert_eq!(program.functions.len(), 1);
+    }
+}
@@ -6,8 +6,8 @@ use crate::mir::*;
 impl fmt::Display for Program {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for func in &self.functions {
+            // Each function ends with its own newline
             writeln!(f, "{}", func)?;
-            writeln!(f)?;
         }
         writeln!(f, "// Entry point: {}", self.entry)?;
         Ok(())
@@ -50,9 +50,9 @@ impl fmt::Display for Function {
 impl fmt::Display for Block {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for stmt in &self.stmts {
-            writeln!(f, "  {}", stmt)?;
+            writeln!(f, "    {}", stmt)?;
         }
-        writeln!(f, "  {}", self.term)?;
+        writeln!(f, "    {}", self.term)?;
         Ok(())
     }
 }
@@ -74,6 +74,9 @@ impl fmt::Display for Rhs {
             Rhs::UnaryOp(op, operand) => {
                 write!(f, "UnaryOp({:?}, {})", op, operand)
             }
+            Rhs::Cast { from, to, operand } => {
+                write!(f, "Cast({}, {} -> {})", operand, from, to)
+            }
             Rhs::Call { func, args, arg_tys: _ } => {
                 write!(f, "Call({}",  func)?;
                 for arg in args {
@@ -209,8 +212,34 @@ impl fmt::Display for Constant {
             Constant::Int(n) => write!(f, "{}", n),
             Constant::Float(x) => write!(f, "{}", x),
             Constant::Bool(b) => write!(f, "{}", b),
+            Constant::Char(c) => write!(f, "{:?}", c),
             Constant::String(s) => write!(f, "\"{}\"", s),
             Constant::Unit => write!(f, "()"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lower::lower_expr;
+    use crate::print_program;
+    use goth_ast::expr::Expr;
+    use goth_ast::op::BinOp;
+
+    #[test]
+    fn test_print_lowered_arithmetic() {
+        // (1 + 2) * 3
+        let expr = Expr::binop(BinOp::Mul, Expr::binop(BinOp::Add, Expr::int(1), Expr::int(2)), Expr::int(3));
+        let printed = print_program(&lower_expr(&expr).unwrap());
+        assert_eq!(printed, "\
+fn main() -> I64 {
+  entry:
+    _0: I64 = BinOp(Add, Const(1), Const(2))
+    _1: I64 = BinOp(Mul, _0, Const(3))
+    Return(_1)
+}
+
+// Entry point: main
+");
+    }
+}
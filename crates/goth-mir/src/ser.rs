@@ -0,0 +1,70 @@
+//! JSON serialization for MIR
+//!
+//! Lets a lowered [`Program`] be cached between compiler runs and reloaded
+//! without lowering again.
+
+use crate::mir::Program;
+
+/// Serialize a program to a JSON string
+pub fn to_mir_json(program: &Program) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(program)
+}
+
+/// Deserialize a program from a JSON string
+pub fn from_mir_json(json: &str) -> serde_json::Result<Program> {
+    serde_json::from_str(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::lower_expr;
+    use crate::mir::*;
+    use goth_ast::expr::Expr;
+    use goth_ast::op::BinOp;
+    use goth_ast::types::{PrimType, Type};
+
+    fn round_trip(program: &Program) {
+        let json = to_mir_json(program).unwrap();
+        let loaded = from_mir_json(&json).unwrap();
+        assert_eq!(to_mir_json(&loaded).unwrap(), json);
+        assert_eq!(loaded.to_string(), program.to_string());
+    }
+
+    #[test]
+    fn test_round_trip_lowered_program() {
+        // let f = λ→ ₀ × 2 in if f 3 > 5 then (f 1, 1.5) else (0, 2.5)
+        let f = Expr::lam(Expr::binop(BinOp::Mul, Expr::idx(0), Expr::int(2)));
+        let cond = Expr::binop(BinOp::Gt, Expr::app(Expr::idx(0), Expr::int(3)), Expr::int(5));
+        let then_ = Expr::Tuple(vec![Expr::app(Expr::idx(0), Expr::int(1)), Expr::float(1.5)]);
+        let else_ = Expr::Tuple(vec![Expr::int(0), Expr::float(2.5)]);
+        let expr = Expr::let_var("f", f, Expr::if_(cond, then_, else_));
+        round_trip(&lower_expr(&expr).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_constants_and_switch() {
+        let int = Type::Prim(PrimType::I64);
+        let body = Block {
+            stmts: vec![
+                Stmt { dest: LocalId(0), ty: Type::Prim(PrimType::F64), rhs: Rhs::Const(Constant::Float(0.1)) },
+                Stmt { dest: LocalId(1), ty: Type::Prim(PrimType::String), rhs: Rhs::Const(Constant::String("hé\n".into())) },
+                Stmt { dest: LocalId(2), ty: Type::Tuple(vec![]), rhs: Rhs::Const(Constant::Unit) },
+                Stmt { dest: LocalId(3), ty: Type::Prim(PrimType::Bool), rhs: Rhs::Const(Constant::Bool(true)) },
+                Stmt { dest: LocalId(4), ty: int.clone(), rhs: Rhs::TensorReduce { tensor: Operand::Local(LocalId(1)), op: ReduceOp::Max } },
+            ],
+            term: Terminator::Switch {
+                scrutinee: Operand::Const(Constant::Char('λ')),
+                cases: vec![(Constant::Int(-7), BlockId(0)), (Constant::Char('λ'), BlockId(1))],
+                default: BlockId(0),
+            },
+        };
+        let blocks = vec![
+            (BlockId(0), Block { stmts: vec![], term: Terminator::Unreachable }),
+            (BlockId(1), Block { stmts: vec![], term: Terminator::Goto(BlockId(2)) }),
+            (BlockId(2), Block::with_return(Operand::Local(LocalId(4)))),
+        ];
+        let func = Function { name: "main".into(), params: vec![], ret_ty: int, body, blocks, is_closure: false };
+        round_trip(&Program { functions: vec![func], entry: "main".into() });
+    }
+}
@@ -0,0 +1,262 @@
+//! Well-formedness checks for MIR
+//!
+//! Run after lowering and after each optimization pass to catch malformed
+//! output early. Checked:
+//!
+//! - the entry function exists and function names are unique
+//! - every block id is defined once and every branch targets a defined block
+//! - within a block, a local is not used before the statement defining it
+//! - locals that are never defined are no more than the function's parameters
+//! - statement types agree with constants, comparisons, casts, tuples and copies
+//! - casts convert between integer and float scalars only
+//!
+//! Definitions in other blocks are accepted without a dominance check, and
+//! types containing variables or holes are not compared.
+
+use std::collections::{HashMap, HashSet};
+use goth_ast::op::BinOp;
+use goth_ast::types::{PrimType, Type};
+use crate::error::MirError;
+use crate::mir::*;
+use crate::opt::successors;
+
+/// Check `program`, reporting every violation found
+pub fn verify(program: &Program) -> Result<(), Vec<MirError>> {
+    let mut errors = Vec::new();
+
+    let mut names = HashSet::new();
+    for func in &program.functions {
+        if !names.insert(func.name.as_str()) {
+            errors.push(invalid(&func.name, "function is defined more than once".into()));
+        }
+        verify_function(func, &mut errors);
+    }
+    if !names.contains(program.entry.as_str()) {
+        errors.push(MirError::Invalid(format!("entry function {} does not exist", program.entry)));
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+fn invalid(func: &str, msg: String) -> MirError {
+    MirError::Invalid(format!("in {}: {}", func, msg))
+}
+
+fn verify_function(func: &Function, errors: &mut Vec<MirError>) {
+    let mut block_ids = HashSet::new();
+    for (id, _) in &func.blocks {
+        if !block_ids.insert(*id) {
+            errors.push(invalid(&func.name, format!("block {} is defined more than once", id)));
+        }
+    }
+
+    let blocks: Vec<(String, &Block)> = std::iter::once(("entry".to_string(), &func.body))
+        .chain(func.blocks.iter().map(|(id, block)| (id.to_string(), block)))
+        .collect();
+
+    // Where each local is defined, and the type given at its first definition
+    let mut defined_in: HashMap<LocalId, HashSet<usize>> = HashMap::new();
+    let mut types: HashMap<LocalId, &Type> = HashMap::new();
+    for (b, (_, block)) in blocks.iter().enumerate() {
+        for stmt in &block.stmts {
+            defined_in.entry(stmt.dest).or_default().insert(b);
+            types.entry(stmt.dest).or_insert(&stmt.ty);
+        }
+    }
+
+    let mut params = HashSet::new();
+    for (b, (label, block)) in blocks.iter().enumerate() {
+        let mut defined_here = HashSet::new();
+        let mut uses = Vec::new();
+        for stmt in &block.stmts {
+            uses.extend(rhs_locals(&stmt.rhs).into_iter().filter(|l| !defined_here.contains(l)));
+            if let Some(msg) = type_mismatch(stmt, &types) {
+                errors.push(invalid(&func.name, format!("{} in block {}", msg, label)));
+            }
+            defined_here.insert(stmt.dest);
+        }
+        uses.extend(term_locals(&block.term).into_iter().filter(|l| !defined_here.contains(l)));
+
+        // A use not preceded by a definition in this block needs one elsewhere, or is a parameter
+        for local in uses {
+            match defined_in.get(&local) {
+                None => { params.insert(local); }
+                Some(in_blocks) if in_blocks.len() == 1 && in_blocks.contains(&b) => {
+                    errors.push(invalid(&func.name, format!("{} is used before its definition in block {}", local, label)));
+                }
+                Some(_) => {}
+            }
+        }
+
+        for target in successors(&block.term) {
+            if !block_ids.contains(&target) {
+                errors.push(invalid(&func.name, format!("block {} branches to missing block {}", label, target)));
+            }
+        }
+    }
+
+    if params.len() > func.params.len() {
+        let mut undefined: Vec<_> = params.into_iter().map(|l| l.0).collect();
+        undefined.sort();
+        errors.push(invalid(&func.name, format!("{} undefined locals {:?} but only {} parameters", undefined.len(), undefined, func.params.len())));
+    }
+}
+
+/// Why `stmt`'s declared type cannot be the type of its right-hand side, if it cannot
+fn type_mismatch(stmt: &Stmt, types: &HashMap<LocalId, &Type>) -> Option<String> {
+    if !is_concrete(&stmt.ty) { return None; }
+    let mismatch = |expected: &str| Some(format!("{} declared {} but its value is {}", stmt.dest, stmt.ty, expected));
+    match &stmt.rhs {
+        Rhs::Const(c) | Rhs::Use(Operand::Const(c)) => {
+            if constant_fits(c, &stmt.ty) { None } else { mismatch(&format!("the constant {}", c)) }
+        }
+        Rhs::Use(Operand::Local(src)) => match types.get(src) {
+            Some(ty) if is_concrete(ty) && *ty != &stmt.ty => mismatch(&format!("{} of type {}", src, ty)),
+            _ => None,
+        },
+        Rhs::BinOp(BinOp::Eq | BinOp::Neq | BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq, _, _) => {
+            if stmt.ty == Type::Prim(PrimType::Bool) { None } else { mismatch("a comparison") }
+        }
+        Rhs::Cast { to, .. } if is_concrete(to) && *to != stmt.ty => mismatch(&format!("a cast to {}", to)),
+        Rhs::Cast { from, to, .. } if is_concrete(from) && is_concrete(to) && !is_valid_cast(from, to) => {
+            Some(MirError::InvalidCast { from: from.to_string(), to: to.to_string() }.to_string())
+        }
+        Rhs::Tuple(ops) => match &stmt.ty {
+            Type::Tuple(fields) if fields.len() == ops.len() => None,
+            _ => mismatch(&format!("a {}-tuple", ops.len())),
+        },
+        Rhs::TupleField(Operand::Local(src), i) => match types.get(src) {
+            Some(Type::Tuple(fields)) => match fields.get(*i) {
+                Some(field) if is_concrete(&field.ty) && field.ty != stmt.ty => mismatch(&format!("field {} of type {}", i, field.ty)),
+                None => mismatch(&format!("missing field {} of {}", i, src)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn constant_fits(c: &Constant, ty: &Type) -> bool {
+    match (c, ty) {
+        (Constant::Int(_), Type::Prim(p)) => p.is_int(),
+        (Constant::Float(_), Type::Prim(p)) => p.is_float(),
+        (Constant::Bool(_), Type::Prim(p)) => *p == PrimType::Bool,
+        (Constant::Char(_), Type::Prim(p)) => *p == PrimType::Char,
+        (Constant::String(_), Type::Prim(p)) => *p == PrimType::String,
+        (Constant::Unit, Type::Tuple(fields)) => fields.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether `ty` contains no type variables or holes
+fn is_concrete(ty: &Type) -> bool {
+    match ty {
+        Type::Var(_) | Type::Hole => false,
+        Type::Tensor(_, elem) => is_concrete(elem),
+        Type::Tuple(fields) => fields.iter().all(|f| is_concrete(&f.ty)),
+        Type::Fn(a, b) => is_concrete(a) && is_concrete(b),
+        Type::Prim(_) => true,
+        _ => false,
+    }
+}
+
+fn operand_local(op: &Operand) -> Option<LocalId> {
+    match op {
+        Operand::Local(l) => Some(*l),
+        Operand::Const(_) => None,
+    }
+}
+
+/// Locals read by `rhs`
+fn rhs_locals(rhs: &Rhs) -> Vec<LocalId> {
+    let ops: Vec<&Operand> = match rhs {
+        Rhs::Use(a) | Rhs::UnaryOp(_, a) | Rhs::Cast { operand: a, .. } | Rhs::TupleField(a, _) | Rhs::Iota(a) | Rhs::GetTag(a) | Rhs::GetPayload(a) => vec![a],
+        Rhs::BinOp(_, a, b) | Rhs::Index(a, b) | Rhs::Range(a, b) => vec![a, b],
+        Rhs::ArrayFill { size: a, value: b } | Rhs::TensorMap { tensor: a, func: b } | Rhs::TensorFilter { tensor: a, pred: b }
+        | Rhs::TensorZip { left: a, right: b } | Rhs::Uncertain { value: a, uncertainty: b } => vec![a, b],
+        Rhs::TensorReduce { tensor, .. } => vec![tensor],
+        Rhs::ContractCheck { predicate, .. } => vec![predicate],
+        Rhs::Call { args, .. } | Rhs::Tuple(args) | Rhs::Array(args) | Rhs::Prim { args, .. } => args.iter().collect(),
+        Rhs::MakeClosure { captures, .. } => captures.iter().collect(),
+        Rhs::ClosureCall { closure, args } => std::iter::once(closure).chain(args).collect(),
+        Rhs::Slice { array, start, end } => std::iter::once(array).chain(start).chain(end).collect(),
+        Rhs::MakeVariant { payload, .. } => payload.iter().collect(),
+        Rhs::Const(_) => vec![],
+    };
+    ops.into_iter().filter_map(operand_local).collect()
+}
+
+fn term_locals(term: &Terminator) -> Vec<LocalId> {
+    match term {
+        Terminator::Return(op) | Terminator::If { cond: op, .. } | Terminator::Switch { scrutinee: op, .. } => operand_local(op).into_iter().collect(),
+        Terminator::Goto(_) | Terminator::Unreachable => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lower::lower_expr;
+    use goth_ast::expr::Expr;
+
+    fn int() -> Type { Type::Prim(PrimType::I64) }
+
+    fn function(name: &str, body: Block, blocks: Vec<(BlockId, Block)>) -> Function {
+        Function { name: name.into(), params: vec![], ret_ty: int(), body, blocks, is_closure: false }
+    }
+
+    #[test]
+    fn test_verify_lowered_program() {
+        let expr = Expr::If {
+            cond: Box::new(Expr::binop(BinOp::Lt, Expr::int(1), Expr::int(2))),
+            then_: Box::new(Expr::binop(BinOp::Add, Expr::int(3), Expr::int(4))),
+            else_: Box::new(Expr::int(5)),
+        };
+        verify(&lower_expr(&expr).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_verify_reports_all_violations() {
+        let body = Block {
+            stmts: vec![
+                Stmt { dest: LocalId(1), ty: int(), rhs: Rhs::BinOp(BinOp::Add, Operand::Local(LocalId(2)), Operand::Const(Constant::Int(1))) },
+                Stmt { dest: LocalId(2), ty: int(), rhs: Rhs::Const(Constant::Bool(true)) },
+            ],
+            term: Terminator::Goto(BlockId(7)),
+        };
+        let program = Program { functions: vec![function("f", body, vec![])], entry: "main".into() };
+
+        let errors: Vec<String> = verify(&program).unwrap_err().iter().map(|e| e.to_string()).collect();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("_2 is used before its definition")));
+        assert!(errors.iter().any(|e| e.contains("the constant true")));
+        assert!(errors.iter().any(|e| e.contains("missing block bb7")));
+        assert!(errors.iter().any(|e| e.contains("entry function main does not exist")));
+    }
+
+    #[test]
+    fn test_verify_undefined_locals_beyond_params() {
+        let body = Block::with_return(Operand::Local(LocalId(0)));
+        let program = Program { functions: vec![function("main", body, vec![])], entry: "main".into() };
+        let errors = verify(&program).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("undefined locals [0]"));
+    }
+
+    #[test]
+    fn test_verify_rejects_invalid_cast() {
+        let tuple = Type::Tuple(vec![]);
+        let body = Block {
+            stmts: vec![
+                Stmt { dest: LocalId(0), ty: tuple.clone(), rhs: Rhs::Const(Constant::Unit) },
+                Stmt { dest: LocalId(1), ty: int(), rhs: Rhs::Cast { from: tuple, to: int(), operand: Operand::Local(LocalId(0)) } },
+            ],
+            term: Terminator::Return(Operand::Local(LocalId(1))),
+        };
+        let program = Program { functions: vec![function("main", body, vec![])], entry: "main".into() };
+        let errors = verify(&program).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("Invalid cast from ⟨⟩ to I64"), "{}", errors[0]);
+    }
+}
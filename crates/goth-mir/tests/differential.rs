@@ -0,0 +1,153 @@
+//! Differential tests: the MIR interpreter must agree with the evaluator
+//!
+//! Each program is evaluated directly and also lowered to MIR and run by
+//! `run_mir`; both must succeed with the same value, or both must fail.
+
+use goth_eval::prelude::{eval, Value as EvalValue};
+use goth_mir::interp::Value as MirValue;
+use goth_mir::{lower_expr, run_mir, verify};
+use goth_parse::prelude::*;
+
+/// Whether an evaluator result and a MIR result denote the same value
+fn agree(expected: &EvalValue, actual: &MirValue) -> bool {
+    match (expected, actual) {
+        (EvalValue::Int(a), MirValue::Int(b)) => *a == *b as i128,
+        (EvalValue::Float(a), MirValue::Float(b)) => a.0 == *b || (a.0.is_nan() && b.is_nan()),
+        (EvalValue::Bool(a), MirValue::Bool(b)) => a == b,
+        (EvalValue::Char(a), MirValue::Char(b)) => a == b,
+        (EvalValue::Unit, MirValue::Unit) => true,
+        (EvalValue::Tuple(a), MirValue::Tuple(b)) => a.len() == b.len() && a.iter().zip(b).all(|(x, y)| agree(x, y)),
+        (EvalValue::Tensor(t), MirValue::String(s)) => t.to_string_value().as_deref() == Some(s.as_str()),
+        (EvalValue::Tensor(t), MirValue::Array(elems)) => {
+            // The MIR array nests one level per dimension
+            let Some((&outer, inner)) = t.shape.split_first() else { return false };
+            let stride: usize = inner.iter().product();
+            outer == elems.len() && elems.iter().enumerate().all(|(i, elem)| {
+                let part: Vec<EvalValue> = (i * stride..(i + 1) * stride).filter_map(|j| t.get_flat(j)).collect();
+                if inner.is_empty() { agree(&part[0], elem) } else { agree(&EvalValue::Tensor(goth_eval::prelude::Tensor::pack(inner.to_vec(), part)), elem) }
+            })
+        }
+        (EvalValue::Variant { tag, payload: p }, MirValue::Variant { constructor, payload: q, .. }) => {
+            tag == constructor && match (p, q) {
+                (Some(p), Some(q)) => agree(p, q),
+                (p, q) => p.is_none() && q.is_none(),
+            }
+        }
+        // Functions have no common representation to compare
+        (EvalValue::Closure(_) | EvalValue::Partial { .. } | EvalValue::Primitive(_), MirValue::Closure { .. }) => true,
+        _ => false,
+    }
+}
+
+fn assert_backends_agree(source: &str) {
+    let expr = resolve_expr(parse_expr(source).unwrap_or_else(|e| panic!("{}: parse error {}", source, e)));
+    let expected = eval(&expr);
+    let program = lower_expr(&expr).unwrap_or_else(|e| panic!("{}: lowering failed: {}", source, e));
+    verify(&program).unwrap_or_else(|e| panic!("{}: invalid MIR: {:?}", source, e));
+    let actual = run_mir(&program, vec![]);
+    match (&expected, &actual) {
+        (Ok(v), Ok(w)) => assert!(agree(v, w), "{}: eval gave {:?} but MIR gave {:?}", source, v, w),
+        (Err(_), Err(_)) => {}
+        _ => panic!("{}: eval gave {:?} but MIR gave {:?}", source, expected, actual),
+    }
+}
+
+// ============ Arithmetic ============
+
+#[test]
+fn test_literals_agree() {
+    for source in ["42", "3.14", "true", "false", "'a'"] {
+        assert_backends_agree(source);
+    }
+}
+
+#[test]
+fn test_arithmetic_agrees() {
+    for source in ["1 + 2", "5 - 3", "4 × 5", "10 / 2", "7 / 2", "10 % 3", "2 ^ 10", "1 + 2.5", "7.5 / 2", "-(3 - 10)", "let n ← 10 in (n × (n + 1)) / 2"] {
+        assert_backends_agree(source);
+    }
+}
+
+#[test]
+fn test_comparisons_and_logic_agree() {
+    for source in ["1 < 2", "3 ≥ 4", "1 < 2.5", "0.5 ≥ 3", "2 = 2", "2 ≠ 2", "1 < 2 ∧ 3 > 4", "1 < 2 ∨ 3 > 4", "if 5 > 3 then 100 else 0", "if false then 1 else if true then 2 else 3"] {
+        assert_backends_agree(source);
+    }
+}
+
+#[test]
+fn test_rounding_agrees() {
+    // MIR types floor and ceiling as F64 unless an integer is expected, so
+    // the comparison is made where the result is bound as I64
+    for source in ["let n : I64 ← ⌊2.7⌋ in n + 1", "let n : I64 ← ⌈2.1⌉ in n × 2"] {
+        assert_backends_agree(source);
+    }
+}
+
+#[test]
+fn test_chars_agree() {
+    for source in ["'a' = 'a'", "'a' = 'b'", "ord 'A'", "chr 955", "chr (ord 'a' + 1)"] {
+        assert_backends_agree(source);
+    }
+}
+
+// ============ Let and Functions ============
+
+#[test]
+fn test_let_and_lambdas_agree() {
+    for source in [
+        "let x ← 5 in x + 1",
+        "let x ← 5 in let y ← x × 2 in y + 1",
+        "(λ→ ₀ + 1) 5",
+        "let k ← 3 in let f ← λ→ ₀ × k in f 14",
+    ] {
+        assert_backends_agree(source);
+    }
+}
+
+// ============ Tuples and Arrays ============
+
+#[test]
+fn test_tuples_agree() {
+    for source in ["⟨1, 2, 3⟩", "⟨1, 2⟩.0", "⟨1, ⟨2.5, true⟩⟩", "match ⟨⟩ { ⟨⟩ → 7 }", "match ⟨⟩ { ⟨⟩ → 1; _ → 2 }"] {
+        assert_backends_agree(source);
+    }
+}
+
+#[test]
+fn test_arrays_agree() {
+    for source in [
+        "[1, 2, 3]",
+        "iota 5",
+        "range 1 10",
+        "sum (iota 5)",
+        "Σ (⍳ 5)",
+        "[1, 2, 3] ↦ (λ→ ₀ × 2.5)",
+        "[1, 2, 3] ▸ (λ→ ₀ > 1)",
+    ] {
+        assert_backends_agree(source);
+    }
+}
+
+// ============ I/O ============
+
+#[test]
+fn test_print_returns_its_argument_in_both() {
+    for source in ["print 5", "(print 5) + 1", "let x ← print 7 in x × 2"] {
+        assert_backends_agree(source);
+    }
+}
+
+// ============ Errors ============
+
+#[test]
+fn test_division_by_zero_fails_in_both() {
+    for source in ["1 / 0", "7 % 0", "1.5 / 0", "let d ← 5 - 5 in 10 / d"] {
+        assert_backends_agree(source);
+    }
+}
+
+#[test]
+fn test_negative_integer_exponent_fails_in_both() {
+    assert_backends_agree("2 ^ (0 - 1)");
+}
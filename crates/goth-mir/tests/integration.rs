@@ -3,7 +3,7 @@
 //! These tests verify the full pipeline: source → parse → resolve → MIR → MLIR
 
 use goth_parse::prelude::*;
-use goth_mir::{lower_expr, lower_module};
+use goth_mir::{lower_expr, lower_module, prune_unreachable, verify};
 use goth_mlir::emit_program;
 
 /// Helper to test the full pipeline from source to MLIR
@@ -15,7 +15,12 @@ fn compile_expr_to_mlir(source: &str) -> Result<String, String> {
     let resolved = resolve_expr(parsed);
 
     // Lower to MIR
-    let mir = lower_expr(&resolved).map_err(|e| format!("MIR error: {:?}", e))?;
+    let mut mir = lower_expr(&resolved).map_err(|e| format!("MIR error: {:?}", e))?;
+    verify(&mir).map_err(|e| format!("MIR verification failed: {:?}", e))?;
+
+    // Optimize
+    prune_unreachable(&mut mir);
+    verify(&mir).map_err(|e| format!("MIR verification failed after pruning: {:?}", e))?;
 
     // Emit MLIR
     let mlir = emit_program(&mir).map_err(|e| format!("MLIR error: {:?}", e))?;
@@ -27,7 +32,10 @@ fn compile_expr_to_mlir(source: &str) -> Result<String, String> {
 fn compile_module_to_mlir(source: &str) -> Result<String, String> {
     let parsed = parse_module(source, "test").map_err(|e| format!("Parse error: {}", e))?;
     let resolved = resolve_module(parsed);
-    let mir = lower_module(&resolved).map_err(|e| format!("MIR error: {:?}", e))?;
+    let mut mir = lower_module(&resolved).map_err(|e| format!("MIR error: {:?}", e))?;
+    verify(&mir).map_err(|e| format!("MIR verification failed: {:?}", e))?;
+    prune_unreachable(&mut mir);
+    verify(&mir).map_err(|e| format!("MIR verification failed after pruning: {:?}", e))?;
     let mlir = emit_program(&mir).map_err(|e| format!("MLIR error: {:?}", e))?;
     Ok(mlir)
 }
@@ -92,6 +100,21 @@ fn test_mod() {
 
 // ============ Let Binding Tests ============
 
+#[test]
+fn test_floor_float_result() {
+    let mlir = compile_expr_to_mlir("let x ← ⌊2.7⌋ in x").unwrap();
+    assert!(mlir.contains("math.floor"));
+    assert!(!mlir.contains("arith.fptosi"));
+}
+
+#[test]
+fn test_floor_int_result() {
+    let mlir = compile_expr_to_mlir("let n : I64 ← ⌊2.7⌋ in n + 1").unwrap();
+    assert!(mlir.contains("math.floor"));
+    assert!(mlir.contains("arith.fptosi"));
+    assert!(mlir.contains("arith.addi"));
+}
+
 #[test]
 fn test_let_binding() {
     let mlir = compile_expr_to_mlir("let x ← 5 in x + 1").unwrap();
@@ -181,6 +204,20 @@ fn test_array_literal() {
     assert!(mlir.contains("tensor.from_elements") || mlir.contains("Array"));
 }
 
+#[test]
+fn test_map_preserves_shape() {
+    let mlir = compile_expr_to_mlir("[1, 2, 3] ↦ (λ→ ₀ × 2.5)").unwrap();
+    assert!(mlir.contains("goth.map"));
+    assert!(mlir.contains(": tensor<3xf64>"));
+}
+
+#[test]
+fn test_filter_has_dynamic_length() {
+    let mlir = compile_expr_to_mlir("[1, 2, 3] ▸ (λ→ ₀ > 1)").unwrap();
+    assert!(mlir.contains("goth.filter"));
+    assert!(mlir.contains(": tensor<?xi64>"));
+}
+
 // ============ Lambda Tests ============
 
 #[test]
@@ -8,7 +8,7 @@ use goth_ast::op::{BinOp, UnaryOp};
 use goth_mir::mir::*;
 
 use crate::context::TextMlirContext;
-use crate::types::{type_to_mlir_string, is_integer_type, is_float_type};
+use crate::types::{type_to_mlir_string, is_integer_type, is_float_type, operand_type};
 use crate::dialects::{arith, func, cf, scf, tensor, goth as goth_dialect};
 use crate::error::{MlirError, Result};
 
@@ -39,6 +39,11 @@ impl<'a> MlirBuilder<'a> {
                 self.ctx.emit(&code);
                 Ok(format!("%{}", self.ctx.fresh_ssa().strip_prefix('%').unwrap_or("0").parse::<usize>().unwrap_or(0) - 1))
             }
+            Constant::Char(c) => {
+                let code = arith::emit_constant_int(self.ctx, *c as i64, ty)?;
+                self.ctx.emit(&code);
+                Ok(format!("%{}", self.ctx.fresh_ssa().strip_prefix('%').unwrap_or("0").parse::<usize>().unwrap_or(0) - 1))
+            }
             Constant::Float(f) => {
                 let code = arith::emit_constant_float(self.ctx, *f, ty)?;
                 self.ctx.emit(&code);
@@ -92,6 +97,7 @@ impl<'a> MlirBuilder<'a> {
             Constant::Int(_) => Type::Prim(goth_ast::types::PrimType::I64),
             Constant::Float(_) => Type::Prim(goth_ast::types::PrimType::F64),
             Constant::Bool(_) => Type::Prim(goth_ast::types::PrimType::Bool),
+            Constant::Char(_) => Type::Prim(goth_ast::types::PrimType::Char),
             Constant::String(_) => Type::Prim(goth_ast::types::PrimType::String),
             Constant::Unit => Type::Tuple(vec![]),
         }
@@ -203,7 +209,9 @@ impl<'a> MlirBuilder<'a> {
             Rhs::BinOp(op, left, right) => {
                 let lhs = self.emit_operand(left)?;
                 let rhs = self.emit_operand(right)?;
-                self.emit_binop(op, &lhs, &rhs, &stmt.ty)?
+                // Comparisons are typed by their operands, which lowering has made agree
+                let ty = if arith::is_comparison(op) { operand_type(left, |local| self.ctx.get_local_type(local)) } else { stmt.ty.clone() };
+                self.emit_binop(op, &lhs, &rhs, &ty)?
             }
 
             Rhs::UnaryOp(op, operand) => {
@@ -211,6 +219,13 @@ impl<'a> MlirBuilder<'a> {
                 self.emit_unary(op, &op_ssa, &stmt.ty)?
             }
 
+            Rhs::Cast { from, to, operand } => {
+                let op_ssa = self.emit_operand(operand)?;
+                let code = arith::emit_cast(self.ctx, &op_ssa, from, to)?;
+                self.ctx.emit(&code);
+                format!("%{}", self.ctx.fresh_ssa().strip_prefix('%').unwrap_or("0").parse::<usize>().unwrap_or(0) - 1)
+            }
+
             Rhs::Tuple(ops) => {
                 let mut op_ssas = Vec::new();
                 for op in ops {
@@ -509,13 +524,7 @@ impl<'a> MlirBuilder<'a> {
             Terminator::Switch { scrutinee, cases, default } => {
                 let scrut_ssa = self.emit_operand(scrutinee)?;
                 let int_cases: Vec<(i64, BlockId)> = cases.iter()
-                    .filter_map(|(c, b)| {
-                        if let Constant::Int(n) = c {
-                            Some((*n, *b))
-                        } else {
-                            None
-                        }
-                    })
+                    .filter_map(|(c, b)| c.as_int().map(|n| (n, *b)))
                     .collect();
                 let code = cf::emit_switch(self.ctx, &scrut_ssa, &int_cases, *default);
                 self.ctx.emit(&code);
@@ -0,0 +1,110 @@
+//! One-call compilation from Goth expressions to MLIR
+//!
+//! [`compile_to_mlir`] runs the whole pipeline:
+//!
+//! ```text
+//! Expr → MIR (lower_expr)
+//!      → verify
+//!      → MIR passes (prune unreachable blocks and merge duplicate lambdas at O1+,
+//!        inline small functions at O2+)
+//!      → MLIR text (emit_program)
+//!      → MLIR passes (default_pipeline, or llvm_pipeline when lowering to LLVM)
+//! ```
+
+use goth_ast::expr::Expr;
+use goth_mir::mir::Program;
+use goth_mir::{inline_functions, lower_expr, merge_duplicate_functions, prune_unreachable};
+use crate::emit::emit_program;
+use crate::error::{MlirError, Result};
+use crate::passes::{default_pipeline, llvm_pipeline, OptLevel};
+
+/// Settings for [`compile_to_mlir`]
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// Optimization level for both the MIR passes and the MLIR pass pipeline
+    pub opt_level: OptLevel,
+    /// Continue lowering to the LLVM dialect
+    pub lower_to_llvm: bool,
+    /// Verify the MIR after lowering and after each MIR pass
+    pub verify: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions { opt_level: OptLevel::default(), lower_to_llvm: false, verify: true }
+    }
+}
+
+impl CompileOptions {
+    /// Options for the given optimization level
+    pub fn with_opt_level(opt_level: OptLevel) -> Self {
+        CompileOptions { opt_level, ..Self::default() }
+    }
+}
+
+/// Compile `expr` to MLIR text
+pub fn compile_to_mlir(expr: &Expr, options: &CompileOptions) -> Result<String> {
+    let mut program = lower_expr(expr)?;
+    check(&program, options, "lowering")?;
+
+    if options.opt_level >= OptLevel::O1 {
+        prune_unreachable(&mut program);
+        check(&program, options, "pruning unreachable blocks")?;
+        merge_duplicate_functions(&mut program);
+        check(&program, options, "merging duplicate functions")?;
+    }
+    if options.opt_level >= OptLevel::O2 {
+        inline_functions(&mut program);
+        check(&program, options, "inlining")?;
+    }
+
+    let mlir = emit_program(&program)?;
+    let pipeline = if options.lower_to_llvm { llvm_pipeline(options.opt_level) } else { default_pipeline(options.opt_level) };
+    pipeline.run(&mlir)
+}
+
+fn check(program: &Program, options: &CompileOptions, after: &str) -> Result<()> {
+    if !options.verify { return Ok(()); }
+    goth_mir::verify(program).map_err(|errors| {
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        MlirError::Verification(format!("after {}: {}", after, messages.join("; ")))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use goth_ast::op::BinOp;
+
+    #[test]
+    fn test_compile_arithmetic() {
+        // (1 + 2) × 3
+        let expr = Expr::binop(BinOp::Mul, Expr::binop(BinOp::Add, Expr::int(1), Expr::int(2)), Expr::int(3));
+        for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2, OptLevel::O3] {
+            let mlir = compile_to_mlir(&expr, &CompileOptions::with_opt_level(level)).unwrap();
+            assert!(mlir.contains("func.func @main"), "{:?}: {}", level, mlir);
+            assert!(mlir.contains("return"), "{:?}: {}", level, mlir);
+        }
+        let unoptimized = compile_to_mlir(&expr, &CompileOptions::with_opt_level(OptLevel::O0)).unwrap();
+        assert!(unoptimized.contains("arith.addi") && unoptimized.contains("arith.muli"), "{}", unoptimized);
+    }
+
+    #[test]
+    fn test_compile_to_llvm_dialect() {
+        let expr = Expr::binop(BinOp::Add, Expr::int(40), Expr::int(2));
+        let options = CompileOptions { lower_to_llvm: true, ..CompileOptions::default() };
+        let mlir = compile_to_mlir(&expr, &options).unwrap();
+        assert!(mlir.contains("llvm."), "{}", mlir);
+    }
+
+    #[test]
+    fn test_compile_merges_identical_lambdas() {
+        // let f = λ→ ₀ × 2 in let g = λ→ ₀ × 2 in f 1 + g 2
+        let double = || Expr::lam(Expr::binop(BinOp::Mul, Expr::idx(0), Expr::int(2)));
+        let body = Expr::binop(BinOp::Add, Expr::app(Expr::idx(1), Expr::int(1)), Expr::app(Expr::idx(0), Expr::int(2)));
+        let expr = Expr::let_var("f", double(), Expr::let_var("g", double(), body));
+        let lambdas = |level| compile_to_mlir(&expr, &CompileOptions::with_opt_level(level)).unwrap().matches("func.func @lambda_").count();
+        assert_eq!(lambdas(OptLevel::O0), 2);
+        assert_eq!(lambdas(OptLevel::O1), 1);
+    }
+}
@@ -86,7 +86,7 @@ fn float_binop_name(op: &BinOp) -> Result<&'static str> {
 }
 
 /// Check if an operation is a comparison
-fn is_comparison(op: &BinOp) -> bool {
+pub fn is_comparison(op: &BinOp) -> bool {
     matches!(
         op,
         BinOp::Lt | BinOp::Gt | BinOp::Leq | BinOp::Geq | BinOp::Eq | BinOp::Neq
@@ -499,7 +499,7 @@ pub fn type_to_memref_string(ty: &Type) -> Result<String> {
             let dims: Vec<String> = shape.0.iter().map(|d| {
                 match d {
                     goth_ast::shape::Dim::Const(n) => n.to_string(),
-                    goth_ast::shape::Dim::Var(_) => "?".to_string(),
+                    goth_ast::shape::Dim::Var(_) | goth_ast::shape::Dim::Dynamic => "?".to_string(),
                     goth_ast::shape::Dim::BinOp(_, _, _) => "?".to_string(),
                 }
             }).collect();
@@ -17,7 +17,7 @@
 use crate::error::{MlirError, Result};
 use crate::context::TextMlirContext;
 use crate::builder::MlirBuilder;
-use crate::types::type_to_mlir_string;
+use crate::types::{type_to_mlir_string, operand_type};
 use goth_ast::types::{Type, PrimType};
 use goth_mir::mir::*;
 use std::collections::HashMap;
@@ -88,6 +88,7 @@ pub fn emit_type(ty: &Type) -> Result<String> {
         Type::Prim(PrimType::I64) => Ok("i64".to_string()),
         Type::Prim(PrimType::F64) => Ok("f64".to_string()),
         Type::Prim(PrimType::Bool) => Ok("i1".to_string()),
+        Type::Prim(PrimType::Char) => Ok("i32".to_string()), // Unicode code point
         Type::Prim(PrimType::String) => Ok("!llvm.ptr<i8>".to_string()),
 
         Type::Tuple(fields) if fields.is_empty() => Ok("()".to_string()),
@@ -158,6 +159,10 @@ fn emit_constant(ctx: &mut MlirContext, constant: &Constant, ty: &Type) -> Resul
             format!("{}{} = arith.constant {} : {}", 
                 ctx.indent_str(), ssa, val, mlir_ty)
         }
+        Constant::Char(c) => {
+            format!("{}{} = arith.constant {} : {}",
+                ctx.indent_str(), ssa, *c as u32, mlir_ty)
+        }
         Constant::Unit => {
             format!("{}{} = arith.constant () : ()",
                 ctx.indent_str(), ssa)
@@ -182,6 +187,7 @@ fn emit_operand(ctx: &mut MlirContext, op: &Operand, output: &mut String) -> Res
                 Constant::Int(_) => Type::Prim(PrimType::I64),
                 Constant::Float(_) => Type::Prim(PrimType::F64),
                 Constant::Bool(_) => Type::Prim(PrimType::Bool),
+                Constant::Char(_) => Type::Prim(PrimType::Char),
                 Constant::String(_) => Type::Prim(PrimType::String),
                 Constant::Unit => Type::Tuple(vec![]),
             };
@@ -230,9 +236,25 @@ fn is_bool_type(ty: &Type) -> bool {
 
 /// Emit binary operation
 fn emit_binop(ctx: &mut MlirContext, op: &goth_ast::op::BinOp,
-              left: String, right: String, ty: &Type) -> Result<String> {
+              left: String, right: String, operand_ty: &Type, ty: &Type) -> Result<String> {
     let ssa = ctx.fresh_ssa();
 
+    // Lowering casts mixed operands to a common type, so float operands
+    // compare with the ordered float predicates
+    if is_bool_type(ty) && is_float_type(operand_ty) {
+        let predicate = match op {
+            goth_ast::op::BinOp::Lt => "olt",
+            goth_ast::op::BinOp::Gt => "ogt",
+            goth_ast::op::BinOp::Leq => "ole",
+            goth_ast::op::BinOp::Geq => "oge",
+            goth_ast::op::BinOp::Eq => "oeq",
+            goth_ast::op::BinOp::Neq => "one",
+            _ => return Err(MlirError::UnsupportedOp(format!("Float {:?}", op))),
+        };
+        return Ok(format!("{}{} = arith.cmpf {}, {}, {} : {}\n",
+            ctx.indent_str(), ssa, predicate, left, right, emit_type(operand_ty)?));
+    }
+
     // For Bool result type, handle logical ops and comparisons specially
     if is_bool_type(ty) {
         match op {
@@ -310,8 +332,27 @@ fn emit_binop(ctx: &mut MlirContext, op: &goth_ast::op::BinOp,
         return Err(MlirError::UnsupportedType(format!("{:?}", ty)));
     };
 
-    Ok(format!("{}{} = {} {}, {} : {}\n",
-        ctx.indent_str(), ssa, op_name, left, right, mlir_ty))
+    // Division traps on a zero divisor, as it does in the interpreters,
+    // instead of being undefined (integers) or producing infinity (floats)
+    let guard = match op {
+        goth_ast::op::BinOp::Div | goth_ast::op::BinOp::Mod => emit_divisor_guard(ctx, &right, ty)?,
+        _ => String::new(),
+    };
+
+    Ok(format!("{}{}{} = {} {}, {} : {}\n",
+        guard, ctx.indent_str(), ssa, op_name, left, right, mlir_ty))
+}
+
+/// Emit a `cf.assert` that `divisor` is nonzero
+fn emit_divisor_guard(ctx: &mut MlirContext, divisor: &str, ty: &Type) -> Result<String> {
+    let mlir_ty = emit_type(ty)?;
+    let (zero_lit, compare) = if is_float_type(ty) { ("0.0", "arith.cmpf one") } else { ("0", "arith.cmpi ne") };
+    let zero = ctx.fresh_ssa();
+    let nonzero = ctx.fresh_ssa();
+    let indent = ctx.indent_str();
+    Ok(format!("{indent}{zero} = arith.constant {zero_lit} : {mlir_ty}\n\
+                {indent}{nonzero} = {compare}, {divisor}, {zero} : {mlir_ty}\n\
+                {indent}cf.assert {nonzero}, \"division by zero\"\n"))
 }
 
 /// Emit unary operation
@@ -387,7 +428,8 @@ fn emit_stmt(ctx: &mut MlirContext, stmt: &Stmt, output: &mut String) -> Result<
         Rhs::BinOp(op, left, right) => {
             let left_ssa = emit_operand(ctx, left, output)?;
             let right_ssa = emit_operand(ctx, right, output)?;
-            let code = emit_binop(ctx, op, left_ssa, right_ssa, &stmt.ty)?;
+            let operand_ty = operand_type(left, |local| ctx.local_types.get(local));
+            let code = emit_binop(ctx, op, left_ssa, right_ssa, &operand_ty, &stmt.ty)?;
             // Extract ssa from binop
             let ssa = format!("%{}", ctx.next_ssa - 1);
             (ssa, code)
@@ -401,6 +443,22 @@ fn emit_stmt(ctx: &mut MlirContext, stmt: &Stmt, output: &mut String) -> Result<
             (ssa, code)
         }
         
+        Rhs::Cast { from, to, operand } => {
+            if !is_valid_cast(from, to) {
+                return Err(goth_mir::MirError::InvalidCast { from: from.to_string(), to: to.to_string() }.into());
+            }
+            let op_ssa = emit_operand(ctx, operand, output)?;
+            let cast_op = match (is_int_type(from), is_float_type(from), is_int_type(to), is_float_type(to)) {
+                (true, false, false, true) => "arith.sitofp",
+                (false, true, true, false) => "arith.fptosi",
+                _ => return Err(MlirError::UnsupportedOp(format!("Cast from {} to {}", from, to))),
+            };
+            let ssa = ctx.fresh_ssa();
+            let code = format!("{}{} = {} {} : {} to {}\n",
+                ctx.indent_str(), ssa, cast_op, op_ssa, emit_type(from)?, emit_type(to)?);
+            (ssa, code)
+        }
+
         Rhs::Tuple(ops) => {
             let ssa = ctx.fresh_ssa();
             let mut op_ssas = Vec::new();
@@ -604,7 +662,10 @@ fn emit_block_with_label(ctx: &mut MlirContext, block: &Block, label: Option<&st
         Terminator::Switch { scrutinee, cases, default } => {
             let scrut_ssa = emit_operand(ctx, scrutinee, output)?;
             let case_str: Vec<String> = cases.iter()
-                .map(|(c, b)| format!("{}: ^bb{}", c, b.0))
+                .map(|(c, b)| match c.as_int() {
+                    Some(n) => format!("{}: ^bb{}", n, b.0),
+                    None => format!("{}: ^bb{}", c, b.0),
+                })
                 .collect();
             output.push_str(&format!("{}cf.switch {}, [{}], ^bb{}\n",
                 ctx.indent_str(), scrut_ssa, case_str.join(", "), default.0));
@@ -783,6 +844,21 @@ mod tests {
         assert!(mlir.contains("i64"));
     }
     
+    #[test]
+    fn test_emit_char_constant() {
+        let func = Function {
+            name: "letter".to_string(),
+            params: vec![],
+            ret_ty: Type::Prim(PrimType::Char),
+            body: Block::with_return(Operand::Const(Constant::Char('A'))),
+            blocks: vec![],
+            is_closure: false,
+        };
+
+        let mlir = emit_function(&func).unwrap();
+        assert!(mlir.contains("arith.constant 65 : i32"));
+    }
+
     #[test]
     fn test_emit_binop_int() {
         // fn test() -> i64 { return 1 + 2 }
@@ -934,6 +1010,77 @@ mod tests {
         assert!(mlir.contains("arith.addi"));
     }
     
+    #[test]
+    fn test_emit_mixed_comparison() {
+        use goth_mir::lower_expr;
+        use goth_ast::expr::Expr;
+
+        // 1 < 2.5 casts the integer and compares floats
+        let expr = Expr::binop(BinOp::Lt, Expr::int(1), Expr::float(2.5));
+        let mlir = emit_program(&lower_expr(&expr).unwrap()).unwrap();
+
+        assert!(mlir.contains("arith.sitofp"), "{}", mlir);
+        assert!(mlir.contains("arith.cmpf olt"), "{}", mlir);
+        assert!(!mlir.contains("arith.cmpi"), "{}", mlir);
+    }
+
+    #[test]
+    fn test_emit_division_guards_divisor() {
+        use goth_mir::lower_expr;
+        use goth_ast::expr::Expr;
+
+        let mlir = emit_program(&lower_expr(&Expr::div(Expr::int(1), Expr::int(0))).unwrap()).unwrap();
+        let guard = mlir.find("cf.assert").expect("division is guarded");
+        assert!(mlir.contains("arith.cmpi ne"), "{}", mlir);
+        assert!(guard < mlir.find("arith.divsi").unwrap(), "{}", mlir);
+
+        let mlir = emit_program(&lower_expr(&Expr::div(Expr::float(1.0), Expr::float(0.0))).unwrap()).unwrap();
+        assert!(mlir.contains("arith.cmpf one"), "{}", mlir);
+        assert!(mlir.contains("cf.assert"), "{}", mlir);
+
+        let mlir = emit_program(&lower_expr(&Expr::add(Expr::int(1), Expr::int(2))).unwrap()).unwrap();
+        assert!(!mlir.contains("cf.assert"), "{}", mlir);
+    }
+
+    #[test]
+    fn test_emit_float_to_int_cast() {
+        use goth_mir::lower_expr;
+        use goth_ast::expr::Expr;
+
+        // ⌊2.7⌋ : I64 floors as F64, then truncates to an integer
+        let expr = Expr::Annot(
+            Box::new(Expr::app(Expr::name("floor"), Expr::float(2.7))),
+            Type::Prim(PrimType::I64),
+        );
+        let mlir = emit_program(&lower_expr(&expr).unwrap()).unwrap();
+
+        assert!(mlir.contains("arith.fptosi"), "{}", mlir);
+        assert!(mlir.contains(": f64 to i64"), "{}", mlir);
+    }
+
+    #[test]
+    fn test_emit_invalid_cast_is_an_error() {
+        let tuple = Type::Tuple(vec![]);
+        let func = Function {
+            name: "main".to_string(),
+            params: vec![tuple.clone()],
+            ret_ty: Type::Prim(PrimType::I64),
+            body: Block {
+                stmts: vec![Stmt {
+                    dest: LocalId::new(1),
+                    ty: Type::Prim(PrimType::I64),
+                    rhs: Rhs::Cast { from: tuple, to: Type::Prim(PrimType::I64), operand: Operand::Local(LocalId::new(0)) },
+                }],
+                term: Terminator::Return(Operand::Local(LocalId::new(1))),
+            },
+            blocks: vec![],
+            is_closure: false,
+        };
+
+        let err = emit_function(&func).unwrap_err();
+        assert!(matches!(err, MlirError::MirError(goth_mir::MirError::InvalidCast { .. })), "{:?}", err);
+    }
+
     #[test]
     fn test_emit_lambda() {
         use goth_mir::lower_expr;
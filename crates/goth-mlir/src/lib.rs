@@ -46,6 +46,14 @@
 //! println!("{}", mlir_code);
 //! ```
 //!
+//! # Compiling in One Call
+//!
+//! ```rust,ignore
+//! use goth_mlir::{compile_to_mlir, CompileOptions, OptLevel};
+//!
+//! let mlir_code = compile_to_mlir(&expr, &CompileOptions::with_opt_level(OptLevel::O2))?;
+//! ```
+//!
 //! # Using the Pass Pipeline
 //!
 //! ```rust,ignore
@@ -63,6 +71,7 @@ pub mod dialects;
 pub mod builder;
 pub mod emit;
 pub mod passes;
+pub mod compile;
 
 // Re-exports
 pub use error::{MlirError, Result};
@@ -70,6 +79,7 @@ pub use context::TextMlirContext;
 pub use types::type_to_mlir_string;
 pub use builder::MlirBuilder;
 pub use emit::{emit_program, emit_function, emit_type};
+pub use compile::{compile_to_mlir, CompileOptions};
 
 // Pass-related exports
 pub use passes::{Pass, PassManager, OptLevel, default_pipeline, llvm_pipeline};
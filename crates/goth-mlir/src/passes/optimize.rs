@@ -118,6 +118,7 @@ impl OptimizePass {
                     || line.contains("cf.cond_br ")
                     || line.contains("scf.yield ")
                     || line.contains("func.call ")
+                    || line.contains("cf.assert ")
                 {
                     for cap in Regex::new(r"%\w+").unwrap().find_iter(line) {
                         used_values.insert(cap.as_str().to_string());
@@ -469,6 +470,27 @@ return %2 : i64
         assert!(result.contains("%2"));
     }
 
+    #[test]
+    fn test_dce_keeps_division_guard() {
+        let pass = OptimizePass::new(OptLevel::O1);
+
+        let mlir = r#"
+%0 = arith.constant 1 : i64
+%1 = arith.constant 0 : i64
+%3 = arith.constant 0 : i64
+%4 = arith.cmpi ne, %1, %3 : i64
+cf.assert %4, "division by zero"
+%2 = arith.divsi %0, %1 : i64
+return %0 : i64
+"#;
+
+        let result = pass.run_dce(mlir);
+
+        // The quotient is unused, but the check on its divisor still runs
+        assert!(result.contains("%4 = arith.cmpi ne, %1, %3"));
+        assert!(result.contains("cf.assert %4"));
+    }
+
     #[test]
     fn test_cse() {
         let pass = OptimizePass::new(OptLevel::O2);
@@ -14,6 +14,7 @@
 
 use goth_ast::types::{Type, PrimType, TupleField};
 use goth_ast::shape::{Shape, Dim};
+use goth_mir::mir::{Constant, LocalId, Operand};
 use crate::error::{MlirError, Result};
 
 #[cfg(feature = "melior")]
@@ -134,13 +135,26 @@ fn shape_to_mlir_string(shape: &Shape) -> String {
     shape.0.iter()
         .map(|dim| match dim {
             Dim::Const(n) => n.to_string(),
-            Dim::Var(_) => "?".to_string(),
+            Dim::Var(_) | Dim::Dynamic => "?".to_string(),
             Dim::BinOp(_, _, _) => "?".to_string(), // Dynamic/computed dimension
         })
         .collect::<Vec<_>>()
         .join("x")
 }
 
+/// The type of an operand, defaulting to `I64` for locals whose type has not been recorded
+pub fn operand_type<'a>(op: &Operand, local_type: impl FnOnce(&LocalId) -> Option<&'a Type>) -> Type {
+    match op {
+        Operand::Const(Constant::Int(_)) => Type::Prim(PrimType::I64),
+        Operand::Const(Constant::Float(_)) => Type::Prim(PrimType::F64),
+        Operand::Const(Constant::Bool(_)) => Type::Prim(PrimType::Bool),
+        Operand::Const(Constant::Char(_)) => Type::Prim(PrimType::Char),
+        Operand::Const(Constant::String(_)) => Type::Prim(PrimType::String),
+        Operand::Const(Constant::Unit) => Type::Tuple(vec![]),
+        Operand::Local(local) => local_type(local).cloned().unwrap_or(Type::Prim(PrimType::I64)),
+    }
+}
+
 /// Check if a type is an integer type
 pub fn is_integer_type(ty: &Type) -> bool {
     match ty {
@@ -240,7 +254,7 @@ pub mod melior_types {
         shape.0.iter()
             .map(|dim| match dim {
                 Dim::Const(n) => *n as i64,
-                Dim::Var(_) => -1, // Dynamic dimension
+                Dim::Var(_) | Dim::Dynamic => -1, // Dynamic dimension
                 Dim::BinOp(_, _, _) => -1, // Computed dimension
             })
             .collect()
@@ -290,6 +304,13 @@ mod tests {
             Box::new(Type::Prim(PrimType::F64)),
         );
         assert_eq!(type_to_mlir_string(&tensor_2d).unwrap(), "tensor<?x5xf64>");
+
+        // Dimension only known at run time
+        let tensor_dyn = Type::Tensor(
+            Shape(vec![Dim::Const(3), Dim::Dynamic]),
+            Box::new(Type::Prim(PrimType::I64)),
+        );
+        assert_eq!(type_to_mlir_string(&tensor_dyn).unwrap(), "tensor<3x?xi64>");
     }
 
     #[test]
@@ -19,7 +19,7 @@ pub mod loader;
 pub mod prelude {
     pub use crate::lexer::{Token, Lexer, Loc, Spanned};
     pub use crate::parser::{Parser, ParseError, ParseResult, parse_expr, parse_type, parse_pattern, parse_module};
-    pub use crate::resolve::{resolve_expr, resolve_module};
+    pub use crate::resolve::{resolve_expr, resolve_module, resolve_module_checked, NamedArgError};
     pub use crate::loader::{Loader, LoadError, LoadResult, load_file, load_source, save_file};
 }
 
@@ -1176,6 +1176,7 @@ impl<'a> Parser<'a> {
         Ok(Decl::Fn(FnDecl {
             name: name.into(),
             type_params: vec![],
+            param_names: vec![],
             signature: sig,
             effects,
             constraints,
@@ -13,10 +13,32 @@
 //! Parsed: Let { pattern: Var("x"), value: 5, body: Name("x") * Name("x") }
 //! Resolved: Let { pattern: Var("x"), value: 5, body: Idx(0) * Idx(0) }
 //! ```
+//!
+//! Named-argument applications (`f ⟨width: 3, height: 4⟩`) are rewritten
+//! here to positional applications, using the parameter names recorded on
+//! the callee's `FnDecl`.
 
 use goth_ast::expr::{Expr, MatchArm, DoOp};
 use goth_ast::pattern::Pattern;
 use goth_ast::decl::{Module, Decl, FnDecl, LetDecl};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Errors from rewriting named-argument applications
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum NamedArgError {
+    #[error("{func} has no parameter named '{arg}'")]
+    UnexpectedArg { func: String, arg: String },
+
+    #[error("missing argument '{param}' in call to {func}")]
+    MissingArg { func: String, param: String },
+
+    #[error("argument '{arg}' given more than once in call to {func}")]
+    DuplicateArg { func: String, arg: String },
+
+    #[error("cannot apply {func} by name: parameter names unknown")]
+    UnknownParams { func: String },
+}
 
 /// Resolve names to de Bruijn indices in an expression
 pub fn resolve_expr(expr: Expr) -> Expr {
@@ -25,21 +47,36 @@ pub fn resolve_expr(expr: Expr) -> Expr {
 }
 
 /// Resolve names in a module
+///
+/// Named-argument applications that cannot be rewritten are left in place;
+/// use [`resolve_module_checked`] to report them.
 pub fn resolve_module(module: Module) -> Module {
     let mut ctx = ResolveCtx::new();
     ctx.resolve_module(module)
 }
 
+/// Resolve names in a module, failing if any named-argument application
+/// has missing, extra or duplicate arguments
+pub fn resolve_module_checked(module: Module) -> Result<Module, Vec<NamedArgError>> {
+    let mut ctx = ResolveCtx::new();
+    let module = ctx.resolve_module(module);
+    if ctx.errors.is_empty() { Ok(module) } else { Err(ctx.errors) }
+}
+
 /// Resolution context tracking the current scope
 struct ResolveCtx {
     /// Stack of bound names (innermost last)
     /// Each entry is a list of names bound at that level (for patterns with multiple bindings)
     scopes: Vec<Vec<String>>,
+    /// Parameter names of module functions, for named-argument application
+    fn_params: HashMap<String, Vec<String>>,
+    /// Errors from named-argument applications
+    errors: Vec<NamedArgError>,
 }
 
 impl ResolveCtx {
     fn new() -> Self {
-        ResolveCtx { scopes: Vec::new() }
+        ResolveCtx { scopes: Vec::new(), fn_params: HashMap::new(), errors: Vec::new() }
     }
 
     /// Push a new scope with a single binding
@@ -204,6 +241,15 @@ impl ResolveCtx {
                 Expr::App(Box::new(func), Box::new(arg))
             }
 
+            // Named-argument application - reorder into positional applications
+            Expr::AppNamed(func, args) => {
+                let func = self.resolve_expr(*func);
+                let args = args.into_iter()
+                    .map(|(name, e)| (name, self.resolve_expr(e)))
+                    .collect();
+                self.resolve_named_app(func, args)
+            }
+
             // Binary operation
             Expr::BinOp(op, left, right) => {
                 let left = self.resolve_expr(*left);
@@ -362,8 +408,9 @@ impl ResolveCtx {
                 Expr::Disabled(Box::new(inner))
             }
 
-            // Hole - no resolution
+            // Hole and unknown nodes - no resolution
             Expr::Hole => Expr::Hole,
+            Expr::Unknown(raw) => Expr::Unknown(raw),
 
             // Quote/Unquote - resolve inside
             Expr::Quote(inner) => {
@@ -412,8 +459,65 @@ impl ResolveCtx {
         }
     }
 
+    /// Rewrite `func ⟨name: arg, ...⟩` to `func arg₁ arg₂ ...` in parameter order.
+    /// Only global functions with recorded parameter names can be applied by name.
+    fn resolve_named_app(&mut self, func: Expr, args: Vec<(Box<str>, Expr)>) -> Expr {
+        let fname = match &func {
+            Expr::Name(name) => name.to_string(),
+            other => format!("{}", other),
+        };
+        let params = match &func {
+            Expr::Name(name) => self.fn_params.get(name.as_ref()).cloned(),
+            _ => None,
+        };
+        let Some(params) = params else {
+            self.errors.push(NamedArgError::UnknownParams { func: fname });
+            return Expr::AppNamed(Box::new(func), args);
+        };
+
+        let errors_before = self.errors.len();
+        let mut slots: Vec<Option<Expr>> = vec![None; params.len()];
+        let mut leftover = Vec::new();
+        for (name, arg) in args {
+            match params.iter().position(|p| p.as_str() == name.as_ref()) {
+                Some(i) if slots[i].is_some() => {
+                    self.errors.push(NamedArgError::DuplicateArg { func: fname.clone(), arg: name.to_string() });
+                    leftover.push((name, arg));
+                }
+                Some(i) => slots[i] = Some(arg),
+                None => {
+                    self.errors.push(NamedArgError::UnexpectedArg { func: fname.clone(), arg: name.to_string() });
+                    leftover.push((name, arg));
+                }
+            }
+        }
+        for (param, slot) in params.iter().zip(&slots) {
+            if slot.is_none() {
+                self.errors.push(NamedArgError::MissingArg { func: fname.clone(), param: param.clone() });
+            }
+        }
+
+        if self.errors.len() > errors_before {
+            // Leave the call as written so the evaluator reports it too
+            let mut args: Vec<(Box<str>, Expr)> = params.iter().zip(slots)
+                .filter_map(|(p, slot)| slot.map(|e| (p.as_str().into(), e)))
+                .collect();
+            args.extend(leftover);
+            return Expr::AppNamed(Box::new(func), args);
+        }
+        slots.into_iter().flatten().fold(func, |f, arg| Expr::App(Box::new(f), Box::new(arg)))
+    }
+
     /// Resolve a module
     fn resolve_module(&mut self, module: Module) -> Module {
+        for decl in &module.decls {
+            if let Decl::Fn(fn_decl) = decl {
+                if !fn_decl.param_names.is_empty() {
+                    let names = fn_decl.param_names.iter().map(|n| n.to_string()).collect();
+                    self.fn_params.insert(fn_decl.name.to_string(), names);
+                }
+            }
+        }
         let decls = module.decls.into_iter()
             .map(|d| self.resolve_decl(d))
             .collect();
@@ -442,11 +546,14 @@ impl ResolveCtx {
                 // Count number of parameters from signature
                 let num_params = Self::count_params(&fn_decl.signature).max(1);
 
-                // Push anonymous bindings for each parameter
+                // Push bindings for each parameter, named if the declaration records names
                 // These are pushed in order (first param first), so with lookup_index
                 // reversing the order, ₀ will be the last param, ₁ the second-to-last, etc.
                 let param_names: Vec<String> = (0..num_params)
-                    .map(|i| format!("_param{}", i))
+                    .map(|i| match fn_decl.param_names.get(i) {
+                        Some(name) => name.to_string(),
+                        None => format!("_param{}", i),
+                    })
                     .collect();
                 self.push_many(param_names);
 
@@ -465,6 +572,7 @@ impl ResolveCtx {
                 Decl::Fn(FnDecl {
                     name: fn_decl.name,
                     type_params: fn_decl.type_params,
+                    param_names: fn_decl.param_names,
                     signature: fn_decl.signature,
                     effects: fn_decl.effects,
                     constraints: fn_decl.constraints,
@@ -673,4 +781,47 @@ mod tests {
             _ => panic!("Expected outer Let"),
         }
     }
+
+    fn make_rect_module(call: Expr) -> Module {
+        use goth_ast::types::{Type, PrimType};
+        let i64_ = || Type::Prim(PrimType::I64);
+        let make_rect = FnDecl::simple("make_rect", Type::func(i64_(), Type::func(i64_(), i64_())), Expr::sub(Expr::name("width"), Expr::name("height")))
+            .with_param_names(["width", "height"]);
+        Module::new(vec![Decl::Fn(make_rect), Decl::Let(LetDecl::new("r", call))])
+    }
+
+    fn resolved_let_value(module: &Module) -> &Expr {
+        match &module.decls[1] {
+            Decl::Let(l) => &l.value,
+            _ => panic!("Expected Let"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_named_args_reordered() {
+        let call = Expr::app_named(Expr::name("make_rect"), vec![("height", Expr::int(4)), ("width", Expr::int(3))]);
+        let module = resolve_module_checked(make_rect_module(call)).unwrap();
+        let expected = Expr::app(Expr::app(Expr::name("make_rect"), Expr::int(3)), Expr::int(4));
+        assert_eq!(resolved_let_value(&module), &expected);
+
+        // Parameter names resolve to their positional indices in the body
+        match &module.decls[0] {
+            Decl::Fn(f) => assert_eq!(f.body, Expr::sub(Expr::idx(1), Expr::idx(0))),
+            _ => panic!("Expected Fn"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_named_args_errors() {
+        let call = Expr::app_named(Expr::name("make_rect"), vec![("width", Expr::int(3)), ("depth", Expr::int(5))]);
+        let errors = resolve_module_checked(make_rect_module(call)).unwrap_err();
+        assert_eq!(errors, vec![
+            NamedArgError::UnexpectedArg { func: "make_rect".into(), arg: "depth".into() },
+            NamedArgError::MissingArg { func: "make_rect".into(), param: "height".into() },
+        ]);
+
+        let call = Expr::app_named(Expr::name("area"), vec![("width", Expr::int(3))]);
+        let errors = resolve_module_checked(make_rect_module(call)).unwrap_err();
+        assert_eq!(errors, vec![NamedArgError::UnknownParams { func: "area".into() }]);
+    }
 }